@@ -28,6 +28,7 @@ mod target;
 
 #[cfg(test)]
 pub(crate) mod tests;
+pub(crate) use association::positional_formals;
 pub(crate) use root::{Library, LockedUnit};
 
 pub use self::root::{DesignRoot, EntHierarchy};
@@ -9,6 +9,7 @@ pub(crate) use super::scope::Scope;
 use crate::ast::*;
 use crate::data::*;
 use crate::named_entity::*;
+use crate::standard::VHDLStandard;
 use crate::syntax::TokenAccess;
 use fnv::FnvHashSet;
 use std::cell::RefCell;
@@ -136,8 +137,12 @@ pub(super) struct AnalyzeContext<'a> {
     pub work_sym: Symbol,
     std_sym: Symbol,
     standard_sym: Symbol,
+    env_sym: Symbol,
     pub(super) is_std_logic_1164: bool,
 
+    // The revision of the standard the current unit is analyzed with
+    pub standard: VHDLStandard,
+
     // Record dependencies and sensitivies when
     // analyzing design units
     //
@@ -165,11 +170,14 @@ impl<'a> AnalyzeContext<'a> {
         current_unit: &UnitId,
         arena: &'a Arena,
         ctx: &'a dyn TokenAccess,
+        standard: VHDLStandard,
     ) -> AnalyzeContext<'a> {
         AnalyzeContext {
             work_sym: root.symbol_utf8("work"),
             std_sym: root.symbol_utf8("std"),
             standard_sym: root.symbol_utf8("standard"),
+            env_sym: root.symbol_utf8("env"),
+            standard,
             is_std_logic_1164: current_unit
                 == &UnitId::package(
                     &root.symbol_utf8("ieee"),
@@ -185,6 +193,27 @@ impl<'a> AnalyzeContext<'a> {
         }
     }
 
+    /// Returns an error when a construct is used that was introduced in a later revision
+    /// of the standard than the one the current unit is analyzed with
+    pub fn check_standard(
+        &self,
+        pos: &SrcPos,
+        introduced_in: VHDLStandard,
+        what: &str,
+    ) -> Result<(), Diagnostic> {
+        if self.standard < introduced_in {
+            Err(Diagnostic::error(
+                pos,
+                format!(
+                    "{what} was introduced in {introduced_in} but the design unit is analyzed as {}",
+                    self.standard
+                ),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn work_library_name(&self) -> &Symbol {
         self.current_unit.library_name()
     }
@@ -305,6 +334,7 @@ impl<'a> AnalyzeContext<'a> {
         units
             .get(&UnitKey::Secondary(name.clone(), name.clone()))
             .filter(|&unit| unit.kind() == AnyKind::Secondary(SecondaryKind::PackageBody))
+            .map(AsRef::as_ref)
     }
 
     pub fn has_package_body(&self) -> bool {
@@ -321,6 +351,8 @@ impl<'a> AnalyzeContext<'a> {
 
         // Add all referenced declaration arenas from other unit
         self.arena.link(&data.result().arena);
+        self.arena
+            .link_latest(&data.result().arena, unit.arena_id());
 
         // Change circular dependency reference when used by another unit during analysis
         // The error is changed from within the used unit into the position of the use of the unit
@@ -397,6 +429,10 @@ impl<'a> AnalyzeContext<'a> {
         primary_name: &Designator,
     ) -> AnalysisResult<DesignEnt<'a>> {
         if let Designator::Identifier(ref primary_name) = primary_name {
+            if library_name == &self.std_sym && primary_name == &self.env_sym {
+                self.check_standard(pos, VHDLStandard::VHDL2008, "Package 'std.env'")?;
+            }
+
             if let Some(unit) = self.get_primary_unit(library_name, primary_name) {
                 let data = self.get_analysis(Some(pos), unit)?;
                 if let AnyDesignUnit::Primary(primary) = data.deref() {
@@ -447,6 +447,20 @@ impl<'a> AnalyzeContext<'a> {
                 .zip(elems.iter_mut().map(|assoc| &mut assoc.actual))
             {
                 match &mut actual.item {
+                    ActualPart::Expression(Expression::Name(name)) if matches!(resolved_formal, Ok(formal) if formal.iface.is_out()) =>
+                    {
+                        // The actual of a formal of mode out is written and not read
+                        if let Ok(resolved_formal) = resolved_formal {
+                            self.expression_name_with_ttyp_and_access(
+                                scope,
+                                &actual.pos,
+                                name,
+                                resolved_formal.type_mark,
+                                false,
+                                diagnostics,
+                            )?;
+                        }
+                    }
                     ActualPart::Expression(expr) => {
                         if let Ok(resolved_formal) = resolved_formal {
                             // Error case is already checked in check_missing_and_duplicates
@@ -469,6 +483,20 @@ impl<'a> AnalyzeContext<'a> {
     }
 }
 
+/// The formals associated with the positional elements of an association list.
+/// As in the analysis a positional element is associated with the formal at the same index.
+pub(crate) fn positional_formals<'a, 'e>(
+    formal_region: &FormalRegion<'a>,
+    elems: &'e [AssociationElement],
+) -> Vec<(&'e WithPos<ActualPart>, InterfaceEnt<'a>)> {
+    elems
+        .iter()
+        .enumerate()
+        .take_while(|(_, elem)| elem.formal.is_none())
+        .filter_map(|(idx, elem)| Some((&elem.actual, formal_region.nth(idx)?)))
+        .collect()
+}
+
 fn to_formal_conversion_argument(
     parameters: &mut [AssociationElement],
 ) -> Option<(&SrcPos, &mut Box<Name>)> {
@@ -11,6 +11,7 @@ use super::*;
 use crate::ast::*;
 use crate::data::*;
 use crate::named_entity::*;
+use crate::standard::VHDLStandard;
 use analyze::*;
 use target::AssignmentType;
 
@@ -442,6 +443,15 @@ impl<'a> AnalyzeContext<'a> {
                             object_name.base.describe_class()
                         ),
                     )
+                } else if object_name.base.mode() == Some(Mode::Out) {
+                    diagnostics.push_result(self.check_standard(
+                        &name.pos,
+                        VHDLStandard::VHDL2008,
+                        &format!(
+                            "Reading {} in a sensitivity list",
+                            object_name.base.describe_class()
+                        ),
+                    ));
                 }
             }
         }
@@ -9,6 +9,7 @@ use super::*;
 use crate::ast::*;
 use crate::data::*;
 use crate::named_entity::{Signature, *};
+use crate::standard::VHDLStandard;
 use crate::{ast, named_entity, HasTokenSpan};
 use analyze::*;
 use fnv::FnvHashMap;
@@ -580,6 +581,12 @@ impl<'a> AnalyzeContext<'a> {
             }
 
             Declaration::Package(ref mut instance) => {
+                diagnostics.push_result(self.check_standard(
+                    &instance.ident.tree.pos,
+                    VHDLStandard::VHDL2008,
+                    "Package instantiation",
+                ));
+
                 let ent = self.arena.define(
                     &mut instance.ident,
                     parent,
@@ -1581,6 +1588,12 @@ impl<'a> AnalyzeContext<'a> {
                 )
             }
             InterfaceDeclaration::Type(ref mut ident) => {
+                diagnostics.push_result(self.check_standard(
+                    &ident.tree.pos,
+                    VHDLStandard::VHDL2008,
+                    "Generic type",
+                ));
+
                 let typ = TypeEnt::from_any(self.arena.define(
                     ident,
                     parent,
@@ -1604,6 +1617,12 @@ impl<'a> AnalyzeContext<'a> {
                 typ.into()
             }
             InterfaceDeclaration::Subprogram(ref mut subpgm, ..) => {
+                diagnostics.push_result(self.check_standard(
+                    subpgm.pos(),
+                    VHDLStandard::VHDL2008,
+                    "Generic subprogram",
+                ));
+
                 let (_, ent) = self.subprogram_specification(
                     scope,
                     parent,
@@ -1614,6 +1633,12 @@ impl<'a> AnalyzeContext<'a> {
                 ent.into()
             }
             InterfaceDeclaration::Package(ref mut instance) => {
+                diagnostics.push_result(self.check_standard(
+                    &instance.ident.tree.pos,
+                    VHDLStandard::VHDL2008,
+                    "Generic package",
+                ));
+
                 let package_region =
                     self.analyze_package_instance_name(scope, &mut instance.package_name)?;
 
@@ -8,6 +8,7 @@ use super::*;
 use crate::ast::*;
 use crate::data::*;
 use crate::named_entity::*;
+use crate::standard::VHDLStandard;
 use analyze::*;
 
 impl<'a> AnalyzeContext<'a> {
@@ -146,6 +147,11 @@ impl<'a> AnalyzeContext<'a> {
         let scope = root_scope.nested().in_package_declaration();
 
         if let Some(ref mut list) = unit.generic_clause {
+            diagnostics.push_result(self.check_standard(
+                &unit.ident.tree.pos,
+                VHDLStandard::VHDL2008,
+                "Generic package",
+            ));
             self.analyze_interface_list(&scope, ent, list, diagnostics)?;
         }
         self.analyze_declarative_part(&scope, ent, &mut unit.decl, diagnostics)?;
@@ -187,6 +193,11 @@ impl<'a> AnalyzeContext<'a> {
         self.add_implicit_context_clause(&root_scope)?;
 
         self.analyze_context_clause(&root_scope, &mut unit.context_clause, diagnostics)?;
+        diagnostics.push_result(self.check_standard(
+            &unit.ident.tree.pos,
+            VHDLStandard::VHDL2008,
+            "Package instantiation",
+        ));
 
         if let Some(pkg_region) =
             as_fatal(self.generic_package_instance(&root_scope, ent, unit, diagnostics))?
@@ -11,6 +11,7 @@ use parking_lot::{MappedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGu
 
 /// Combines an item to be analyzed (typically, a design unit) with the optional results
 /// of that analysis.
+#[derive(Clone)]
 struct AnalysisState<T, R> {
     /// Data gathered during analysis; `None` while not yet analyzed.
     result: Option<R>,
@@ -26,6 +27,15 @@ pub struct AnalysisLock<T, R> {
     state: RwLock<AnalysisState<T, R>>,
 }
 
+impl<T: Clone, R: Clone> Clone for AnalysisLock<T, R> {
+    /// Copies the data together with the current result of the analysis.
+    fn clone(&self) -> Self {
+        AnalysisLock {
+            state: RwLock::new(self.state.read().clone()),
+        }
+    }
+}
+
 impl<T, R> AnalysisLock<T, R> {
     pub fn new(data: T) -> AnalysisLock<T, R> {
         AnalysisLock {
@@ -15,6 +15,7 @@ use super::scope::*;
 use crate::ast::*;
 use crate::data::*;
 use crate::named_entity::*;
+use crate::standard::VHDLStandard;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ObjectBase<'a> {
@@ -1457,6 +1458,7 @@ impl<'a> AnalyzeContext<'a> {
     ) -> EvalResult<DisambiguatedType<'a>> {
         let resolved =
             self.name_resolve_with_suffixes(scope, expr_pos, name, None, false, diagnostics)?;
+        self.check_read_of_out_object(expr_pos, &resolved, diagnostics);
         match self.name_to_type(expr_pos, name.suffix_reference_mut(), resolved) {
             Ok(Some(typ)) => Ok(typ),
             Ok(None) => Err(EvalError::Unknown),
@@ -1475,6 +1477,20 @@ impl<'a> AnalyzeContext<'a> {
         name: &mut Name,
         ttyp: TypeEnt<'a>,
         diagnostics: &mut dyn DiagnosticHandler,
+    ) -> FatalResult {
+        self.expression_name_with_ttyp_and_access(scope, expr_pos, name, ttyp, true, diagnostics)
+    }
+
+    /// Analyze a name that must be unambiguous
+    /// When is_read is false the name is the actual of a formal that is written
+    pub fn expression_name_with_ttyp_and_access(
+        &self,
+        scope: &Scope<'a>,
+        expr_pos: &SrcPos,
+        name: &mut Name,
+        ttyp: TypeEnt<'a>,
+        is_read: bool,
+        diagnostics: &mut dyn DiagnosticHandler,
     ) -> FatalResult {
         if let Some(resolved) = as_fatal(self.name_resolve_with_suffixes(
             scope,
@@ -1484,6 +1500,10 @@ impl<'a> AnalyzeContext<'a> {
             false,
             diagnostics,
         ))? {
+            if is_read {
+                self.check_read_of_out_object(expr_pos, &resolved, diagnostics);
+            }
+
             // @TODO target_type already used above, functions could probably be simplified
             match self.name_to_unambiguous_type(
                 expr_pos,
@@ -1509,6 +1529,24 @@ impl<'a> AnalyzeContext<'a> {
         Ok(())
     }
 
+    /// Objects of mode out may only be read from VHDL-2008
+    fn check_read_of_out_object(
+        &self,
+        pos: &SrcPos,
+        resolved: &ResolvedName<'a>,
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) {
+        if let ResolvedName::ObjectName(object_name) = resolved {
+            if object_name.base.mode() == Some(Mode::Out) {
+                diagnostics.push_result(self.check_standard(
+                    pos,
+                    VHDLStandard::VHDL2008,
+                    &format!("Reading {}", object_name.base.describe_class()),
+                ));
+            }
+        }
+    }
+
     /// Analyze an indexed name where the prefix entity is already known
     /// Returns the type of the array element
     pub fn analyze_indexed_name(
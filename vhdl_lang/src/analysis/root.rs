@@ -11,18 +11,23 @@ use super::standard::UniversalTypes;
 use crate::named_entity::*;
 
 use crate::ast::search::*;
-use crate::ast::visitor::{walk, Visitor};
+use crate::ast::visitor::{walk, Visitor, VisitorResult};
 use crate::ast::*;
 use crate::data::*;
+use crate::dependency_graph::DependencyGraph;
+use crate::documentation::Documentation;
+use crate::standard::VHDLStandard;
 use crate::syntax::{Symbols, Token, TokenAccess};
 use fnv::{FnvHashMap, FnvHashSet};
 use parking_lot::RwLock;
 use std::collections::hash_map::Entry;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 /// A design unit with design unit data
+#[derive(Clone)]
 pub(crate) struct AnalysisData {
     pub diagnostics: Vec<Diagnostic>,
     pub has_circular_dependency: bool,
@@ -34,12 +39,14 @@ pub(super) type UnitWriteGuard<'a> = WriteGuard<'a, AnyDesignUnit, AnalysisData>
 
 /// Wraps the AST of a [design unit](../../ast/enum.AnyDesignUnit.html) in a thread-safe
 /// r/w-lock for analysis.
+#[derive(Clone)]
 pub(crate) struct LockedUnit {
     ident: Ident,
     arena_id: ArenaId,
     unit_id: UnitId,
     pub unit: AnalysisLock<AnyDesignUnit, AnalysisData>,
     pub tokens: Vec<Token>,
+    pub standard: VHDLStandard,
 }
 
 impl HasUnitId for LockedUnit {
@@ -49,7 +56,12 @@ impl HasUnitId for LockedUnit {
 }
 
 impl LockedUnit {
-    fn new(library_name: &Symbol, unit: AnyDesignUnit, tokens: Vec<Token>) -> LockedUnit {
+    fn new(
+        library_name: &Symbol,
+        unit: AnyDesignUnit,
+        tokens: Vec<Token>,
+        standard: VHDLStandard,
+    ) -> LockedUnit {
         let unit_id = match unit {
             AnyDesignUnit::Primary(ref unit) => {
                 UnitId::primary(library_name, PrimaryKind::kind_of(unit), unit.name())
@@ -68,8 +80,27 @@ impl LockedUnit {
             unit_id,
             unit: AnalysisLock::new(unit),
             tokens,
+            standard,
         }
     }
+
+    pub(super) fn arena_id(&self) -> ArenaId {
+        self.arena_id
+    }
+
+    /// True if the unit was parsed from exactly the same text as `other`
+    fn is_unchanged(&self, other: &LockedUnit) -> bool {
+        self.unit_id == other.unit_id
+            && self.standard == other.standard
+            && self.tokens == other.tokens
+    }
+
+    /// Hash of the named entities declared by the unit, `None` if the unit is not analyzed.
+    /// Users of the unit depend on nothing else of it, see `AnyEnt::hash_declaration`.
+    fn declarations_hash(&self) -> Option<u64> {
+        let data = self.unit.get()?;
+        data.result().arena.declarations_hash(self.arena_id)
+    }
 }
 
 impl HasIdent for LockedUnit {
@@ -81,6 +112,7 @@ impl HasIdent for LockedUnit {
 /// Represents a VHDL library containing zero or more design units.
 ///
 /// This struct also keeps track of which source file contained which design units.
+#[derive(Clone)]
 pub struct Library {
     name: Symbol,
 
@@ -89,17 +121,25 @@ pub struct Library {
     arena: FinalArena,
     id: EntityId,
 
-    units: FnvHashMap<UnitKey, LockedUnit>,
+    /// Units are shared with snapshots of the design and copied before they are changed,
+    /// see `DesignRoot::clone`
+    units: FnvHashMap<UnitKey, Arc<LockedUnit>>,
     units_by_source: FnvHashMap<Source, FnvHashSet<UnitId>>,
 
     /// Units removed since last analysis.
     removed: FnvHashSet<UnitId>,
     /// Units added since last analysis.
     added: FnvHashSet<UnitId>,
+    /// Units added since last analysis that replace a removed unit with a different text,
+    /// together with the declarations hash of the removed unit if it was analyzed.
+    /// These keep the arena id of the removed unit such that unchanged declarations keep their ids.
+    changed: FnvHashMap<UnitId, Option<u64>>,
+    /// Units removed since last analysis, kept to detect units that are added again unchanged.
+    previous: FnvHashMap<UnitKey, Arc<LockedUnit>>,
 
     /// Design units which were not added since they were duplicates.
     /// They need to be kept for later refresh which might make them not duplicates.
-    duplicates: Vec<(SrcPos, LockedUnit)>,
+    duplicates: Vec<(SrcPos, Arc<LockedUnit>)>,
 }
 
 impl Library {
@@ -122,6 +162,8 @@ impl Library {
             units_by_source: FnvHashMap::default(),
             added: FnvHashSet::default(),
             removed: FnvHashSet::default(),
+            changed: FnvHashMap::default(),
+            previous: FnvHashMap::default(),
             duplicates: Vec::new(),
         }
     }
@@ -130,7 +172,7 @@ impl Library {
         &self.name
     }
 
-    fn add_design_unit(&mut self, unit: LockedUnit) {
+    fn add_design_unit(&mut self, mut unit: Arc<LockedUnit>) {
         let unit_id = unit.unit_id().clone();
         match self.units.entry(unit.key().clone()) {
             Entry::Occupied(entry) => {
@@ -138,7 +180,25 @@ impl Library {
                     .push((entry.get().ident().pos.clone(), unit));
             }
             Entry::Vacant(entry) => {
-                self.added.insert(unit_id);
+                match self.previous.remove(unit.key()) {
+                    Some(previous) if unit.is_unchanged(&previous) => {
+                        // Keep the previous unit together with its analysis
+                        self.removed.remove(&unit_id);
+                        unit = previous;
+                    }
+                    Some(previous) => {
+                        Arc::make_mut(&mut unit).arena_id = previous.arena_id;
+                        // A unit changed again before being analyzed keeps the oldest hash,
+                        // which is the one its users were analyzed with
+                        self.changed
+                            .entry(unit_id.clone())
+                            .or_insert_with(|| previous.declarations_hash());
+                        self.added.insert(unit_id);
+                    }
+                    _ => {
+                        self.added.insert(unit_id);
+                    }
+                }
                 match self.units_by_source.entry(unit.source().clone()) {
                     Entry::Occupied(mut entry) => {
                         entry.get_mut().insert(unit.unit_id().clone());
@@ -156,7 +216,12 @@ impl Library {
 
     fn add_design_file(&mut self, design_file: DesignFile) {
         for (tokens, design_unit) in design_file.design_units {
-            self.add_design_unit(LockedUnit::new(self.name(), design_unit, tokens));
+            self.add_design_unit(Arc::new(LockedUnit::new(
+                self.name(),
+                design_unit,
+                tokens,
+                design_file.standard,
+            )));
         }
     }
 
@@ -198,15 +263,17 @@ impl Library {
     /// Remove all design units defined in source.
     /// This is used for incremental analysis where only a single source file is updated.
     fn remove_source(&mut self, source: &Source) {
-        let removed = &mut self.removed;
-        self.units.retain(|_, value| {
-            if value.source() != source {
-                true
-            } else {
-                removed.insert(value.unit_id().clone());
-                false
-            }
-        });
+        let removed_units: Vec<UnitKey> = self
+            .units
+            .iter()
+            .filter(|(_, value)| value.source() == source)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in removed_units {
+            let unit = self.units.remove(&key).unwrap();
+            self.removed.insert(unit.unit_id().clone());
+            self.previous.insert(key, unit);
+        }
         self.units_by_source.remove(source);
         self.duplicates
             .retain(|(_, value)| value.source() != source);
@@ -224,6 +291,16 @@ impl Library {
         }
     }
 
+    /// Copy the units that are not analyzed and still shared with a snapshot,
+    /// such that analyzing them leaves the snapshot as it is
+    fn unshare_unanalyzed_units(&mut self) {
+        for unit in self.units.values_mut() {
+            if !unit.unit.is_analyzed() {
+                Arc::make_mut(unit);
+            }
+        }
+    }
+
     /// Iterate over units in the order they appear in the file.
     /// Ensures diagnostics do not have to be sorted later.
     fn sorted_unit_ids(&self) -> Vec<UnitId> {
@@ -247,7 +324,7 @@ impl Library {
     }
 
     pub(crate) fn get_unit(&self, key: &UnitKey) -> Option<&LockedUnit> {
-        self.units.get(key)
+        self.units.get(key).map(AsRef::as_ref)
     }
 
     pub fn id(&self) -> EntityId {
@@ -256,7 +333,7 @@ impl Library {
 
     pub(crate) fn primary_units(&self) -> impl Iterator<Item = &LockedUnit> {
         self.units.iter().filter_map(|(key, value)| match key {
-            UnitKey::Primary(_) => Some(value),
+            UnitKey::Primary(_) => Some(value.as_ref()),
             UnitKey::Secondary(_, _) => None,
         })
     }
@@ -266,13 +343,20 @@ impl Library {
         primary: &'a Symbol,
     ) -> impl Iterator<Item = &'a LockedUnit> {
         self.units.iter().filter_map(move |(key, value)| match key {
-            UnitKey::Secondary(sym, _) if primary == sym => Some(value),
+            UnitKey::Secondary(sym, _) if primary == sym => Some(value.as_ref()),
             _ => None,
         })
     }
 
     pub(crate) fn primary_unit(&self, symbol: &Symbol) -> Option<&LockedUnit> {
-        self.units.get(&UnitKey::Primary(symbol.clone()))
+        self.units
+            .get(&UnitKey::Primary(symbol.clone()))
+            .map(AsRef::as_ref)
+    }
+
+    /// True if the design units of the source belong to this library
+    pub(crate) fn contains_source(&self, source: &Source) -> bool {
+        self.units_by_source.contains_key(source)
     }
 }
 
@@ -303,6 +387,36 @@ pub struct DesignRoot {
     // Tracks which units have a "use library.all;" clause.
     // library name  =>  set(affected)
     users_of_library_all: RwLock<FnvHashMap<Symbol, FnvHashSet<UnitId>>>,
+
+    // Changed units whose users are only re-analyzed if the declarations of the unit change.
+    // changed unit  =>  hash of the declarations the users were analyzed with
+    changed_declarations: FnvHashMap<UnitId, u64>,
+}
+
+impl Clone for DesignRoot {
+    /// Creates a snapshot of the design state.
+    ///
+    /// The design units together with their analysis results are shared with the snapshot
+    /// rather than copied. A unit is only copied when it is reset or analyzed again, which
+    /// gives it a new arena instead of changing the one of the snapshot. Only the dependencies
+    /// between the units are copied. The sources of the units are shared as well, so they
+    /// must be replaced rather than changed in place to keep the snapshot consistent.
+    fn clone(&self) -> Self {
+        DesignRoot {
+            symbols: self.symbols.clone(),
+            standard_pkg_id: self.standard_pkg_id,
+            standard_arena: self.standard_arena.clone(),
+            universal: self.universal,
+            standard_types: self.standard_types.clone(),
+            std_ulogic: self.std_ulogic,
+            libraries: self.libraries.clone(),
+            arenas: self.arenas.clone(),
+            users_of: RwLock::new(self.users_of.read().clone()),
+            missing_unit: RwLock::new(self.missing_unit.read().clone()),
+            users_of_library_all: RwLock::new(self.users_of_library_all.read().clone()),
+            changed_declarations: self.changed_declarations.clone(),
+        }
+    }
 }
 
 impl DesignRoot {
@@ -319,6 +433,7 @@ impl DesignRoot {
             users_of: RwLock::new(FnvHashMap::default()),
             missing_unit: RwLock::new(FnvHashMap::default()),
             users_of_library_all: RwLock::new(FnvHashMap::default()),
+            changed_declarations: FnvHashMap::default(),
         }
     }
 
@@ -341,7 +456,7 @@ impl DesignRoot {
     pub(super) fn get_library_units(
         &self,
         library_name: &Symbol,
-    ) -> Option<&FnvHashMap<UnitKey, LockedUnit>> {
+    ) -> Option<&FnvHashMap<UnitKey, Arc<LockedUnit>>> {
         self.libraries
             .get(library_name)
             .map(|library| &library.units)
@@ -360,6 +475,54 @@ impl DesignRoot {
         self.libraries.get(sym)
     }
 
+    /// Iterate over all design units of all libraries
+    pub(crate) fn units(&self) -> impl Iterator<Item = &LockedUnit> {
+        self.libraries
+            .values()
+            .flat_map(|library| library.units.values())
+            .map(AsRef::as_ref)
+    }
+
+    /// The revision of the standard that the design units of a source file were parsed with
+    pub(crate) fn standard_of_source(&self, source: &Source) -> VHDLStandard {
+        self.units()
+            .find(|unit| unit.source() == source)
+            .map(|unit| unit.standard)
+            .unwrap_or_default()
+    }
+
+    /// The graph of dependencies between design units found by the latest analysis
+    ///
+    /// It contains the same dependencies that are used to find the units to re-analyze
+    /// and to detect circular dependencies
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::new(self)
+    }
+
+    /// The documentation of the entities and packages found by the latest analysis
+    pub fn documentation(&self) -> Documentation {
+        Documentation::new(self)
+    }
+
+    /// The design units that each design unit uses as found by the latest analysis
+    pub(crate) fn unit_dependencies(&self) -> FnvHashMap<UnitId, FnvHashSet<UnitId>> {
+        let mut dependencies: FnvHashMap<UnitId, FnvHashSet<UnitId>> = FnvHashMap::default();
+        for (unit_id, users) in self.users_of.read().iter() {
+            if self.get_unit(unit_id).is_none() {
+                continue;
+            }
+            for user in users.iter() {
+                if self.get_unit(user).is_some() {
+                    dependencies
+                        .entry(user.clone())
+                        .or_default()
+                        .insert(unit_id.clone());
+                }
+            }
+        }
+        dependencies
+    }
+
     pub(crate) fn get_design_entity<'a>(
         &'a self,
         library_name: &Symbol,
@@ -439,18 +602,52 @@ impl DesignRoot {
         }
     }
 
+    /// The type of an object, element, alias, literal or the return type of a function
+    pub fn find_type_definition_of<'a>(&'a self, ent: EntRef<'a>) -> Option<EntRef<'a>> {
+        let type_mark = match ent.kind() {
+            AnyEntKind::Object(object) => object.subtype.type_mark(),
+            AnyEntKind::ObjectAlias { type_mark, .. }
+            | AnyEntKind::ExternalAlias { type_mark, .. }
+            | AnyEntKind::InterfaceFile(type_mark)
+            | AnyEntKind::Attribute(type_mark)
+            | AnyEntKind::PhysicalLiteral(type_mark) => *type_mark,
+            AnyEntKind::File(subtype)
+            | AnyEntKind::ElementDeclaration(subtype)
+            | AnyEntKind::DeferredConstant(subtype) => subtype.type_mark(),
+            AnyEntKind::LoopParameter(base_type) => (*base_type)?.into(),
+            AnyEntKind::Overloaded(overloaded) => overloaded.signature().return_type()?,
+            _ => return None,
+        };
+        Some(self.latest(type_mark.into()))
+    }
+
     pub fn find_implementation<'a>(&'a self, ent: EntRef<'a>) -> Vec<EntRef<'a>> {
         if let Designator::Identifier(ident) = ent.designator() {
             if let Some(library_name) = ent.library_name() {
                 match ent.kind() {
                     // Find entity with same name as component in the library
+                    // together with the entities bound to the component by configurations
                     AnyEntKind::Component(_) => {
+                        let mut result: Vec<EntRef<'a>> = Vec::new();
                         if let Some(design) = self.get_design_entity(library_name, ident) {
-                            return vec![design.into()];
+                            result.push(design.into());
                         }
+                        for design in self.find_bound_entities(library_name, ident) {
+                            if !result.iter().any(|other| other.id() == design.id()) {
+                                result.push(design.into());
+                            }
+                        }
+                        return result;
                     }
-                    // Find all components with same name as entity in the library
+                    // Find all architectures of the entity and all components with same name
+                    // as entity in the library
                     AnyEntKind::Design(Design::Entity(..)) => {
+                        let mut result = self.secondary_unit_ents(
+                            library_name,
+                            ident,
+                            SecondaryKind::Architecture,
+                        );
+
                         let mut searcher = FindAllEnt::new(self, |ent| {
                             matches!(ent.kind(), AnyEntKind::Component(_))
                                 && matches!(
@@ -460,13 +657,127 @@ impl DesignRoot {
                         });
 
                         let _ = self.search_library(library_name, &mut searcher);
-                        return searcher.result;
+                        result.append(&mut searcher.result);
+                        return result;
+                    }
+                    // Find the package body of the package
+                    AnyEntKind::Design(Design::Package(..) | Design::UninstPackage(..)) => {
+                        return self.secondary_unit_ents(
+                            library_name,
+                            ident,
+                            SecondaryKind::PackageBody,
+                        );
                     }
                     _ => {}
                 }
             }
         }
-        Vec::default()
+
+        // Find the body of a subprogram, protected type or deferred constant
+        match self.find_definition_of(ent) {
+            Some(definition) if definition.id() != ent.id() => vec![definition],
+            _ => Vec::default(),
+        }
+    }
+
+    /// The named entities of the secondary units of a primary unit sorted by position
+    fn secondary_unit_ents<'a>(
+        &'a self,
+        library_name: &Symbol,
+        primary: &Symbol,
+        kind: SecondaryKind,
+    ) -> Vec<EntRef<'a>> {
+        let Some(library) = self.libraries.get(library_name) else {
+            return Vec::default();
+        };
+
+        let mut result: Vec<EntRef<'a>> = library
+            .secondary_units(primary)
+            .filter(|unit| unit.secondary_kind() == Some(kind))
+            .filter_map(|unit| self.get_analysis(unit).ent_id())
+            .map(|id| self.get_ent(id))
+            .collect();
+        result.sort_by(|a, b| a.decl_pos().cmp(&b.decl_pos()));
+        result
+    }
+
+    /// The entities bound to a component in configuration declarations and configuration
+    /// specifications of the library of the component
+    fn find_bound_entities<'a>(
+        &'a self,
+        library_name: &Symbol,
+        component: &Symbol,
+    ) -> Vec<DesignEnt<'a>> {
+        let Some(library) = self.libraries.get(library_name) else {
+            return Vec::default();
+        };
+
+        let mut visitor = BindingVisitor {
+            component,
+            entity_aspects: Vec::new(),
+        };
+        for unit in library.units.values() {
+            self.walk(unit.unit_id(), &mut visitor);
+        }
+
+        visitor
+            .entity_aspects
+            .iter()
+            .filter_map(|aspect| self.bound_entity(library_name, aspect))
+            .collect()
+    }
+
+    /// The entity denoted by an entity aspect, either directly or through a configuration
+    fn bound_entity(&self, library_name: &Symbol, aspect: &EntityAspect) -> Option<DesignEnt<'_>> {
+        match aspect {
+            EntityAspect::Entity(name, _) => {
+                let (library_name, ident) = self.library_unit_name(library_name, &name.item)?;
+                self.get_design_entity(&library_name, &ident)
+            }
+            EntityAspect::Configuration(name) => {
+                let (library_name, ident) = self.library_unit_name(library_name, &name.item)?;
+                let unit = self
+                    .get_library_units(&library_name)?
+                    .get(&UnitKey::Primary(ident))?;
+                let data = self.get_analysis(unit);
+                if let AnyDesignUnit::Primary(AnyPrimaryUnit::Configuration(config)) = data.deref()
+                {
+                    let (library_name, ident) =
+                        self.library_unit_name(&library_name, &config.entity_name.item)?;
+                    self.get_design_entity(&library_name, &ident)
+                } else {
+                    None
+                }
+            }
+            EntityAspect::Open => None,
+        }
+    }
+
+    /// The library and the name of a design unit denoted by `lib.unit` or just `unit`
+    /// where `work` and a missing library refer to the library the name is written in
+    fn library_unit_name(
+        &self,
+        library_name: &Symbol,
+        name: &SelectedName,
+    ) -> Option<(Symbol, Symbol)> {
+        match name {
+            SelectedName::Designator(designator) => {
+                let ident = designator.item.as_identifier()?;
+                Some((library_name.clone(), ident.clone()))
+            }
+            SelectedName::Selected(prefix, suffix) => {
+                let SelectedName::Designator(ref prefix) = prefix.item else {
+                    return None;
+                };
+                let prefix = prefix.item.as_identifier()?;
+                let ident = suffix.item.item.as_identifier()?;
+                if *prefix == self.symbol_utf8("work") {
+                    Some((library_name.clone(), ident.clone()))
+                } else {
+                    Some((prefix.clone(), ident.clone()))
+                }
+            }
+        }
     }
 
     #[cfg(test)]
@@ -500,45 +811,49 @@ impl DesignRoot {
 
     pub fn public_symbols<'a>(&'a self) -> Box<dyn Iterator<Item = EntRef<'a>> + 'a> {
         Box::new(self.libraries.values().flat_map(|library| {
-            std::iter::once(self.arenas.get(library.id)).chain(library.units.values().flat_map(
-                |unit| -> Box<dyn Iterator<Item = EntRef<'a>>> {
-                    if matches!(unit.kind(), AnyKind::Primary(_)) {
-                        let data = self.get_analysis(unit);
-                        if let AnyDesignUnit::Primary(primary) = data.deref() {
-                            if let Some(id) = primary.ent_id() {
-                                let ent = self.arenas.get(id);
-                                return Box::new(std::iter::once(ent).chain(public_symbols(ent)));
-                            }
-                        }
-                    } else if matches!(unit.kind(), AnyKind::Secondary(SecondaryKind::Architecture))
-                    {
-                        let data = self.get_analysis(unit);
-                        if let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) =
-                            data.deref()
-                        {
-                            if let Some(id) = arch.ident.decl {
-                                let ent = self.arenas.get(id);
-                                return Box::new(std::iter::once(ent));
-                            }
-                        }
-                    } else if matches!(unit.kind(), AnyKind::Secondary(SecondaryKind::PackageBody))
-                    {
-                        let data = self.get_analysis(unit);
-                        if let AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(body)) =
-                            data.deref()
-                        {
-                            if let Some(id) = body.ident.decl {
-                                let ent = self.arenas.get(id);
-                                return Box::new(std::iter::once(ent));
-                            }
-                        }
-                    }
-                    Box::new(std::iter::empty())
-                },
-            ))
+            std::iter::once(self.arenas.get(library.id)).chain(
+                library
+                    .units
+                    .values()
+                    .flat_map(|unit| self.public_symbols_of_unit(unit)),
+            )
         }))
     }
 
+    /// The named entities of a design unit that are visible outside of it.
+    /// These are the unit itself and, for primary units, their declarations.
+    pub(crate) fn public_symbols_of_unit<'a>(
+        &'a self,
+        unit: &'a LockedUnit,
+    ) -> Box<dyn Iterator<Item = EntRef<'a>> + 'a> {
+        if matches!(unit.kind(), AnyKind::Primary(_)) {
+            let data = self.get_analysis(unit);
+            if let AnyDesignUnit::Primary(primary) = data.deref() {
+                if let Some(id) = primary.ent_id() {
+                    let ent = self.arenas.get(id);
+                    return Box::new(std::iter::once(ent).chain(public_symbols(ent)));
+                }
+            }
+        } else if matches!(unit.kind(), AnyKind::Secondary(SecondaryKind::Architecture)) {
+            let data = self.get_analysis(unit);
+            if let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = data.deref() {
+                if let Some(id) = arch.ident.decl {
+                    let ent = self.arenas.get(id);
+                    return Box::new(std::iter::once(ent));
+                }
+            }
+        } else if matches!(unit.kind(), AnyKind::Secondary(SecondaryKind::PackageBody)) {
+            let data = self.get_analysis(unit);
+            if let AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(body)) = data.deref() {
+                if let Some(id) = body.ident.decl {
+                    let ent = self.arenas.get(id);
+                    return Box::new(std::iter::once(ent));
+                }
+            }
+        }
+        Box::new(std::iter::empty())
+    }
+
     pub fn document_symbols<'a>(
         &'a self,
         library_name: &Symbol,
@@ -559,6 +874,38 @@ impl DesignRoot {
         EntHierarchy::from_vec(searcher.result)
     }
 
+    /// The standard type integer, once the standard package has been analyzed
+    pub(crate) fn integer_type(&self) -> Option<EntRef<'_>> {
+        let standard_types = self.standard_types.as_ref()?;
+        Some(self.get_ent(standard_types.integer))
+    }
+
+    /// The id of the package std.standard, once it has been analyzed
+    pub(crate) fn standard_package_id(&self) -> Option<EntityId> {
+        self.standard_pkg_id
+    }
+
+    /// The tokens of the design unit containing a position
+    pub(crate) fn tokens_at(&self, pos: &SrcPos) -> Option<&[Token]> {
+        for library in self.libraries.values() {
+            let Some(unit_ids) = library.units_by_source.get(pos.source()) else {
+                continue;
+            };
+            for unit_id in unit_ids {
+                let Some(unit) = library.units.get(unit_id.key()) else {
+                    continue;
+                };
+                let (Some(first), Some(last)) = (unit.tokens.first(), unit.tokens.last()) else {
+                    continue;
+                };
+                if first.pos.start() <= pos.start() && pos.end() <= last.pos.end() {
+                    return Some(&unit.tokens);
+                }
+            }
+        }
+        None
+    }
+
     pub fn find_all_unresolved(&self) -> (usize, Vec<SrcPos>) {
         let mut searcher = FindAllUnresolved::default();
         let _ = self.search(&mut searcher);
@@ -587,6 +934,23 @@ impl DesignRoot {
         }
     }
 
+    /// The number of design units that are shared with a copy of the design
+    #[cfg(test)]
+    pub(crate) fn units_shared_with(&self, other: &DesignRoot) -> usize {
+        self.libraries
+            .iter()
+            .filter_map(|(name, library)| Some((library, other.libraries.get(name)?)))
+            .flat_map(|(library, other)| {
+                library.units.iter().filter(|(key, unit)| {
+                    other
+                        .units
+                        .get(key)
+                        .is_some_and(|other| Arc::ptr_eq(unit, other))
+                })
+            })
+            .count()
+    }
+
     #[cfg(test)]
     pub fn find_standard_pkg(&self) -> &AnyEnt {
         self.find_std_package("standard")
@@ -696,11 +1060,12 @@ impl DesignRoot {
         unit_id: &UnitId,
         unit: &mut UnitWriteGuard,
         ctx: &dyn TokenAccess,
+        standard: VHDLStandard,
     ) {
         // All units reference the standard arena
         // @TODO keep the same ArenaId when re-using unit
         let arena = Arena::new(arena_id);
-        let context = AnalyzeContext::new(self, unit_id, &arena, ctx);
+        let context = AnalyzeContext::new(self, unit_id, &arena, ctx, standard);
 
         let mut diagnostics = Vec::new();
         let mut has_circular_dependency = false;
@@ -746,6 +1111,7 @@ impl DesignRoot {
                     locked_unit.unit_id(),
                     &mut unit,
                     &locked_unit.tokens,
+                    locked_unit.standard,
                 );
                 unit.downgrade()
             }
@@ -753,16 +1119,25 @@ impl DesignRoot {
         }
     }
 
-    pub(super) fn get_unit<'a>(&'a self, unit_id: &UnitId) -> Option<&'a LockedUnit> {
+    pub(crate) fn get_unit<'a>(&'a self, unit_id: &UnitId) -> Option<&'a LockedUnit> {
         self.libraries
             .get(unit_id.library_name())
             .and_then(|library| library.units.get(unit_id.key()))
+            .map(AsRef::as_ref)
+    }
+
+    /// The unit to be changed, it is copied first if it is shared with a snapshot
+    fn get_unit_mut(&mut self, unit_id: &UnitId) -> Option<&mut LockedUnit> {
+        self.libraries
+            .get_mut(unit_id.library_name())
+            .and_then(|library| library.units.get_mut(unit_id.key()))
+            .map(Arc::make_mut)
     }
 
-    fn reset_affected(&self, mut affected: FnvHashSet<UnitId>) {
+    fn reset_affected(&mut self, mut affected: FnvHashSet<UnitId>) {
         // Reset analysis state of all design units
         for unit_id in affected.drain() {
-            if let Some(unit) = self.get_unit(&unit_id) {
+            if let Some(unit) = self.get_unit_mut(&unit_id) {
                 unit.unit.reset();
 
                 // Ensure no remaining references from previous analysis
@@ -845,11 +1220,72 @@ impl DesignRoot {
         }
     }
 
+    /// Design units that are not analyzed
+    fn unanalyzed_units(&self) -> Vec<UnitId> {
+        let mut units = Vec::default();
+        for library in self.libraries.values() {
+            for unit in library.units.values() {
+                if !unit.unit.is_analyzed() {
+                    units.push(unit.unit_id().clone());
+                }
+            }
+        }
+        units
+    }
+
+    /// Resets the users of the changed units whose declarations differ from the declarations
+    /// that the users were analyzed with. The changed units must be analyzed.
+    ///
+    /// Users of a unit with the same declarations are not re-analyzed. The named entities they
+    /// refer to belong to the previous analysis of the unit, which is kept alive by their arenas
+    /// and is identical in everything but the address and position, see `AnyEnt::hash_declaration`.
+    /// Users with diagnostics in the source of the unit are re-analyzed to update their positions.
+    fn reset_users_of_changed(&mut self) {
+        let users_of = self.users_of.read();
+        let mut affected = FnvHashSet::default();
+        let mut spared = FnvHashSet::default();
+        let mut moved_sources = FnvHashSet::default();
+        for (unit_id, hash) in self.changed_declarations.iter() {
+            let unit = self.get_unit(unit_id).unwrap();
+            let Some(users) = users_of.get(unit_id) else {
+                continue;
+            };
+            if unit.declarations_hash() != Some(*hash) {
+                affected.extend(users.iter().cloned());
+            } else {
+                spared.extend(users.iter().cloned());
+                moved_sources.insert(unit.source().file_name().to_owned());
+            }
+        }
+
+        for unit_id in get_all_affected(&users_of, spared) {
+            let has_moved_diagnostics = self
+                .get_unit(&unit_id)
+                .and_then(|unit| unit.unit.get())
+                .is_some_and(|data| {
+                    data.result().diagnostics.iter().any(|diagnostic| {
+                        std::iter::once(&diagnostic.pos)
+                            .chain(diagnostic.related.iter().map(|(pos, _)| pos))
+                            .any(|pos| moved_sources.contains(pos.source().file_name()))
+                    })
+                });
+            if has_moved_diagnostics {
+                affected.insert(unit_id);
+            }
+        }
+
+        let affected = get_all_affected(&users_of, affected);
+        drop(users_of);
+        self.reset_affected(affected);
+        self.changed_declarations.clear();
+    }
+
     /// Resets the analysis state of all design units which need to be re-analyzed
     /// because another design unit has been added or removed.
     fn reset(&mut self) {
         let mut removed = FnvHashSet::default();
         let mut added = FnvHashSet::default();
+        let mut changed_declarations = FnvHashMap::default();
 
         for library in self.libraries.values_mut() {
             for unit_id in library.added.drain() {
@@ -858,10 +1294,27 @@ impl DesignRoot {
             for unit_id in library.removed.drain() {
                 removed.insert(unit_id);
             }
+            changed_declarations.extend(library.changed.drain());
+            library.previous.clear();
         }
 
         let mut affected: FnvHashSet<_> = added.union(&removed).cloned().collect();
         let changed: FnvHashSet<_> = removed.intersection(&added).cloned().collect();
+        // Changed units are re-analyzed first, their users are only affected
+        // if the declarations of the unit have changed, see `reset_users_of_changed`
+        for unit_id in changed.iter() {
+            if let Some(Some(hash)) = changed_declarations.remove(unit_id) {
+                self.changed_declarations
+                    .entry(unit_id.clone())
+                    .or_insert(hash);
+            }
+        }
+        let spared: FnvHashSet<_> = changed
+            .iter()
+            .filter(|unit_id| self.changed_declarations.contains_key(unit_id))
+            .cloned()
+            .collect();
+        affected.retain(|unit_id| !spared.contains(unit_id));
         removed = removed.difference(&changed).cloned().collect();
         added = added.difference(&changed).cloned().collect();
 
@@ -902,10 +1355,12 @@ impl DesignRoot {
             }
         }
 
-        self.reset_affected(get_all_affected(&users_of, affected));
+        let mut all_affected = get_all_affected(&users_of, affected);
+        all_affected.extend(spared);
         drop(users_of);
         drop(users_of_library_all);
         drop(missing_unit);
+        self.reset_affected(all_affected);
 
         let mut users_of = self.users_of.write();
         let mut users_of_library_all = self.users_of_library_all.write();
@@ -913,6 +1368,7 @@ impl DesignRoot {
 
         // Clean-up after removed units
         for removed_unit in removed.iter() {
+            self.changed_declarations.remove(removed_unit);
             users_of.remove(removed_unit);
             if let Some(library_all_affected) =
                 users_of_library_all.get_mut(removed_unit.library_name())
@@ -983,7 +1439,13 @@ impl DesignRoot {
             &mut std_package.decl,
         ));
 
-        let context = AnalyzeContext::new(self, locked_unit.unit_id(), &arena, &locked_unit.tokens);
+        let context = AnalyzeContext::new(
+            self,
+            locked_unit.unit_id(),
+            &arena,
+            &locked_unit.tokens,
+            locked_unit.standard,
+        );
 
         let mut diagnostics = Vec::new();
         let root_scope = Scope::default();
@@ -1083,18 +1545,25 @@ impl DesignRoot {
 
     // Returns the units that where re-analyzed
     pub fn analyze(&mut self, diagnostics: &mut dyn DiagnosticHandler) -> Vec<UnitId> {
-        self.reset();
+        self.analyze_cancellable(diagnostics, &AtomicBool::new(false))
+            .expect("Analysis was cancelled")
+    }
 
-        let mut units = Vec::default();
-        for library in self.libraries.values() {
-            for unit in library.units.values() {
-                if !unit.unit.is_analyzed() {
-                    units.push(unit.unit_id().clone());
-                }
-            }
-        }
+    /// Analyze all design units that are not yet analyzed unless `cancelled` is set.
+    ///
+    /// When cancelled, the remaining design units are skipped and `None` is returned.
+    /// Design units that were analyzed before the cancellation keep their results
+    /// such that the next analysis continues where this one stopped.
+    pub fn analyze_cancellable(
+        &mut self,
+        diagnostics: &mut dyn DiagnosticHandler,
+        cancelled: &AtomicBool,
+    ) -> Option<Vec<UnitId>> {
+        self.reset();
+        let mut units = self.unanalyzed_units();
 
         for library in self.libraries.values_mut() {
+            library.unshare_unanalyzed_units();
             library.refresh(diagnostics);
         }
 
@@ -1114,10 +1583,47 @@ impl DesignRoot {
 
         use rayon::prelude::*;
 
-        units.par_iter().for_each(|id| {
-            self.get_analysis(self.get_unit(id).unwrap());
+        let changed: Vec<_> = self.changed_declarations.keys().cloned().collect();
+        changed.par_iter().for_each(|id| {
+            if !cancelled.load(Ordering::Relaxed) {
+                self.get_analysis(self.get_unit(id).unwrap());
+            }
         });
 
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.reset_users_of_changed();
+
+        let remaining = self.unanalyzed_units();
+        remaining.par_iter().for_each(|id| {
+            if !cancelled.load(Ordering::Relaxed) {
+                self.get_analysis(self.get_unit(id).unwrap());
+            }
+        });
+
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut analyzed: FnvHashSet<_> = units.iter().cloned().collect();
+        for unit_id in remaining {
+            if analyzed.insert(unit_id.clone()) {
+                units.push(unit_id);
+            }
+        }
+
+        // Units that were not re-analyzed may reference older analyses of changed units
+        // with the same declarations, link the latest analysis of each unit first
+        for library in self.libraries.values() {
+            for unit in library.units.values() {
+                if let Some(result) = unit.unit.get() {
+                    self.arenas
+                        .link_latest(&result.result().arena, unit.arena_id);
+                }
+            }
+        }
+
         for library in self.libraries.values() {
             self.arenas.link(&library.arena);
             for unit in library.units.values() {
@@ -1135,7 +1641,7 @@ impl DesignRoot {
             }
         }
 
-        units
+        Some(units)
     }
 
     /// Get the named entity
@@ -1143,6 +1649,15 @@ impl DesignRoot {
         self.arenas.get(id)
     }
 
+    /// The named entity of the latest analysis of its design unit.
+    ///
+    /// Design units that were not re-analyzed after a unit they use changed without changing
+    /// its declarations refer to the named entities of the previous analysis of that unit.
+    /// These have the same id but may have another position than the latest ones.
+    pub fn latest<'a>(&'a self, ent: EntRef<'a>) -> EntRef<'a> {
+        self.arenas.try_get(ent.id()).unwrap_or(ent)
+    }
+
     /// Returns a reference to the symbols that were used to analyze and parse the design root.
     pub fn symbols(&self) -> &Symbols {
         self.symbols.as_ref()
@@ -1179,6 +1694,47 @@ pub struct EntHierarchy<'a> {
     pub children: Vec<EntHierarchy<'a>>,
 }
 
+/// Collects the entity aspects of the bindings of a component
+struct BindingVisitor<'a> {
+    component: &'a Symbol,
+    entity_aspects: Vec<EntityAspect>,
+}
+
+impl<'a> BindingVisitor<'a> {
+    fn add_binding(&mut self, spec: &ComponentSpecification, binding: Option<&BindingIndication>) {
+        let designator = match spec.component_name.item {
+            SelectedName::Designator(ref designator) => &designator.item,
+            SelectedName::Selected(_, ref designator) => &designator.item.item,
+        };
+        if designator.as_identifier() == Some(self.component) {
+            if let Some(entity_aspect) = binding.and_then(|binding| binding.entity_aspect.as_ref())
+            {
+                self.entity_aspects.push(entity_aspect.clone());
+            }
+        }
+    }
+}
+
+impl<'a> Visitor for BindingVisitor<'a> {
+    fn visit_configuration_specification(
+        &mut self,
+        node: &ConfigurationSpecification,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.add_binding(&node.spec, Some(&node.bind_ind));
+        VisitorResult::Continue
+    }
+
+    fn visit_component_configuration(
+        &mut self,
+        node: &ComponentConfiguration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.add_binding(&node.spec, node.bind_ind.as_ref());
+        VisitorResult::Continue
+    }
+}
+
 impl<'a> EntHierarchy<'a> {
     fn from_vec(mut symbols: Vec<EntRef<'a>>) -> Vec<EntHierarchy<'a>> {
         let mut by_parent: FnvHashMap<EntityId, Vec<EntRef>> = Default::default();
@@ -44,6 +44,7 @@ impl UniversalTypes {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct StandardTypes {
     pub boolean: EntityId,
     pub boolean_vector: EntityId,
@@ -313,10 +313,127 @@ end architecture;
         .search_reference(code.source(), code.sa("component ", "ent0").start())
         .unwrap();
 
-    assert_eq!(root.find_implementation(ent), vec![comp]);
+    let arch = root
+        .search_reference(code.source(), code.s1("a of ent0").start())
+        .unwrap();
+
+    assert_eq!(root.find_implementation(ent), vec![arch, comp]);
     assert_eq!(root.find_implementation(comp), vec![ent]);
 }
 
+#[test]
+fn find_implementation_of_component_bound_by_configuration() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+entity ent0 is
+end entity;
+
+architecture a of ent0 is
+begin
+end architecture;
+
+entity ent1 is
+end entity;
+
+architecture a of ent1 is
+begin
+end architecture;
+
+entity ent2 is
+end entity;
+
+architecture a of ent2 is
+begin
+end architecture;
+
+configuration cfg2 of ent2 is
+  for a
+  end for;
+end configuration;
+
+entity top is
+end entity;
+
+architecture a of top is
+  component ent0 is
+  end component;
+  for inst1 : ent0 use entity work.ent1;
+begin
+  inst0: ent0;
+  inst1: ent0;
+  inst2: ent0;
+end architecture;
+
+configuration cfg of top is
+  for a
+    for inst2 : ent0
+      use configuration work.cfg2;
+    end for;
+  end for;
+end configuration;
+      ",
+    );
+
+    let (root, diagnostics) = builder.get_analyzed_root();
+    check_no_diagnostics(&diagnostics);
+
+    let comp = root
+        .search_reference(code.source(), code.sa("component ", "ent0").start())
+        .unwrap();
+    let ent0 = root
+        .search_reference(code.source(), code.s1("ent0").start())
+        .unwrap();
+    let ent1 = root
+        .search_reference(code.source(), code.s1("ent1").start())
+        .unwrap();
+    let ent2 = root
+        .search_reference(code.source(), code.s1("ent2").start())
+        .unwrap();
+
+    assert_eq!(root.find_implementation(comp), vec![ent0, ent1, ent2]);
+}
+
+#[test]
+fn find_implementation_of_package_declarations() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  procedure proc;
+end package;
+
+package body pkg is
+  procedure proc is
+  begin
+  end procedure;
+end package body;
+      ",
+    );
+
+    let (root, diagnostics) = builder.get_analyzed_root();
+    check_no_diagnostics(&diagnostics);
+
+    let pkg = root
+        .search_reference(code.source(), code.s1("pkg").start())
+        .unwrap();
+    let body = root
+        .search_reference(code.source(), code.s("pkg", 2).start())
+        .unwrap();
+    let proc_decl = root
+        .search_reference(code.source(), code.s1("proc;").start())
+        .unwrap();
+    let proc_body = root
+        .search_reference(code.source(), code.s1("proc is").start())
+        .unwrap();
+
+    assert_eq!(root.find_implementation(pkg), vec![body]);
+    assert_eq!(root.find_implementation(proc_decl), vec![proc_body]);
+    assert!(root.find_implementation(proc_body).is_empty());
+}
+
 #[test]
 fn exit_and_next_outside_of_loop() {
     let mut builder = LibraryBuilder::new();
@@ -7,7 +7,7 @@
 use super::*;
 use crate::analysis::DesignRoot;
 use crate::ast::search::*;
-use crate::ast::Reference;
+use crate::ast::{AnyKind, HasUnitId, Reference, SecondaryKind};
 use crate::data::SrcPos;
 use crate::named_entity::EntityId;
 use crate::syntax::TokenAccess;
@@ -259,6 +259,362 @@ end package body;
     check_incremental_analysis(builder, vec![]);
 }
 
+#[test]
+fn change_of_package_body_does_not_reanalyze_users_of_package() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  constant const : natural := 0;
+end package;
+
+package body pkg is
+end package body;
+",
+    );
+
+    builder.code(
+        "libname",
+        "
+use work.pkg.const;
+
+package pkg2 is
+  constant const2 : natural := const;
+end package;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+package pkg is
+  constant const : natural := 0;
+end package;
+
+package body pkg is
+  constant const3 : natural := const;
+end package body;
+",
+    );
+
+    check_incremental_update(builder, &code, &changed, &["pkg body"]);
+}
+
+#[test]
+fn private_change_of_package_does_not_reanalyze_users() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  type typ_t is range 0 to 1;
+  constant const : typ_t := 0;
+end package;
+",
+    );
+
+    let user = builder.code(
+        "libname",
+        "
+use work.pkg.all;
+
+package pkg2 is
+  constant const2 : typ_t := const;
+end package;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+package pkg is
+  type typ_t is range 0 to 1;
+  constant const : typ_t := 1; -- The value
+end package;
+",
+    );
+
+    let root = check_incremental_update(builder, &code, &changed, &["pkg"]);
+    assert_eq!(
+        type_mark_pos(&root, &user, "const2"),
+        changed.s1("typ_t").pos()
+    );
+}
+
+#[test]
+fn moved_package_does_not_reanalyze_users() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  type typ_t is range 0 to 1;
+end package;
+",
+    );
+
+    let user = builder.code(
+        "libname",
+        "
+use work.pkg.typ_t;
+
+package pkg2 is
+  constant const2 : typ_t := 0;
+end package;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+-- A comment before the package
+
+package pkg is
+  -- The type
+  type typ_t is range 0 to 1;
+end package;
+",
+    );
+
+    // References from pkg2 must refer to the new position of typ_t
+    let root = check_incremental_update(builder, &code, &changed, &["pkg"]);
+    assert_eq!(
+        type_mark_pos(&root, &user, "const2"),
+        changed.s1("typ_t").pos()
+    );
+}
+
+#[test]
+fn moved_package_reanalyzes_users_with_related_diagnostics() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  constant const : integer := 0;
+end package;
+",
+    );
+
+    let body = builder.code(
+        "libname",
+        "
+package body pkg is
+  constant const : integer := 0;
+end package body;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+-- A comment before the package
+
+package pkg is
+  constant const : integer := 0;
+end package;
+",
+    );
+
+    let symbols = builder.symbols();
+    let codes = builder.take_code();
+    let library_name = codes[0].0.clone();
+
+    let mut fresh_root = DesignRoot::new(symbols.clone());
+    add_standard_library(symbols.clone(), &mut fresh_root);
+    fresh_root.add_design_file(library_name.clone(), changed.design_file());
+    fresh_root.add_design_file(library_name.clone(), body.design_file());
+
+    let mut root = DesignRoot::new(symbols.clone());
+    add_standard_library(symbols, &mut root);
+    root.add_design_file(library_name.clone(), code.design_file());
+    root.add_design_file(library_name.clone(), body.design_file());
+
+    let mut diagnostics = Vec::new();
+    root.analyze(&mut diagnostics);
+    check_diagnostics(diagnostics, vec![duplicate_in_body(&code, &body)]);
+
+    root.remove_source(library_name.clone(), code.source());
+    root.add_design_file(library_name, changed.design_file());
+
+    // The diagnostic of the body must refer to the new position of const
+    let diagnostics = check_analysis_equal(&mut root, &mut fresh_root);
+    check_diagnostics(diagnostics, vec![duplicate_in_body(&changed, &body)]);
+}
+
+fn duplicate_in_body(code: &Code, body: &Code) -> Diagnostic {
+    Diagnostic::error(body.s("const", 2), "Duplicate declaration of 'const'")
+        .related(code.s("const", 2), "Previously defined here")
+}
+
+/// Position of the type mark of the object declared as `name` in `code`
+fn type_mark_pos(root: &DesignRoot, code: &Code, name: &str) -> SrcPos {
+    let ent = root
+        .search_reference(code.source(), code.s1(name).start())
+        .unwrap();
+    root.find_type_definition_of(ent)
+        .unwrap()
+        .decl_pos()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn changed_package_interface_reanalyzes_users() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  constant const : natural := 0;
+end package;
+",
+    );
+
+    builder.code(
+        "libname",
+        "
+use work.pkg.const;
+
+package pkg2 is
+  constant const2 : natural := const;
+end package;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+package pkg is
+  constant const0 : natural := 0;
+  constant const : natural := const0;
+end package;
+",
+    );
+
+    check_incremental_update(builder, &code, &changed, &["pkg", "pkg2"]);
+}
+
+#[test]
+fn change_within_process_only_reanalyzes_architecture() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+entity ent is
+  port (clk : in bit);
+end entity;
+
+architecture a of ent is
+begin
+  process
+    variable v : natural;
+  begin
+    v := 0;
+    wait;
+  end process;
+end architecture;
+",
+    );
+
+    builder.code(
+        "libname",
+        "
+entity top is
+end entity;
+
+architecture a of top is
+  signal clk : bit;
+begin
+  inst: entity work.ent port map (clk => clk);
+end architecture;
+",
+    );
+
+    let changed = builder.changed_code(
+        &code,
+        "
+entity ent is
+  port (clk : in bit);
+end entity;
+
+architecture a of ent is
+begin
+  process
+    variable v : natural;
+  begin
+    v := 1;
+    v := v + 1;
+    wait until clk = '1';
+  end process;
+end architecture;
+",
+    );
+
+    check_incremental_update(builder, &code, &changed, &["ent(a)"]);
+}
+
+/// Replace the file of `code` with `changed` and check that only the expected design units are
+/// re-analyzed and that the result is the same as when analyzing from scratch
+fn check_incremental_update(
+    builder: LibraryBuilder,
+    code: &Code,
+    changed: &Code,
+    expected_reanalyzed: &[&str],
+) -> DesignRoot {
+    let symbols = builder.symbols();
+    let codes = builder.take_code();
+
+    let mut fresh_root = DesignRoot::new(symbols.clone());
+    add_standard_library(symbols.clone(), &mut fresh_root);
+
+    let mut root = DesignRoot::new(symbols.clone());
+    add_standard_library(symbols, &mut root);
+
+    let mut changed_library_name = None;
+    for (library_name, other) in codes.iter() {
+        root.add_design_file(library_name.clone(), other.design_file());
+
+        if other.source() == code.source() {
+            changed_library_name = Some(library_name.clone());
+            fresh_root.add_design_file(library_name.clone(), changed.design_file());
+        } else {
+            fresh_root.add_design_file(library_name.clone(), other.design_file());
+        }
+    }
+    let library_name = changed_library_name.unwrap();
+
+    let mut diagnostics = Vec::new();
+    root.analyze(&mut diagnostics);
+    check_no_diagnostics(&diagnostics);
+
+    root.remove_source(library_name.clone(), code.source());
+    root.add_design_file(library_name, changed.design_file());
+
+    let mut diagnostics = Vec::new();
+    let mut reanalyzed: Vec<String> = root
+        .analyze(&mut diagnostics)
+        .iter()
+        .map(|unit_id| match unit_id.kind() {
+            AnyKind::Secondary(SecondaryKind::PackageBody) => {
+                format!("{} body", unit_id.primary_name())
+            }
+            AnyKind::Secondary(_) => format!(
+                "{}({})",
+                unit_id.primary_name(),
+                unit_id.secondary_name().unwrap()
+            ),
+            AnyKind::Primary(_) => unit_id.primary_name().to_string(),
+        })
+        .collect();
+    reanalyzed.sort();
+    assert_eq!(reanalyzed, expected_reanalyzed);
+
+    check_no_diagnostics(&check_analysis_equal(&mut root, &mut fresh_root));
+    root
+}
+
 fn check_incremental_analysis(builder: LibraryBuilder, expected_diagnostics: Vec<Diagnostic>) {
     let symbols = builder.symbols();
     let codes = builder.take_code();
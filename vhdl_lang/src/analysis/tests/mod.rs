@@ -27,6 +27,7 @@ mod subprogram_instance;
 mod tool_directive;
 mod typecheck_expression;
 mod util;
+mod vhdl_standard;
 mod visibility;
 
 use std::cell::RefCell;
@@ -36,6 +37,7 @@ use crate::ast::Designator;
 use crate::ast::UnitId;
 pub use crate::data::Diagnostic;
 use crate::data::NoDiagnostics;
+use crate::standard::VHDLStandard;
 pub use crate::syntax::test::*;
 use crate::syntax::Token;
 
@@ -77,6 +79,7 @@ impl<'a> TestSetup<'a> {
             ),
             &self.arena,
             &self.tokens,
+            VHDLStandard::default(),
         );
         ctx.add_implicit_context_clause(&self.scope).unwrap();
         ctx
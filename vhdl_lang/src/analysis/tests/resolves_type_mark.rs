@@ -482,3 +482,43 @@ pub fn kind_error(
     Diagnostic::error(code.s(name, occ), format!("Expected {expected}, got {got}"))
         .related(code.s(name, occ_decl), "Defined here")
 }
+
+#[test]
+fn find_type_definition_of_objects_elements_and_functions() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+package pkg is
+  type word_t is range 0 to 255;
+  type rec_t is record
+    elem : word_t;
+  end record;
+
+  constant const : rec_t := (elem => 0);
+  alias const_alias is const;
+  function fun return rec_t;
+  procedure proc;
+end package;",
+    );
+
+    let (root, diagnostics) = builder.get_analyzed_root();
+    check_no_diagnostics(&diagnostics);
+
+    let type_definition_pos = |code: Code| {
+        let ent = root.search_reference(code.source(), code.start()).unwrap();
+        root.find_type_definition_of(ent)
+            .and_then(|ent| ent.decl_pos().cloned())
+    };
+
+    let rec_pos = Some(code.s1("rec_t").pos());
+    assert_eq!(type_definition_pos(code.s1("const ")), rec_pos);
+    assert_eq!(type_definition_pos(code.s1("const_alias")), rec_pos);
+    assert_eq!(type_definition_pos(code.s1("fun return")), rec_pos);
+    assert_eq!(
+        type_definition_pos(code.s1("elem")),
+        Some(code.s1("word_t").pos())
+    );
+    assert_eq!(type_definition_pos(code.s1("proc;")), None);
+    assert_eq!(type_definition_pos(code.s1("rec_t")), None);
+}
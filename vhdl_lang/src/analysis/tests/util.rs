@@ -6,6 +6,7 @@
 
 use crate::analysis::DesignRoot;
 use crate::data::*;
+use crate::standard::VHDLStandard;
 use crate::syntax::test::*;
 use crate::syntax::Symbols;
 use pretty_assertions::assert_eq;
@@ -25,6 +26,13 @@ impl LibraryBuilder {
         }
     }
 
+    pub fn with_standard(standard: VHDLStandard) -> LibraryBuilder {
+        LibraryBuilder {
+            code_builder: CodeBuilder::with_standard(standard),
+            libraries: HashMap::default(),
+        }
+    }
+
     fn add_code(&mut self, library_name: &str, code: Code) {
         let library_name = self.code_builder.symbol(library_name);
         match self.libraries.entry(library_name) {
@@ -43,11 +51,25 @@ impl LibraryBuilder {
         code
     }
 
+    pub fn code_with_file_name(&mut self, library_name: &str, file_name: &str, code: &str) -> Code {
+        let code = self
+            .code_builder
+            .code_with_file_name(Path::new(file_name), code);
+        self.add_code(library_name, code.clone());
+        code
+    }
+
     /// Just get a Code object using the same symbol table but without adding it to any library
     pub fn snippet(&mut self, code: &str) -> Code {
         self.code_builder.code(code)
     }
 
+    /// Get a Code object with new contents for the file of `code` without adding it to any library
+    pub fn changed_code(&mut self, code: &Code, contents: &str) -> Code {
+        self.code_builder
+            .code_with_file_name(code.source().file_name(), contents)
+    }
+
     pub fn in_declarative_region(&mut self, code: &str) -> Code {
         self.code(
             "libname",
@@ -140,6 +162,7 @@ fn std_logic_1164_package() -> Source {
 pub fn add_standard_library(symbols: Arc<Symbols>, root: &mut DesignRoot) {
     let builder = CodeBuilder {
         symbols: symbols.clone(),
+        standard: VHDLStandard::VHDL2008,
     };
     let std_standard = builder.code_from_source(standard_package());
     let std_textio = builder.code_from_source(textio_package());
@@ -0,0 +1,270 @@
+//! This Source Code Form is subject to the terms of the Mozilla Public
+//! License, v. 2.0. If a copy of the MPL was not distributed with this file,
+//! You can obtain one at http://mozilla.org/MPL/2.0/.
+//!
+//! Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+use super::*;
+use crate::standard::VHDLStandard;
+
+#[test]
+fn std_env_requires_vhdl_2008() {
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL1993);
+    let code = builder.code(
+        "libname",
+        "
+use std.env.all;
+
+entity ent is
+end entity;
+        ",
+    );
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![Diagnostic::error(
+            code.s1("std.env").s1("env"),
+            "Package 'std.env' was introduced in VHDL-2008 but the design unit is analyzed as VHDL-1993",
+        )],
+    );
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2008);
+    builder.code(
+        "libname",
+        "
+use std.env.all;
+
+entity ent is
+end entity;
+        ",
+    );
+    check_no_diagnostics(&builder.analyze());
+}
+
+#[test]
+fn reading_out_port_requires_vhdl_2008() {
+    let contents = "
+entity ent is
+  port (
+    o : out bit;
+    o2 : out bit
+  );
+end entity;
+
+architecture a of ent is
+  signal s : bit;
+begin
+  s <= o;
+  o2 <= '1';
+end architecture;
+
+entity top is
+  port (
+    o : out bit
+  );
+end entity;
+
+architecture a of top is
+begin
+  inst: entity work.ent port map (o => o, o2 => open);
+end architecture;
+        ";
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2002);
+    let code = builder.code("libname", contents);
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![Diagnostic::error(
+            code.s1("s <= o").s1("o"),
+            "Reading interface signal 'o' of mode out was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+        )],
+    );
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2008);
+    builder.code("libname", contents);
+    check_no_diagnostics(&builder.analyze());
+}
+
+#[test]
+fn out_port_as_actual_of_inout_formal_requires_vhdl_2008() {
+    let contents = "
+entity ent is
+  port (
+    io : inout bit
+  );
+end entity;
+
+architecture a of ent is
+begin
+end architecture;
+
+entity top is
+  port (
+    o : out bit
+  );
+end entity;
+
+architecture a of top is
+begin
+  inst: entity work.ent port map (io => o);
+end architecture;
+        ";
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2002);
+    let code = builder.code("libname", contents);
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![Diagnostic::error(
+            code.s1("io => o").s("o", 2),
+            "Reading interface signal 'o' of mode out was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+        )],
+    );
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2008);
+    builder.code("libname", contents);
+    check_no_diagnostics(&builder.analyze());
+}
+
+#[test]
+fn out_port_in_sensitivity_list_requires_vhdl_2008() {
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL1993);
+    let code = builder.code(
+        "libname",
+        "
+entity ent is
+  port (
+    o : out bit
+  );
+end entity;
+
+architecture a of ent is
+begin
+  process (o)
+  begin
+  end process;
+end architecture;
+        ",
+    );
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![Diagnostic::error(
+            code.s1("process (o)").s1("(o)").s1("o"),
+            "Reading interface signal 'o' of mode out in a sensitivity list was introduced in VHDL-2008 but the design unit is analyzed as VHDL-1993",
+        )],
+    );
+}
+
+#[test]
+fn generic_types_and_packages_require_vhdl_2008() {
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2002);
+    let code = builder.code(
+        "libname",
+        "
+package gpkg is
+  generic (
+    type data_t;
+    function to_string(value : data_t) return string
+  );
+end package;
+
+entity ent is
+  generic (
+    package p is new work.gpkg generic map (<>)
+  );
+end entity;
+        ",
+    );
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![
+            Diagnostic::error(
+                code.s1("gpkg"),
+                "Generic package was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+            Diagnostic::error(
+                code.s1("data_t"),
+                "Generic type was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+            Diagnostic::error(
+                code.s1("to_string"),
+                "Generic subprogram was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+            Diagnostic::error(
+                code.s1("package p").s("p", 2),
+                "Generic package was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn package_instantiations_require_vhdl_2008() {
+    let contents = "
+package gpkg is
+  generic (
+    constant width : natural
+  );
+end package;
+
+package inst is new work.gpkg generic map (width => 8);
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  package local_inst is new work.gpkg generic map (width => 4);
+begin
+end architecture;
+        ";
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2002);
+    let code = builder.code("libname", contents);
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![
+            Diagnostic::error(
+                code.s1("gpkg"),
+                "Generic package was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+            Diagnostic::error(
+                code.s1("inst"),
+                "Package instantiation was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+            Diagnostic::error(
+                code.s1("local_inst"),
+                "Package instantiation was introduced in VHDL-2008 but the design unit is analyzed as VHDL-2002",
+            ),
+        ],
+    );
+
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2008);
+    builder.code("libname", contents);
+    check_no_diagnostics(&builder.analyze());
+}
+
+#[test]
+fn later_keywords_are_identifiers_in_vhdl_1993() {
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL1993);
+    builder.code(
+        "libname",
+        "
+entity ent is
+  port (
+    context : in bit;
+    force : out bit
+  );
+end entity;
+
+architecture a of ent is
+begin
+  force <= context;
+end architecture;
+        ",
+    );
+    check_no_diagnostics(&builder.analyze());
+}
@@ -25,6 +25,7 @@ pub(crate) use any_design_unit::*;
 
 use crate::data::*;
 use crate::named_entity::EntityId;
+use crate::standard::VHDLStandard;
 use crate::syntax::{Token, TokenAccess, TokenId};
 
 /// LRM 15.8 Bit string literals
@@ -1455,4 +1456,6 @@ pub enum AnyDesignUnit {
 #[derive(PartialEq, Debug, Clone, Default)]
 pub struct DesignFile {
     pub design_units: Vec<(Vec<Token>, AnyDesignUnit)>,
+    /// The revision of the VHDL standard the file was parsed with
+    pub standard: VHDLStandard,
 }
@@ -110,6 +110,27 @@ pub trait Searcher {
     fn search_with_pos(&mut self, _ctx: &dyn TokenAccess, _pos: &SrcPos) -> SearchState {
         NotFinished
     }
+
+    /// Search an instantiation statement together with its label
+    fn search_instance(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        _label: &WithDecl<Option<Ident>>,
+        _instance: &InstantiationStatement,
+    ) -> SearchState {
+        NotFinished
+    }
+
+    /// Search a process statement together with its label and the position of the whole statement
+    fn search_process(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        _label: &WithDecl<Option<Ident>>,
+        _statement_pos: &SrcPos,
+    ) -> SearchState {
+        NotFinished
+    }
+
     fn search_source(&mut self, _ctx: &dyn TokenAccess, _source: &Source) -> SearchState {
         NotFinished
     }
@@ -502,6 +523,7 @@ impl Search for LabeledConcurrentStatement {
                 return_if_found!(block.statements.search(ctx, searcher));
             }
             ConcurrentStatement::Process(ref mut process) => {
+                return_if_finished!(searcher.search_process(ctx, &self.label, &self.statement.pos));
                 let ProcessStatement {
                     postponed: _,
                     sensitivity_list,
@@ -536,6 +558,7 @@ impl Search for LabeledConcurrentStatement {
                 return_if_found!(search_selection(&mut gen.sels, false, searcher, ctx));
             }
             ConcurrentStatement::Instance(ref mut inst) => {
+                return_if_finished!(searcher.search_instance(ctx, &self.label, inst));
                 return_if_found!(inst.search(ctx, searcher));
             }
             ConcurrentStatement::Assignment(ref mut assign) => {
@@ -1600,7 +1623,7 @@ fn is_implicit_of(ent: EntRef, id: EntityId) -> bool {
     }
 }
 
-fn is_reference(ent: EntRef, other: EntRef) -> bool {
+pub(crate) fn is_reference(ent: EntRef, other: EntRef) -> bool {
     if ent.id() == other.id() {
         return true;
     }
@@ -1657,7 +1680,7 @@ impl<'a> Searcher for FindAllReferences<'a> {
 }
 
 impl<'a> FoundDeclaration<'a> {
-    fn end_ident_pos(&self) -> Option<&SrcPos> {
+    pub(crate) fn end_ident_pos(&self) -> Option<&SrcPos> {
         match self {
             FoundDeclaration::InterfaceObject(_) => None,
             FoundDeclaration::ForIndex(..) => None,
@@ -220,6 +220,26 @@ impl HasIdent for AnyDesignUnit {
     }
 }
 
+impl AnyDesignUnit {
+    /// The context clause preceding the design unit
+    /// or the context items of a context declaration
+    pub fn context_clause(&self) -> &ContextClause {
+        match self {
+            AnyDesignUnit::Primary(unit) => match unit {
+                AnyPrimaryUnit::Entity(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Configuration(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Package(unit) => &unit.context_clause,
+                AnyPrimaryUnit::PackageInstance(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Context(unit) => &unit.items,
+            },
+            AnyDesignUnit::Secondary(unit) => match unit {
+                AnySecondaryUnit::Architecture(unit) => &unit.context_clause,
+                AnySecondaryUnit::PackageBody(unit) => &unit.context_clause,
+            },
+        }
+    }
+}
+
 impl<'a, T: HasIdent> From<&'a T> for WithPos<Designator> {
     fn from(other: &'a T) -> WithPos<Designator> {
         other.ident().to_owned().map_into(Designator::Identifier)
@@ -830,6 +830,32 @@ pub fn walk(node: &dyn ASTNode, visitor: &mut dyn Visitor, ctx: &dyn TokenAccess
     }
 }
 
+/// The position of the item that is visited next.
+///
+/// The position of a [`WithPos`] is visited right before its item, so visitors that need
+/// the position of an item such as an expression set it in [`Visitor::visit_item_with_pos`]
+/// and take it when visiting the item.
+#[derive(Default)]
+pub(crate) struct NextItemPos {
+    /// The address of the item together with its position
+    next: Option<(usize, SrcPos)>,
+}
+
+impl NextItemPos {
+    pub fn set(&mut self, pos: &SrcPos, node: &dyn ASTNode) {
+        self.next = Some((
+            node as *const dyn ASTNode as *const () as usize,
+            pos.clone(),
+        ));
+    }
+
+    /// The position of the item, `None` if the item is not the item of a [`WithPos`]
+    pub fn take<T>(&mut self, node: &T) -> Option<SrcPos> {
+        let (address, pos) = self.next.take()?;
+        (address == node as *const T as *const () as usize).then_some(pos)
+    }
+}
+
 pub fn walk_design_file(node: &DesignFile, visitor: &mut dyn Visitor) {
     visitor.visit_design_file(node);
     for (tokens, unit) in &node.design_units {
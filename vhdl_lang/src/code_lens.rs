@@ -0,0 +1,382 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Code lenses summarizing the uses of declarations
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{
+    is_reference, FoundDeclaration, NotFinished, NotFound, SearchState, Searcher,
+};
+use crate::ast::*;
+use crate::data::{HasSrcPos, Source, SrcPos};
+use crate::named_entity::{HasEntityId, Related};
+use crate::syntax::TokenAccess;
+use crate::{AnyEntKind, Design, EntRef, EntityId};
+use fnv::FnvHashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CodeLensKind {
+    /// The references to a subprogram, type or signal
+    References,
+    /// The instantiations of an entity
+    Instantiations,
+    /// The architectures of an entity
+    Architectures,
+}
+
+/// A summary of the uses of a declaration shown above it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CodeLens {
+    pub kind: CodeLensKind,
+    /// The designator of the declaration
+    pub pos: SrcPos,
+}
+
+/// The uses summarized by a code lens
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ResolvedCodeLens {
+    /// Such as `3 references`
+    pub title: String,
+    /// The uses sorted by position
+    pub locations: Vec<SrcPos>,
+}
+
+impl DesignRoot {
+    /// The code lenses of the declarations of a source file sorted by position.
+    ///
+    /// Finding the lenses is cheap, the uses they summarize are only found when they are resolved.
+    pub fn code_lenses(&self, source: &Source) -> Vec<CodeLens> {
+        let mut searcher = CodeLensSearcher {
+            root: self,
+            source: source.clone(),
+            lenses: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+        let mut lenses = searcher.lenses;
+        lenses.sort_by(|a, b| a.pos.cmp(&b.pos));
+        lenses
+    }
+
+    /// The uses summarized by a code lens.
+    /// Returns `None` when there is no longer a declaration at the lens.
+    pub fn resolve_code_lens(&self, lens: &CodeLens) -> Option<ResolvedCodeLens> {
+        let ent = self.search_reference(&lens.pos.source, lens.pos.start())?;
+        let (title, mut locations) = match lens.kind {
+            CodeLensKind::References => {
+                let locations = self.find_uses(ent);
+                (count(locations.len(), "reference", "references"), locations)
+            }
+            CodeLensKind::Instantiations => {
+                let locations = self.find_instantiations(ent);
+                (
+                    format!("instantiated {}", count(locations.len(), "time", "times")),
+                    locations,
+                )
+            }
+            CodeLensKind::Architectures => {
+                let architectures: Vec<EntRef> = self
+                    .find_implementation(ent)
+                    .into_iter()
+                    .filter(|ent| {
+                        matches!(ent.kind(), AnyEntKind::Design(Design::Architecture(..)))
+                    })
+                    .collect();
+                let title = if architectures.is_empty() {
+                    "no architectures".to_owned()
+                } else {
+                    let names: Vec<String> = architectures
+                        .iter()
+                        .map(|ent| ent.designator().to_string())
+                        .collect();
+                    format!("architectures: {}", names.join(", "))
+                };
+                let locations = architectures
+                    .into_iter()
+                    .filter_map(|ent| ent.decl_pos().cloned())
+                    .collect();
+                (title, locations)
+            }
+        };
+        locations.sort();
+        Some(ResolvedCodeLens { title, locations })
+    }
+
+    /// The positions that refer to a named entity, excluding its declarations
+    pub(crate) fn find_uses(&self, ent: EntRef) -> Vec<SrcPos> {
+        let mut searcher = UseSearcher {
+            root: self,
+            ent,
+            uses: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+        searcher.uses
+    }
+
+    /// The positions of the unit names of the instantiation statements of an entity.
+    /// Instantiations of components bound to the entity are included.
+    pub fn find_instantiations(&self, ent: EntRef) -> Vec<SrcPos> {
+        let mut searcher = InstanceSearcher {
+            instances: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+
+        // Components are resolved after the search since it locks the design units
+        let mut components: FnvHashMap<EntityId, bool> = FnvHashMap::default();
+        searcher
+            .instances
+            .into_iter()
+            .filter(|(_, id)| match self.get_ent(*id).kind() {
+                AnyEntKind::Component(_) => *components.entry(*id).or_insert_with(|| {
+                    self.find_implementation(self.get_ent(*id))
+                        .iter()
+                        .any(|bound| bound.id() == ent.id())
+                }),
+                _ => *id == ent.id(),
+            })
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+}
+
+fn count(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{count} {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+struct CodeLensSearcher<'a> {
+    root: &'a DesignRoot,
+    source: Source,
+    lenses: Vec<CodeLens>,
+}
+
+impl<'a> CodeLensSearcher<'a> {
+    fn push(&mut self, kind: CodeLensKind, pos: &SrcPos) {
+        self.lenses.push(CodeLens {
+            kind,
+            pos: pos.clone(),
+        });
+    }
+}
+
+impl<'a> Searcher for CodeLensSearcher<'a> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let Some(id) = decl.ent_id() else {
+            return NotFinished;
+        };
+        // Bodies of subprograms and protected types are summarized at their declaration
+        if matches!(self.root.get_ent(id).related, Related::DeclaredBy(_)) {
+            return NotFinished;
+        }
+        match decl {
+            FoundDeclaration::Entity(..) => {
+                self.push(CodeLensKind::Instantiations, decl.pos());
+                self.push(CodeLensKind::Architectures, decl.pos());
+            }
+            FoundDeclaration::Type(..)
+            | FoundDeclaration::SubprogramDecl(..)
+            | FoundDeclaration::Subprogram(..) => self.push(CodeLensKind::References, decl.pos()),
+            FoundDeclaration::Object(ref object) if object.class == ObjectClass::Signal => {
+                self.push(CodeLensKind::References, decl.pos())
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+
+    fn search_source(&mut self, _ctx: &dyn TokenAccess, source: &Source) -> SearchState {
+        if source == &self.source {
+            NotFinished
+        } else {
+            SearchState::Finished(NotFound)
+        }
+    }
+}
+
+struct UseSearcher<'a> {
+    root: &'a DesignRoot,
+    ent: EntRef<'a>,
+    uses: Vec<SrcPos>,
+}
+
+impl<'a> Searcher for UseSearcher<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &mut Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.as_ref() {
+            if is_reference(self.ent, self.root.get_ent(*id)) {
+                self.uses.push(pos.clone());
+            }
+        }
+        NotFinished
+    }
+}
+
+/// Collects the unit names of instantiation statements with the entity or component they refer to
+struct InstanceSearcher {
+    instances: Vec<(SrcPos, EntityId)>,
+}
+
+impl Searcher for InstanceSearcher {
+    fn search_instance(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        _label: &WithDecl<Option<Ident>>,
+        instance: &InstantiationStatement,
+    ) -> SearchState {
+        if let InstantiatedUnit::Entity(ref name, _) | InstantiatedUnit::Component(ref name) =
+            instance.unit
+        {
+            if let Some(id) = name.item.reference() {
+                self.instances.push((name.pos.clone(), id));
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    #[test]
+    fn lenses_of_entities_subprograms_types_and_signals() {
+        let mut builder = LibraryBuilder::new();
+        let pkg = builder.code(
+            "libname",
+            "
+package pkg is
+  type state_t is (idle, busy);
+  function advance(state : state_t) return state_t;
+  constant c : state_t := idle;
+end package;
+
+package body pkg is
+  function advance(state : state_t) return state_t is
+  begin
+    return busy;
+  end function;
+end package body;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        assert_eq!(
+            root.code_lenses(pkg.source()),
+            vec![
+                CodeLens {
+                    kind: CodeLensKind::References,
+                    pos: pkg.s1("state_t").pos(),
+                },
+                CodeLens {
+                    kind: CodeLensKind::References,
+                    pos: pkg.s1("advance").pos(),
+                },
+            ]
+        );
+
+        let resolved = |lens: &CodeLens| root.resolve_code_lens(lens).unwrap();
+        let lenses = root.code_lenses(pkg.source());
+        assert_eq!(
+            resolved(&lenses[0]),
+            ResolvedCodeLens {
+                title: "5 references".to_owned(),
+                locations: vec![
+                    pkg.s("state_t", 2).pos(),
+                    pkg.s("state_t", 3).pos(),
+                    pkg.s("state_t", 4).pos(),
+                    pkg.s("state_t", 5).pos(),
+                    pkg.s("state_t", 6).pos(),
+                ],
+            }
+        );
+        assert_eq!(resolved(&lenses[1]).title, "0 references");
+    }
+
+    #[test]
+    fn lenses_of_entity_instantiations_and_architectures() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity child is
+end entity;
+
+architecture rtl of child is
+  signal s : bit;
+begin
+  s <= '1';
+end architecture;
+
+architecture sim of child is
+begin
+end architecture;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+  component child is
+  end component;
+begin
+  inst0 : entity work.child;
+  inst1 : component child;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let child = code.s1("child").pos();
+        let lenses = root.code_lenses(code.source());
+        assert_eq!(
+            lenses
+                .iter()
+                .map(|lens| (lens.kind, lens.pos.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (CodeLensKind::Instantiations, child.clone()),
+                (CodeLensKind::Architectures, child.clone()),
+                (CodeLensKind::References, code.s1("s :").s1("s").pos()),
+                (CodeLensKind::Instantiations, code.s1("top").pos()),
+                (CodeLensKind::Architectures, code.s1("top").pos()),
+            ]
+        );
+
+        assert_eq!(
+            root.resolve_code_lens(&lenses[0]).unwrap(),
+            ResolvedCodeLens {
+                title: "instantiated 2 times".to_owned(),
+                locations: vec![
+                    code.s1("work.child").pos(),
+                    code.s1("component child;").s1("child").pos(),
+                ],
+            }
+        );
+        assert_eq!(
+            root.resolve_code_lens(&lenses[1]).unwrap(),
+            ResolvedCodeLens {
+                title: "architectures: rtl, sim".to_owned(),
+                locations: vec![code.s1("rtl").pos(), code.s1("sim").pos()],
+            }
+        );
+        assert_eq!(
+            root.resolve_code_lens(&lenses[2]).unwrap().title,
+            "1 reference"
+        );
+        assert_eq!(
+            root.resolve_code_lens(&lenses[3]).unwrap().title,
+            "instantiated 0 times"
+        );
+    }
+}
@@ -1,15 +1,15 @@
 use crate::analysis::DesignRoot;
 use crate::ast::visitor::{Visitor, VisitorResult};
 use crate::ast::{
-    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, Designator, InstantiationStatement, MapAspect,
-    ObjectClass, PackageInstantiation, Reference,
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, AttributeDesignator, Designator,
+    InstantiationStatement, MapAspect, ObjectClass, PackageInstantiation, Reference,
 };
 use crate::data::{ContentReader, Symbol};
 use crate::named_entity::AsUnique;
 use crate::named_entity::{self, HasEntityId, NamedEntities, Region};
 use crate::syntax::Kind::*;
 use crate::syntax::{Kind, Symbols, Token, TokenAccess, Tokenizer, Value};
-use crate::{AnyEntKind, Design, EntRef, EntityId, Overloaded, Position, Source};
+use crate::{AnyEntKind, Design, EntRef, EntityId, Overloaded, Position, Source, VHDLStandard};
 use std::collections::HashSet;
 use std::default::Default;
 use std::iter::once;
@@ -27,6 +27,13 @@ pub enum CompletionItem<'a> {
     Overloaded(Designator, usize),
     /// Complete a keyword
     Keyword(Kind),
+    /// A predefined attribute, e.g., `event` after `clk'`
+    Attribute(AttributeDesignator),
+    /// A template of a design unit, declaration or statement
+    Snippet(Snippet),
+    /// A name declared in a package that is not visible,
+    /// e.g., `to_unsigned` together with `use ieee.numeric_std.all`
+    AutoImport(EntRef<'a>, ImportEdit),
 }
 
 macro_rules! kind {
@@ -45,6 +52,14 @@ macro_rules! ident {
     };
 }
 
+mod auto_import;
+mod expression;
+mod regions;
+mod snippets;
+
+pub use auto_import::ImportEdit;
+pub use snippets::{default_snippets, list_snippets, Snippet, SnippetContext};
+
 #[derive(Eq, PartialEq, Debug)]
 enum MapAspectKind {
     Port,
@@ -262,9 +277,14 @@ impl<'a> Visitor for AutocompletionVisitor<'a> {
 /// `tokenize_input(input)` -> {USE, ieee, DOT, std_logic_1164, DOT, a}
 ///
 /// On error, or if the source is empty, returns an empty vector.
-fn tokenize_input(symbols: &Symbols, source: &Source, cursor: Position) -> Vec<Token> {
+fn tokenize_input(
+    symbols: &Symbols,
+    standard: VHDLStandard,
+    source: &Source,
+    cursor: Position,
+) -> Vec<Token> {
     let contents = source.contents();
-    let mut tokenizer = Tokenizer::new(symbols, source, ContentReader::new(&contents));
+    let mut tokenizer = Tokenizer::new(symbols, standard, source, ContentReader::new(&contents));
     let mut tokens = Vec::new();
     loop {
         match tokenizer.pop() {
@@ -323,19 +343,7 @@ fn list_available_declarations<'a>(
             };
             let ent = root.get_ent(pkg_id);
             match &ent.kind {
-                AnyEntKind::Design(Design::Package(_, region)) => region
-                    .entities
-                    .values()
-                    .map(|named_ent| match named_ent {
-                        NamedEntities::Single(ent) => CompletionItem::Simple(ent),
-                        NamedEntities::Overloaded(overloaded) => match overloaded.as_unique() {
-                            None => CompletionItem::Overloaded(
-                                overloaded.designator().clone(),
-                                overloaded.len(),
-                            ),
-                            Some(ent_ref) => CompletionItem::Simple(ent_ref),
-                        },
-                    })
+                AnyEntKind::Design(Design::Package(_, region)) => list_region(region)
                     .chain(once(CompletionItem::Keyword(All)))
                     .collect(),
                 _ => Vec::default(),
@@ -345,14 +353,31 @@ fn list_available_declarations<'a>(
     }
 }
 
+/// Lists the declarations of a region where overloaded declarations are listed once
+fn list_region<'a, 'r>(region: &'r Region<'a>) -> impl Iterator<Item = CompletionItem<'a>> + 'r {
+    region.entities.values().map(|named_ent| match named_ent {
+        NamedEntities::Single(ent) => CompletionItem::Simple(ent),
+        NamedEntities::Overloaded(overloaded) => match overloaded.as_unique() {
+            None => CompletionItem::Overloaded(overloaded.designator().clone(), overloaded.len()),
+            Some(ent_ref) => CompletionItem::Simple(ent_ref),
+        },
+    })
+}
+
 /// Main entry point for completion. Given a source-file and a cursor position,
 /// lists available completion options at the cursor position.
+/// Inside expressions the options of the expected type are listed first.
 pub fn list_completion_options<'a>(
     root: &'a DesignRoot,
     source: &Source,
     cursor: Position,
 ) -> Vec<CompletionItem<'a>> {
-    let tokens = tokenize_input(root.symbols(), source, cursor);
+    let tokens = tokenize_input(
+        root.symbols(),
+        root.standard_of_source(source),
+        source,
+        cursor,
+    );
     match &tokens[..] {
         [.., kind!(Library)] | [.., kind!(Use)] | [.., kind!(Use), kind!(Identifier)] => {
             list_all_libraries(root)
@@ -366,9 +391,18 @@ pub fn list_completion_options<'a>(
             list_available_declarations(root, library, selected)
         }
         _ => {
-            let mut visitor = AutocompletionVisitor::new(root, cursor, tokens);
-            root.walk_source(source, &mut visitor);
-            visitor.completions
+            let imports = auto_import::list_auto_imports(root, source, cursor, &tokens);
+            let mut completions =
+                match expression::list_expression_completions(root, source, cursor, &tokens) {
+                    Some(completions) => completions,
+                    None => {
+                        let mut visitor = AutocompletionVisitor::new(root, cursor, tokens);
+                        root.walk_source(source, &mut visitor);
+                        visitor.completions
+                    }
+                };
+            completions.extend(imports);
+            completions
         }
     }
 }
@@ -377,14 +411,21 @@ pub fn list_completion_options<'a>(
 mod test {
     use super::*;
     use crate::analysis::tests::LibraryBuilder;
+    use crate::ast::{RangeAttribute, SignalAttribute};
     use crate::completion::tokenize_input;
+    use crate::kind_str;
     use crate::syntax::test::Code;
     use assert_matches::assert_matches;
 
     #[test]
     fn tokenizing_an_empty_input() {
         let input = Code::new("");
-        let tokens = tokenize_input(&input.symbols, input.source(), Position::new(0, 0));
+        let tokens = tokenize_input(
+            &input.symbols,
+            VHDLStandard::default(),
+            input.source(),
+            Position::new(0, 0),
+        );
         assert_eq!(tokens.len(), 0);
     }
 
@@ -392,19 +433,34 @@ mod test {
     fn tokenizing_stops_at_the_cursors_position() {
         let input = Code::new("use ieee.std_logic_1164.all");
         let mut cursor = input.s1("std_logic_11").pos().end();
-        let tokens = tokenize_input(&input.symbols, input.source(), cursor);
+        let tokens = tokenize_input(
+            &input.symbols,
+            VHDLStandard::default(),
+            input.source(),
+            cursor,
+        );
         assert_matches!(
             tokens[..],
             [kind!(Use), kind!(Identifier), kind!(Dot), kind!(Identifier)]
         );
         cursor = input.s1("std_logic_1164").pos().end();
-        let tokens = tokenize_input(&input.symbols, input.source(), cursor);
+        let tokens = tokenize_input(
+            &input.symbols,
+            VHDLStandard::default(),
+            input.source(),
+            cursor,
+        );
         assert_matches!(
             tokens[..],
             [kind!(Use), kind!(Identifier), kind!(Dot), kind!(Identifier)]
         );
         cursor = input.s1("std_logic_1164.").pos().end();
-        let tokens = tokenize_input(&input.symbols, input.source(), cursor);
+        let tokens = tokenize_input(
+            &input.symbols,
+            VHDLStandard::default(),
+            input.source(),
+            cursor,
+        );
         assert_matches!(
             tokens[..],
             [
@@ -416,7 +472,12 @@ mod test {
             ]
         );
         cursor = input.s1("std_logic_1164.all").pos().end();
-        let tokens = tokenize_input(&input.symbols, input.source(), cursor);
+        let tokens = tokenize_input(
+            &input.symbols,
+            VHDLStandard::default(),
+            input.source(),
+            cursor,
+        );
         assert_matches!(
             tokens[..],
             [
@@ -484,6 +545,31 @@ mod test {
         assert_eq!(options.len(), 4);
     }
 
+    #[test]
+    pub fn completing_with_the_standard_of_the_file() {
+        // 'force' is only reserved from VHDL-2008
+        let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL1993);
+        let code = builder.code(
+            "libname",
+            "
+package force is
+  constant c : natural := 0;
+end package;
+
+use libname.force.all;
+entity ent is
+end entity;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let cursor = code.s1("use libname.force.").end();
+        let options = list_completion_options(&root, code.source(), cursor);
+        assert!(options.contains(&CompletionItem::Simple(
+            root.search_reference(code.source(), code.s1("c :").start())
+                .unwrap()
+        )));
+    }
+
     #[test]
     pub fn completing_instantiation_statement() {
         let mut input = LibraryBuilder::new();
@@ -592,4 +678,174 @@ mod test {
         assert!(options.contains(&CompletionItem::Formal(t)));
         assert_eq!(options.len(), 3);
     }
+
+    fn labels(options: &[CompletionItem]) -> Vec<String> {
+        options
+            .iter()
+            .map(|option| match option {
+                CompletionItem::Simple(ent)
+                | CompletionItem::Formal(ent)
+                | CompletionItem::AutoImport(ent, _) => ent.designator().to_string(),
+                CompletionItem::Overloaded(designator, _) => designator.to_string(),
+                CompletionItem::Keyword(kind) => kind_str(*kind).to_owned(),
+                CompletionItem::Attribute(attribute) => attribute.to_string(),
+                CompletionItem::Snippet(snippet) => snippet.name.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn complete_values_of_the_target_type_first() {
+        let mut input = LibraryBuilder::new();
+        let code = input.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  type state_t is (idle, busy);
+  signal state : state_t;
+  signal next_state : state_t;
+  signal count : natural;
+  function first_state return state_t is
+  begin
+    return idle;
+  end function;
+begin
+  main : process
+    variable v : state_t;
+  begin
+    state <= next_state;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, _) = input.get_analyzed_root();
+        let cursor = code.s1("state <= ").end();
+        let options = labels(&list_completion_options(&root, code.source(), cursor));
+        assert_eq!(
+            options[..6],
+            ["busy", "first_state", "idle", "next_state", "state", "v"]
+        );
+        assert!(options[6..].contains(&"count".to_owned()));
+        assert!(!options.contains(&"ent".to_owned()));
+        assert!(!options.contains(&"=".to_owned()));
+    }
+
+    #[test]
+    pub fn complete_record_elements_and_package_declarations() {
+        let mut input = LibraryBuilder::new();
+        let code = input.code(
+            "libname",
+            "\
+package pkg is
+  type rec_t is record
+    valid : bit;
+    data : bit_vector(7 downto 0);
+  end record;
+  constant empty : rec_t := ('0', x\"00\");
+end package;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal rec : rec_t;
+  signal valid : bit;
+begin
+  valid <= rec.valid;
+  rec <= work.pkg.empty;
+end architecture;",
+        );
+        let (root, _) = input.get_analyzed_root();
+        let cursor = code.s1("rec.").end();
+        let options = labels(&list_completion_options(&root, code.source(), cursor));
+        assert_eq!(options, ["valid", "data"]);
+
+        let cursor = code.s1("pkg.empty").s1(".").end();
+        let mut options = labels(&list_completion_options(&root, code.source(), cursor));
+        options.sort();
+        assert_eq!(options, ["\"/=\"", "\"=\"", "empty", "rec_t"]);
+    }
+
+    #[test]
+    pub fn complete_predefined_and_user_defined_attributes() {
+        let mut input = LibraryBuilder::new();
+        let code = input.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal data : bit_vector(7 downto 0);
+  attribute max_delay : time;
+  attribute max_delay of clk : signal is 1 ns;
+begin
+  process (clk)
+  begin
+    if clk'event and data'length = 8 and bit'pos('1') = 1 then
+    end if;
+  end process;
+end architecture;",
+        );
+        let (root, _) = input.get_analyzed_root();
+        let options = list_completion_options(&root, code.source(), code.s1("clk'").end());
+        assert!(
+            options.contains(&CompletionItem::Attribute(AttributeDesignator::Signal(
+                SignalAttribute::Event
+            )))
+        );
+        assert!(!options.contains(&CompletionItem::Attribute(AttributeDesignator::Length)));
+        assert_eq!(labels(&options).last().unwrap(), "max_delay");
+
+        let options = list_completion_options(&root, code.source(), code.s1("data'").end());
+        assert!(options.contains(&CompletionItem::Attribute(AttributeDesignator::Length)));
+        assert!(
+            options.contains(&CompletionItem::Attribute(AttributeDesignator::Range(
+                RangeAttribute::Range
+            )))
+        );
+
+        let options = list_completion_options(&root, code.source(), code.s1("bit'").end());
+        assert!(options.contains(&CompletionItem::Attribute(AttributeDesignator::Pos)));
+        assert!(
+            !options.contains(&CompletionItem::Attribute(AttributeDesignator::Signal(
+                SignalAttribute::Event
+            )))
+        );
+    }
+
+    #[test]
+    pub fn complete_remaining_case_choices() {
+        let mut input = LibraryBuilder::new();
+        let code = input.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  type state_t is (idle, busy, done);
+  signal state : state_t;
+begin
+  process
+  begin
+    case state is
+      when idle => null;
+      when busy => null;
+    end case;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, _) = input.get_analyzed_root();
+        let cursor = code.s1("when busy").s1("busy").start();
+        let options = labels(&list_completion_options(&root, code.source(), cursor));
+        assert_eq!(options, ["busy", "done", "others"]);
+    }
 }
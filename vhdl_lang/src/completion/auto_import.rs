@@ -0,0 +1,306 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Completion of names declared in packages that are not visible,
+//! together with the context clause that makes them visible
+
+use super::expression::visible_at_cursor;
+use super::regions::scan_regions;
+use super::CompletionItem;
+use crate::analysis::DesignRoot;
+use crate::ast::Designator;
+use crate::data::{Position, Source, Symbol};
+use crate::syntax::Kind::{self, *};
+use crate::syntax::{Token, Value};
+use crate::{AnyEntKind, Design, EntRef, EntityId};
+use std::collections::HashSet;
+
+/// The library and use clauses to insert into the context clause of the current design unit
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImportEdit {
+    /// Where the text is inserted
+    pub position: Position,
+    /// The clauses, each on a line of its own
+    pub text: String,
+}
+
+/// The library and use clauses before the design unit being edited
+#[derive(Default)]
+struct ContextClause {
+    libraries: Vec<Symbol>,
+    /// Every name of a use clause
+    used: Vec<Symbol>,
+    /// The end of the last context item
+    end: Option<Position>,
+}
+
+impl ContextClause {
+    fn from_tokens(tokens: &[Token]) -> ContextClause {
+        let mut clause = ContextClause::default();
+        let mut item: Option<Kind> = None;
+        for token in tokens {
+            match &token.kind {
+                Library | Use | Context => item = Some(token.kind),
+                SemiColon => {
+                    item = None;
+                    clause.end = Some(token.pos.end());
+                }
+                Identifier => {
+                    if let Value::Identifier(name) = &token.value {
+                        match item {
+                            Some(Library) => clause.libraries.push(name.clone()),
+                            Some(Use) => clause.used.push(name.clone()),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        clause
+    }
+}
+
+/// Names declared in packages which are not visible at the cursor and that start with the
+/// identifier being typed. Each name comes with the clauses that make it visible.
+pub(super) fn list_auto_imports<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    tokens: &[Token],
+) -> Vec<CompletionItem<'a>> {
+    let (prefix, tokens) = match tokens {
+        [.., kind!(Dot | Tick | Library | Use | Context), kind!(Identifier)] => return Vec::new(),
+        [rest @ .., last @ ident!(name)] if last.pos.end() >= cursor => {
+            let typed = if last.pos.start().line == cursor.line {
+                cursor.character.saturating_sub(last.pos.start().character) as usize
+            } else {
+                0
+            };
+            let prefix: String = name.name_utf8().chars().take(typed).collect();
+            (prefix.to_lowercase(), rest)
+        }
+        _ => return Vec::new(),
+    };
+
+    // Names can only be imported from within a design unit
+    let regions = scan_regions(tokens);
+    let Some(unit_keyword) = regions.unit_keyword() else {
+        return Vec::new();
+    };
+    let context_clause = ContextClause::from_tokens(&tokens[regions.unit_start..unit_keyword]);
+    let position = match context_clause.end {
+        Some(end) => Position::new(end.line + 1, 0),
+        None => Position::new(tokens[unit_keyword].pos.start().line, 0),
+    };
+    let work = root
+        .libraries()
+        .find(|library| library.contains_source(source))
+        .map(|library| library.name().clone());
+
+    let standard = root.standard_package_id();
+    let visible: HashSet<EntityId> = visible_at_cursor(root, source, cursor)
+        .into_iter()
+        .map(|ent| ent.id())
+        .collect();
+    let mut seen: HashSet<(EntityId, &Designator)> = HashSet::new();
+
+    let mut candidates: Vec<(EntRef<'a>, EntRef<'a>)> = root
+        .public_symbols()
+        .filter_map(|ent| {
+            let package = ent.parent?;
+            if !matches!(package.kind(), AnyEntKind::Design(Design::Package(..))) {
+                return None;
+            }
+            Some((ent, package))
+        })
+        .filter(|(ent, package)| {
+            let Designator::Identifier(name) = ent.designator() else {
+                return false;
+            };
+            Some(package.id()) != standard
+                && !visible.contains(&ent.id())
+                && name.name_utf8().to_lowercase().starts_with(&prefix)
+                && !context_clause
+                    .used
+                    .contains(package.designator().as_identifier().unwrap())
+                && package.decl_pos().is_none_or(|pos| &pos.source != source)
+        })
+        .filter(|(ent, package)| seen.insert((package.id(), ent.designator())))
+        .collect();
+    candidates.sort_by_key(|(ent, package)| {
+        (
+            ent.designator().to_string().to_lowercase(),
+            package.library_name().map(|name| name.name_utf8()),
+            package.designator().to_string(),
+        )
+    });
+
+    candidates
+        .into_iter()
+        .filter_map(|(ent, package)| {
+            let library = package.library_name()?;
+            let mut text = String::new();
+            let library = if Some(library) == work.as_ref() {
+                "work".to_owned()
+            } else {
+                if library.name_utf8() != "std" && !context_clause.libraries.contains(library) {
+                    text.push_str(&format!("library {library};\n"));
+                }
+                library.name_utf8()
+            };
+            text.push_str(&format!("use {library}.{}.all;\n", package.designator()));
+            Some(CompletionItem::AutoImport(
+                ent,
+                ImportEdit { position, text },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::completion::{list_completion_options, tokenize_input};
+    use crate::syntax::test::Code;
+
+    fn imports(root: &DesignRoot, code: &Code, cursor: Position) -> Vec<(String, String)> {
+        let tokens = tokenize_input(
+            root.symbols(),
+            root.standard_of_source(code.source()),
+            code.source(),
+            cursor,
+        );
+        list_auto_imports(root, code.source(), cursor, &tokens)
+            .into_iter()
+            .map(|item| match item {
+                CompletionItem::AutoImport(ent, edit) => (ent.designator().to_string(), edit.text),
+                _ => panic!("Expected an auto import"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn imports_names_from_packages_of_other_libraries() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "lib2",
+            "
+package pkg is
+  constant my_constant : natural := 0;
+  function my_function return natural;
+end package;
+",
+        );
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  constant c : natural := my_;
+begin
+end architecture;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let cursor = code.s1(":= my_").end();
+        assert_eq!(
+            imports(&root, &code, cursor),
+            vec![
+                (
+                    "my_constant".to_owned(),
+                    "library lib2;\nuse lib2.pkg.all;\n".to_owned()
+                ),
+                (
+                    "my_function".to_owned(),
+                    "library lib2;\nuse lib2.pkg.all;\n".to_owned()
+                ),
+            ]
+        );
+
+        let options = list_completion_options(&root, code.source(), cursor);
+        let edit = options
+            .iter()
+            .find_map(|option| match option {
+                CompletionItem::AutoImport(_, edit) => Some(edit),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(edit.position, code.s1("architecture").start());
+    }
+
+    #[test]
+    fn imports_from_the_same_library_through_work_after_the_context_clause() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  constant my_constant : natural := 0;
+end package;
+",
+        );
+        let code = builder.code(
+            "libname",
+            "
+library lib2;
+use lib2.other.all;
+
+entity ent is
+  generic (g : natural := my_co);
+end entity;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let cursor = code.s1(":= my_co").end();
+        let tokens = tokenize_input(
+            root.symbols(),
+            root.standard_of_source(code.source()),
+            code.source(),
+            cursor,
+        );
+        let options = list_auto_imports(&root, code.source(), cursor, &tokens);
+        assert_eq!(
+            options
+                .into_iter()
+                .map(|item| match item {
+                    CompletionItem::AutoImport(_, edit) => edit,
+                    _ => panic!("Expected an auto import"),
+                })
+                .collect::<Vec<_>>(),
+            vec![ImportEdit {
+                position: Position::new(code.s1("use lib2").start().line + 1, 0),
+                text: "use work.pkg.all;\n".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn does_not_import_visible_names() {
+        let mut builder = LibraryBuilder::new();
+        builder.add_std_logic_1164();
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s : std_ul;
+begin
+end architecture;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        assert_eq!(imports(&root, &code, code.s1("std_ul").end()), vec![]);
+    }
+}
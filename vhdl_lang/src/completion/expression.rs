@@ -0,0 +1,546 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Completions inside expressions using the types found by the analysis
+
+use super::{list_primaries_for_lib, list_region, CompletionItem};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, NotFound, SearchState, Searcher};
+use crate::ast::visitor::{Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Position, Source, SrcPos, WithPos};
+use crate::named_entity::{HasEntityId, Related, TypeEnt};
+use crate::syntax::Kind::*;
+use crate::syntax::{HasTokenSpan, Token, TokenAccess};
+use crate::{AnyEntKind, Design, EntRef, EntityId, Overloaded, Type};
+
+/// Completions after the prefix of a selected name or an attribute name, in a case choice
+/// or on the right hand side of an assignment.
+/// Returns `None` when the cursor is in neither of these positions.
+pub(super) fn list_expression_completions<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    tokens: &[Token],
+) -> Option<Vec<CompletionItem<'a>>> {
+    match tokens {
+        [.., prefix @ kind!(Identifier), kind!(Dot)]
+        | [.., prefix @ kind!(Identifier), kind!(Dot), kind!(Identifier)] => {
+            let prefix = root.search_reference(source, prefix.pos.start())?;
+            Some(list_selected(root, prefix))
+        }
+        [.., prefix @ kind!(Identifier), kind!(Tick)]
+        | [.., prefix @ kind!(Identifier), kind!(Tick), kind!(Identifier | Range | Subtype)] => {
+            let prefix = root.search_reference(source, prefix.pos.start())?;
+            Some(list_attributes(root, prefix))
+        }
+        [.., kind!(When | Bar)] | [.., kind!(When | Bar), kind!(Identifier)] => {
+            list_case_choices(root, source, cursor)
+        }
+        [.., target, kind!(LTE | ColonEq)]
+        | [.., target, kind!(LTE | ColonEq), kind!(Identifier)] => {
+            let expected = match target {
+                kind!(Identifier) => root
+                    .search_reference(source, target.pos.start())
+                    .and_then(|ent| type_of(root, ent)),
+                _ => None,
+            };
+            Some(list_values(root, source, cursor, expected))
+        }
+        _ => None,
+    }
+}
+
+/// The type of an object, alias, literal or function, or the type itself for a type mark
+fn type_of<'a>(root: &'a DesignRoot, ent: EntRef<'a>) -> Option<TypeEnt<'a>> {
+    TypeEnt::from_any(ent).or_else(|| {
+        root.find_type_definition_of(ent)
+            .and_then(TypeEnt::from_any)
+    })
+}
+
+/// The elements of a record, the declarations of a package or the primary units of a library
+fn list_selected<'a>(root: &'a DesignRoot, prefix: EntRef<'a>) -> Vec<CompletionItem<'a>> {
+    match prefix.kind() {
+        AnyEntKind::Library => match prefix.designator() {
+            Designator::Identifier(library) => list_primaries_for_lib(root, library),
+            _ => Vec::new(),
+        },
+        AnyEntKind::Design(
+            Design::Package(_, region)
+            | Design::UninstPackage(_, region)
+            | Design::PackageInstance(region),
+        ) => list_region(region).collect(),
+        _ => {
+            let Some(typ) = type_of(root, prefix) else {
+                return Vec::new();
+            };
+            // Access values are dereferenced implicitly by selected names
+            let typ = typ.accessed_type().unwrap_or(typ).base_type();
+            match typ.kind() {
+                Type::Record(region) => region
+                    .iter()
+                    .map(|elem| CompletionItem::Simple(elem.into()))
+                    .collect(),
+                Type::Protected(region, _) => list_region(region).collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+/// The predefined attributes applicable to a type or object followed by the user defined
+/// attributes specified for the named entity
+fn list_attributes<'a>(root: &'a DesignRoot, prefix: EntRef<'a>) -> Vec<CompletionItem<'a>> {
+    let mut attributes = Vec::new();
+    if let Some(typ) = TypeEnt::from_any(prefix) {
+        match typ.base_type().kind() {
+            Type::Enum(_) | Type::Integer | Type::Physical => {
+                attributes.extend(scalar_attributes());
+                attributes.extend(discrete_attributes());
+            }
+            Type::Real => attributes.extend(scalar_attributes()),
+            Type::Array { .. } => attributes.extend(array_attributes()),
+            _ => {}
+        }
+    } else if let Some(typ) = type_of(root, prefix) {
+        if matches!(typ.base_type().kind(), Type::Array { .. }) {
+            attributes.extend(array_attributes());
+        }
+        attributes.push(AttributeDesignator::Type(TypeAttribute::Subtype));
+        if is_signal(prefix) {
+            attributes.extend(signal_attributes());
+        }
+    }
+    attributes.extend([
+        AttributeDesignator::SimpleName,
+        AttributeDesignator::InstanceName,
+        AttributeDesignator::PathName,
+    ]);
+
+    let mut user_defined: Vec<_> = prefix
+        .attrs
+        .values()
+        .map(|(_, attr)| EntRef::from(*attr))
+        .collect();
+    user_defined.sort_by_key(|attr| attr.designator().to_string());
+
+    attributes
+        .into_iter()
+        .map(CompletionItem::Attribute)
+        .chain(user_defined.into_iter().map(CompletionItem::Simple))
+        .collect()
+}
+
+fn scalar_attributes() -> [AttributeDesignator; 7] {
+    use AttributeDesignator::*;
+    [Left, Right, High, Low, Ascending, Image, Value]
+}
+
+/// The attributes of discrete and physical types
+fn discrete_attributes() -> [AttributeDesignator; 6] {
+    use AttributeDesignator::*;
+    [Pos, Val, Succ, Pred, LeftOf, RightOf]
+}
+
+fn array_attributes() -> [AttributeDesignator; 9] {
+    use AttributeDesignator::*;
+    [
+        Left,
+        Right,
+        High,
+        Low,
+        Ascending,
+        Length,
+        Range(RangeAttribute::Range),
+        Range(RangeAttribute::ReverseRange),
+        Type(TypeAttribute::Element),
+    ]
+}
+
+fn signal_attributes() -> impl Iterator<Item = AttributeDesignator> {
+    use SignalAttribute::*;
+    [
+        Delayed,
+        Stable,
+        Quiet,
+        Transaction,
+        Event,
+        Active,
+        LastEvent,
+        LastActive,
+        LastValue,
+        Driving,
+        DrivingValue,
+    ]
+    .into_iter()
+    .map(AttributeDesignator::Signal)
+}
+
+fn is_signal(ent: EntRef) -> bool {
+    match ent.kind() {
+        AnyEntKind::Object(object) => object.class == ObjectClass::Signal,
+        AnyEntKind::ObjectAlias { base_object, .. } => base_object.class() == ObjectClass::Signal,
+        AnyEntKind::ExternalAlias { class, .. } => *class == ExternalObjectClass::Signal,
+        _ => false,
+    }
+}
+
+/// The enumeration literals of the case expression type not yet chosen by another alternative
+fn list_case_choices<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Option<Vec<CompletionItem<'a>>> {
+    let mut visitor = CaseVisitor { cursor, case: None };
+    root.walk_source(source, &mut visitor);
+    let case = visitor.case?;
+
+    let typ = type_of(root, root.get_ent(case.expression?))?.base_type();
+    let literals = typ.implicits.iter().filter(|ent| {
+        matches!(
+            ent.kind(),
+            AnyEntKind::Overloaded(Overloaded::EnumLiteral(_))
+        ) && !case.chosen.contains(&ent.id())
+    });
+    Some(
+        literals
+            .map(|ent| CompletionItem::Simple(ent))
+            .chain((!case.has_others).then_some(CompletionItem::Keyword(Others)))
+            .collect(),
+    )
+}
+
+/// The innermost case statement or case generate statement containing the cursor
+struct CaseVisitor {
+    cursor: Position,
+    case: Option<CaseChoices>,
+}
+
+struct CaseChoices {
+    /// The named entity denoted by the case expression
+    expression: Option<EntityId>,
+    /// The enumeration literals chosen by the alternatives apart from the one being edited
+    chosen: Vec<EntityId>,
+    has_others: bool,
+}
+
+impl CaseVisitor {
+    fn choose<'c>(
+        &mut self,
+        expression: &WithPos<Expression>,
+        choices: impl Iterator<Item = &'c WithPos<Choice>>,
+    ) {
+        let mut case = CaseChoices {
+            expression: name_reference(&expression.item),
+            chosen: Vec::new(),
+            has_others: false,
+        };
+        for choice in choices.filter(|choice| !choice.pos.contains(self.cursor)) {
+            match &choice.item {
+                Choice::Expression(expression) => case.chosen.extend(name_reference(expression)),
+                Choice::Others => case.has_others = true,
+                Choice::DiscreteRange(_) => {}
+            }
+        }
+        self.case = Some(case);
+    }
+}
+
+/// The named entity denoted by an expression that is a simple or selected name
+fn name_reference(expression: &Expression) -> Option<EntityId> {
+    match expression {
+        Expression::Name(name) => match name.as_ref() {
+            Name::Designator(designator) => designator.reference,
+            Name::Selected(_, suffix) => suffix.item.reference,
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Visitor for CaseVisitor {
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if !node.statement.pos.contains(self.cursor) {
+            return VisitorResult::Skip;
+        }
+        if let SequentialStatement::Case(case) = &node.statement.item {
+            self.choose(
+                &case.expression,
+                case.alternatives.iter().flat_map(|alt| alt.choices.iter()),
+            );
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if !node.statement.pos.contains(self.cursor) {
+            return VisitorResult::Skip;
+        }
+        if let ConcurrentStatement::CaseGenerate(gen) = &node.statement.item {
+            self.choose(
+                &gen.sels.expression,
+                gen.sels
+                    .alternatives
+                    .iter()
+                    .flat_map(|alt| alt.choices.iter()),
+            );
+        }
+        VisitorResult::Continue
+    }
+}
+
+/// The values visible at the cursor with those of the expected type first
+fn list_values<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    expected: Option<TypeEnt<'a>>,
+) -> Vec<CompletionItem<'a>> {
+    let mut values: Vec<EntRef<'a>> = Vec::new();
+    let visible = visible_at_cursor(root, source, cursor);
+    // Literals are implicitly declared by their types
+    let literals = visible
+        .iter()
+        .copied()
+        .chain(expected.map(EntRef::from))
+        .flat_map(|ent| ent.implicits.iter().copied())
+        .filter(|ent| {
+            matches!(
+                ent.kind(),
+                AnyEntKind::Overloaded(Overloaded::EnumLiteral(_)) | AnyEntKind::PhysicalLiteral(_)
+            )
+        });
+    for ent in visible.iter().copied().chain(literals) {
+        if is_value(ent) && !values.iter().any(|other| other.id() == ent.id()) {
+            values.push(ent);
+        }
+    }
+
+    let is_compatible = |ent: EntRef<'a>| match expected {
+        Some(expected) => {
+            type_of(root, ent).is_some_and(|typ| typ.base_type().id() == expected.base_type().id())
+        }
+        None => false,
+    };
+
+    // Overloaded names are completed once
+    let mut groups: Vec<(&Designator, Vec<EntRef<'a>>)> = Vec::new();
+    for ent in values {
+        match groups
+            .iter_mut()
+            .find(|(designator, _)| *designator == ent.designator())
+        {
+            Some((_, ents)) => ents.push(ent),
+            None => groups.push((ent.designator(), vec![ent])),
+        }
+    }
+    groups.sort_by_cached_key(|(designator, ents)| {
+        (
+            !ents.iter().any(|ent| is_compatible(ent)),
+            designator.to_string().to_lowercase(),
+        )
+    });
+
+    groups
+        .into_iter()
+        .map(|(designator, ents)| match ents[..] {
+            [ent] => CompletionItem::Simple(ent),
+            _ if ents.iter().all(|ent| ent.is_overloaded()) => {
+                CompletionItem::Overloaded(designator.clone(), ents.len())
+            }
+            _ => CompletionItem::Simple(ents[0]),
+        })
+        .collect()
+}
+
+fn is_value(ent: EntRef) -> bool {
+    match ent.kind() {
+        AnyEntKind::Object(_)
+        | AnyEntKind::ObjectAlias { .. }
+        | AnyEntKind::ExternalAlias { .. }
+        | AnyEntKind::DeferredConstant(_)
+        | AnyEntKind::PhysicalLiteral(_)
+        | AnyEntKind::LoopParameter(_) => true,
+        // Operators are not completed as names
+        AnyEntKind::Overloaded(overloaded) => {
+            overloaded.signature().return_type().is_some()
+                && !matches!(ent.designator(), Designator::OperatorSymbol(_))
+        }
+        _ => false,
+    }
+}
+
+/// The named entities visible at the cursor.
+///
+/// These are the declarations of the source in regions enclosing the cursor, the declarations
+/// of the enclosing primary unit together with the declarations made visible by its context
+/// clause and the declarations made visible by the context clause of an enclosing secondary unit.
+pub(super) fn visible_at_cursor<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Vec<EntRef<'a>> {
+    let mut searcher = ScopeSearcher {
+        source: source.clone(),
+        scopes: Vec::new(),
+        declared: Vec::new(),
+        used: Vec::new(),
+    };
+    let _ = root.search(&mut searcher);
+
+    let scopes: Vec<EntityId> = searcher
+        .scopes
+        .iter()
+        .filter(|(span, _)| span.contains(cursor))
+        .map(|(_, id)| *id)
+        .collect();
+
+    let mut result: Vec<EntRef<'a>> = searcher
+        .declared
+        .iter()
+        .map(|id| root.get_ent(*id))
+        .filter(|ent| {
+            ent.parent
+                .is_some_and(|parent| scopes.contains(&parent.id()))
+        })
+        .collect();
+
+    for id in scopes.iter() {
+        let unit = root.get_ent(*id);
+        let primary = match unit.kind() {
+            AnyEntKind::Design(Design::Architecture(entity)) => entity,
+            AnyEntKind::Design(Design::PackageBody) => match unit.related {
+                Related::DeclaredBy(package) => package,
+                _ => continue,
+            },
+            _ => unit,
+        };
+        if let AnyEntKind::Design(
+            Design::Entity(visibility, region)
+            | Design::Package(visibility, region)
+            | Design::UninstPackage(visibility, region),
+        ) = primary.kind()
+        {
+            result.extend(region.immediates());
+            result.extend(region.visibility.visible_entities());
+            result.extend(visibility.visible_entities());
+        }
+    }
+
+    for (span, id, all) in searcher.used.iter() {
+        if !span.contains(cursor) {
+            continue;
+        }
+        let ent = root.get_ent(*id);
+        if !all {
+            result.push(ent);
+            continue;
+        }
+        if let AnyEntKind::Design(
+            Design::Package(_, region)
+            | Design::UninstPackage(_, region)
+            | Design::PackageInstance(region),
+        ) = ent.kind()
+        {
+            result.extend(region.immediates());
+        }
+    }
+    result
+}
+
+/// Collects the declarations of a source together with the spans of the regions they can be
+/// declared in and the names used by the context clauses of secondary units
+struct ScopeSearcher {
+    source: Source,
+    scopes: Vec<(SrcPos, EntityId)>,
+    declared: Vec<EntityId>,
+    /// The span of a secondary unit, the used named entity and whether all of its
+    /// declarations are used
+    used: Vec<(SrcPos, EntityId, bool)>,
+}
+
+impl ScopeSearcher {
+    fn use_context_clause(&mut self, span: &SrcPos, context_clause: &[ContextItem]) {
+        let names = context_clause
+            .iter()
+            .filter_map(|item| match item {
+                ContextItem::Use(use_clause) => Some(use_clause.name_list.items.iter()),
+                _ => None,
+            })
+            .flatten();
+        for name in names {
+            let (reference, all) = match &name.item {
+                Name::SelectedAll(prefix) => match &prefix.item {
+                    Name::Selected(_, suffix) => (suffix.item.reference, true),
+                    Name::Designator(designator) => (designator.reference, true),
+                    _ => continue,
+                },
+                Name::Selected(_, suffix) => (suffix.item.reference, false),
+                _ => continue,
+            };
+            if let Some(id) = reference {
+                self.used.push((span.clone(), id, all));
+            }
+        }
+    }
+}
+
+impl Searcher for ScopeSearcher {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let Some(id) = decl.ent_id() else {
+            return NotFinished;
+        };
+        self.declared.push(id);
+
+        let span = match decl {
+            FoundDeclaration::Subprogram(ref body) => body.get_pos(ctx),
+            FoundDeclaration::Entity(ref unit) => unit.get_pos(ctx),
+            FoundDeclaration::Package(ref unit) => unit.get_pos(ctx),
+            FoundDeclaration::Architecture(ref unit) => {
+                let span = unit.get_pos(ctx);
+                self.use_context_clause(&span, &unit.context_clause);
+                span
+            }
+            FoundDeclaration::PackageBody(ref unit) => {
+                let span = unit.get_pos(ctx);
+                self.use_context_clause(&span, &unit.context_clause);
+                span
+            }
+            _ => return NotFinished,
+        };
+        self.scopes.push((span, id));
+        NotFinished
+    }
+
+    fn search_process(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        label: &WithDecl<Option<Ident>>,
+        statement_pos: &SrcPos,
+    ) -> SearchState {
+        if let Some(id) = label.decl {
+            self.scopes.push((statement_pos.clone(), id));
+        }
+        NotFinished
+    }
+
+    fn search_source(&mut self, _ctx: &dyn TokenAccess, source: &Source) -> SearchState {
+        if source == &self.source {
+            NotFinished
+        } else {
+            SearchState::Finished(NotFound)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Regions of the design file before the cursor found from the keywords that open and close them.
+//!
+//! The design unit being edited is typically not parsed successfully so the tokens are
+//! scanned instead of the AST.
+
+use crate::syntax::Kind::*;
+use crate::syntax::Token;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum Scope {
+    /// A declarative part followed by the statements of the given scope after `begin`
+    Declarative(Statements),
+    /// A subprogram that is only a declaration unless `is` follows
+    Subprogram,
+    Statements(Statements),
+    /// Records, components, configurations and other constructs without statements
+    Other,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum Statements {
+    Concurrent,
+    Sequential,
+    /// Package declarations and bodies have no statements
+    None,
+}
+
+/// The regions that are open at the end of the tokens
+pub(super) struct OpenRegions {
+    /// The open regions from the outermost to the innermost with the index of the opening token
+    pub scopes: Vec<(Scope, usize)>,
+    /// The tokens end inside parentheses
+    pub in_parens: bool,
+    /// The index of the first token following the previous design unit.
+    /// The context clause of the current design unit starts here.
+    pub unit_start: usize,
+}
+
+impl OpenRegions {
+    pub fn innermost(&self) -> Option<Scope> {
+        self.scopes.last().map(|(scope, _)| *scope)
+    }
+
+    pub fn in_sequential(&self) -> bool {
+        self.innermost() == Some(Scope::Statements(Statements::Sequential))
+    }
+
+    /// The index of the keyword starting the current design unit
+    pub fn unit_keyword(&self) -> Option<usize> {
+        self.scopes.first().map(|(_, idx)| *idx)
+    }
+}
+
+pub(super) fn scan_regions(tokens: &[Token]) -> OpenRegions {
+    let mut scopes: Vec<(Scope, usize)> = Vec::new();
+    let mut parens = 0usize;
+    // The index of the first token of the current statement or declaration
+    let mut start = 0;
+    let mut unit_start = 0;
+    // A design unit has been closed and ends at the next semicolon
+    let mut unit_closed = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let kind = tokens[i].kind;
+        let previous = i.checked_sub(1).map(|prev| tokens[prev].kind);
+        let next = tokens.get(i + 1).map(|token| token.kind);
+        let innermost = scopes.last().map(|(scope, _)| *scope);
+        match kind {
+            LeftPar => parens += 1,
+            RightPar => parens = parens.saturating_sub(1),
+            _ if parens > 0 => {}
+            End => {
+                scopes.pop();
+                unit_closed = scopes.is_empty();
+                // Skip the optional keywords and label following `end`
+                while i + 1 < tokens.len() && tokens[i + 1].kind != SemiColon {
+                    i += 1;
+                }
+            }
+            Begin => {
+                if let Some(Scope::Declarative(statements)) = innermost {
+                    scopes.pop();
+                    scopes.push((Scope::Statements(statements), i));
+                }
+            }
+            SemiColon => {
+                if innermost == Some(Scope::Subprogram) {
+                    scopes.pop();
+                }
+                if unit_closed {
+                    unit_start = i + 1;
+                    unit_closed = false;
+                }
+            }
+            Is => {
+                if innermost == Some(Scope::Subprogram) {
+                    let (_, opening) = scopes.pop().unwrap();
+                    scopes.push((Scope::Declarative(Statements::Sequential), opening));
+                }
+                // Instantiations of packages and subprograms have no body
+                if next == Some(New) {
+                    scopes.pop();
+                    unit_closed = scopes.is_empty();
+                }
+            }
+            Architecture | Block => scopes.push((Scope::Declarative(Statements::Concurrent), i)),
+            Entity if !matches!(previous, Some(Colon | Use)) => {
+                scopes.push((Scope::Declarative(Statements::Concurrent), i))
+            }
+            Package if !matches!(previous, Some(Colon)) => {
+                scopes.push((Scope::Declarative(Statements::None), i))
+            }
+            Process => scopes.push((Scope::Declarative(Statements::Sequential), i)),
+            Function | Procedure => scopes.push((Scope::Subprogram, i)),
+            // The alternatives of an if generate statement are part of the same statement
+            Generate
+                if !tokens[start..i]
+                    .iter()
+                    .any(|token| matches!(token.kind, Elsif | Else | When)) =>
+            {
+                scopes.push((Scope::Statements(Statements::Concurrent), i));
+            }
+            If | Case | Loop if innermost == Some(Scope::Statements(Statements::Sequential)) => {
+                scopes.push((Scope::Statements(Statements::Sequential), i))
+            }
+            Record | Units | Protected => scopes.push((Scope::Other, i)),
+            Configuration if !matches!(previous, Some(Colon | Use)) => {
+                scopes.push((Scope::Other, i))
+            }
+            Component if !matches!(previous, Some(Colon)) => scopes.push((Scope::Other, i)),
+            Context if tokens.get(i + 2).map(|token| token.kind) == Some(Is) => {
+                scopes.push((Scope::Other, i))
+            }
+            For if innermost == Some(Scope::Other) => scopes.push((Scope::Other, i)),
+            _ => {}
+        }
+        if parens == 0 && matches!(kind, SemiColon | Begin | Is | Then | Generate | RightArrow) {
+            start = i + 1;
+        }
+        i += 1;
+    }
+
+    OpenRegions {
+        scopes,
+        in_parens: parens > 0,
+        unit_start,
+    }
+}
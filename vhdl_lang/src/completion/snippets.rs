@@ -0,0 +1,444 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Snippets of design units, declarations and statements
+
+use super::regions::{scan_regions, Scope, Statements};
+use super::{tokenize_input, CompletionItem};
+use crate::analysis::DesignRoot;
+use crate::data::{Position, Source};
+use crate::named_entity::HasEntityId;
+use crate::syntax::Kind::*;
+use crate::syntax::Token;
+use crate::{AnyEntKind, Design, EntRef};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Where a snippet can be inserted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SnippetContext {
+    /// Outside of design units
+    DesignUnit,
+    /// The declarative part of a design unit, process or subprogram
+    Declaration,
+    /// The statement part of an architecture, block or generate statement
+    ConcurrentStatement,
+    /// The statement part of a process or subprogram
+    SequentialStatement,
+}
+
+impl FromStr for SnippetContext {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "design_unit" => Ok(SnippetContext::DesignUnit),
+            "declaration" => Ok(SnippetContext::Declaration),
+            "concurrent" => Ok(SnippetContext::ConcurrentStatement),
+            "sequential" => Ok(SnippetContext::SequentialStatement),
+            _ => Err(format!(
+                "Unknown snippet context '{s}', expected one of design_unit, declaration, concurrent or sequential"
+            )),
+        }
+    }
+}
+
+/// A template using the snippet syntax of the language server protocol,
+/// e.g., `${1:name}` for a placeholder and `$0` for the final cursor position
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Snippet {
+    /// The name that is completed
+    pub name: String,
+    pub context: SnippetContext,
+    pub description: String,
+    pub body: String,
+}
+
+impl Snippet {
+    pub fn new(
+        name: impl Into<String>,
+        context: SnippetContext,
+        description: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Snippet {
+        Snippet {
+            name: name.into(),
+            context,
+            description: description.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// The snippets available when they are not replaced by the configuration
+pub fn default_snippets() -> Vec<Snippet> {
+    use SnippetContext::*;
+    vec![
+        Snippet::new(
+            "entity",
+            DesignUnit,
+            "Entity declaration",
+            "entity ${1:name} is\n  port (\n    ${2:clk} : in ${3:std_logic}$0\n  );\nend entity;",
+        ),
+        Snippet::new(
+            "architecture",
+            DesignUnit,
+            "Architecture body",
+            "architecture ${1:rtl} of ${2:name} is\nbegin\n  $0\nend architecture;",
+        ),
+        Snippet::new(
+            "package",
+            DesignUnit,
+            "Package declaration",
+            "package ${1:name} is\n  $0\nend package;",
+        ),
+        Snippet::new(
+            "package body",
+            DesignUnit,
+            "Package body",
+            "package body ${1:name} is\n  $0\nend package body;",
+        ),
+        Snippet::new(
+            "record",
+            Declaration,
+            "Record type declaration",
+            "type ${1:name} is record\n  ${2:element} : ${3:std_logic};$0\nend record;",
+        ),
+        Snippet::new(
+            "process clocked",
+            ConcurrentStatement,
+            "Process triggered by the rising edge of a clock",
+            "${1:name} : process (${2:clk})\nbegin\n  if rising_edge($2) then\n    $0\n  end if;\nend process;",
+        ),
+        Snippet::new(
+            "process combinational",
+            ConcurrentStatement,
+            "Process sensitive to all signals it reads",
+            "${1:name} : process (all)\nbegin\n  $0\nend process;",
+        ),
+        Snippet::new(
+            "for generate",
+            ConcurrentStatement,
+            "For generate statement",
+            "${1:name} : for ${2:i} in ${3:0} to ${4:7} generate\n  $0\nend generate;",
+        ),
+        Snippet::new(
+            "if generate",
+            ConcurrentStatement,
+            "If generate statement",
+            "${1:name} : if ${2:condition} generate\n  $0\nend generate;",
+        ),
+        Snippet::new(
+            "with select",
+            ConcurrentStatement,
+            "Selected signal assignment",
+            "with ${1:selector} select ${2:target} <=\n  ${3:value} when ${4:choice},\n  ${5:default} when others;",
+        ),
+        Snippet::new(
+            "case",
+            SequentialStatement,
+            "Case statement",
+            "case ${1:expression} is\n  when ${2:choice} =>\n    $0\n  when others =>\n    null;\nend case;",
+        ),
+        Snippet::new(
+            "if",
+            SequentialStatement,
+            "If statement",
+            "if ${1:condition} then\n  $0\nend if;",
+        ),
+        Snippet::new(
+            "for loop",
+            SequentialStatement,
+            "For loop",
+            "for ${1:i} in ${2:0} to ${3:7} loop\n  $0\nend loop;",
+        ),
+    ]
+}
+
+/// Lists the snippets applicable at the cursor.
+/// Within the statement part of an architecture the instantiations of all entities are listed
+/// with their generic and port maps.
+pub fn list_snippets<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    snippets: &[Snippet],
+) -> Vec<CompletionItem<'a>> {
+    let tokens = tokenize_input(
+        root.symbols(),
+        root.standard_of_source(source),
+        source,
+        cursor,
+    );
+    let Some(context) = snippet_context(&tokens) else {
+        return Vec::new();
+    };
+
+    let mut result: Vec<CompletionItem> = snippets
+        .iter()
+        .filter(|snippet| snippet.context == context)
+        .cloned()
+        .map(CompletionItem::Snippet)
+        .collect();
+
+    if context == SnippetContext::ConcurrentStatement {
+        for lib in root.libraries() {
+            let entities = lib
+                .primary_units()
+                .filter_map(|unit| unit.unit.get().and_then(|unit| unit.ent_id()))
+                .map(|id| root.get_ent(id))
+                .filter(|ent| matches!(ent.kind(), AnyEntKind::Design(Design::Entity(..))));
+            result.extend(
+                entities.map(|ent| CompletionItem::Snippet(instantiation_snippet(root, ent))),
+            );
+        }
+    }
+    result
+}
+
+/// An entity instantiation with a placeholder for every generic and port
+fn instantiation_snippet(root: &DesignRoot, ent: EntRef) -> Snippet {
+    let library = ent
+        .library_name()
+        .map(|library| library.to_string())
+        .unwrap_or_else(|| "work".to_owned());
+    let name = ent.designator().to_string();
+
+    let mut body = format!("${{1:{name}_inst}} : entity {library}.{name}");
+    let mut placeholder = 2;
+    for (keyword, ids) in [
+        ("generic", root.extract_generic_names(ent.id())),
+        ("port", root.extract_port_names(ent.id())),
+    ] {
+        let mut formals: Vec<_> = ids.into_iter().map(|id| root.get_ent(id)).collect();
+        if formals.is_empty() {
+            continue;
+        }
+        formals.sort_by_key(|formal| formal.decl_pos());
+        write!(body, "\n  {keyword} map (").unwrap();
+        for (i, formal) in formals.iter().enumerate() {
+            let separator = if i + 1 < formals.len() { "," } else { "" };
+            let formal = formal.designator();
+            write!(
+                body,
+                "\n    {formal} => ${{{placeholder}:{formal}}}{separator}"
+            )
+            .unwrap();
+            placeholder += 1;
+        }
+        body.push_str("\n  )");
+    }
+    body.push_str(";$0");
+
+    Snippet::new(
+        name,
+        SnippetContext::ConcurrentStatement,
+        format!("Instantiation of entity {library}.{}", ent.designator()),
+        body,
+    )
+}
+
+/// Finds the kind of region at the end of the tokens, where a snippet can be inserted
+/// only at the start of a declaration or statement
+fn snippet_context(tokens: &[Token]) -> Option<SnippetContext> {
+    // The name of the snippet being typed
+    let tokens = match tokens {
+        [rest @ .., Token {
+            kind: Identifier, ..
+        }] => rest,
+        _ => tokens,
+    };
+
+    let regions = scan_regions(tokens);
+    if regions.in_parens {
+        return None;
+    }
+    let last = tokens.last().map(|token| token.kind);
+    let at_start = match last {
+        None | Some(SemiColon | Begin | Is | Generate | RightArrow) => true,
+        Some(Then | Else | Loop) => regions.in_sequential(),
+        _ => false,
+    };
+    if !at_start {
+        return None;
+    }
+
+    match regions.innermost() {
+        None => Some(SnippetContext::DesignUnit),
+        Some(Scope::Declarative(_)) => Some(SnippetContext::Declaration),
+        Some(Scope::Statements(Statements::Concurrent)) => {
+            Some(SnippetContext::ConcurrentStatement)
+        }
+        Some(Scope::Statements(Statements::Sequential)) => {
+            Some(SnippetContext::SequentialStatement)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::Code;
+    use crate::VHDLStandard;
+    use SnippetContext::*;
+
+    fn context_at_end(code: &str) -> Option<SnippetContext> {
+        let code = Code::new(code);
+        snippet_context(&tokenize_input(
+            &code.symbols,
+            VHDLStandard::default(),
+            code.source(),
+            code.end(),
+        ))
+    }
+
+    #[test]
+    fn snippet_context_follows_the_enclosing_region() {
+        assert_eq!(context_at_end(""), Some(DesignUnit));
+        assert_eq!(
+            context_at_end("entity ent is\nend entity;\nent"),
+            Some(DesignUnit)
+        );
+        assert_eq!(
+            context_at_end("architecture rtl of ent is\n  signal s : bit;\n  rec"),
+            Some(Declaration)
+        );
+        assert_eq!(
+            context_at_end("architecture rtl of ent is\nbegin\n  inst : entity work.ent;\n  pro"),
+            Some(ConcurrentStatement)
+        );
+        assert_eq!(
+            context_at_end(
+                "\
+architecture rtl of ent is
+begin
+  main : process
+  begin
+    case s is
+      when '0' =>
+        i"
+            ),
+            Some(SequentialStatement)
+        );
+        assert_eq!(
+            context_at_end(
+                "\
+architecture rtl of ent is
+begin
+  main : process
+  begin
+    if s = '1' then
+      null;
+    end if;
+  end process;
+  gen : if true generate
+  elsif false generate
+  end generate;
+  "
+            ),
+            Some(ConcurrentStatement)
+        );
+        assert_eq!(
+            context_at_end(
+                "\
+package pkg is
+  function f(a : bit; b : bit) return bit;
+  type rec_t is record
+    a : bit;
+  end record;
+  "
+            ),
+            Some(Declaration)
+        );
+        assert_eq!(
+            context_at_end(
+                "\
+package body pkg is
+  function f(a : bit; b : bit) return bit is
+  begin
+    "
+            ),
+            Some(SequentialStatement)
+        );
+    }
+
+    #[test]
+    fn no_snippets_inside_statements_and_records() {
+        assert_eq!(
+            context_at_end("architecture rtl of ent is\nbegin\n  s <= "),
+            None
+        );
+        assert_eq!(
+            context_at_end(
+                "architecture rtl of ent is\nbegin\n  inst : entity work.ent port map ("
+            ),
+            None
+        );
+        assert_eq!(
+            context_at_end("package pkg is\n  type rec_t is record\n    "),
+            None
+        );
+    }
+
+    #[test]
+    fn instantiation_snippets_map_all_generics_and_ports() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity counter is
+  generic (
+    width : natural;
+    reset_value : natural := 0
+  );
+  port (
+    clk : in bit;
+    count : out bit_vector(width - 1 downto 0)
+  );
+end entity;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+begin
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let cursor = code.s1("begin").end();
+        let options = list_snippets(&root, code.source(), cursor, &default_snippets());
+
+        assert!(options.contains(&CompletionItem::Snippet(Snippet::new(
+            "counter",
+            ConcurrentStatement,
+            "Instantiation of entity libname.counter",
+            "\
+${1:counter_inst} : entity libname.counter
+  generic map (
+    width => ${2:width},
+    reset_value => ${3:reset_value}
+  )
+  port map (
+    clk => ${4:clk},
+    count => ${5:count}
+  );$0"
+        ))));
+        assert!(options.contains(&CompletionItem::Snippet(Snippet::new(
+            "top",
+            ConcurrentStatement,
+            "Instantiation of entity libname.top",
+            "${1:top_inst} : entity libname.top;$0"
+        ))));
+        assert!(options.iter().any(
+            |option| matches!(option, CompletionItem::Snippet(snippet) if snippet.name == "process clocked")
+        ));
+        assert!(!options.iter().any(
+            |option| matches!(option, CompletionItem::Snippet(snippet) if snippet.name == "case")
+        ));
+    }
+}
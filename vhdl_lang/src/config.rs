@@ -6,7 +6,11 @@
 
 //! Configuration of the design hierarchy and other settings
 
+use crate::completion::{default_snippets, Snippet};
 use crate::data::*;
+use crate::lint::naming::NamingRule;
+use crate::lint::rules::rule_code;
+use crate::standard::VHDLStandard;
 use fnv::FnvHashMap;
 use std::env;
 use std::fs::File;
@@ -19,27 +23,54 @@ use toml::Value;
 pub struct Config {
     // A map from library name to file name
     libraries: FnvHashMap<String, LibraryConfig>,
+    // Other configuration files that are loaded before this one when appended
+    includes: Vec<PathBuf>,
+    // The default revision of the standard for all libraries
+    standard: Option<VHDLStandard>,
+    // Revision of the standard for files matching a pattern, later patterns take precedence
+    file_standards: Vec<(glob::Pattern, VHDLStandard)>,
+    // The library of files that are not part of any library
+    default_library: Option<String>,
+    // The library of unmapped files within a directory, the deepest directory takes precedence
+    directory_libraries: Vec<(PathBuf, String)>,
+    // Snippets that are added to the defaults or replace the default with the same name
+    snippets: Vec<Snippet>,
+    // Patterns that the names of each kind of declaration must match
+    naming_rules: Vec<NamingRule>,
+    // The severity of each lint rule, `None` when the rule is disabled
+    lint_rules: Vec<(&'static str, Option<Severity>)>,
+    // The canonical path of the file the configuration was read from
+    file_name: Option<PathBuf>,
+    // Errors of entries that were skipped, reported when the configuration is appended
+    errors: Vec<String>,
+}
+
+/// A file that should be added to a library
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LibraryMapping {
+    pub library_name: String,
+    pub file_name: PathBuf,
 }
 
 #[derive(Clone, PartialEq, Eq, Default, Debug)]
 pub struct LibraryConfig {
     name: String,
     patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    standard: Option<VHDLStandard>,
     pub(crate) is_third_party: bool,
+    naming: Option<bool>,
 }
 
 impl LibraryConfig {
     /// Return a vector of file names
     /// Only include files that exists
     /// Files that do not exist produce a warning message
+    /// Files matching any of the exclude patterns are removed
     pub fn file_names(&self, messages: &mut dyn MessageHandler) -> Vec<PathBuf> {
         let mut result = Vec::new();
         for pattern in self.patterns.iter() {
-            let stripped_pattern = if cfg!(windows) {
-                pattern.strip_prefix("\\\\?\\").unwrap_or(pattern.as_str())
-            } else {
-                pattern.as_str()
-            };
+            let stripped_pattern = strip_verbatim_prefix(pattern);
 
             if is_literal(stripped_pattern) {
                 let file_path = Path::new(pattern).to_owned();
@@ -78,9 +109,31 @@ impl LibraryConfig {
                 }
             }
         }
+        let excludes = self.exclude_patterns(messages);
+        result.retain(|file_path| {
+            !excludes
+                .iter()
+                .any(|exclude| exclude.matches_path(file_path))
+        });
         Self::remove_duplicates(result)
     }
 
+    /// Compile the exclude patterns, invalid patterns produce an error message
+    fn exclude_patterns(&self, messages: &mut dyn MessageHandler) -> Vec<glob::Pattern> {
+        let mut result = Vec::with_capacity(self.exclude_patterns.len());
+        for pattern in self.exclude_patterns.iter() {
+            match glob::Pattern::new(strip_verbatim_prefix(pattern)) {
+                Ok(pattern) => result.push(pattern),
+                Err(err) => {
+                    messages.push(Message::error(format!(
+                        "Invalid exclude pattern '{pattern}' {err}"
+                    )));
+                }
+            }
+        }
+        result
+    }
+
     /// Remove duplicate file names from the result
     fn remove_duplicates(file_names: Vec<PathBuf>) -> Vec<PathBuf> {
         let mut result = Vec::with_capacity(file_names.len());
@@ -94,16 +147,38 @@ impl LibraryConfig {
         result
     }
 
+    /// Returns true if the file matches any of the file patterns and none of the exclude patterns
+    pub fn matches_file(&self, file_name: &Path) -> bool {
+        let matches = |pattern: &String| {
+            glob::Pattern::new(strip_verbatim_prefix(pattern))
+                .map(|pattern| pattern.matches_path(file_name))
+                .unwrap_or(false)
+        };
+        self.patterns.iter().any(matches) && !self.exclude_patterns.iter().any(matches)
+    }
+
     /// Returns the name of the library
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
+
+    /// Returns the revision of the standard if set explicitly for the library
+    pub fn standard(&self) -> Option<VHDLStandard> {
+        self.standard
+    }
+
+    /// Returns true if the naming conventions are checked for the library,
+    /// which by default they are unless the library is third-party
+    pub fn checks_naming(&self) -> bool {
+        self.naming.unwrap_or(!self.is_third_party)
+    }
 }
 
 impl Config {
     pub fn from_str(string: &str, parent: &Path) -> Result<Config, String> {
         let config = string.parse::<Value>().map_err(|err| err.to_string())?;
         let mut libraries = FnvHashMap::default();
+        let mut errors = Vec::new();
 
         let libs = config
             .get("libraries")
@@ -118,19 +193,19 @@ impl Config {
                 .as_array()
                 .ok_or_else(|| format!("files for library {name} is not array"))?;
 
-            let mut patterns = Vec::new();
-            for file in file_arr.iter() {
-                let file = file
-                    .as_str()
-                    .ok_or_else(|| format!("not a string {file}"))?;
+            let patterns = parse_patterns(file_arr, parent, name, &mut errors)?;
 
-                let path = parent.join(file);
-                let path = path
-                    .to_str()
-                    .ok_or_else(|| format!("Could not convert {path:?} to string"))?
-                    .to_owned();
-                patterns.push(path);
-            }
+            let exclude_patterns = if let Some(exclude_arr) = lib.get("exclude") {
+                let exclude_arr = exclude_arr
+                    .as_array()
+                    .ok_or_else(|| format!("exclude for library {name} is not array"))?;
+                parse_patterns(exclude_arr, parent, name, &mut errors)?
+            } else {
+                Vec::new()
+            };
+
+            let standard = parse_standard(lib.get("standard"))
+                .map_err(|err| format!("{err} for library {name}"))?;
 
             let mut is_third_party = false;
             if let Some(opt) = lib.get("is_third_party") {
@@ -143,17 +218,178 @@ impl Config {
                 }
             }
 
+            let naming = lib
+                .get("naming")
+                .map(|opt| {
+                    opt.as_bool()
+                        .ok_or_else(|| format!("Expected naming to be boolean for library {name}"))
+                })
+                .transpose()?;
+
             libraries.insert(
                 name.to_owned(),
                 LibraryConfig {
                     name: name.to_owned(),
                     patterns,
+                    exclude_patterns,
+                    standard,
                     is_third_party,
+                    naming,
                 },
             );
         }
 
-        Ok(Config { libraries })
+        let mut includes = Vec::new();
+        if let Some(include_arr) = config.get("include") {
+            let include_arr = include_arr.as_array().ok_or("include must be an array")?;
+            for file in include_arr.iter() {
+                let file = file
+                    .as_str()
+                    .ok_or_else(|| format!("not a string {file}"))?;
+                match expand_env_vars(file) {
+                    Ok(file) => includes.push(parent.join(file)),
+                    Err(err) => errors.push(format!("{err}, the file is not included")),
+                }
+            }
+        }
+
+        let standard = parse_standard(config.get("standard"))?;
+
+        let mut file_standards = Vec::new();
+        if let Some(file_standards_table) = config.get("file_standards") {
+            let file_standards_table = file_standards_table
+                .as_table()
+                .ok_or("file_standards must be a table")?;
+            for (file, standard) in file_standards_table.iter() {
+                let path = match expand_env_vars(file) {
+                    Ok(path) => parent.join(path),
+                    Err(err) => {
+                        errors.push(format!("{err}, the standard of the files is not set"));
+                        continue;
+                    }
+                };
+                let path = path
+                    .to_str()
+                    .ok_or_else(|| format!("Could not convert {path:?} to string"))?;
+                let pattern = glob::Pattern::new(strip_verbatim_prefix(path))
+                    .map_err(|err| format!("Invalid pattern '{path}' {err}"))?;
+                let standard = parse_standard(Some(standard))
+                    .map_err(|err| format!("{err} for files '{file}'"))?
+                    .unwrap();
+                file_standards.push((pattern, standard));
+            }
+        }
+
+        let default_library = if let Some(default_library) = config.get("default_library") {
+            Some(
+                default_library
+                    .as_str()
+                    .ok_or("default_library must be a string")?
+                    .to_owned(),
+            )
+        } else {
+            None
+        };
+
+        let mut directory_libraries = Vec::new();
+        if let Some(directory_libraries_table) = config.get("directory_libraries") {
+            let directory_libraries_table = directory_libraries_table
+                .as_table()
+                .ok_or("directory_libraries must be a table")?;
+            for (directory, library_name) in directory_libraries_table.iter() {
+                let library_name = library_name.as_str().ok_or_else(|| {
+                    format!("Expected library name of directory '{directory}' to be a string")
+                })?;
+                match expand_env_vars(directory) {
+                    Ok(directory) => {
+                        directory_libraries.push((parent.join(directory), library_name.to_owned()))
+                    }
+                    Err(err) => errors.push(format!(
+                        "{err}, the files of the directory are not mapped to library {library_name}"
+                    )),
+                }
+            }
+        }
+
+        let mut snippets = Vec::new();
+        if let Some(snippets_table) = config.get("snippets") {
+            let snippets_table = snippets_table
+                .as_table()
+                .ok_or("snippets must be a table")?;
+            for (name, snippet) in snippets_table.iter() {
+                let get_str = |field: &str| -> Result<Option<&str>, String> {
+                    snippet
+                        .get(field)
+                        .map(|value| {
+                            value.as_str().ok_or_else(|| {
+                                format!("Expected {field} of snippet '{name}' to be a string")
+                            })
+                        })
+                        .transpose()
+                };
+                let context = get_str("context")?
+                    .ok_or_else(|| format!("missing field context for snippet '{name}'"))?
+                    .parse()
+                    .map_err(|err| format!("{err} for snippet '{name}'"))?;
+                let body = get_str("body")?
+                    .ok_or_else(|| format!("missing field body for snippet '{name}'"))?;
+                let description = get_str("description")?.unwrap_or_default();
+                snippets.push(Snippet::new(name, context, description, body));
+            }
+        }
+
+        let mut naming_rules = Vec::new();
+        if let Some(naming_table) = config.get("naming") {
+            let naming_table = naming_table.as_table().ok_or("naming must be a table")?;
+            for (kind, pattern) in naming_table.iter() {
+                let pattern = pattern.as_str().ok_or_else(|| {
+                    format!("Expected naming convention of {kind} to be a string")
+                })?;
+                naming_rules.push(NamingRule::new(kind.parse()?, pattern)?);
+            }
+        }
+
+        let mut lint_rules = Vec::new();
+        if let Some(lint_table) = config.get("lint") {
+            let lint_table = lint_table.as_table().ok_or("lint must be a table")?;
+            for (name, value) in lint_table.iter() {
+                let code = rule_code(name).ok_or_else(|| format!("Unknown lint rule '{name}'"))?;
+                let severity = match value {
+                    Value::Boolean(enabled) => enabled.then_some(Severity::Warning),
+                    Value::String(severity) => Some(match severity.as_str() {
+                        "hint" => Severity::Hint,
+                        "info" => Severity::Info,
+                        "warning" => Severity::Warning,
+                        "error" => Severity::Error,
+                        _ => {
+                            return Err(format!(
+                                "Unknown severity '{severity}' of lint rule {name}, expected one of hint, info, warning or error"
+                            ))
+                        }
+                    }),
+                    _ => {
+                        return Err(format!(
+                            "Expected lint rule {name} to be a boolean or a severity"
+                        ))
+                    }
+                };
+                lint_rules.push((code, severity));
+            }
+        }
+
+        Ok(Config {
+            libraries,
+            includes,
+            standard,
+            file_standards,
+            default_library,
+            directory_libraries,
+            snippets,
+            naming_rules,
+            lint_rules,
+            file_name: None,
+            errors,
+        })
     }
 
     pub fn read_file_path(file_name: &Path) -> io::Result<Config> {
@@ -163,7 +399,173 @@ impl Config {
 
         let parent = file_name.parent().unwrap();
 
-        Config::from_str(&contents, parent).map_err(|msg| io::Error::new(io::ErrorKind::Other, msg))
+        let mut config = Config::from_str(&contents, parent)
+            .map_err(|msg| io::Error::new(io::ErrorKind::Other, msg))?;
+        config.file_name = Some(canonical_path(file_name));
+        Ok(config)
+    }
+
+    /// Add files to the libraries of a configuration file
+    ///
+    /// Formatting and comments of the existing file are kept, the file is created if it does not exist
+    pub fn write_library_mappings(file_name: &Path, mappings: &[LibraryMapping]) -> io::Result<()> {
+        let contents = if file_name.exists() {
+            std::fs::read_to_string(file_name)?
+        } else {
+            String::new()
+        };
+
+        let parent = file_name.parent().unwrap();
+        let contents = add_library_mappings(&contents, parent, mappings)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+        std::fs::write(file_name, contents)
+    }
+
+    /// Replace the files of the libraries of the contents of a configuration file that are renamed.
+    ///
+    /// Only files that are listed by name are replaced, patterns matching a renamed file are kept.
+    /// Formatting and comments of the contents are kept.
+    pub fn rename_library_files(
+        contents: &str,
+        parent: &Path,
+        renames: &[(PathBuf, PathBuf)],
+    ) -> Result<String, String> {
+        let mut document = contents
+            .parse::<toml_edit::Document>()
+            .map_err(|err| err.to_string())?;
+        let Some(libraries) = document
+            .get_mut("libraries")
+            .and_then(|libraries| libraries.as_table_like_mut())
+        else {
+            return Ok(contents.to_owned());
+        };
+
+        for (_, library) in libraries.iter_mut() {
+            let Some(files) = library
+                .as_table_like_mut()
+                .and_then(|library| library.get_mut("files"))
+                .and_then(|files| files.as_array_mut())
+            else {
+                continue;
+            };
+            for file in files.iter_mut() {
+                let Some(file_name) = file.as_str() else {
+                    continue;
+                };
+                let Some((_, to)) = renames
+                    .iter()
+                    .find(|(from, _)| parent.join(file_name) == *from)
+                else {
+                    continue;
+                };
+                // Files within the parent folder are kept relative to it
+                let to = if Path::new(file_name).is_relative() {
+                    to.strip_prefix(parent).unwrap_or(to)
+                } else {
+                    to
+                };
+                let to = to
+                    .to_str()
+                    .ok_or_else(|| format!("Could not convert {to:?} to string"))?;
+                let decor = file.decor().clone();
+                *file = to.into();
+                *file.decor_mut() = decor;
+            }
+        }
+
+        Ok(document.to_string())
+    }
+
+    /// The default snippets where those of the configuration are added or replace the default
+    /// with the same name
+    pub fn snippets(&self) -> Vec<Snippet> {
+        let mut snippets = default_snippets();
+        for snippet in self.snippets.iter() {
+            match snippets.iter_mut().find(|other| other.name == snippet.name) {
+                Some(other) => *other = snippet.clone(),
+                None => snippets.push(snippet.clone()),
+            }
+        }
+        snippets
+    }
+
+    /// The patterns that the names of each kind of declaration must match
+    pub(crate) fn naming_rules(&self) -> &[NamingRule] {
+        &self.naming_rules
+    }
+
+    /// The severity of the diagnostics of a lint rule, `None` when the rule is disabled
+    pub(crate) fn lint_severity(&self, code: &str) -> Option<Severity> {
+        self.lint_rules
+            .iter()
+            .find(|(other, _)| *other == code)
+            .and_then(|(_, severity)| *severity)
+    }
+
+    /// The revision of the standard used for libraries that do not set it explicitly
+    pub fn standard(&self) -> VHDLStandard {
+        self.standard.unwrap_or_default()
+    }
+
+    /// The revision of the standard used to analyze a file that belongs to some libraries
+    ///
+    /// A matching file pattern takes precedence over the standard of the libraries
+    /// which takes precedence over the global standard
+    pub fn standard_of_file<'a>(
+        &self,
+        file_name: &Path,
+        library_names: impl IntoIterator<Item = &'a str>,
+    ) -> VHDLStandard {
+        if let Some((_, standard)) = self
+            .file_standards
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches_path(file_name))
+        {
+            return *standard;
+        }
+
+        library_names
+            .into_iter()
+            .filter_map(|name| self.get_library(name)?.standard())
+            .next()
+            .unwrap_or_else(|| self.standard())
+    }
+
+    /// The library of files that are not part of any library and not within a directory
+    /// mapped by `directory_libraries`
+    pub fn default_library(&self) -> &str {
+        self.default_library.as_deref().unwrap_or("work")
+    }
+
+    /// The libraries of a file that was not found when the project was loaded
+    ///
+    /// Libraries with a file pattern matching the file take precedence over
+    /// the library of the deepest directory containing the file
+    /// which takes precedence over the default library
+    pub fn libraries_of_unmapped_file(&self, file_name: &Path) -> Vec<&str> {
+        let mut library_names: Vec<_> = self
+            .iter_libraries()
+            .filter(|library| library.matches_file(file_name))
+            .map(|library| library.name())
+            .collect();
+
+        if library_names.is_empty() {
+            let directory_library = self
+                .directory_libraries
+                .iter()
+                .filter(|(directory, _)| file_name.starts_with(directory))
+                .max_by_key(|(directory, _)| directory.components().count());
+
+            if let Some((_, library_name)) = directory_library {
+                library_names.push(library_name.as_str());
+            } else {
+                library_names.push(self.default_library());
+            }
+        }
+
+        library_names.sort_unstable();
+        library_names
     }
 
     pub fn get_library<'a>(&'a self, name: &str) -> Option<&'a LibraryConfig> {
@@ -176,8 +578,78 @@ impl Config {
 
     /// Append another config to self
     ///
+    /// The files included by the other config are appended before the config itself.
     /// In case of conflict the appended config takes precedence
     pub fn append(&mut self, config: &Config, messages: &mut dyn MessageHandler) {
+        let mut include_stack: Vec<PathBuf> = config.file_name.iter().cloned().collect();
+        self.append_with_includes(config, &mut include_stack, messages);
+    }
+
+    /// Append another config to self after recursively appending its included files
+    ///
+    /// The stack of currently included files is used to detect circular includes
+    fn append_with_includes(
+        &mut self,
+        config: &Config,
+        include_stack: &mut Vec<PathBuf>,
+        messages: &mut dyn MessageHandler,
+    ) {
+        for error in config.errors.iter() {
+            messages.push(Message::error(error.clone()));
+        }
+
+        for file_name in config.includes.iter() {
+            if include_stack.contains(&canonical_path(file_name)) {
+                messages.push(Message::error(format!(
+                    "Circular include of configuration file {}",
+                    file_name.to_string_lossy()
+                )));
+                continue;
+            }
+
+            match Config::read_file_path(file_name) {
+                Ok(included_config) => {
+                    messages.push(Message::log(format!(
+                        "Loaded included configuration file: {}",
+                        file_name.to_string_lossy()
+                    )));
+
+                    include_stack.push(canonical_path(file_name));
+                    self.append_with_includes(&included_config, include_stack, messages);
+                    include_stack.pop();
+                }
+                Err(ref err) => {
+                    messages.push(Message::error(format!(
+                        "Error while loading included configuration file {}: {err}",
+                        file_name.to_string_lossy()
+                    )));
+                }
+            }
+        }
+
+        if config.standard.is_some() {
+            self.standard = config.standard;
+        }
+        self.file_standards
+            .extend(config.file_standards.iter().cloned());
+        if config.default_library.is_some() {
+            self.default_library = config.default_library.clone();
+        }
+        self.directory_libraries
+            .extend(config.directory_libraries.iter().cloned());
+        for snippet in config.snippets.iter() {
+            self.snippets.retain(|other| other.name != snippet.name);
+            self.snippets.push(snippet.clone());
+        }
+        for rule in config.naming_rules.iter() {
+            self.naming_rules.retain(|other| other.kind != rule.kind);
+            self.naming_rules.push(rule.clone());
+        }
+        for rule in config.lint_rules.iter() {
+            self.lint_rules.retain(|other| other.0 != rule.0);
+            self.lint_rules.push(*rule);
+        }
+
         for library in config.iter_libraries() {
             if let Some(parent_library) = self.libraries.get_mut(&library.name) {
                 *parent_library = library.clone();
@@ -270,6 +742,154 @@ impl Config {
     }
 }
 
+/// The canonical form of a path, or the path itself when it does not exist
+fn canonical_path(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// Parse an array of file name patterns relative to the parent folder
+///
+/// A pattern with an undefined environment variable is skipped and its error is kept
+fn parse_patterns(
+    values: &[Value],
+    parent: &Path,
+    library_name: &str,
+    errors: &mut Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut patterns = Vec::with_capacity(values.len());
+    for value in values.iter() {
+        let file = value
+            .as_str()
+            .ok_or_else(|| format!("not a string {value}"))?;
+
+        let path = match expand_env_vars(file) {
+            Ok(file) => parent.join(file),
+            Err(err) => {
+                errors.push(format!("{err} for library {library_name}"));
+                continue;
+            }
+        };
+        let path = path
+            .to_str()
+            .ok_or_else(|| format!("Could not convert {path:?} to string"))?
+            .to_owned();
+        patterns.push(path);
+    }
+    Ok(patterns)
+}
+
+/// Add files to the libraries of the contents of a configuration file
+///
+/// Files within the parent folder are added relative to it
+fn add_library_mappings(
+    contents: &str,
+    parent: &Path,
+    mappings: &[LibraryMapping],
+) -> Result<String, String> {
+    let mut document = contents
+        .parse::<toml_edit::Document>()
+        .map_err(|err| err.to_string())?;
+
+    let libraries = document
+        .entry("libraries")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or("libraries must be a table")?;
+
+    for mapping in mappings.iter() {
+        let name = &mapping.library_name;
+        let library = libraries.entry(name).or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            table.set_dotted(true);
+            toml_edit::Item::Table(table)
+        });
+        let files = library
+            .as_table_like_mut()
+            .ok_or_else(|| format!("library {name} must be a table"))?
+            .entry("files")
+            .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| format!("files for library {name} is not array"))?;
+
+        let file_name = mapping
+            .file_name
+            .strip_prefix(parent)
+            .unwrap_or(&mapping.file_name);
+        let file_name = file_name
+            .to_str()
+            .ok_or_else(|| format!("Could not convert {file_name:?} to string"))?;
+
+        if !files.iter().any(|file| file.as_str() == Some(file_name)) {
+            files.push(file_name);
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Parse an optional revision of the standard
+fn parse_standard(value: Option<&Value>) -> Result<Option<VHDLStandard>, String> {
+    if let Some(value) = value {
+        let standard = value
+            .as_str()
+            .ok_or_else(|| format!("Expected standard to be a string, got {value}"))?;
+        Ok(Some(standard.parse()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Remove the verbatim path prefix on Windows which is not understood by glob
+fn strip_verbatim_prefix(pattern: &str) -> &str {
+    if cfg!(windows) {
+        pattern.strip_prefix("\\\\?\\").unwrap_or(pattern)
+    } else {
+        pattern
+    }
+}
+
+/// Expand environment variables written as `$VAR` or `${VAR}`
+///
+/// A `$` that is not followed by a variable name is kept as is
+fn expand_env_vars(value: &str) -> Result<String, String> {
+    expand_vars(value, |name| std::env::var(name).ok())
+}
+
+fn expand_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(idx) = rest.find('$') {
+        result.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        let (name, remaining) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("Missing closing brace of variable in '{value}'"))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|chr: char| !(chr.is_ascii_alphanumeric() || chr == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+
+        if name.is_empty() {
+            result.push('$');
+            continue;
+        }
+
+        let expanded = lookup(name)
+            .ok_or_else(|| format!("Environment variable {name} is not defined in '{value}'"))?;
+        result.push_str(&expanded);
+        rest = remaining;
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
 /// Returns true if the pattern is a plain file name and not a glob pattern
 fn is_literal(pattern: &str) -> bool {
     for chr in pattern.chars() {
@@ -286,6 +906,8 @@ fn is_literal(pattern: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::completion::SnippetContext;
+    use crate::lint::naming::NamingKind;
     use pretty_assertions::assert_eq;
 
     /// Utility function to create an empty file in parent folder
@@ -518,4 +1140,618 @@ lib.files = [
             ))]
         );
     }
+
+    #[test]
+    fn test_exclude_pattern() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        let config = Config::from_str(
+            "
+[libraries]
+lib.files = [
+  '*.vhd'
+]
+lib.exclude = [
+  '*_sim.vhd'
+]
+",
+            parent,
+        )
+        .unwrap();
+
+        let file1 = touch(parent, "file1.vhd");
+        touch(parent, "file1_sim.vhd");
+        let file2 = touch(parent, "file2.vhd");
+
+        let mut messages = vec![];
+        let file_names = config.get_library("lib").unwrap().file_names(&mut messages);
+        assert_files_eq(&file_names, &[file1, file2]);
+        assert_eq!(messages, vec![]);
+    }
+
+    #[test]
+    fn test_error_on_invalid_exclude_pattern() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+[libraries]
+lib.files = []
+lib.exclude = [
+  'file[.vhd'
+]
+",
+            parent,
+        )
+        .unwrap();
+
+        let mut messages = vec![];
+        let file_names = config.get_library("lib").unwrap().file_names(&mut messages);
+        assert_files_eq(&file_names, &[]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message_type, MessageType::Error);
+        assert!(messages[0].message.starts_with(&format!(
+            "Invalid exclude pattern '{}'",
+            parent.join("file[.vhd").to_str().unwrap()
+        )));
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let lookup = |name: &str| match name {
+            "TOOL" => Some("/opt/tool".to_owned()),
+            "VERSION" => Some("2023.1".to_owned()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_vars("$TOOL/${VERSION}/src/*.vhd", lookup),
+            Ok("/opt/tool/2023.1/src/*.vhd".to_owned())
+        );
+        assert_eq!(
+            expand_vars("${TOOL}_lib/file.vhd", lookup),
+            Ok("/opt/tool_lib/file.vhd".to_owned())
+        );
+        assert_eq!(expand_vars("file$.vhd", lookup), Ok("file$.vhd".to_owned()));
+        assert_eq!(
+            expand_vars("$MISSING/file.vhd", lookup),
+            Err("Environment variable MISSING is not defined in '$MISSING/file.vhd'".to_owned())
+        );
+        assert_eq!(
+            expand_vars("${TOOL/file.vhd", lookup),
+            Err("Missing closing brace of variable in '${TOOL/file.vhd'".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_include_config() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        std::fs::create_dir(parent.join("sub")).unwrap();
+        std::fs::write(
+            parent.join("sub").join("vhdl_ls.toml"),
+            "
+[libraries]
+lib1.files = ['pkg1.vhd']
+lib2.files = ['pkg2.vhd']
+",
+        )
+        .unwrap();
+
+        let config = Config::from_str(
+            "
+include = ['sub/vhdl_ls.toml']
+
+[libraries]
+lib2.files = ['ent.vhd']
+",
+            parent,
+        )
+        .unwrap();
+
+        let mut merged_config = Config::default();
+        let mut messages = vec![];
+        merged_config.append(&config, &mut messages);
+
+        let sub_parent = parent.join("sub");
+        let expected_config = Config::from_str(
+            &format!(
+                "
+[libraries]
+lib1.files = ['{pkg1}']
+lib2.files = ['{ent}']
+",
+                pkg1 = sub_parent.join("pkg1.vhd").to_str().unwrap(),
+                ent = parent.join("ent.vhd").to_str().unwrap(),
+            ),
+            Path::new(""),
+        )
+        .unwrap();
+
+        assert_eq!(merged_config, expected_config);
+        assert_eq!(
+            messages,
+            vec![
+                Message::log(format!(
+                    "Loaded included configuration file: {}",
+                    parent.join("sub/vhdl_ls.toml").to_string_lossy()
+                )),
+                Message::warning("Re-defined library lib2")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_on_circular_include() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        let file_name = parent.join("vhdl_ls.toml");
+        std::fs::write(
+            &file_name,
+            "
+include = ['vhdl_ls.toml']
+
+[libraries]
+",
+        )
+        .unwrap();
+
+        let config = Config::read_file_path(&file_name).unwrap();
+        let mut merged_config = Config::default();
+        let mut messages = vec![];
+        merged_config.append(&config, &mut messages);
+
+        // The file is not loaded again so its libraries are not re-defined
+        assert_eq!(
+            messages,
+            vec![Message::error(format!(
+                "Circular include of configuration file {}",
+                file_name.to_string_lossy()
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_undefined_environment_variables_only_skip_their_entry() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+include = ['$VHDL_LS_UNDEFINED_VAR/vhdl_ls.toml']
+
+[libraries]
+lib.files = ['pkg.vhd', '$VHDL_LS_UNDEFINED_VAR/ent.vhd']
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(
+            config.get_library("lib").unwrap().patterns,
+            vec![parent.join("pkg.vhd").to_str().unwrap().to_owned()]
+        );
+
+        let mut merged_config = Config::default();
+        let mut messages = vec![];
+        merged_config.append(&config, &mut messages);
+        assert_eq!(
+            messages,
+            vec![
+                Message::error(
+                    "Environment variable VHDL_LS_UNDEFINED_VAR is not defined in '$VHDL_LS_UNDEFINED_VAR/ent.vhd' for library lib"
+                ),
+                Message::error(
+                    "Environment variable VHDL_LS_UNDEFINED_VAR is not defined in '$VHDL_LS_UNDEFINED_VAR/vhdl_ls.toml', the file is not included"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_on_missing_include() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+include = ['missing.toml']
+
+[libraries]
+",
+            parent,
+        )
+        .unwrap();
+
+        let mut merged_config = Config::default();
+        let mut messages = vec![];
+        merged_config.append(&config, &mut messages);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message_type, MessageType::Error);
+        assert!(messages[0].message.starts_with(&format!(
+            "Error while loading included configuration file {}",
+            parent.join("missing.toml").to_string_lossy()
+        )));
+    }
+
+    #[test]
+    fn test_standard_of_file() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+standard = '2019'
+
+[libraries]
+lib1.files = ['*.vhd']
+lib1.standard = '1993'
+lib2.files = ['*.vhd']
+
+[file_standards]
+'legacy/*.vhd' = '2002'
+",
+            parent,
+        )
+        .unwrap();
+
+        assert_eq!(config.standard(), VHDLStandard::VHDL2019);
+        assert_eq!(
+            config.standard_of_file(&parent.join("file.vhd"), ["lib1"]),
+            VHDLStandard::VHDL1993
+        );
+        assert_eq!(
+            config.standard_of_file(&parent.join("file.vhd"), ["lib2"]),
+            VHDLStandard::VHDL2019
+        );
+        assert_eq!(
+            config.standard_of_file(&parent.join("legacy/file.vhd"), ["lib1"]),
+            VHDLStandard::VHDL2002
+        );
+    }
+
+    #[test]
+    fn test_append_config_standard() {
+        let parent = Path::new("parent_folder");
+        let mut config = Config::from_str(
+            "
+standard = '1993'
+[libraries]
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(config.standard(), VHDLStandard::VHDL1993);
+
+        config.append(
+            &Config::from_str("[libraries]", parent).unwrap(),
+            &mut Vec::new(),
+        );
+        assert_eq!(config.standard(), VHDLStandard::VHDL1993);
+
+        config.append(
+            &Config::from_str("standard = '2008'\n[libraries]", parent).unwrap(),
+            &mut Vec::new(),
+        );
+        assert_eq!(config.standard(), VHDLStandard::VHDL2008);
+    }
+
+    #[test]
+    fn test_error_on_unknown_standard() {
+        let parent = Path::new("parent_folder");
+        assert_eq!(
+            Config::from_str(
+                "
+[libraries]
+lib.files = []
+lib.standard = '2000'
+",
+                parent,
+            ),
+            Err(
+                "Unknown VHDL standard '2000', expected one of 1993, 2002, 2008 or 2019 for library lib"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_libraries_of_unmapped_file() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+[libraries]
+lib1.files = ['src/*.vhd']
+lib1.exclude = ['src/*_tb.vhd']
+lib2.files = ['src/*.vhd', 'tb/*.vhd']
+
+[directory_libraries]
+'ip' = 'ip_lib'
+'ip/uart' = 'uart_lib'
+",
+            parent,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("src/new.vhd")),
+            vec!["lib1", "lib2"]
+        );
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("src/new_tb.vhd")),
+            vec!["lib2"]
+        );
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("ip/uart/rx.vhd")),
+            vec!["uart_lib"]
+        );
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("ip/spi/spi.vhd")),
+            vec!["ip_lib"]
+        );
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("other/file.vhd")),
+            vec!["work"]
+        );
+    }
+
+    #[test]
+    fn test_default_library() {
+        let parent = Path::new("parent_folder");
+        let mut config = Config::from_str("[libraries]", parent).unwrap();
+        assert_eq!(config.default_library(), "work");
+
+        config.append(
+            &Config::from_str("default_library = 'scratch'\n[libraries]", parent).unwrap(),
+            &mut Vec::new(),
+        );
+        assert_eq!(config.default_library(), "scratch");
+        assert_eq!(
+            config.libraries_of_unmapped_file(&parent.join("file.vhd")),
+            vec!["scratch"]
+        );
+    }
+
+    #[test]
+    fn test_add_library_mappings() {
+        let parent = Path::new("parent_folder");
+        let contents = "\
+# Project libraries
+[libraries]
+lib1.files = ['pkg.vhd'] # The packages
+";
+        let mappings = [
+            LibraryMapping {
+                library_name: "lib1".to_owned(),
+                file_name: parent.join("src/ent.vhd"),
+            },
+            LibraryMapping {
+                library_name: "lib1".to_owned(),
+                file_name: parent.join("pkg.vhd"),
+            },
+            LibraryMapping {
+                library_name: "lib2".to_owned(),
+                file_name: PathBuf::from("/other/file.vhd"),
+            },
+        ];
+
+        let contents = add_library_mappings(contents, parent, &mappings).unwrap();
+        assert_eq!(
+            contents,
+            "\
+# Project libraries
+[libraries]
+lib1.files = ['pkg.vhd', \"src/ent.vhd\"] # The packages
+lib2.files = [\"/other/file.vhd\"]
+"
+        );
+
+        let config = Config::from_str(&contents, parent).unwrap();
+        assert!(config
+            .get_library("lib1")
+            .unwrap()
+            .matches_file(&parent.join("src/ent.vhd")));
+        assert!(config
+            .get_library("lib2")
+            .unwrap()
+            .matches_file(Path::new("/other/file.vhd")));
+    }
+
+    #[test]
+    fn test_add_library_mappings_to_empty_file() {
+        let parent = Path::new("parent_folder");
+        let mappings = [LibraryMapping {
+            library_name: "lib".to_owned(),
+            file_name: parent.join("file.vhd"),
+        }];
+
+        let contents = add_library_mappings("", parent, &mappings).unwrap();
+        let config = Config::from_str(&contents, parent).unwrap();
+        assert!(config
+            .get_library("lib")
+            .unwrap()
+            .matches_file(&parent.join("file.vhd")));
+    }
+
+    #[test]
+    fn test_renamed_library_files_are_replaced() {
+        let parent = Path::new("parent_folder");
+        let contents = "\
+# Project libraries
+[libraries]
+lib1.files = ['pkg.vhd', 'src/*.vhd'] # The packages
+
+[libraries.lib2]
+files = [
+  'src/ent.vhd',
+  '/other/file.vhd',
+]
+";
+        let renames = [
+            (parent.join("pkg.vhd"), parent.join("pkg/pkg.vhd")),
+            (parent.join("src/ent.vhd"), parent.join("src/top.vhd")),
+            (
+                PathBuf::from("/other/file.vhd"),
+                PathBuf::from("/other/renamed.vhd"),
+            ),
+        ];
+
+        assert_eq!(
+            Config::rename_library_files(contents, parent, &renames).unwrap(),
+            "\
+# Project libraries
+[libraries]
+lib1.files = [\"pkg/pkg.vhd\", 'src/*.vhd'] # The packages
+
+[libraries.lib2]
+files = [
+  \"src/top.vhd\",
+  \"/other/renamed.vhd\",
+]
+"
+        );
+    }
+
+    #[test]
+    fn test_snippets_are_added_to_or_replace_the_defaults() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+[libraries]
+
+[snippets.case]
+context = 'sequential'
+body = \"case $1 is\\n  when others => null;\\nend case;\"
+
+[snippets.assert]
+context = 'sequential'
+description = 'Assertion'
+body = 'assert $1 report \"$2\";'
+",
+            parent,
+        )
+        .unwrap();
+
+        let snippets = config.snippets();
+        assert_eq!(snippets.len(), default_snippets().len() + 1);
+        let case = snippets
+            .iter()
+            .find(|snippet| snippet.name == "case")
+            .unwrap();
+        assert_eq!(case.body, "case $1 is\n  when others => null;\nend case;");
+        assert_eq!(case.description, "");
+        assert_eq!(
+            snippets.last(),
+            Some(&Snippet::new(
+                "assert",
+                SnippetContext::SequentialStatement,
+                "Assertion",
+                "assert $1 report \"$2\";"
+            ))
+        );
+
+        assert_eq!(
+            Config::from_str(
+                "
+[libraries]
+[snippets.assert]
+context = 'everywhere'
+body = 'assert $1;'
+",
+                parent,
+            ),
+            Err("Unknown snippet context 'everywhere', expected one of design_unit, declaration, concurrent or sequential for snippet 'assert'".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_naming_rules_are_parsed_and_replaced_when_appended() {
+        let parent = Path::new("parent_folder");
+        let mut config = Config::from_str(
+            "
+[libraries]
+lib.files = []
+vendor.files = []
+vendor.is_third_party = true
+tb.files = []
+tb.naming = false
+
+[naming]
+in_port = '^i_'
+constant = '^C_'
+",
+            parent,
+        )
+        .unwrap();
+        assert!(config.get_library("lib").unwrap().checks_naming());
+        assert!(!config.get_library("vendor").unwrap().checks_naming());
+        assert!(!config.get_library("tb").unwrap().checks_naming());
+
+        let other = Config::from_str(
+            "
+[libraries]
+[naming]
+constant = '^K_'
+",
+            parent,
+        )
+        .unwrap();
+        config.append(&other, &mut Vec::new());
+        assert_eq!(
+            config
+                .naming_rules()
+                .iter()
+                .map(|rule| (rule.kind, rule.regex.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(NamingKind::InPort, "^i_"), (NamingKind::Constant, "^K_")]
+        );
+
+        assert_eq!(
+            Config::from_str("[libraries]\n[naming]\nport = '^p_'", parent),
+            Err("Unknown kind of declaration 'port'".to_owned())
+        );
+        assert!(
+            Config::from_str("[libraries]\n[naming]\nsignal = '('", parent)
+                .unwrap_err()
+                .starts_with("Invalid naming convention '(' of signal")
+        );
+    }
+
+    #[test]
+    fn test_lint_rules_are_parsed_and_replaced_when_appended() {
+        let parent = Path::new("parent_folder");
+        let mut config = Config::from_str(
+            "
+[libraries]
+[lint]
+magic_number = true
+keyword_case = 'error'
+buffer_port = false
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(
+            config.lint_severity("magic_number"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(config.lint_severity("keyword_case"), Some(Severity::Error));
+        assert_eq!(config.lint_severity("buffer_port"), None);
+        assert_eq!(config.lint_severity("unlabeled_process"), None);
+
+        let other = Config::from_str(
+            "
+[libraries]
+[lint]
+magic_number = false
+buffer_port = 'hint'
+",
+            parent,
+        )
+        .unwrap();
+        config.append(&other, &mut Vec::new());
+        assert_eq!(config.lint_severity("magic_number"), None);
+        assert_eq!(config.lint_severity("keyword_case"), Some(Severity::Error));
+        assert_eq!(config.lint_severity("buffer_port"), Some(Severity::Hint));
+
+        assert_eq!(
+            Config::from_str("[libraries]\n[lint]\ntabs = true", parent),
+            Err("Unknown lint rule 'tabs'".to_owned())
+        );
+        assert_eq!(
+            Config::from_str("[libraries]\n[lint]\nbuffer_port = 'fatal'", parent),
+            Err("Unknown severity 'fatal' of lint rule buffer_port, expected one of hint, info, warning or error".to_owned())
+        );
+    }
 }
@@ -11,6 +11,7 @@ use std::io;
 use std::io::prelude::Read;
 use std::path::Path;
 
+#[derive(Clone)]
 pub struct Contents {
     lines: Vec<String>,
 }
@@ -22,6 +22,8 @@ pub struct Diagnostic {
     pub message: String,
     pub severity: Severity,
     pub related: Vec<(SrcPos, String)>,
+    /// The code of the lint rule that reported the diagnostic
+    pub code: Option<&'static str>,
 }
 
 impl Diagnostic {
@@ -31,6 +33,7 @@ impl Diagnostic {
             message: msg.into(),
             severity,
             related: vec![],
+            code: None,
         }
     }
 
@@ -56,6 +59,14 @@ impl Diagnostic {
             pos: self.pos,
             severity: self.severity,
             related: vec![],
+            code: self.code,
+        }
+    }
+
+    pub fn with_code(self, code: &'static str) -> Diagnostic {
+        Diagnostic {
+            code: Some(code),
+            ..self
         }
     }
 
@@ -107,7 +118,11 @@ impl Diagnostic {
             Severity::Info => &"info",
             Severity::Hint => &"hint",
         };
-        result.push_str(&self.pos.show(&format!("{}: {}", severity, self.message)));
+        let header = match self.code {
+            Some(code) => format!("{}[{}]: {}", severity, code, self.message),
+            None => format!("{}: {}", severity, self.message),
+        };
+        result.push_str(&self.pos.show(&header));
         result
     }
 }
@@ -201,6 +216,25 @@ warning: Greetings
         );
     }
 
+    #[test]
+    fn show_warning_with_code() {
+        let code = Code::new_with_file_name(Path::new("{unknown file}"), "hello\nworld\nline\n");
+        assert_eq!(
+            Diagnostic::warning(code.s1("world"), "Greetings")
+                .with_code("greeting")
+                .show(),
+            "\
+warning[greeting]: Greetings
+  --> {unknown file}:2
+   |
+1  |  hello
+2 --> world
+   |  ~~~~~
+3  |  line
+"
+        );
+    }
+
     #[test]
     fn show_error() {
         let code = Code::new_with_file_name(Path::new("{unknown file}"), "hello\nworld\nline\n");
@@ -16,6 +16,7 @@ use std::io;
 pub use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+#[derive(Clone)]
 struct FileId {
     name: FilePath,
     /// Hash value of `self.name`.
@@ -175,6 +176,25 @@ impl Source {
         }
     }
 
+    /// Creates a copy of the source with a change applied to the copied contents.
+    /// Unlike `change`, the source itself and the positions referring to it are not affected.
+    pub fn changed(&self, range: Option<&Range>, content: &str) -> Source {
+        let contents = match range {
+            Some(range) => {
+                let mut contents = self.contents().clone();
+                contents.change(range, content);
+                contents
+            }
+            None => Contents::from_str(content),
+        };
+        Source {
+            source: Arc::new(UniqueSource {
+                file_id: self.source.file_id.clone(),
+                contents: RwLock::new(contents),
+            }),
+        }
+    }
+
     pub fn change(&self, range: Option<&Range>, content: &str) {
         let mut contents = self.source.contents.write();
         if let Some(range) = range {
@@ -652,6 +672,20 @@ mod tests {
         fun(CodeBuilder::new().code_from_source(Source::from_latin1_file(&file_name).unwrap()))
     }
 
+    #[test]
+    fn changed_source_is_a_copy() {
+        let code = Code::new("hello\nworld");
+        let pos = code.s1("world").pos();
+        let changed = code.source().changed(Some(&pos.range()), "there");
+
+        assert_eq!(changed, *code.source());
+        assert_eq!(changed.contents().get_line(1), Some("there"));
+        assert_eq!(
+            pos.code_context(),
+            "1  |  hello\n2 --> world\n   |  ~~~~~\n"
+        );
+    }
+
     #[test]
     fn code_context_pos_from_filename() {
         with_code_from_file("hello\nworld\n", |code: Code| {
@@ -0,0 +1,716 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Dependencies between design units and the files that contain them
+
+use crate::analysis::DesignRoot;
+use crate::ast::{AnyKind, HasUnitId, PrimaryKind, SecondaryKind, UnitId};
+use crate::data::HasSource;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The kind of a design unit
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum UnitKind {
+    Entity,
+    Architecture,
+    Configuration,
+    Package,
+    PackageBody,
+    PackageInstance,
+    Context,
+}
+
+impl UnitKind {
+    fn of(kind: AnyKind) -> UnitKind {
+        match kind {
+            AnyKind::Primary(PrimaryKind::Entity) => UnitKind::Entity,
+            AnyKind::Primary(PrimaryKind::Configuration) => UnitKind::Configuration,
+            AnyKind::Primary(PrimaryKind::Package) => UnitKind::Package,
+            AnyKind::Primary(PrimaryKind::PackageInstance) => UnitKind::PackageInstance,
+            AnyKind::Primary(PrimaryKind::Context) => UnitKind::Context,
+            AnyKind::Secondary(SecondaryKind::Architecture) => UnitKind::Architecture,
+            AnyKind::Secondary(SecondaryKind::PackageBody) => UnitKind::PackageBody,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            UnitKind::Entity => "entity",
+            UnitKind::Architecture => "architecture",
+            UnitKind::Configuration => "configuration",
+            UnitKind::Package => "package",
+            UnitKind::PackageBody => "package body",
+            UnitKind::PackageInstance => "package instance",
+            UnitKind::Context => "context",
+        }
+    }
+}
+
+impl Display for UnitKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe())
+    }
+}
+
+impl FromStr for UnitKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.replace(['_', '-'], " ").as_str() {
+            "entity" => Ok(UnitKind::Entity),
+            "architecture" => Ok(UnitKind::Architecture),
+            "configuration" => Ok(UnitKind::Configuration),
+            "package" => Ok(UnitKind::Package),
+            "package body" => Ok(UnitKind::PackageBody),
+            "package instance" => Ok(UnitKind::PackageInstance),
+            "context" => Ok(UnitKind::Context),
+            _ => Err(format!(
+                "Unknown design unit kind '{value}', expected one of entity, architecture, \
+                 configuration, package, package_body, package_instance or context"
+            )),
+        }
+    }
+}
+
+/// A design unit of the dependency graph
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DesignUnitNode {
+    pub library_name: String,
+    /// The name of the primary unit, the same as the name for primary units
+    pub primary_name: String,
+    pub kind: UnitKind,
+    pub name: String,
+    pub file_name: PathBuf,
+}
+
+impl DesignUnitNode {
+    /// A unique name of the design unit such as `lib.ent` or `lib.ent(rtl)`
+    pub fn id(&self) -> String {
+        match self.kind {
+            UnitKind::Architecture => {
+                format!("{}.{}({})", self.library_name, self.primary_name, self.name)
+            }
+            UnitKind::PackageBody => format!("{}.{}(body)", self.library_name, self.name),
+            _ => format!("{}.{}", self.library_name, self.name),
+        }
+    }
+
+    /// A description of the design unit such as `architecture rtl of ent`
+    pub fn describe(&self) -> String {
+        match self.kind {
+            UnitKind::Architecture => {
+                format!("architecture {} of {}", self.name, self.primary_name)
+            }
+            _ => format!("{} {}", self.kind, self.name),
+        }
+    }
+}
+
+/// The design units and the design units they depend on as found by the analysis
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DependencyGraph {
+    /// The design units sorted by library and name
+    pub units: Vec<DesignUnitNode>,
+    /// Pairs of indexes into units where the first unit depends on the second unit
+    pub dependencies: Vec<(usize, usize)>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(root: &DesignRoot) -> DependencyGraph {
+        let mut units: Vec<_> = root
+            .units()
+            .map(|unit| {
+                let unit_id = unit.unit_id();
+                let node = DesignUnitNode {
+                    library_name: unit_id.library_name().name_utf8(),
+                    primary_name: unit_id.primary_name().name_utf8(),
+                    kind: UnitKind::of(unit_id.kind()),
+                    name: unit_id
+                        .secondary_name()
+                        .unwrap_or(unit_id.primary_name())
+                        .name_utf8(),
+                    file_name: unit.source().file_name().to_owned(),
+                };
+                (node, unit_id)
+            })
+            .collect();
+        units.sort_by(|(node1, _), (node2, _)| node1.cmp(node2));
+
+        let index_of: FnvHashMap<&UnitId, usize> = units
+            .iter()
+            .enumerate()
+            .map(|(index, (_, unit_id))| (*unit_id, index))
+            .collect();
+
+        let mut dependencies = Vec::new();
+        for (user, used) in root.unit_dependencies().iter() {
+            for used in used.iter() {
+                if let (Some(&user), Some(&used)) = (index_of.get(user), index_of.get(used)) {
+                    dependencies.push((user, used));
+                }
+            }
+        }
+        dependencies.sort_unstable();
+
+        DependencyGraph {
+            units: units.into_iter().map(|(node, _)| node).collect(),
+            dependencies,
+        }
+    }
+
+    /// The graph of the design units to keep and the dependencies between them
+    pub fn filter(&self, keep: impl Fn(&DesignUnitNode) -> bool) -> DependencyGraph {
+        let mut new_index = vec![None; self.units.len()];
+        let mut units = Vec::new();
+        for (index, unit) in self.units.iter().enumerate() {
+            if keep(unit) {
+                new_index[index] = Some(units.len());
+                units.push(unit.clone());
+            }
+        }
+
+        let dependencies = self
+            .dependencies
+            .iter()
+            .filter_map(|&(user, used)| Some((new_index[user]?, new_index[used]?)))
+            .collect();
+
+        DependencyGraph {
+            units,
+            dependencies,
+        }
+    }
+
+    /// The dependencies between libraries implied by the dependencies of their design units
+    pub fn library_dependencies(&self) -> LibraryDependencyGraph {
+        let libraries: Vec<String> = self
+            .units
+            .iter()
+            .map(|unit| unit.library_name.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let index_of = |library_name: &String| libraries.binary_search(library_name).unwrap();
+
+        let dependencies: BTreeSet<_> = self
+            .dependencies
+            .iter()
+            .map(|&(user, used)| {
+                (
+                    index_of(&self.units[user].library_name),
+                    index_of(&self.units[used].library_name),
+                )
+            })
+            .filter(|(user, used)| user != used)
+            .collect();
+
+        LibraryDependencyGraph {
+            dependencies: dependencies.into_iter().collect(),
+            libraries,
+        }
+    }
+
+    /// Format as a Graphviz DOT digraph with a cluster per library
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        let mut units = self.units.iter().peekable();
+        while let Some(unit) = units.next() {
+            let library_name = &unit.library_name;
+            writeln!(
+                dot,
+                "  subgraph {} {{",
+                dot_id(&format!("cluster_{library_name}"))
+            )
+            .unwrap();
+            writeln!(dot, "    label = {};", dot_id(library_name)).unwrap();
+            let mut unit = Some(unit);
+            while let Some(this) = unit {
+                writeln!(
+                    dot,
+                    "    {} [label = {}];",
+                    dot_id(&this.id()),
+                    dot_id(&this.describe())
+                )
+                .unwrap();
+                unit = units.next_if(|next| &next.library_name == library_name);
+            }
+            dot.push_str("  }\n");
+        }
+        for &(user, used) in self.dependencies.iter() {
+            writeln!(
+                dot,
+                "  {} -> {};",
+                dot_id(&self.units[user].id()),
+                dot_id(&self.units[used].id())
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Format as a JSON object with a list of units and a list of dependencies between their ids
+    pub fn to_json(&self) -> serde_json::Value {
+        let units: Vec<_> = self
+            .units
+            .iter()
+            .map(|unit| {
+                serde_json::json!({
+                    "id": unit.id(),
+                    "library": unit.library_name,
+                    "kind": unit.kind.describe(),
+                    "name": unit.name,
+                    "primary": unit.primary_name,
+                    "file": unit.file_name.to_string_lossy(),
+                })
+            })
+            .collect();
+        let dependencies: Vec<_> = self
+            .dependencies
+            .iter()
+            .map(|&(user, used)| {
+                serde_json::json!({
+                    "from": self.units[user].id(),
+                    "to": self.units[used].id(),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "units": units,
+            "dependencies": dependencies,
+        })
+    }
+}
+
+/// The libraries and the libraries they depend on
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LibraryDependencyGraph {
+    /// The library names in sorted order
+    pub libraries: Vec<String>,
+    /// Pairs of indexes into libraries where the first library depends on the second library
+    pub dependencies: Vec<(usize, usize)>,
+}
+
+impl LibraryDependencyGraph {
+    /// Format as a Graphviz DOT digraph
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for library_name in self.libraries.iter() {
+            writeln!(dot, "  {};", dot_id(library_name)).unwrap();
+        }
+        for &(user, used) in self.dependencies.iter() {
+            writeln!(
+                dot,
+                "  {} -> {};",
+                dot_id(&self.libraries[user]),
+                dot_id(&self.libraries[used])
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Format as a JSON object with a list of libraries and a list of dependencies between them
+    pub fn to_json(&self) -> serde_json::Value {
+        let dependencies: Vec<_> = self
+            .dependencies
+            .iter()
+            .map(|&(user, used)| {
+                serde_json::json!({
+                    "from": self.libraries[user],
+                    "to": self.libraries[used],
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "libraries": self.libraries,
+            "dependencies": dependencies,
+        })
+    }
+}
+
+/// Quote an identifier of the DOT language
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A file that is compiled into a library
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LibraryFile {
+    pub library_name: String,
+    pub file_name: PathBuf,
+}
+
+/// The files of the design ordered such that every file comes after the files it depends on
+///
+/// When top units are given only the files needed by these units are included.
+/// A top unit is either the name of a primary unit or a name selected by its library such as `lib.ent`.
+/// Files that depend on each other circularly are ordered arbitrarily.
+pub fn compile_order(root: &DesignRoot, top_units: &[String]) -> Result<Vec<LibraryFile>, String> {
+    let mut files_of_units: FnvHashMap<UnitId, LibraryFile> = FnvHashMap::default();
+    for unit in root.units() {
+        files_of_units.insert(
+            unit.unit_id().clone(),
+            LibraryFile {
+                library_name: unit.unit_id().library_name().name_utf8(),
+                file_name: unit.source().file_name().to_owned(),
+            },
+        );
+    }
+
+    let dependencies = root.unit_dependencies();
+    let units: FnvHashSet<UnitId> = if top_units.is_empty() {
+        files_of_units.keys().cloned().collect()
+    } else {
+        let top_units = find_top_units(root, top_units)?;
+        needed_units(top_units, &dependencies, files_of_units.keys())
+    };
+
+    // Files depend on each other when their design units do
+    let mut file_dependencies: BTreeMap<&LibraryFile, BTreeSet<&LibraryFile>> = BTreeMap::new();
+    for unit_id in units.iter() {
+        let file = &files_of_units[unit_id];
+        let used_files = file_dependencies.entry(file).or_default();
+        for used in dependencies.get(unit_id).into_iter().flatten() {
+            if let Some(used_file) = files_of_units.get(used) {
+                if used_file != file {
+                    used_files.insert(used_file);
+                }
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(file_dependencies.len());
+    let mut visited = FnvHashSet::default();
+    for file in file_dependencies.keys() {
+        visit_file(file, &file_dependencies, &mut visited, &mut order);
+    }
+    Ok(order.into_iter().cloned().collect())
+}
+
+/// Depth first traversal adding files after the files they depend on
+fn visit_file<'a>(
+    file: &'a LibraryFile,
+    file_dependencies: &BTreeMap<&'a LibraryFile, BTreeSet<&'a LibraryFile>>,
+    visited: &mut FnvHashSet<&'a LibraryFile>,
+    order: &mut Vec<&'a LibraryFile>,
+) {
+    if !visited.insert(file) {
+        return;
+    }
+
+    if let Some(used_files) = file_dependencies.get(file) {
+        for used_file in used_files.iter() {
+            visit_file(used_file, file_dependencies, visited, order);
+        }
+    }
+    order.push(file);
+}
+
+/// Find the primary units with the given names
+fn find_top_units(root: &DesignRoot, names: &[String]) -> Result<Vec<UnitId>, String> {
+    let mut top_units = Vec::new();
+    for name in names.iter() {
+        let (library_name, unit_name) = match name.split_once('.') {
+            Some((library_name, unit_name)) => (Some(root.symbol_utf8(library_name)), unit_name),
+            None => (None, name.as_str()),
+        };
+        let unit_name = root.symbol_utf8(unit_name);
+
+        let len = top_units.len();
+        for library in root.libraries() {
+            if library_name.is_none() || library_name.as_ref() == Some(library.name()) {
+                if let Some(unit) = library.primary_unit(&unit_name) {
+                    top_units.push(unit.unit_id().clone());
+                }
+            }
+        }
+
+        if top_units.len() == len {
+            return Err(format!("No primary unit '{name}' found"));
+        }
+    }
+    Ok(top_units)
+}
+
+/// The top units and all units they transitively depend on
+///
+/// The secondary units of a needed primary unit are also needed
+/// since architectures and package bodies are required to elaborate the design
+fn needed_units<'a>(
+    top_units: Vec<UnitId>,
+    dependencies: &FnvHashMap<UnitId, FnvHashSet<UnitId>>,
+    all_units: impl Iterator<Item = &'a UnitId>,
+) -> FnvHashSet<UnitId> {
+    let mut secondary_units: FnvHashMap<(_, _), Vec<&UnitId>> = FnvHashMap::default();
+    for unit_id in all_units {
+        if let AnyKind::Secondary(_) = unit_id.kind() {
+            secondary_units
+                .entry((unit_id.library_name(), unit_id.primary_name()))
+                .or_default()
+                .push(unit_id);
+        }
+    }
+
+    let mut needed = FnvHashSet::default();
+    let mut pending = top_units;
+    while let Some(unit_id) = pending.pop() {
+        if needed.contains(&unit_id) {
+            continue;
+        }
+
+        if let Some(used) = dependencies.get(&unit_id) {
+            pending.extend(used.iter().cloned());
+        }
+        if let AnyKind::Primary(_) = unit_id.kind() {
+            if let Some(secondary) =
+                secondary_units.get(&(unit_id.library_name(), unit_id.primary_name()))
+            {
+                pending.extend(secondary.iter().map(|&unit_id| unit_id.clone()));
+            }
+        }
+        needed.insert(unit_id);
+    }
+    needed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::test::check_no_diagnostics;
+    use crate::{Config, Project};
+    use std::path::Path;
+
+    fn write_project(root: &Path) -> Project {
+        let files = [
+            (
+                "pkg.vhd",
+                "
+package pkg is
+end package;
+",
+            ),
+            (
+                "pkg_body.vhd",
+                "
+package body pkg is
+end package body;
+",
+            ),
+            (
+                "ent.vhd",
+                "
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+end architecture;
+",
+            ),
+            (
+                "top.vhd",
+                "
+entity top is
+end entity;
+
+architecture a of top is
+begin
+  inst: entity work.ent;
+end architecture;
+",
+            ),
+            (
+                "other.vhd",
+                "
+package other is
+end package;
+",
+            ),
+        ];
+        for (file_name, contents) in files {
+            std::fs::write(root.join(file_name), contents).unwrap();
+        }
+
+        let config = Config::from_str("[libraries]\nlib.files = ['*.vhd']", root).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        assert_eq!(messages, vec![]);
+        check_no_diagnostics(&project.analyse());
+        project
+    }
+
+    fn file_names(root: &Path, files: Vec<LibraryFile>) -> Vec<String> {
+        files
+            .into_iter()
+            .map(|file| {
+                assert_eq!(file.library_name, "lib");
+                file.file_name
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn files_are_ordered_after_their_dependencies() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let project = write_project(&root);
+
+        assert_eq!(
+            file_names(&root, project.compile_order(&[]).unwrap()),
+            vec!["pkg.vhd", "ent.vhd", "other.vhd", "pkg_body.vhd", "top.vhd"]
+        );
+    }
+
+    #[test]
+    fn compile_order_of_top_unit() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let project = write_project(&root);
+
+        assert_eq!(
+            file_names(
+                &root,
+                project.compile_order(&["lib.top".to_owned()]).unwrap()
+            ),
+            vec!["pkg.vhd", "ent.vhd", "pkg_body.vhd", "top.vhd"]
+        );
+        assert_eq!(
+            file_names(&root, project.compile_order(&["PKG".to_owned()]).unwrap()),
+            vec!["pkg.vhd", "pkg_body.vhd"]
+        );
+        assert_eq!(
+            project.compile_order(&["lib.missing".to_owned()]),
+            Err("No primary unit 'lib.missing' found".to_owned())
+        );
+    }
+
+    fn dependency_ids(graph: &DependencyGraph) -> Vec<(String, String)> {
+        graph
+            .dependencies
+            .iter()
+            .map(|&(user, used)| (graph.units[user].id(), graph.units[used].id()))
+            .collect()
+    }
+
+    #[test]
+    fn dependency_graph_of_design_units() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let project = write_project(&root);
+        let graph = project.dependency_graph();
+
+        assert_eq!(
+            graph
+                .units
+                .iter()
+                .map(|unit| unit.describe())
+                .collect::<Vec<_>>(),
+            vec![
+                "entity ent",
+                "architecture a of ent",
+                "package other",
+                "package pkg",
+                "package body pkg",
+                "entity top",
+                "architecture a of top",
+            ]
+        );
+        assert_eq!(
+            dependency_ids(&graph),
+            vec![
+                ("lib.ent".to_owned(), "lib.pkg".to_owned()),
+                ("lib.ent(a)".to_owned(), "lib.ent".to_owned()),
+                ("lib.pkg(body)".to_owned(), "lib.pkg".to_owned()),
+                ("lib.top(a)".to_owned(), "lib.ent".to_owned()),
+                ("lib.top(a)".to_owned(), "lib.top".to_owned()),
+            ]
+        );
+
+        let packages =
+            graph.filter(|unit| matches!(unit.kind, UnitKind::Package | UnitKind::PackageBody));
+        assert_eq!(
+            dependency_ids(&packages),
+            vec![("lib.pkg(body)".to_owned(), "lib.pkg".to_owned())]
+        );
+        assert_eq!(
+            packages.to_dot(),
+            "\
+digraph dependencies {
+  subgraph \"cluster_lib\" {
+    label = \"lib\";
+    \"lib.other\" [label = \"package other\"];
+    \"lib.pkg\" [label = \"package pkg\"];
+    \"lib.pkg(body)\" [label = \"package body pkg\"];
+  }
+  \"lib.pkg(body)\" -> \"lib.pkg\";
+}
+"
+        );
+        assert_eq!(
+            packages.to_json()["dependencies"],
+            serde_json::json!([{"from": "lib.pkg(body)", "to": "lib.pkg"}])
+        );
+    }
+
+    #[test]
+    fn dependency_graph_of_libraries() {
+        let graph = DependencyGraph {
+            units: ["lib1", "lib2", "lib3"]
+                .into_iter()
+                .map(|library_name| DesignUnitNode {
+                    library_name: library_name.to_owned(),
+                    primary_name: "pkg".to_owned(),
+                    kind: UnitKind::Package,
+                    name: "pkg".to_owned(),
+                    file_name: PathBuf::from("pkg.vhd"),
+                })
+                .collect(),
+            dependencies: vec![(0, 1), (0, 2), (1, 2)],
+        };
+
+        let libraries = graph
+            .filter(|unit| unit.library_name != "lib3")
+            .library_dependencies();
+        assert_eq!(libraries.libraries, vec!["lib1", "lib2"]);
+        assert_eq!(libraries.dependencies, vec![(0, 1)]);
+        assert_eq!(
+            libraries.to_dot(),
+            "\
+digraph dependencies {
+  \"lib1\";
+  \"lib2\";
+  \"lib1\" -> \"lib2\";
+}
+"
+        );
+        assert_eq!(
+            libraries.to_json(),
+            serde_json::json!({
+                "libraries": ["lib1", "lib2"],
+                "dependencies": [{"from": "lib1", "to": "lib2"}],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_unit_kind() {
+        assert_eq!("package_body".parse(), Ok(UnitKind::PackageBody));
+        assert_eq!("architecture".parse(), Ok(UnitKind::Architecture));
+        assert!("module".parse::<UnitKind>().is_err());
+    }
+}
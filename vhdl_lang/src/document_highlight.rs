@@ -0,0 +1,444 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Highlights of all uses of a named entity within a source file
+
+use crate::analysis::{positional_formals, DesignRoot};
+use crate::ast::search::{
+    is_reference, FoundDeclaration, NotFinished, NotFound, SearchState, Searcher,
+};
+use crate::ast::visitor::{Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{HasSrcPos, Position, Source, SrcPos, WithPos};
+use crate::named_entity::{FormalRegion, HasEntityId, ObjectInterface};
+use crate::syntax::TokenAccess;
+use crate::{AnyEntKind, Design, EntRef, Object};
+use fnv::FnvHashSet;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DocumentHighlightKind {
+    /// The declaration or an end label repeating its name
+    Declaration,
+    /// A use where the value is read
+    Read,
+    /// A use where the value is assigned such as an assignment target or an output actual
+    Write,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DocumentHighlight {
+    pub kind: DocumentHighlightKind,
+    pub pos: SrcPos,
+}
+
+impl DesignRoot {
+    /// All uses within a source file of the named entity at the cursor
+    pub fn document_highlights(&self, source: &Source, cursor: Position) -> Vec<DocumentHighlight> {
+        let Some(ent) = self.search_reference(source, cursor) else {
+            return Vec::new();
+        };
+
+        let mut searcher = HighlightSearcher {
+            root: self,
+            ent,
+            source: source.clone(),
+            highlights: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+
+        let mut visitor = WriteVisitor::new(self);
+        self.walk_source(source, &mut visitor);
+
+        let mut highlights = searcher.highlights;
+        for highlight in highlights.iter_mut() {
+            if highlight.kind == DocumentHighlightKind::Read
+                && visitor.writes.contains(&highlight.pos)
+            {
+                highlight.kind = DocumentHighlightKind::Write;
+            }
+        }
+        highlights.sort_by(|a, b| a.pos.cmp(&b.pos));
+        highlights.dedup();
+        highlights
+    }
+}
+
+struct HighlightSearcher<'a> {
+    root: &'a DesignRoot,
+    ent: EntRef<'a>,
+    source: Source,
+    highlights: Vec<DocumentHighlight>,
+}
+
+impl<'a> HighlightSearcher<'a> {
+    fn push(&mut self, kind: DocumentHighlightKind, pos: &SrcPos) {
+        self.highlights.push(DocumentHighlight {
+            kind,
+            pos: pos.clone(),
+        });
+    }
+}
+
+impl<'a> Searcher for HighlightSearcher<'a> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            if is_reference(self.ent, self.root.get_ent(id)) {
+                self.push(DocumentHighlightKind::Declaration, decl.pos());
+                if let Some(pos) = decl.end_ident_pos() {
+                    self.push(DocumentHighlightKind::Declaration, pos);
+                }
+            }
+        }
+        NotFinished
+    }
+
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &mut Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.as_ref() {
+            if is_reference(self.ent, self.root.get_ent(*id)) {
+                self.push(DocumentHighlightKind::Read, pos);
+            }
+        }
+        NotFinished
+    }
+
+    fn search_source(&mut self, _ctx: &dyn TokenAccess, source: &Source) -> SearchState {
+        if source == &self.source {
+            NotFinished
+        } else {
+            SearchState::Finished(NotFound)
+        }
+    }
+}
+
+/// Collects the positions of the names that are written
+pub(crate) struct WriteVisitor<'a> {
+    root: &'a DesignRoot,
+    pub(crate) writes: FnvHashSet<SrcPos>,
+}
+
+impl<'a> WriteVisitor<'a> {
+    pub(crate) fn new(root: &'a DesignRoot) -> WriteVisitor<'a> {
+        WriteVisitor {
+            root,
+            writes: FnvHashSet::default(),
+        }
+    }
+
+    fn target(&mut self, target: &WithPos<Target>) {
+        match target.item {
+            Target::Name(ref name) => self.written_name(&target.pos, name),
+            Target::Aggregate(ref elems) => {
+                for elem in elems.iter() {
+                    let expr = match elem {
+                        ElementAssociation::Positional(expr) => expr,
+                        ElementAssociation::Named(_, expr) => expr,
+                    };
+                    if let Expression::Name(ref name) = expr.item {
+                        self.written_name(&expr.pos, name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The designators of a name that are written, which excludes indexes and ranges
+    fn written_name(&mut self, pos: &SrcPos, name: &Name) {
+        match name {
+            Name::Designator(_) => {
+                self.writes.insert(pos.clone());
+            }
+            Name::Selected(prefix, suffix) => {
+                self.writes.insert(suffix.pos.clone());
+                self.written_name(&prefix.pos, &prefix.item);
+            }
+            Name::Slice(prefix, _) => self.written_name(&prefix.pos, &prefix.item),
+            Name::CallOrIndexed(coi) => self.written_name(&coi.name.pos, &coi.name.item),
+            Name::SelectedAll(_) | Name::Attribute(_) | Name::External(_) => {}
+        }
+    }
+
+    fn actual(&mut self, actual: &WithPos<ActualPart>) {
+        if let ActualPart::Expression(Expression::Name(ref name)) = actual.item {
+            self.written_name(&actual.pos, name);
+        }
+    }
+
+    /// Actuals associated with formals of mode out, inout or buffer are written
+    fn associations(&mut self, formal_region: &FormalRegion, elems: &[AssociationElement]) {
+        for (actual, formal) in positional_formals(formal_region, elems) {
+            if is_written_formal(formal.inner()) {
+                self.actual(actual);
+            }
+        }
+
+        for elem in elems.iter() {
+            let Some(ref formal) = elem.formal else {
+                continue;
+            };
+            let Some(id) = formal_reference(&formal.item) else {
+                continue;
+            };
+            if is_written_formal(self.root.get_ent(id)) {
+                self.actual(&elem.actual);
+            }
+        }
+    }
+}
+
+/// The interface object denoted by a formal such as `data` in `data(0) => bit0`
+pub(crate) fn formal_reference(name: &Name) -> Option<crate::EntityId> {
+    match name {
+        Name::CallOrIndexed(coi) => formal_reference(&coi.name.item),
+        Name::Slice(prefix, _) => formal_reference(&prefix.item),
+        Name::Selected(prefix, suffix) => {
+            // A record element of the formal or an expanded name of the formal
+            match formal_reference(&prefix.item) {
+                Some(id) => Some(id),
+                None => suffix.item.reference,
+            }
+        }
+        _ => name.get_suffix_reference(),
+    }
+}
+
+fn is_written_formal(ent: EntRef) -> bool {
+    matches!(
+        ent.kind(),
+        AnyEntKind::Object(Object {
+            iface: Some(
+                ObjectInterface::Port(Mode::Out | Mode::InOut | Mode::Buffer)
+                    | ObjectInterface::Parameter(Mode::Out | Mode::InOut | Mode::Buffer)
+            ),
+            ..
+        })
+    )
+}
+
+impl<'a> Visitor for WriteVisitor<'a> {
+    fn visit_signal_assignment(
+        &mut self,
+        node: &SignalAssignment,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.target(&node.target);
+        VisitorResult::Continue
+    }
+
+    fn visit_signal_force_assignment(
+        &mut self,
+        node: &SignalForceAssignment,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.target(&node.target);
+        VisitorResult::Continue
+    }
+
+    fn visit_signal_release_assignment(
+        &mut self,
+        node: &SignalReleaseAssignment,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.target(&node.target);
+        VisitorResult::Continue
+    }
+
+    fn visit_variable_assignment(
+        &mut self,
+        node: &VariableAssignment,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.target(&node.target);
+        VisitorResult::Continue
+    }
+
+    fn visit_concurrent_signal_assignment(
+        &mut self,
+        node: &ConcurrentSignalAssignment,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.target(&node.target);
+        VisitorResult::Continue
+    }
+
+    fn visit_instantiation_statement(
+        &mut self,
+        node: &InstantiationStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let Some(id) = node.entity_reference() else {
+            return VisitorResult::Continue;
+        };
+        let (_, ports) = match self.root.get_ent(id).kind() {
+            AnyEntKind::Design(Design::Entity(_, region)) => region.to_entity_formal(),
+            AnyEntKind::Component(region) => region.to_entity_formal(),
+            _ => return VisitorResult::Continue,
+        };
+        if let Some(ref port_map) = node.port_map {
+            self.associations(&ports, &port_map.list.items);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_call_or_indexed(
+        &mut self,
+        node: &CallOrIndexed,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let Some(id) = node.name.item.get_suffix_reference() {
+            if let AnyEntKind::Overloaded(overloaded) = self.root.get_ent(id).kind() {
+                self.associations(&overloaded.signature().formals, &node.parameters);
+            }
+        }
+        VisitorResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use DocumentHighlightKind::*;
+
+    #[test]
+    fn highlights_distinguish_declarations_reads_and_writes() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal cnt : natural;
+  signal other : natural;
+begin
+  main : process
+  begin
+    cnt <= cnt + 1;
+    other <= cnt;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let highlights = root.document_highlights(code.source(), code.s1("cnt").start());
+        assert_eq!(
+            highlights,
+            vec![
+                DocumentHighlight {
+                    kind: Declaration,
+                    pos: code.s("cnt", 1).pos()
+                },
+                DocumentHighlight {
+                    kind: Write,
+                    pos: code.s("cnt", 2).pos()
+                },
+                DocumentHighlight {
+                    kind: Read,
+                    pos: code.s("cnt", 3).pos()
+                },
+                DocumentHighlight {
+                    kind: Read,
+                    pos: code.s("cnt", 4).pos()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn actuals_of_output_formals_are_written() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity child is
+  port (
+    d : in bit;
+    q : out bit);
+end entity;
+
+architecture rtl of child is
+begin
+end architecture;
+
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal sig_d, sig_q : bit;
+
+  procedure get(variable value : out bit) is
+  begin
+    value := '1';
+  end procedure;
+begin
+  inst : entity work.child port map (sig_d, q => sig_q);
+
+  main : process
+    variable v : bit;
+  begin
+    get(v);
+    sig_d <= v;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let kinds = |name: &str, occurence: usize| -> Vec<DocumentHighlightKind> {
+            root.document_highlights(code.source(), code.s(name, occurence).start())
+                .into_iter()
+                .map(|highlight| highlight.kind)
+                .collect()
+        };
+        assert_eq!(kinds("sig_q", 1), vec![Declaration, Write]);
+        assert_eq!(kinds("sig_d", 1), vec![Declaration, Read, Write]);
+        assert_eq!(kinds("v :", 1), vec![Declaration, Write, Read]);
+        assert_eq!(kinds("value", 1), vec![Declaration, Write]);
+    }
+
+    #[test]
+    fn highlights_are_limited_to_the_source() {
+        let mut builder = LibraryBuilder::new();
+        let pkg = builder.code(
+            "libname",
+            "
+package pkg is
+  constant c : natural := 0;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.all;
+
+entity ent is
+  generic (g : natural := c);
+end entity;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let highlights = root.document_highlights(code.source(), code.s1(":= c").s1("c").start());
+        assert_eq!(
+            highlights,
+            vec![DocumentHighlight {
+                kind: Read,
+                pos: code.s1(":= c").s1("c").pos()
+            }]
+        );
+        let highlights = root.document_highlights(pkg.source(), pkg.s1("c :").start());
+        assert_eq!(highlights.len(), 1);
+    }
+}
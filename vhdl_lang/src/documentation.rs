@@ -0,0 +1,778 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Documentation of libraries generated from design units and their comments
+
+use crate::analysis::DesignRoot;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, Declaration, HasUnitId, Ident, InterfaceDeclaration,
+    InterfaceType, ObjectClass, SubprogramDesignator, SubprogramSpecification, SubtypeIndication,
+};
+use crate::data::WithPos;
+use crate::dependency_graph::UnitKind;
+use crate::AnyEntKind;
+use fnv::FnvHashSet;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+/// The output format of the documentation
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DocumentationFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl DocumentationFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentationFormat::Markdown => "md",
+            DocumentationFormat::Html => "html",
+        }
+    }
+}
+
+impl Display for DocumentationFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentationFormat::Markdown => write!(f, "markdown"),
+            DocumentationFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+impl FromStr for DocumentationFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(DocumentationFormat::Markdown),
+            "html" => Ok(DocumentationFormat::Html),
+            _ => Err(format!(
+                "Unknown documentation format '{value}', expected markdown or html"
+            )),
+        }
+    }
+}
+
+/// A link to a documented declaration
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DocLink {
+    pub library_name: String,
+    /// The lower case path name of the declaration such as `lib.pkg.word_t`
+    pub anchor: String,
+}
+
+/// An object or interface element such as a port, generic or constant
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ObjectDoc {
+    pub name: String,
+    /// The mode of a port or the kind of a generic that is not a constant
+    pub mode: Option<String>,
+    pub subtype: String,
+    /// The declaration of the type mark of the subtype, if it is documented
+    pub type_link: Option<DocLink>,
+    /// The default value of an interface element or the value of a constant
+    pub value: Option<String>,
+    pub documentation: Option<String>,
+}
+
+/// A declaration shown with its full text such as a type or subprogram
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DeclarationDoc {
+    pub name: String,
+    pub declaration: String,
+    pub documentation: Option<String>,
+}
+
+/// The documentation of an entity or package
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnitDoc {
+    pub kind: UnitKind,
+    pub name: String,
+    pub documentation: Option<String>,
+    pub generics: Vec<ObjectDoc>,
+    pub ports: Vec<ObjectDoc>,
+    pub types: Vec<DeclarationDoc>,
+    pub constants: Vec<ObjectDoc>,
+    pub subprograms: Vec<DeclarationDoc>,
+    pub components: Vec<DeclarationDoc>,
+}
+
+/// The documented design units of a library
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LibraryDoc {
+    pub name: String,
+    /// The entities and packages sorted by name
+    pub units: Vec<UnitDoc>,
+}
+
+/// The documentation of the entities and packages of all libraries
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Documentation {
+    /// The libraries sorted by name
+    pub libraries: Vec<LibraryDoc>,
+}
+
+impl Documentation {
+    pub(crate) fn new(root: &DesignRoot) -> Documentation {
+        let mut libraries: Vec<LibraryDoc> = Vec::new();
+        for unit in root.units() {
+            let library_name = unit.unit_id().library_name().name_utf8();
+            let design_unit = unit.unit.expect_analyzed();
+            let Some(doc) = UnitDocBuilder { root }.unit(&design_unit) else {
+                continue;
+            };
+
+            if let Some(library) = libraries
+                .iter_mut()
+                .find(|library| library.name == library_name)
+            {
+                library.units.push(doc);
+            } else {
+                libraries.push(LibraryDoc {
+                    name: library_name,
+                    units: vec![doc],
+                });
+            }
+        }
+
+        libraries.sort_by(|a, b| a.name.cmp(&b.name));
+        for library in libraries.iter_mut() {
+            library.units.sort_by_key(|unit| unit.name.to_lowercase());
+        }
+
+        Documentation { libraries }.filter(|_| true)
+    }
+
+    /// The documentation of the libraries to keep.
+    /// Links to declarations that are no longer documented are removed.
+    pub fn filter(&self, keep: impl Fn(&LibraryDoc) -> bool) -> Documentation {
+        let mut libraries: Vec<LibraryDoc> = self
+            .libraries
+            .iter()
+            .filter(|library| keep(library))
+            .cloned()
+            .collect();
+
+        let anchors: FnvHashSet<String> = libraries
+            .iter()
+            .flat_map(|library| library.anchors())
+            .collect();
+
+        for library in libraries.iter_mut() {
+            for unit in library.units.iter_mut() {
+                for object in unit
+                    .generics
+                    .iter_mut()
+                    .chain(unit.ports.iter_mut())
+                    .chain(unit.constants.iter_mut())
+                {
+                    if let Some(ref link) = object.type_link {
+                        if !anchors.contains(&link.anchor) {
+                            object.type_link = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        Documentation { libraries }
+    }
+}
+
+impl LibraryDoc {
+    /// The name of the file that contains the documentation of the library
+    pub fn file_name(&self, format: DocumentationFormat) -> String {
+        file_name(&self.name, format)
+    }
+
+    /// The anchors of the documented units and types
+    fn anchors(&self) -> impl Iterator<Item = String> + '_ {
+        self.units.iter().flat_map(move |unit| {
+            let unit_anchor = anchor(&[&self.name, &unit.name]);
+            unit.types
+                .iter()
+                .map(move |typ| anchor(&[&self.name, &unit.name, &typ.name]))
+                .chain(std::iter::once(unit_anchor))
+        })
+    }
+
+    pub fn render(&self, format: DocumentationFormat) -> String {
+        match format {
+            DocumentationFormat::Markdown => self.to_markdown(),
+            DocumentationFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let format = DocumentationFormat::Markdown;
+        let mut md = format!("# Library `{}`\n", self.name);
+
+        for unit in self.units.iter() {
+            let unit_anchor = anchor(&[&self.name, &unit.name]);
+            write!(
+                md,
+                "\n<a id=\"{unit_anchor}\"></a>\n\n## {} `{}`\n",
+                unit.kind, unit.name
+            )
+            .unwrap();
+            if let Some(ref documentation) = unit.documentation {
+                write!(md, "\n{documentation}\n").unwrap();
+            }
+
+            for (title, objects) in [
+                ("Generics", &unit.generics),
+                ("Ports", &unit.ports),
+                ("Constants", &unit.constants),
+            ] {
+                if objects.is_empty() {
+                    continue;
+                }
+                let with_mode = objects.iter().any(|object| object.mode.is_some());
+                let value_title = if title == "Constants" {
+                    "Value"
+                } else {
+                    "Default"
+                };
+                write!(md, "\n### {title}\n\n| Name |").unwrap();
+                if with_mode {
+                    md.push_str(" Mode |");
+                }
+                writeln!(md, " Type | {value_title} | Description |").unwrap();
+                md.push_str(if with_mode {
+                    "| --- | --- | --- | --- | --- |\n"
+                } else {
+                    "| --- | --- | --- | --- |\n"
+                });
+
+                for object in objects.iter() {
+                    write!(md, "| {} |", md_cell(&object.name)).unwrap();
+                    if with_mode {
+                        write!(md, " {} |", md_cell(object.mode.as_deref().unwrap_or(""))).unwrap();
+                    }
+                    let subtype = md_cell(&object.subtype);
+                    let subtype = if let Some(ref link) = object.type_link {
+                        format!("[{subtype}]({})", href(link, format))
+                    } else {
+                        subtype
+                    };
+                    writeln!(
+                        md,
+                        " {subtype} | {} | {} |",
+                        md_cell(object.value.as_deref().unwrap_or("")),
+                        md_cell(object.documentation.as_deref().unwrap_or(""))
+                    )
+                    .unwrap();
+                }
+            }
+
+            for (title, declarations) in [
+                ("Types", &unit.types),
+                ("Subprograms", &unit.subprograms),
+                ("Components", &unit.components),
+            ] {
+                if declarations.is_empty() {
+                    continue;
+                }
+                write!(md, "\n### {title}\n").unwrap();
+                for decl in declarations.iter() {
+                    if title == "Types" {
+                        let type_anchor = anchor(&[&self.name, &unit.name, &decl.name]);
+                        write!(md, "\n<a id=\"{type_anchor}\"></a>\n").unwrap();
+                    }
+                    write!(md, "\n```vhdl\n{}\n```\n", decl.declaration).unwrap();
+                    if let Some(ref documentation) = decl.documentation {
+                        write!(md, "\n{documentation}\n").unwrap();
+                    }
+                }
+            }
+        }
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let format = DocumentationFormat::Html;
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Library {0}</title>\n</head>\n<body>\n<h1>Library {0}</h1>\n",
+            escape_html(&self.name)
+        );
+
+        for unit in self.units.iter() {
+            let unit_anchor = anchor(&[&self.name, &unit.name]);
+            writeln!(
+                html,
+                "<h2 id=\"{unit_anchor}\">{} <code>{}</code></h2>",
+                unit.kind,
+                escape_html(&unit.name)
+            )
+            .unwrap();
+            if let Some(ref documentation) = unit.documentation {
+                writeln!(html, "<p>{}</p>", html_text(documentation)).unwrap();
+            }
+
+            for (title, objects) in [
+                ("Generics", &unit.generics),
+                ("Ports", &unit.ports),
+                ("Constants", &unit.constants),
+            ] {
+                if objects.is_empty() {
+                    continue;
+                }
+                let with_mode = objects.iter().any(|object| object.mode.is_some());
+                let value_title = if title == "Constants" {
+                    "Value"
+                } else {
+                    "Default"
+                };
+                write!(html, "<h3>{title}</h3>\n<table>\n<tr><th>Name</th>").unwrap();
+                if with_mode {
+                    html.push_str("<th>Mode</th>");
+                }
+                writeln!(
+                    html,
+                    "<th>Type</th><th>{value_title}</th><th>Description</th></tr>"
+                )
+                .unwrap();
+
+                for object in objects.iter() {
+                    write!(html, "<tr><td>{}</td>", escape_html(&object.name)).unwrap();
+                    if with_mode {
+                        write!(
+                            html,
+                            "<td>{}</td>",
+                            escape_html(object.mode.as_deref().unwrap_or(""))
+                        )
+                        .unwrap();
+                    }
+                    let subtype = escape_html(&object.subtype);
+                    let subtype = if let Some(ref link) = object.type_link {
+                        format!("<a href=\"{}\">{subtype}</a>", href(link, format))
+                    } else {
+                        subtype
+                    };
+                    writeln!(
+                        html,
+                        "<td><code>{subtype}</code></td><td><code>{}</code></td><td>{}</td></tr>",
+                        escape_html(object.value.as_deref().unwrap_or("")),
+                        html_text(object.documentation.as_deref().unwrap_or(""))
+                    )
+                    .unwrap();
+                }
+                html.push_str("</table>\n");
+            }
+
+            for (title, declarations) in [
+                ("Types", &unit.types),
+                ("Subprograms", &unit.subprograms),
+                ("Components", &unit.components),
+            ] {
+                if declarations.is_empty() {
+                    continue;
+                }
+                writeln!(html, "<h3>{title}</h3>").unwrap();
+                for decl in declarations.iter() {
+                    let id = if title == "Types" {
+                        format!(" id=\"{}\"", anchor(&[&self.name, &unit.name, &decl.name]))
+                    } else {
+                        String::new()
+                    };
+                    writeln!(
+                        html,
+                        "<pre{id}><code>{}</code></pre>",
+                        escape_html(&decl.declaration)
+                    )
+                    .unwrap();
+                    if let Some(ref documentation) = decl.documentation {
+                        writeln!(html, "<p>{}</p>", html_text(documentation)).unwrap();
+                    }
+                }
+            }
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+struct UnitDocBuilder<'a> {
+    root: &'a DesignRoot,
+}
+
+impl<'a> UnitDocBuilder<'a> {
+    fn unit(&self, unit: &AnyDesignUnit) -> Option<UnitDoc> {
+        match unit {
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)) => {
+                let mut doc = self.new_unit(UnitKind::Entity, &entity.ident.tree);
+                doc.generics = self.interface_list(entity.generic_clause.as_deref());
+                doc.ports = self.interface_list(entity.port_clause.as_deref());
+                self.declarations(&mut doc, &entity.decl);
+                Some(doc)
+            }
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package)) => {
+                let mut doc = self.new_unit(UnitKind::Package, &package.ident.tree);
+                doc.generics = self.interface_list(package.generic_clause.as_deref());
+                self.declarations(&mut doc, &package.decl);
+                Some(doc)
+            }
+            _ => None,
+        }
+    }
+
+    fn new_unit(&self, kind: UnitKind, ident: &Ident) -> UnitDoc {
+        UnitDoc {
+            kind,
+            name: ident.item.to_string(),
+            documentation: self.root.doc_comment(&ident.pos),
+            generics: Vec::new(),
+            ports: Vec::new(),
+            types: Vec::new(),
+            constants: Vec::new(),
+            subprograms: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn declarations(&self, doc: &mut UnitDoc, declarations: &[Declaration]) {
+        for declaration in declarations.iter() {
+            match declaration {
+                Declaration::Type(typ) => doc.types.push(DeclarationDoc {
+                    name: typ.ident.tree.item.to_string(),
+                    declaration: typ.to_string(),
+                    documentation: self.root.doc_comment(&typ.ident.tree.pos),
+                }),
+                Declaration::Object(object) if object.class == ObjectClass::Constant => {
+                    doc.constants.push(ObjectDoc {
+                        name: object.ident.tree.item.to_string(),
+                        mode: None,
+                        subtype: object.subtype_indication.to_string(),
+                        type_link: self.type_link(&object.subtype_indication),
+                        value: object.expression.as_ref().map(|expr| expr.to_string()),
+                        documentation: self.root.doc_comment(&object.ident.tree.pos),
+                    })
+                }
+                Declaration::SubprogramDeclaration(subprogram) => {
+                    let designator = subprogram_designator(&subprogram.specification);
+                    doc.subprograms.push(DeclarationDoc {
+                        name: designator.item.to_string(),
+                        declaration: subprogram.to_string(),
+                        documentation: self.root.doc_comment(&designator.pos),
+                    })
+                }
+                Declaration::Component(component) => doc.components.push(DeclarationDoc {
+                    name: component.ident.tree.item.to_string(),
+                    declaration: component.to_string(),
+                    documentation: self.root.doc_comment(&component.ident.tree.pos),
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    fn interface_list(&self, list: Option<&[InterfaceDeclaration]>) -> Vec<ObjectDoc> {
+        list.unwrap_or_default()
+            .iter()
+            .map(|decl| match decl {
+                InterfaceDeclaration::Object(object) => ObjectDoc {
+                    name: object.ident.tree.item.to_string(),
+                    mode: match object.list_type {
+                        InterfaceType::Port => Some(object.mode.to_string()),
+                        _ => None,
+                    },
+                    subtype: object.subtype_indication.to_string(),
+                    type_link: self.type_link(&object.subtype_indication),
+                    value: object.expression.as_ref().map(|expr| expr.to_string()),
+                    documentation: self.root.doc_comment(&object.ident.tree.pos),
+                },
+                InterfaceDeclaration::File(file) => ObjectDoc {
+                    name: file.ident.tree.item.to_string(),
+                    mode: Some("file".to_owned()),
+                    subtype: file.subtype_indication.to_string(),
+                    type_link: self.type_link(&file.subtype_indication),
+                    value: None,
+                    documentation: self.root.doc_comment(&file.ident.tree.pos),
+                },
+                InterfaceDeclaration::Type(ident) => ObjectDoc {
+                    name: ident.tree.item.to_string(),
+                    mode: Some("type".to_owned()),
+                    subtype: String::new(),
+                    type_link: None,
+                    value: None,
+                    documentation: self.root.doc_comment(&ident.tree.pos),
+                },
+                InterfaceDeclaration::Subprogram(spec, default) => {
+                    let designator = subprogram_designator(spec);
+                    let kind = match spec {
+                        SubprogramSpecification::Procedure(_) => "procedure",
+                        SubprogramSpecification::Function(_) => "function",
+                    };
+                    ObjectDoc {
+                        name: designator.item.to_string(),
+                        mode: Some(kind.to_owned()),
+                        subtype: spec.to_string(),
+                        type_link: None,
+                        value: default.as_ref().map(|default| default.to_string()),
+                        documentation: self.root.doc_comment(&designator.pos),
+                    }
+                }
+                InterfaceDeclaration::Package(package) => ObjectDoc {
+                    name: package.ident.tree.item.to_string(),
+                    mode: Some("package".to_owned()),
+                    subtype: package.package_name.item.to_string(),
+                    type_link: None,
+                    value: None,
+                    documentation: self.root.doc_comment(&package.ident.tree.pos),
+                },
+            })
+            .collect()
+    }
+
+    /// A link to the type mark of a subtype indication
+    fn type_link(&self, subtype: &SubtypeIndication) -> Option<DocLink> {
+        let ent = self
+            .root
+            .get_ent(subtype.type_mark.item.name.item.reference()?);
+        let AnyEntKind::Type(_) = ent.kind() else {
+            return None;
+        };
+        let unit = ent.parent?;
+        let library = unit.parent?;
+        let AnyEntKind::Library = library.kind() else {
+            return None;
+        };
+        Some(DocLink {
+            library_name: library.designator().to_string(),
+            anchor: ent.path_name().to_lowercase(),
+        })
+    }
+}
+
+fn subprogram_designator(spec: &SubprogramSpecification) -> &WithPos<SubprogramDesignator> {
+    match spec {
+        SubprogramSpecification::Procedure(procedure) => &procedure.designator.tree,
+        SubprogramSpecification::Function(function) => &function.designator.tree,
+    }
+}
+
+fn anchor(names: &[&str]) -> String {
+    names.join(".").to_lowercase()
+}
+
+fn file_name(library_name: &str, format: DocumentationFormat) -> String {
+    format!("{}.{}", library_name.to_lowercase(), format.extension())
+}
+
+fn href(link: &DocLink, format: DocumentationFormat) -> String {
+    format!("{}#{}", file_name(&link.library_name, format), link.anchor)
+}
+
+/// Text that can be put in a cell of a markdown table
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escaped text where line breaks are kept
+fn html_text(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    fn documentation(builder: &LibraryBuilder) -> Documentation {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+        Documentation::new(&root).filter(|library| library.name == "libname")
+    }
+
+    #[test]
+    fn documents_entity_generics_and_ports() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+-- A simple counter
+entity Counter is
+  generic (
+    width : natural := 8 -- Number of bits
+  );
+  port (
+    -- The clock
+    clk : in bit;
+    count : out bit_vector(width - 1 downto 0)
+  );
+end entity;
+
+architecture rtl of Counter is
+begin
+end architecture;",
+        );
+
+        let documentation = documentation(&builder);
+        assert_eq!(documentation.libraries.len(), 1);
+        let library = &documentation.libraries[0];
+        assert_eq!(library.units.len(), 1);
+        let unit = &library.units[0];
+        assert_eq!(unit.kind, UnitKind::Entity);
+        assert_eq!(unit.name, "Counter");
+        assert_eq!(unit.documentation.as_deref(), Some("A simple counter"));
+        assert_eq!(
+            unit.generics,
+            vec![ObjectDoc {
+                name: "width".to_owned(),
+                mode: None,
+                subtype: "natural".to_owned(),
+                type_link: None,
+                value: Some("8".to_owned()),
+                documentation: Some("Number of bits".to_owned()),
+            }]
+        );
+        assert_eq!(
+            unit.ports
+                .iter()
+                .map(|port| (
+                    port.name.as_str(),
+                    port.mode.as_deref(),
+                    port.subtype.as_str(),
+                    port.documentation.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("clk", Some("in"), "bit", Some("The clock")),
+                ("count", Some("out"), "bit_vector(width - 1 downto 0)", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn documents_package_declarations_with_links() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  -- A machine word
+  subtype word_t is bit_vector(31 downto 0);
+  constant zero : word_t := (others => '0');
+  function add(a, b : word_t) return word_t;
+end package;
+
+use work.pkg.all;
+
+entity ent is
+  port (
+    data : in word_t
+  );
+end entity;",
+        );
+
+        let documentation = documentation(&builder);
+        let library = &documentation.libraries[0];
+        assert_eq!(
+            library
+                .units
+                .iter()
+                .map(|unit| unit.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ent", "pkg"]
+        );
+
+        let pkg = &library.units[1];
+        assert_eq!(pkg.types.len(), 1);
+        assert_eq!(pkg.types[0].name, "word_t");
+        assert_eq!(
+            pkg.types[0].documentation.as_deref(),
+            Some("A machine word")
+        );
+        assert_eq!(pkg.constants.len(), 1);
+        assert_eq!(pkg.constants[0].value.as_deref(), Some("(others => '0')"));
+        assert_eq!(pkg.subprograms.len(), 1);
+        assert_eq!(pkg.subprograms[0].name, "add");
+
+        let link = Some(DocLink {
+            library_name: "libname".to_owned(),
+            anchor: "libname.pkg.word_t".to_owned(),
+        });
+        assert_eq!(pkg.constants[0].type_link, link);
+        assert_eq!(library.units[0].ports[0].type_link, link);
+
+        let markdown = library.to_markdown();
+        assert!(markdown.contains("<a id=\"libname.pkg.word_t\"></a>"));
+        assert!(markdown.contains("| data | in | [word_t](libname.md#libname.pkg.word_t) |  |  |"));
+
+        let html = library.to_html();
+        assert!(html.contains("<pre id=\"libname.pkg.word_t\">"));
+        assert!(html.contains("<a href=\"libname.html#libname.pkg.word_t\">word_t</a>"));
+    }
+
+    #[test]
+    fn links_to_filtered_libraries_are_removed() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "other",
+            "
+package pkg is
+  type state_t is (idle, busy);
+end package;",
+        );
+        builder.code(
+            "libname",
+            "
+library other;
+use other.pkg.all;
+
+package user is
+  constant initial : state_t := idle;
+end package;",
+        );
+
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+        let documentation = Documentation::new(&root);
+
+        let user = |documentation: &Documentation| {
+            documentation
+                .libraries
+                .iter()
+                .find(|library| library.name == "libname")
+                .unwrap()
+                .units[0]
+                .constants[0]
+                .type_link
+                .clone()
+        };
+        assert_eq!(
+            user(&documentation),
+            Some(DocLink {
+                library_name: "other".to_owned(),
+                anchor: "other.pkg.state_t".to_owned(),
+            })
+        );
+        assert_eq!(
+            user(&documentation.filter(|library| library.name == "libname")),
+            None
+        );
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(md_cell("a | b\nc"), "a \\| b<br>c");
+        assert_eq!(
+            escape_html("a <= \"b\" & c"),
+            "a &lt;= &quot;b&quot; &amp; c"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Folding ranges of design units, declarations, statements and comment blocks
+
+use crate::analysis::DesignRoot;
+use crate::ast::visitor::{Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Range, Source, SrcPos};
+use crate::syntax::{HasTokenSpan, TokenAccess};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FoldingRangeKind {
+    /// A design unit, declaration or statement
+    Region,
+    /// A block of comments on consecutive lines
+    Comment,
+    /// The library and use clauses of a design unit
+    Imports,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FoldingRange {
+    pub kind: FoldingRangeKind,
+    /// The zero-based line where the folded range starts and that stays visible
+    pub start_line: u32,
+    /// The zero-based last line that is folded
+    pub end_line: u32,
+}
+
+impl DesignRoot {
+    /// The folding ranges of a source file sorted by their start line
+    pub fn folding_ranges(&self, source: &Source) -> Vec<FoldingRange> {
+        let mut visitor = FoldingRangeVisitor { ranges: Vec::new() };
+        self.walk_source(source, &mut visitor);
+        let mut ranges = visitor.ranges;
+        ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+        ranges.dedup();
+        ranges
+    }
+}
+
+struct FoldingRangeVisitor {
+    ranges: Vec<FoldingRange>,
+}
+
+impl FoldingRangeVisitor {
+    fn push(&mut self, kind: FoldingRangeKind, start_line: u32, end_line: u32) {
+        if end_line > start_line {
+            self.ranges.push(FoldingRange {
+                kind,
+                start_line,
+                end_line,
+            });
+        }
+    }
+
+    /// A region where the last line such as `end process;` stays visible
+    fn region(&mut self, pos: &SrcPos) {
+        let Range { start, end } = pos.range();
+        self.push(
+            FoldingRangeKind::Region,
+            start.line,
+            end.line.saturating_sub(1),
+        );
+    }
+
+    fn spanned(&mut self, ctx: &dyn TokenAccess, node: &impl HasTokenSpan) {
+        self.region(&node.get_pos(ctx));
+    }
+
+    fn context_clause(&mut self, ctx: &dyn TokenAccess, context_clause: &[ContextItem]) {
+        if let (Some(first), Some(last)) = (context_clause.first(), context_clause.last()) {
+            self.push(
+                FoldingRangeKind::Imports,
+                first.get_pos(ctx).start().line,
+                last.get_pos(ctx).end().line,
+            );
+        }
+    }
+
+    /// Blocks of comments on consecutive lines and comments spanning several lines
+    fn comments(&mut self, ctx: &dyn TokenAccess, node: &impl HasTokenSpan) {
+        let mut block: Option<(u32, u32)> = None;
+        let comments = node
+            .get_token_slice(ctx)
+            .iter()
+            .filter_map(|token| token.comments.as_ref())
+            .flat_map(|comments| comments.leading.iter());
+
+        for comment in comments {
+            let (start, end) = (comment.range.start.line, comment.range.end.line);
+            block = match block {
+                Some((block_start, block_end)) if !comment.multi_line && start == block_end + 1 => {
+                    Some((block_start, end))
+                }
+                Some((block_start, block_end)) => {
+                    self.push(FoldingRangeKind::Comment, block_start, block_end);
+                    Some((start, end))
+                }
+                None => Some((start, end)),
+            };
+            if comment.multi_line {
+                self.push(FoldingRangeKind::Comment, start, end);
+                block = None;
+            }
+        }
+        if let Some((block_start, block_end)) = block {
+            self.push(FoldingRangeKind::Comment, block_start, block_end);
+        }
+    }
+}
+
+impl Visitor for FoldingRangeVisitor {
+    fn visit_any_design_unit(
+        &mut self,
+        node: &AnyDesignUnit,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.comments(ctx, node);
+        let context_clause = match node {
+            AnyDesignUnit::Primary(primary) => match primary {
+                AnyPrimaryUnit::Entity(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Configuration(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Package(unit) => &unit.context_clause,
+                AnyPrimaryUnit::PackageInstance(unit) => &unit.context_clause,
+                AnyPrimaryUnit::Context(_) => return VisitorResult::Continue,
+            },
+            AnyDesignUnit::Secondary(secondary) => match secondary {
+                AnySecondaryUnit::Architecture(unit) => &unit.context_clause,
+                AnySecondaryUnit::PackageBody(unit) => &unit.context_clause,
+            },
+        };
+        self.context_clause(ctx, context_clause);
+        VisitorResult::Continue
+    }
+
+    fn visit_entity_declaration(
+        &mut self,
+        node: &EntityDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_architecture_body(
+        &mut self,
+        node: &ArchitectureBody,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_declaration(
+        &mut self,
+        node: &PackageDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_body(&mut self, node: &PackageBody, ctx: &dyn TokenAccess) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_configuration_declaration(
+        &mut self,
+        node: &ConfigurationDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_context_declaration(
+        &mut self,
+        node: &ContextDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.spanned(ctx, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_declaration(&mut self, node: &Declaration, ctx: &dyn TokenAccess) -> VisitorResult {
+        match node {
+            Declaration::Type(_)
+            | Declaration::Component(_)
+            | Declaration::SubprogramBody(_)
+            | Declaration::Package(_) => self.spanned(ctx, node),
+            _ => {}
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.region(&node.statement.pos);
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.region(&node.statement.pos);
+        VisitorResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use FoldingRangeKind::*;
+
+    fn folding_range(kind: FoldingRangeKind, start_line: u32, end_line: u32) -> FoldingRange {
+        FoldingRange {
+            kind,
+            start_line,
+            end_line,
+        }
+    }
+
+    #[test]
+    fn folds_units_statements_and_comments() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+-- A block of
+-- three lines
+-- of comments
+architecture rtl of ent is
+  signal sig : bit;
+begin
+  main : process
+  begin
+    if sig = '1' then
+      sig <= '0';
+    end if;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        assert_eq!(
+            root.folding_ranges(code.source()),
+            vec![
+                folding_range(Imports, 0, 1),
+                folding_range(Comment, 6, 8),
+                folding_range(Region, 9, 18),
+                folding_range(Region, 12, 17),
+                folding_range(Region, 14, 15),
+            ]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Call hierarchy of subprograms and processes, and type hierarchy of subtypes and aliases
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{
+    is_reference, FindAllEnt, FoundDeclaration, NotFinished, SearchState, Searcher,
+};
+use crate::ast::{Designator, Ident, Reference, WithDecl};
+use crate::data::SrcPos;
+use crate::named_entity::HasEntityId;
+use crate::syntax::{HasTokenSpan, TokenAccess};
+use crate::{AnyEntKind, EntRef, EntityId, Overloaded, Type};
+use fnv::FnvHashMap;
+
+/// The calls made from or to an entity
+#[derive(Clone, Debug)]
+pub struct Calls<'a> {
+    /// The calling or the called entity
+    pub ent: EntRef<'a>,
+    /// The positions of the called names
+    pub positions: Vec<SrcPos>,
+}
+
+impl DesignRoot {
+    /// The subprograms, processes and design units that call a subprogram
+    pub fn incoming_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        group_calls(
+            self.find_calls()
+                .into_iter()
+                .filter(|call| is_reference(ent, call.callee))
+                .map(|call| (call.caller, call.pos)),
+        )
+    }
+
+    /// The subprograms called by a subprogram, process or design unit
+    pub fn outgoing_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        group_calls(
+            self.find_calls()
+                .into_iter()
+                // Operators are called everywhere and would drown the subprograms
+                .filter(|call| !matches!(call.callee.designator(), Designator::OperatorSymbol(_)))
+                .filter(|call| is_reference(ent, call.caller))
+                .map(|call| (call.callee, call.pos)),
+        )
+    }
+
+    /// The type or subtype that a subtype or type alias is declared from
+    pub fn supertype<'a>(&'a self, ent: EntRef<'a>) -> Option<EntRef<'a>> {
+        supertype_of(ent)
+    }
+
+    /// The subtypes and type aliases declared from a type or subtype
+    pub fn subtypes<'a>(&'a self, ent: EntRef<'a>) -> Vec<EntRef<'a>> {
+        let mut searcher = FindAllEnt::new(self, |other| {
+            supertype_of(other).map(|supertype| supertype.id()) == Some(ent.id())
+        });
+        let _ = self.search(&mut searcher);
+        searcher.result.sort_by_key(|ent| ent.decl_pos());
+        searcher.result
+    }
+
+    fn find_calls(&self) -> Vec<Call<'_>> {
+        let mut searcher = CallSearcher {
+            root: self,
+            callers: Vec::new(),
+            calls: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+
+        let CallSearcher { callers, calls, .. } = searcher;
+        calls
+            .into_iter()
+            .filter_map(|(pos, callee)| {
+                // Callers are nested so the innermost caller is the one starting last
+                let (_, caller) = callers
+                    .iter()
+                    .filter(|(span, _)| span.source == pos.source && span.contains(pos.start()))
+                    .max_by_key(|(span, _)| span.start())?;
+                Some(Call {
+                    caller: self.get_ent(*caller),
+                    callee,
+                    pos,
+                })
+            })
+            .collect()
+    }
+}
+
+fn supertype_of(ent: EntRef) -> Option<EntRef> {
+    match ent.kind() {
+        AnyEntKind::Type(Type::Subtype(subtype)) => Some(subtype.type_mark().into()),
+        AnyEntKind::Type(Type::Alias(type_mark)) => Some((*type_mark).into()),
+        _ => None,
+    }
+}
+
+fn is_callable(ent: EntRef) -> bool {
+    matches!(ent.kind(), AnyEntKind::Overloaded(overloaded) if !matches!(overloaded, Overloaded::EnumLiteral(_)))
+}
+
+/// Group calls by the calling or called entity in the order of their declarations
+fn group_calls<'a>(calls: impl Iterator<Item = (EntRef<'a>, SrcPos)>) -> Vec<Calls<'a>> {
+    let mut index: FnvHashMap<EntityId, usize> = FnvHashMap::default();
+    let mut groups: Vec<Calls<'a>> = Vec::new();
+
+    for (ent, pos) in calls {
+        let idx = *index.entry(ent.id()).or_insert_with(|| {
+            groups.push(Calls {
+                ent,
+                positions: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[idx].positions.push(pos);
+    }
+
+    for group in groups.iter_mut() {
+        group.positions.sort();
+    }
+    groups.sort_by(|a, b| a.ent.decl_pos().cmp(&b.ent.decl_pos()));
+    groups
+}
+
+struct Call<'a> {
+    caller: EntRef<'a>,
+    callee: EntRef<'a>,
+    pos: SrcPos,
+}
+
+/// Collects the spans of everything that can make a call together with all calls made
+struct CallSearcher<'a> {
+    root: &'a DesignRoot,
+    callers: Vec<(SrcPos, EntityId)>,
+    calls: Vec<(SrcPos, EntRef<'a>)>,
+}
+
+impl<'a> Searcher for CallSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let span = match decl {
+            FoundDeclaration::Subprogram(ref body) => body.get_pos(ctx),
+            FoundDeclaration::Entity(ref unit) => unit.get_pos(ctx),
+            FoundDeclaration::Architecture(ref unit) => unit.get_pos(ctx),
+            FoundDeclaration::Package(ref unit) => unit.get_pos(ctx),
+            FoundDeclaration::PackageBody(ref unit) => unit.get_pos(ctx),
+            _ => return NotFinished,
+        };
+        if let Some(id) = decl.ent_id() {
+            self.callers.push((span, id));
+        }
+        NotFinished
+    }
+
+    fn search_process(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        label: &WithDecl<Option<Ident>>,
+        statement_pos: &SrcPos,
+    ) -> SearchState {
+        if let Some(id) = label.decl {
+            self.callers.push((statement_pos.clone(), id));
+        }
+        NotFinished
+    }
+
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &mut Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.as_ref() {
+            let ent = self.root.get_ent(*id);
+            if is_callable(ent) {
+                self.calls.push((pos.clone(), ent));
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::Code;
+
+    fn ent_at<'a>(root: &'a DesignRoot, code: &Code) -> EntRef<'a> {
+        root.search_reference(code.source(), code.start())
+            .expect("no entity at position")
+    }
+
+    fn names(calls: &[Calls]) -> Vec<String> {
+        calls
+            .iter()
+            .map(|calls| calls.ent.designator().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn incoming_calls_are_grouped_by_innermost_caller() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  function double(x : natural) return natural;
+  procedure report_it;
+end package;
+
+package body pkg is
+  function double(x : natural) return natural is
+  begin
+    return 2 * x;
+  end function;
+
+  procedure report_it is
+    function quad(x : natural) return natural is
+    begin
+      return double(double(x));
+    end function;
+  begin
+    report natural'image(quad(1));
+  end procedure;
+end package body;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant c : natural := double(1);
+begin
+  main : process
+    variable v : natural;
+  begin
+    v := double(v);
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let calls = root.incoming_calls(ent_at(&root, &code.s1("double")));
+        assert_eq!(names(&calls), vec!["quad", "rtl", "main"]);
+        assert_eq!(calls[0].positions.len(), 2);
+        assert_eq!(calls[1].positions, vec![code.s("double", 5).pos()]);
+        assert_eq!(calls[2].positions, vec![code.s("double", 6).pos()]);
+    }
+
+    #[test]
+    fn outgoing_calls_of_subprogram_declaration_are_found_in_its_body() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  function inc(x : natural) return natural;
+  function double(x : natural) return natural;
+  function both(x : natural) return natural;
+end package;
+
+package body pkg is
+  function inc(x : natural) return natural is
+  begin
+    return x + 1;
+  end function;
+
+  function double(x : natural) return natural is
+  begin
+    return 2 * x;
+  end function;
+
+  function both(x : natural) return natural is
+  begin
+    return inc(double(inc(x)));
+  end function;
+end package body;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let calls = root.outgoing_calls(ent_at(&root, &code.s1("both")));
+        assert_eq!(names(&calls), vec!["inc", "double"]);
+        assert_eq!(calls[0].positions.len(), 2);
+        assert_eq!(calls[1].positions, vec![code.s("double", 3).pos()]);
+
+        let calls = root.outgoing_calls(ent_at(&root, &code.s1("inc")));
+        assert!(calls.is_empty(), "operators are not listed");
+    }
+
+    #[test]
+    fn type_hierarchy_follows_subtypes_and_aliases() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  type word_t is array (natural range <>) of bit;
+  subtype byte_t is word_t(7 downto 0);
+  subtype octet_t is byte_t;
+  alias byte_alias_t is byte_t;
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let word = ent_at(&root, &code.s1("word_t"));
+        let byte = ent_at(&root, &code.s1("byte_t"));
+        let octet = ent_at(&root, &code.s1("octet_t"));
+
+        assert_eq!(root.supertype(word).map(|ent| ent.id()), None);
+        assert_eq!(root.supertype(byte).map(|ent| ent.id()), Some(word.id()));
+        assert_eq!(root.supertype(octet).map(|ent| ent.id()), Some(byte.id()));
+
+        let subtypes: Vec<_> = root
+            .subtypes(byte)
+            .into_iter()
+            .map(|ent| ent.designator().to_string())
+            .collect();
+        assert_eq!(subtypes, vec!["octet_t", "byte_alias_t"]);
+    }
+}
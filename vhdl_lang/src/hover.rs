@@ -0,0 +1,485 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Information shown when hovering over a named entity
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{
+    FormatDeclaration, FoundDeclaration, SearchResult, SearchState, Searcher,
+};
+use crate::ast::{Expression, Ident, InstantiationStatement, ObjectClass, WithDecl};
+use crate::data::{SrcPos, WithPos};
+use crate::named_entity::{HasEntityId, NamedEntities, Related};
+use crate::syntax::{Comment, Token, TokenAccess};
+use crate::{AnyEntKind, Concurrent, Design, EntRef, EntityId, Overloaded};
+
+/// Constants referring to other constants are resolved at most this deep
+const MAX_VALUE_DEPTH: usize = 8;
+
+/// Information about a named entity shown when hovering over it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HoverInfo {
+    /// The formatted declaration of the entity
+    pub declaration: String,
+    /// The leading and trailing comments of the declaration
+    pub documentation: Option<String>,
+    /// The subtype indication of an object including its constraint
+    pub subtype: Option<String>,
+    /// The base type of an object when it differs from its subtype
+    pub base_type: Option<String>,
+    /// The value of a constant or generic when it is statically known
+    pub value: Option<String>,
+    /// The library and design unit in which the entity is declared
+    pub declared_in: Option<String>,
+    /// The number of overloads sharing the designator of a subprogram
+    pub overloads: Option<usize>,
+    /// The signature of a subprogram that has overloads such as `f[BOOLEAN return INTEGER]`
+    pub signature: Option<String>,
+    /// The unit bound by an instance label
+    pub instance_of: Option<Box<HoverInfo>>,
+}
+
+impl DesignRoot {
+    /// Collect the hover information of a named entity
+    pub fn hover_info(&self, ent: EntRef) -> Option<HoverInfo> {
+        let ent = if let Related::InstanceOf(ent) = ent.related {
+            ent
+        } else {
+            ent
+        };
+
+        if let AnyEntKind::Library = ent.kind() {
+            return Some(HoverInfo {
+                declaration: self.format_declaration(ent)?,
+                ..Default::default()
+            });
+        }
+
+        let mut searcher = HoverSearcher::new(ent);
+        let _ = self.search(&mut searcher);
+
+        let mut info = HoverInfo {
+            declaration: searcher.format.result.take()?,
+            documentation: ent
+                .decl_pos()
+                .and_then(|decl_pos| self.doc_comment(decl_pos)),
+            subtype: searcher.subtype.take(),
+            declared_in: declared_in(ent),
+            overloads: overloads(ent),
+            ..Default::default()
+        };
+
+        if info.overloads.is_some_and(|overloads| overloads > 1) {
+            if let AnyEntKind::Overloaded(ref overloaded) = ent.kind() {
+                info.signature = Some(format!(
+                    "{}{}",
+                    ent.designator(),
+                    overloaded.signature().describe()
+                ));
+            }
+        }
+
+        if let AnyEntKind::Object(ref object) = ent.kind() {
+            let type_mark = object.subtype.type_mark();
+            let base_type = object.subtype.base_type();
+            if base_type.id() != type_mark.id() {
+                info.base_type = Some(base_type.designator().to_string());
+            }
+
+            if matches!(object.class, ObjectClass::Constant) {
+                info.value = searcher
+                    .expression
+                    .as_ref()
+                    .and_then(|expr| self.static_value(&expr.item, MAX_VALUE_DEPTH));
+            }
+        }
+
+        if let Some(id) = searcher.instance_of {
+            info.instance_of = self.hover_info(self.get_ent(id)).map(Box::new);
+        }
+
+        Some(info)
+    }
+
+    /// The value of an expression when it is a literal or a name of a literal or constant
+    fn static_value(&self, expr: &Expression, depth: usize) -> Option<String> {
+        match expr {
+            Expression::Literal(_) => Some(expr.to_string()),
+            Expression::Unary(_, operand) if matches!(operand.item, Expression::Literal(_)) => {
+                Some(expr.to_string())
+            }
+            Expression::Name(name) => {
+                let ent = self.get_ent(name.get_suffix_reference()?);
+                match ent.kind() {
+                    AnyEntKind::Overloaded(Overloaded::EnumLiteral(_)) => Some(name.to_string()),
+                    AnyEntKind::Object(object)
+                        if matches!(object.class, ObjectClass::Constant) && depth > 0 =>
+                    {
+                        let mut searcher = HoverSearcher::new(ent);
+                        let _ = self.search(&mut searcher);
+                        self.static_value(&searcher.expression?.item, depth - 1)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The comments immediately before the line of a declaration and at the end of it
+    pub(crate) fn doc_comment(&self, decl_pos: &SrcPos) -> Option<String> {
+        let tokens = self.tokens_at(decl_pos)?;
+        let idx = tokens
+            .binary_search_by(|token| token.pos.start().cmp(&decl_pos.start()))
+            .ok()?;
+        let line = decl_pos.start().line;
+
+        let mut first = idx;
+        while first > 0 && tokens[first - 1].pos.end().line == line {
+            first -= 1;
+        }
+        let mut last = idx;
+        while last + 1 < tokens.len() && tokens[last + 1].pos.start().line == line {
+            last += 1;
+        }
+
+        let mut lines = leading_comments(&tokens[first], line);
+        if let Some(comment) = tokens[last]
+            .comments
+            .as_ref()
+            .and_then(|comments| comments.trailing.as_ref())
+        {
+            lines.push(comment_text(comment));
+        }
+        lines.retain(|line| !line.is_empty());
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+/// The contiguous block of comments on the lines right before a token
+fn leading_comments(token: &Token, line: u32) -> Vec<String> {
+    let Some(comments) = token.comments.as_ref() else {
+        return Vec::new();
+    };
+
+    let mut next_line = line;
+    let mut block = Vec::new();
+    for comment in comments.leading.iter().rev() {
+        if comment.range.end.line + 1 != next_line {
+            break;
+        }
+        next_line = comment.range.start.line;
+        block.push(comment_text(comment));
+    }
+    block.reverse();
+    block
+}
+
+/// The text of a comment without doc comment markers such as `--!` or `---`
+fn comment_text(comment: &Comment) -> String {
+    comment
+        .value
+        .trim_start_matches(['!', '-'])
+        .trim()
+        .to_owned()
+}
+
+/// The name of the library and design unit declaring an entity
+fn declared_in(ent: EntRef) -> Option<String> {
+    let mut parent = ent.parent;
+    while let Some(ent) = parent {
+        match ent.kind() {
+            AnyEntKind::Design(Design::Architecture(entity)) => {
+                return Some(format!("{}({})", entity.path_name(), ent.designator()));
+            }
+            AnyEntKind::Design(_) | AnyEntKind::Library => return Some(ent.path_name()),
+            _ => parent = ent.parent,
+        }
+    }
+    None
+}
+
+/// The number of overloads with the same designator declared in the same region
+fn overloads(ent: EntRef) -> Option<usize> {
+    let AnyEntKind::Overloaded(ref overloaded) = ent.kind() else {
+        return None;
+    };
+    if matches!(overloaded, Overloaded::EnumLiteral(_)) {
+        return None;
+    }
+
+    let region = match ent.parent?.kind() {
+        AnyEntKind::Design(Design::Entity(_, region))
+        | AnyEntKind::Design(Design::Package(_, region))
+        | AnyEntKind::Design(Design::UninstPackage(_, region))
+        | AnyEntKind::Design(Design::PackageInstance(region)) => region,
+        _ => return None,
+    };
+
+    match region.lookup_immediate(ent.designator())? {
+        NamedEntities::Overloaded(overloaded) => Some(overloaded.len()),
+        NamedEntities::Single(_) => None,
+    }
+}
+
+// Search for the declaration of an entity and the parts of it shown on hover
+struct HoverSearcher<'a> {
+    ent: EntRef<'a>,
+    format: FormatDeclaration<'a>,
+    subtype: Option<String>,
+    expression: Option<WithPos<Expression>>,
+    instance_of: Option<EntityId>,
+}
+
+impl<'a> HoverSearcher<'a> {
+    fn new(ent: EntRef<'a>) -> HoverSearcher<'a> {
+        HoverSearcher {
+            ent,
+            format: FormatDeclaration::new(ent),
+            subtype: None,
+            expression: None,
+            instance_of: None,
+        }
+    }
+
+    fn is_instance(&self) -> bool {
+        matches!(
+            self.ent.kind(),
+            AnyEntKind::Concurrent(Some(Concurrent::Instance))
+        )
+    }
+}
+
+impl<'a> Searcher for HoverSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if decl.ent_id() == Some(self.ent.id()) {
+            match decl {
+                FoundDeclaration::Object(ref object) => {
+                    self.subtype = Some(object.subtype_indication.to_string());
+                    self.expression = object.expression.clone();
+                }
+                FoundDeclaration::InterfaceObject(ref object) => {
+                    self.subtype = Some(object.subtype_indication.to_string());
+                    self.expression = object.expression.clone();
+                }
+                FoundDeclaration::ConcurrentStatement(..) if self.is_instance() => {
+                    // Continue with search_instance to find the bound unit
+                    let _ = self.format.search_decl(ctx, decl);
+                    return SearchState::NotFinished;
+                }
+                _ => {}
+            }
+        }
+
+        self.format.search_decl(ctx, decl)
+    }
+
+    fn search_instance(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        label: &WithDecl<Option<Ident>>,
+        instance: &InstantiationStatement,
+    ) -> SearchState {
+        if label.decl == Some(self.ent.id()) {
+            self.instance_of = instance.entity_reference();
+            SearchState::Finished(SearchResult::Found)
+        } else {
+            SearchState::NotFinished
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::Code;
+
+    fn hover_info(builder: &LibraryBuilder, code: &Code) -> HoverInfo {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+        let ent = root
+            .search_reference(code.source(), code.start())
+            .expect("no entity at position");
+        root.hover_info(ent).expect("no hover info")
+    }
+
+    #[test]
+    fn hover_shows_leading_and_trailing_comments() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  -- Unrelated comment
+
+  --! The width of the bus
+  --! in bits
+  constant width : natural := 8; -- at least one
+end package;",
+        );
+
+        let info = hover_info(&builder, &code.s1("width :"));
+        assert_eq!(
+            info.documentation.as_deref(),
+            Some("The width of the bus\nin bits\nat least one")
+        );
+        assert_eq!(info.declaration, "constant width : natural := 8;");
+        assert_eq!(info.declared_in.as_deref(), Some("libname.pkg"));
+    }
+
+    #[test]
+    fn hover_without_comments_has_no_documentation() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  constant width : natural := 8;
+end package;",
+        );
+
+        let info = hover_info(&builder, &code.s1("width :"));
+        assert_eq!(info.documentation, None);
+    }
+
+    #[test]
+    fn hover_shows_subtype_and_base_type_of_objects() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal sig : integer range 0 to 7;
+begin
+end architecture;",
+        );
+
+        let info = hover_info(&builder, &code.s1("sig :"));
+        assert_eq!(info.subtype.as_deref(), Some("integer range 0 to 7"));
+        assert_eq!(info.base_type, None);
+        assert_eq!(info.value, None);
+        assert_eq!(info.declared_in.as_deref(), Some("libname.ent(a)"));
+
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  constant c : natural := 3;
+end package;",
+        );
+        let info = hover_info(&builder, &code.s1("c :"));
+        assert_eq!(info.subtype.as_deref(), Some("natural"));
+        assert_eq!(info.base_type.as_deref(), Some("INTEGER"));
+    }
+
+    #[test]
+    fn hover_shows_statically_known_values() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  type state_t is (idle, busy);
+  constant a : integer := -1;
+  constant b : integer := a;
+  constant c : state_t := busy;
+  constant d : integer := a + b;
+end package;",
+        );
+
+        let value = |name| hover_info(&builder, &code.s1(name)).value;
+        assert_eq!(value("a :").as_deref(), Some("-1"));
+        assert_eq!(value("b :").as_deref(), Some("-1"));
+        assert_eq!(value("c :").as_deref(), Some("busy"));
+        assert_eq!(value("d :"), None);
+    }
+
+    #[test]
+    fn hover_shows_generic_default_value() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  generic (
+    -- Number of stages
+    depth : positive := 4
+  );
+end entity;",
+        );
+
+        let info = hover_info(&builder, &code.s1("depth"));
+        assert_eq!(info.value.as_deref(), Some("4"));
+        assert_eq!(info.documentation.as_deref(), Some("Number of stages"));
+        assert_eq!(info.declared_in.as_deref(), Some("libname.ent"));
+    }
+
+    #[test]
+    fn hover_shows_number_of_overloads() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  function f(arg : integer) return integer;
+  function f(arg : boolean) return integer;
+  procedure p;
+  constant c : integer := f(true);
+end package;",
+        );
+
+        let info = hover_info(&builder, &code.s1("f(true)"));
+        assert_eq!(
+            info.declaration,
+            "function f(\n  constant arg : in boolean\n) return integer"
+        );
+        assert_eq!(info.overloads, Some(2));
+        assert_eq!(info.signature.as_deref(), Some("f[BOOLEAN return INTEGER]"));
+
+        let info = hover_info(&builder, &code.s1("p;"));
+        assert_eq!(info.overloads, Some(1));
+        assert_eq!(info.signature, None);
+    }
+
+    #[test]
+    fn hover_on_instance_label_shows_bound_entity() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity child is
+  port (
+    clk : in bit
+  );
+end entity;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal clk : bit;
+begin
+  inst: entity work.child port map (clk => clk);
+end architecture;",
+        );
+
+        let info = hover_info(&builder, &code.s1("inst"));
+        assert_eq!(info.declaration, "inst");
+        let bound = info.instance_of.expect("no bound unit");
+        assert!(bound.declaration.starts_with("entity child is"));
+        assert!(bound.declaration.contains("clk : in bit"));
+    }
+}
@@ -0,0 +1,538 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Hints shown inline in the source such as the formal of a positional association
+
+use crate::analysis::{positional_formals, DesignRoot};
+use crate::ast::visitor::{Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{FormalRegion, Type, UniversalType};
+use crate::syntax::{HasTokenSpan, Kind, TokenAccess};
+use crate::{AnyEntKind, Design, EntRef, EntityId};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InlayHintKind {
+    /// The formal of a positional association such as `clk =>`
+    FormalName,
+    /// The type of a loop parameter, generate parameter or alias such as `: integer`
+    Type,
+    /// The label or name omitted after an `end` keyword such as `rtl`
+    EndLabel,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InlayHint {
+    pub kind: InlayHintKind,
+    /// The position where the hint is shown
+    pub pos: Position,
+    pub label: String,
+}
+
+impl DesignRoot {
+    /// The inlay hints of a source file within a range
+    pub fn inlay_hints(&self, source: &Source, range: Range) -> Vec<InlayHint> {
+        let mut visitor = InlayHintVisitor {
+            root: self,
+            range,
+            hints: Vec::new(),
+        };
+        self.walk_source(source, &mut visitor);
+        visitor.hints.sort_by_key(|hint| hint.pos);
+        visitor.hints
+    }
+}
+
+struct InlayHintVisitor<'a> {
+    root: &'a DesignRoot,
+    range: Range,
+    hints: Vec<InlayHint>,
+}
+
+impl<'a> InlayHintVisitor<'a> {
+    fn push(&mut self, kind: InlayHintKind, pos: Position, label: String) {
+        if self.range.start <= pos && pos <= self.range.end {
+            self.hints.push(InlayHint { kind, pos, label });
+        }
+    }
+
+    fn formal_names(&mut self, formal_region: &FormalRegion, elems: &[AssociationElement]) {
+        for (actual, formal) in positional_formals(formal_region, elems) {
+            // A formal associated with an actual of the same name needs no hint
+            if let ActualPart::Expression(Expression::Name(ref name)) = actual.item {
+                if let Name::Designator(ref designator) = **name {
+                    if &designator.item == formal.designator() {
+                        continue;
+                    }
+                }
+            }
+            self.push(
+                InlayHintKind::FormalName,
+                actual.pos.start(),
+                format!("{} =>", formal.designator()),
+            );
+        }
+    }
+
+    fn map_aspect(&mut self, formal_region: &FormalRegion, map_aspect: Option<&MapAspect>) {
+        if let Some(map_aspect) = map_aspect {
+            self.formal_names(formal_region, &map_aspect.list.items);
+        }
+    }
+
+    fn type_of(&mut self, decl_pos: &SrcPos, ent: Option<EntityId>) {
+        let Some(id) = ent else {
+            return;
+        };
+        let type_mark = match self.root.get_ent(id).kind() {
+            AnyEntKind::LoopParameter(Some(base_type)) => match base_type.kind() {
+                // A loop parameter with a universal range has the type integer
+                Type::Universal(UniversalType::Integer) => match self.root.integer_type() {
+                    Some(integer) => integer.designator().clone(),
+                    None => return,
+                },
+                _ => base_type.designator().clone(),
+            },
+            AnyEntKind::Object(object) => object.subtype.type_mark().designator().clone(),
+            AnyEntKind::ObjectAlias { type_mark, .. } => type_mark.designator().clone(),
+            _ => return,
+        };
+        if !matches!(type_mark, Designator::Anonymous(_)) {
+            self.push(
+                InlayHintKind::Type,
+                decl_pos.end(),
+                format!(": {type_mark}"),
+            );
+        }
+    }
+
+    /// The label omitted after the end of a labeled statement
+    fn end_label(&mut self, label: &WithDecl<Option<Ident>>, statement_pos: &SrcPos) {
+        if let Some(ref label) = label.tree {
+            // The statement ends with a semicolon
+            let end = statement_pos.end();
+            self.push(
+                InlayHintKind::EndLabel,
+                Position::new(end.line, end.character.saturating_sub(1)),
+                label.item.to_string(),
+            );
+        }
+    }
+
+    /// The name omitted after the end of a declaration such as `end entity;`.
+    /// The reserved word is also shown when it is omitted such as `end;`.
+    fn end_name(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        node: &impl HasTokenSpan,
+        end_ident_pos: Option<&SrcPos>,
+        reserved_word: &str,
+        name: &dyn std::fmt::Display,
+    ) {
+        if end_ident_pos.is_some() {
+            return;
+        }
+        let tokens = node.get_token_slice(ctx);
+        let [.., last_word, semi_colon] = tokens else {
+            return;
+        };
+        if semi_colon.kind != Kind::SemiColon {
+            return;
+        }
+        let label = if last_word.kind == Kind::End {
+            format!("{reserved_word} {name}")
+        } else {
+            name.to_string()
+        };
+        self.push(InlayHintKind::EndLabel, semi_colon.pos.start(), label);
+    }
+}
+
+impl<'a> Visitor for InlayHintVisitor<'a> {
+    fn visit_instantiation_statement(
+        &mut self,
+        node: &InstantiationStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let Some(id) = node.entity_reference() else {
+            return VisitorResult::Continue;
+        };
+        let ent: EntRef = self.root.get_ent(id);
+        let (generics, ports) = match ent.kind() {
+            AnyEntKind::Design(Design::Entity(_, region)) => region.to_entity_formal(),
+            AnyEntKind::Component(region) => region.to_entity_formal(),
+            _ => return VisitorResult::Continue,
+        };
+        self.map_aspect(&generics, node.generic_map.as_ref());
+        self.map_aspect(&ports, node.port_map.as_ref());
+        VisitorResult::Continue
+    }
+
+    fn visit_call_or_indexed(
+        &mut self,
+        node: &CallOrIndexed,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let Some(id) = node.name.item.get_suffix_reference() {
+            if let AnyEntKind::Overloaded(overloaded) = self.root.get_ent(id).kind() {
+                self.formal_names(&overloaded.signature().formals, &node.parameters);
+            }
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_iteration_scheme(
+        &mut self,
+        node: &IterationScheme,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let IterationScheme::For(ref index, _) = node {
+            self.type_of(&index.tree.pos, index.decl);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_for_generate_statement(
+        &mut self,
+        node: &ForGenerateStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.type_of(&node.index_name.tree.pos, node.index_name.decl);
+        VisitorResult::Continue
+    }
+
+    fn visit_alias_declaration(
+        &mut self,
+        node: &AliasDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if node.subtype_indication.is_none() {
+            self.type_of(&node.designator.tree.pos, node.designator.decl);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let end_label_pos = match node.statement.item {
+            ConcurrentStatement::Block(ref block) => &block.end_label_pos,
+            ConcurrentStatement::Process(ref process) => &process.end_label_pos,
+            ConcurrentStatement::ForGenerate(ref gen) => &gen.end_label_pos,
+            ConcurrentStatement::IfGenerate(ref gen) => &gen.end_label_pos,
+            ConcurrentStatement::CaseGenerate(ref gen) => &gen.end_label_pos,
+            _ => return VisitorResult::Continue,
+        };
+        if end_label_pos.is_none() {
+            self.end_label(&node.label, &node.statement.pos);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let end_label_pos = match node.statement.item {
+            SequentialStatement::If(ref statement) => &statement.end_label_pos,
+            SequentialStatement::Case(ref statement) => &statement.end_label_pos,
+            SequentialStatement::Loop(ref statement) => &statement.end_label_pos,
+            _ => return VisitorResult::Continue,
+        };
+        if end_label_pos.is_none() {
+            self.end_label(&node.label, &node.statement.pos);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_entity_declaration(
+        &mut self,
+        node: &EntityDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "entity", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_architecture_body(
+        &mut self,
+        node: &ArchitectureBody,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "architecture", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_declaration(
+        &mut self,
+        node: &PackageDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "package", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_body(&mut self, node: &PackageBody, ctx: &dyn TokenAccess) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "package body", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_configuration_declaration(
+        &mut self,
+        node: &ConfigurationDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(
+            ctx,
+            node,
+            node.end_ident_pos.as_ref(),
+            "configuration",
+            name,
+        );
+        VisitorResult::Continue
+    }
+
+    fn visit_context_declaration(
+        &mut self,
+        node: &ContextDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "context", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_subprogram_body(
+        &mut self,
+        node: &SubprogramBody,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let (reserved_word, designator) = match node.specification {
+            SubprogramSpecification::Procedure(ref procedure) => {
+                ("procedure", &procedure.designator.tree.item)
+            }
+            SubprogramSpecification::Function(ref function) => {
+                ("function", &function.designator.tree.item)
+            }
+        };
+        self.end_name(
+            ctx,
+            node,
+            node.end_ident_pos.as_ref(),
+            reserved_word,
+            designator,
+        );
+        VisitorResult::Continue
+    }
+
+    fn visit_component_declaration(
+        &mut self,
+        node: &ComponentDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let name = &node.ident.tree.item;
+        self.end_name(ctx, node, node.end_ident_pos.as_ref(), "component", name);
+        VisitorResult::Continue
+    }
+
+    fn visit_type_declaration(
+        &mut self,
+        node: &TypeDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if matches!(
+            node.def,
+            TypeDefinition::Record(_)
+                | TypeDefinition::Protected(_)
+                | TypeDefinition::ProtectedBody(_)
+        ) {
+            let name = &node.ident.tree.item;
+            self.end_name(ctx, node, node.end_ident_pos.as_ref(), "", name);
+        }
+        VisitorResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::Code;
+
+    fn inlay_hints(builder: &LibraryBuilder, code: &Code) -> Vec<(InlayHintKind, String, String)> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+        let range = code.pos().range();
+        let contents = code.source().contents();
+        root.inlay_hints(code.source(), range)
+            .into_iter()
+            .map(|hint| {
+                // The rest of the line at the hint
+                let line = contents.get_line(hint.pos.line as usize).unwrap();
+                let rest = line[hint.pos.character as usize..].trim().to_owned();
+                (hint.kind, hint.label, rest)
+            })
+            .collect()
+    }
+
+    fn hint(kind: InlayHintKind, label: &str, rest: &str) -> (InlayHintKind, String, String) {
+        (kind, label.to_owned(), rest.to_owned())
+    }
+
+    #[test]
+    fn formal_names_of_positional_associations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity child is
+  generic (width : natural);
+  port (clk : in bit; data : in bit; valid : out bit);
+end entity child;
+
+architecture a of child is
+begin
+end architecture a;
+
+entity ent is
+end entity ent;
+
+architecture a of ent is
+  signal clk, d, v : bit;
+  function f(arg : natural; other : natural) return natural is
+  begin
+    return arg;
+  end function f;
+  constant c : natural := f(1, other => 2);
+begin
+  inst: entity work.child
+    generic map (c)
+    port map (clk, d, valid => v);
+end architecture a;",
+        );
+
+        use InlayHintKind::FormalName;
+        assert_eq!(
+            inlay_hints(&builder, &code),
+            vec![
+                hint(FormalName, "arg =>", "1, other => 2);"),
+                hint(FormalName, "width =>", "c)"),
+                hint(FormalName, "data =>", "d, valid => v);"),
+            ]
+        );
+    }
+
+    #[test]
+    fn types_of_loop_parameters_and_aliases() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity ent;
+
+architecture a of ent is
+  signal vec : bit_vector(7 downto 0);
+  alias msb is vec(7);
+begin
+  gen: for i in vec'range generate
+  end generate gen;
+
+  main: process
+  begin
+    for j in 0 to 3 loop
+    end loop;
+    wait;
+  end process main;
+end architecture a;",
+        );
+
+        use InlayHintKind::Type;
+        assert_eq!(
+            inlay_hints(&builder, &code),
+            vec![
+                hint(Type, ": BIT", "is vec(7);"),
+                hint(Type, ": INTEGER", "in vec'range generate"),
+                hint(Type, ": INTEGER", "in 0 to 3 loop"),
+            ]
+        );
+    }
+
+    #[test]
+    fn omitted_end_labels_and_names() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  type rec_t is record
+    field : bit;
+  end record;
+end;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  main: process
+  begin
+    lp: loop
+      exit;
+    end loop;
+  end process;
+
+  unlabeled: process
+  begin
+    wait;
+  end process unlabeled;
+end architecture;",
+        );
+
+        use InlayHintKind::EndLabel;
+        assert_eq!(
+            inlay_hints(&builder, &code),
+            vec![
+                hint(EndLabel, "rec_t", ";"),
+                hint(EndLabel, "package pkg", ";"),
+                hint(EndLabel, "ent", ";"),
+                hint(EndLabel, "lp", ";"),
+                hint(EndLabel, "main", ";"),
+                hint(EndLabel, "a", ";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_hints_within_range() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end;
+
+entity ent2 is
+end;",
+        );
+
+        let (root, _) = builder.get_analyzed_root();
+        let range = code.s1_to_end("entity ent2").pos().range();
+        let hints = root.inlay_hints(code.source(), range);
+        assert_eq!(
+            hints.into_iter().map(|hint| hint.label).collect::<Vec<_>>(),
+            vec!["entity ent2"]
+        );
+    }
+}
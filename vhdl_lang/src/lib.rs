@@ -18,28 +18,61 @@ pub mod ast;
 mod analysis;
 mod config;
 mod data;
+mod dependency_graph;
+mod documentation;
 mod lint;
 mod named_entity;
 mod project;
+mod standard;
 mod syntax;
 
+mod code_lens;
 mod completion;
+mod document_highlight;
+mod folding_ranges;
+mod hierarchy;
+mod hover;
+mod inlay_hints;
+mod refactor;
+mod rename;
+mod selection_ranges;
+mod workspace_symbols;
 
-pub use crate::config::Config;
+pub use crate::config::{Config, LibraryMapping};
 pub use crate::data::{
     Diagnostic, Latin1String, Message, MessageHandler, MessagePrinter, MessageType,
     NullDiagnostics, NullMessages, Position, Range, Severity, Source, SrcPos,
 };
 
 pub use crate::analysis::EntHierarchy;
+pub use crate::dependency_graph::{
+    DependencyGraph, DesignUnitNode, LibraryDependencyGraph, LibraryFile, UnitKind,
+};
+pub use crate::documentation::{
+    DeclarationDoc, DocLink, Documentation, DocumentationFormat, LibraryDoc, ObjectDoc, UnitDoc,
+};
 pub use crate::named_entity::{
     AnyEnt, AnyEntKind, Concurrent, Design, EntRef, EntityId, HasEntityId, Object, Overloaded,
     Related, Sequential, Type,
 };
 
-pub use crate::project::{Project, SourceFile};
+pub use crate::project::{Project, SharedLibraries, SourceFile};
+pub use crate::standard::VHDLStandard;
 pub use crate::syntax::{
     kind_str, HasTokenSpan, ParserResult, Token, TokenAccess, TokenId, TokenSpan, VHDLParser,
 };
 
-pub use completion::{list_completion_options, CompletionItem};
+pub use code_lens::{CodeLens, CodeLensKind, ResolvedCodeLens};
+pub use completion::{
+    default_snippets, list_completion_options, list_snippets, CompletionItem, ImportEdit, Snippet,
+    SnippetContext,
+};
+pub use document_highlight::{DocumentHighlight, DocumentHighlightKind};
+pub use folding_ranges::{FoldingRange, FoldingRangeKind};
+pub use hierarchy::Calls;
+pub use hover::HoverInfo;
+pub use inlay_hints::{InlayHint, InlayHintKind};
+pub use lint::naming::{NamingKind, NamingViolation};
+pub use refactor::{Refactoring, RefactoringKind, SourceEdit};
+pub use rename::RenameError;
+pub use workspace_symbols::SymbolMatch;
@@ -5,3 +5,6 @@
 // Copyright (c) 2022, Olof Kraigher olof.kraigher@gmail.com
 
 pub mod dead_code;
+pub mod naming;
+pub mod rules;
+pub mod style;
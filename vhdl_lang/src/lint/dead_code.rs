@@ -197,7 +197,7 @@ fn find_unused_declarations<'a>(
 }
 
 /// Use a struct to keep state of units that do not need to be re-scanned
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(crate) struct UnusedDeclarationsLinter {
     // library name, primary name
     diagnostics: FnvHashMap<(Symbol, Symbol), Vec<Diagnostic>>,
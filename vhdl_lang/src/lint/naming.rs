@@ -0,0 +1,530 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Checks that the names of declarations follow the naming conventions of the configuration
+
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::search::{FoundDeclaration, HasEntityId, Search, SearchState, Searcher};
+use crate::ast::{Designator, HasUnitId, Mode, ObjectClass, UnitId};
+use crate::config::Config;
+use crate::data::{DiagnosticHandler, HasSrcPos};
+use crate::named_entity::{ObjectInterface, Related};
+use crate::syntax::TokenAccess;
+use crate::{AnyEntKind, Design, Diagnostic, EntRef, EntityId, Object, Overloaded, SrcPos, Type};
+use fnv::FnvHashMap;
+use regex::Regex;
+use std::str::FromStr;
+
+/// The kinds of declarations that have a naming convention
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NamingKind {
+    Entity,
+    Architecture,
+    Package,
+    Configuration,
+    Context,
+    Component,
+    Type,
+    Subtype,
+    Constant,
+    Generic,
+    Signal,
+    Variable,
+    SharedVariable,
+    File,
+    InPort,
+    OutPort,
+    InOutPort,
+    BufferPort,
+    LinkagePort,
+    Parameter,
+    Function,
+    Procedure,
+    EnumLiteral,
+    Element,
+    Alias,
+    Attribute,
+    Label,
+    LoopParameter,
+}
+
+const NAMING_KINDS: [(NamingKind, &str); 28] = [
+    (NamingKind::Entity, "entity"),
+    (NamingKind::Architecture, "architecture"),
+    (NamingKind::Package, "package"),
+    (NamingKind::Configuration, "configuration"),
+    (NamingKind::Context, "context"),
+    (NamingKind::Component, "component"),
+    (NamingKind::Type, "type"),
+    (NamingKind::Subtype, "subtype"),
+    (NamingKind::Constant, "constant"),
+    (NamingKind::Generic, "generic"),
+    (NamingKind::Signal, "signal"),
+    (NamingKind::Variable, "variable"),
+    (NamingKind::SharedVariable, "shared_variable"),
+    (NamingKind::File, "file"),
+    (NamingKind::InPort, "in_port"),
+    (NamingKind::OutPort, "out_port"),
+    (NamingKind::InOutPort, "inout_port"),
+    (NamingKind::BufferPort, "buffer_port"),
+    (NamingKind::LinkagePort, "linkage_port"),
+    (NamingKind::Parameter, "parameter"),
+    (NamingKind::Function, "function"),
+    (NamingKind::Procedure, "procedure"),
+    (NamingKind::EnumLiteral, "enum_literal"),
+    (NamingKind::Element, "element"),
+    (NamingKind::Alias, "alias"),
+    (NamingKind::Attribute, "attribute"),
+    (NamingKind::Label, "label"),
+    (NamingKind::LoopParameter, "loop_parameter"),
+];
+
+impl NamingKind {
+    /// The name of the kind in the configuration such as `in_port`
+    pub fn name(&self) -> &'static str {
+        NAMING_KINDS
+            .iter()
+            .find(|(kind, _)| kind == self)
+            .map(|(_, name)| *name)
+            .unwrap()
+    }
+
+    /// The kind of the declaration of a named entity
+    fn of(ent: EntRef) -> Option<NamingKind> {
+        let kind = match ent.kind() {
+            AnyEntKind::Design(design) => match design {
+                Design::Entity(..) => NamingKind::Entity,
+                Design::Architecture(..) => NamingKind::Architecture,
+                Design::Package(..) | Design::UninstPackage(..) | Design::PackageInstance(..) => {
+                    NamingKind::Package
+                }
+                Design::Configuration => NamingKind::Configuration,
+                Design::Context(..) => NamingKind::Context,
+                Design::PackageBody => return None,
+            },
+            AnyEntKind::Component(..) => NamingKind::Component,
+            AnyEntKind::Type(Type::Subtype(..)) => NamingKind::Subtype,
+            AnyEntKind::Type(Type::Interface) => return None,
+            AnyEntKind::Type(..) => NamingKind::Type,
+            AnyEntKind::Object(Object { class, iface, .. }) => match (class, iface) {
+                (_, Some(ObjectInterface::Generic)) => NamingKind::Generic,
+                (_, Some(ObjectInterface::Port(mode))) => match mode {
+                    Mode::In => NamingKind::InPort,
+                    Mode::Out => NamingKind::OutPort,
+                    Mode::InOut => NamingKind::InOutPort,
+                    Mode::Buffer => NamingKind::BufferPort,
+                    Mode::Linkage => NamingKind::LinkagePort,
+                },
+                (_, Some(ObjectInterface::Parameter(_))) => NamingKind::Parameter,
+                (ObjectClass::Constant, None) => NamingKind::Constant,
+                (ObjectClass::Signal, None) => NamingKind::Signal,
+                (ObjectClass::Variable, None) => NamingKind::Variable,
+                (ObjectClass::SharedVariable, None) => NamingKind::SharedVariable,
+            },
+            AnyEntKind::DeferredConstant(..) => NamingKind::Constant,
+            AnyEntKind::File(..) => NamingKind::File,
+            AnyEntKind::InterfaceFile(..) => NamingKind::Parameter,
+            AnyEntKind::Overloaded(overloaded) => match overloaded {
+                Overloaded::SubprogramDecl(signature)
+                | Overloaded::Subprogram(signature)
+                | Overloaded::UninstSubprogramDecl(signature, _)
+                | Overloaded::UninstSubprogram(signature, _) => {
+                    if signature.return_type().is_some() {
+                        NamingKind::Function
+                    } else {
+                        NamingKind::Procedure
+                    }
+                }
+                Overloaded::EnumLiteral(..) => NamingKind::EnumLiteral,
+                Overloaded::Alias(..) => NamingKind::Alias,
+                Overloaded::InterfaceSubprogram(..) => return None,
+            },
+            AnyEntKind::ElementDeclaration(..) => NamingKind::Element,
+            AnyEntKind::ObjectAlias { .. } | AnyEntKind::ExternalAlias { .. } => NamingKind::Alias,
+            AnyEntKind::Attribute(..) => NamingKind::Attribute,
+            AnyEntKind::Concurrent(..) | AnyEntKind::Sequential(..) => NamingKind::Label,
+            AnyEntKind::LoopParameter(..) => NamingKind::LoopParameter,
+            AnyEntKind::PhysicalLiteral(..) | AnyEntKind::Library => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl FromStr for NamingKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMING_KINDS
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|(kind, _)| *kind)
+            .ok_or_else(|| format!("Unknown kind of declaration '{s}'"))
+    }
+}
+
+/// A regular expression that the names of a kind of declaration must match
+#[derive(Clone, Debug)]
+pub struct NamingRule {
+    pub kind: NamingKind,
+    pub regex: Regex,
+}
+
+impl NamingRule {
+    pub fn new(kind: NamingKind, pattern: &str) -> Result<NamingRule, String> {
+        let regex = Regex::new(pattern).map_err(|err| {
+            format!(
+                "Invalid naming convention '{pattern}' of {}: {err}",
+                kind.name()
+            )
+        })?;
+        Ok(NamingRule { kind, regex })
+    }
+}
+
+impl PartialEq for NamingRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Eq for NamingRule {}
+
+/// A declaration with a name that does not follow the naming convention of its kind
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NamingViolation {
+    /// The designator of the declaration
+    pub pos: SrcPos,
+    pub ent: EntityId,
+    pub message: String,
+    /// A name that follows the naming convention, when one can be made from the pattern
+    pub suggestion: Option<String>,
+}
+
+struct DeclarationSearcher {
+    declarations: Vec<(SrcPos, EntityId)>,
+}
+
+impl Searcher for DeclarationSearcher {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            self.declarations.push((decl.pos().clone(), id));
+        }
+        SearchState::NotFinished
+    }
+}
+
+fn find_violations(
+    root: &DesignRoot,
+    rules: &[NamingRule],
+    unit: &LockedUnit,
+) -> Vec<NamingViolation> {
+    let mut searcher = DeclarationSearcher {
+        declarations: Vec::new(),
+    };
+    let _ = unit.unit.write().search(&unit.tokens, &mut searcher);
+
+    searcher
+        .declarations
+        .into_iter()
+        .filter_map(|(pos, id)| {
+            let ent = root.get_ent(id);
+            // A subprogram body or full constant declaration is checked with its declaration
+            if ent.is_implicit() || matches!(ent.related, Related::DeclaredBy(_)) {
+                return None;
+            }
+            let Designator::Identifier(ref symbol) = ent.designator() else {
+                return None;
+            };
+            let kind = NamingKind::of(ent)?;
+            let rule = rules.iter().find(|rule| rule.kind == kind)?;
+            let name = symbol.name_utf8();
+            if rule.regex.is_match(&name) {
+                return None;
+            }
+
+            let mut description = kind.name().replace('_', " ");
+            description[..1].make_ascii_uppercase();
+            Some(NamingViolation {
+                pos,
+                ent: id,
+                message: format!(
+                    "{description} '{name}' does not match the naming convention '{}'",
+                    rule.regex.as_str()
+                ),
+                suggestion: suggestion(&rule.regex, &name),
+            })
+        })
+        .collect()
+}
+
+/// A name made by adding the literal prefix and suffix of a pattern such as `^i_` or `_t$`,
+/// if it matches the pattern.
+/// A prefix or suffix that differs only in case is replaced and the name may change case.
+fn suggestion(regex: &Regex, name: &str) -> Option<String> {
+    let pattern = regex.as_str();
+    let is_literal = |chr: &char| chr.is_ascii_alphanumeric() || *chr == '_';
+    let prefix: String = match pattern.strip_prefix('^') {
+        Some(rest) => rest.chars().take_while(is_literal).collect(),
+        None => String::new(),
+    };
+    let suffix: String = match pattern.strip_suffix('$') {
+        Some(rest) => {
+            let literal: String = rest.chars().rev().take_while(is_literal).collect();
+            // An escape sequence such as `\d` is not literal
+            if rest[..rest.len() - literal.len()].ends_with('\\') {
+                literal.chars().rev().skip(1).collect()
+            } else {
+                literal.chars().rev().collect()
+            }
+        }
+        None => String::new(),
+    };
+
+    let mut base = name;
+    if base
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(&prefix))
+    {
+        base = &base[prefix.len()..];
+    }
+    if base.len() >= suffix.len()
+        && base
+            .get(base.len() - suffix.len()..)
+            .is_some_and(|end| end.eq_ignore_ascii_case(&suffix))
+    {
+        base = &base[..base.len() - suffix.len()];
+    }
+    if base.is_empty() {
+        return None;
+    }
+
+    let name_with_affixes = format!("{prefix}{base}{suffix}");
+    [
+        name_with_affixes.clone(),
+        name_with_affixes.to_uppercase(),
+        name_with_affixes.to_lowercase(),
+    ]
+    .into_iter()
+    .find(|candidate| candidate != name && regex.is_match(candidate))
+}
+
+/// Whether the naming conventions are checked for the library of a unit
+fn is_checked(config: &Config, unit_id: &UnitId) -> bool {
+    config
+        .get_library(&unit_id.library_name().name_utf8())
+        .is_some_and(|library| library.checks_naming())
+}
+
+/// Keeps the violations of units that are not analysed again
+#[derive(Default, Clone)]
+pub(crate) struct NamingLinter {
+    rules: Vec<NamingRule>,
+    violations: FnvHashMap<UnitId, Vec<NamingViolation>>,
+}
+
+impl NamingLinter {
+    pub fn lint(
+        &mut self,
+        root: &DesignRoot,
+        config: &Config,
+        analyzed_units: &[UnitId],
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) {
+        if self.rules != config.naming_rules() {
+            self.rules = config.naming_rules().to_vec();
+            self.violations.clear();
+        }
+        if self.rules.is_empty() {
+            return;
+        }
+
+        // Prune violations that need to be re-computed or of units that no longer exist
+        for unit in analyzed_units {
+            self.violations.remove(unit);
+        }
+        self.violations
+            .retain(|unit_id, _| root.get_unit(unit_id).is_some());
+
+        for unit in root.units() {
+            let unit_id = unit.unit_id();
+            if is_checked(config, unit_id) && !self.violations.contains_key(unit_id) {
+                let violations = find_violations(root, &self.rules, unit);
+                self.violations.insert(unit_id.clone(), violations);
+            }
+        }
+
+        for violation in self.violations(config) {
+            diagnostics.push(Diagnostic::warning(&violation.pos, &violation.message));
+        }
+    }
+
+    /// The violations of the units of libraries where the naming conventions are checked
+    pub fn violations<'a>(
+        &'a self,
+        config: &'a Config,
+    ) -> impl Iterator<Item = &'a NamingViolation> {
+        self.violations
+            .iter()
+            .filter(|(unit_id, _)| is_checked(config, unit_id))
+            .flat_map(|(_, violations)| violations.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::check_no_diagnostics;
+    use std::path::Path;
+
+    fn config(naming: &str) -> Config {
+        Config::from_str(
+            &format!(
+                "
+[libraries]
+libname.files = []
+third.files = []
+third.is_third_party = true
+
+[naming]
+{naming}
+"
+            ),
+            Path::new(""),
+        )
+        .unwrap()
+    }
+
+    fn messages(builder: LibraryBuilder, config: &Config) -> Vec<(String, Option<String>)> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        let units: Vec<_> = root.units().map(|unit| unit.unit_id().clone()).collect();
+        let mut linter = NamingLinter::default();
+        let mut diagnostics = Vec::new();
+        linter.lint(&root, config, &units, &mut diagnostics);
+
+        let mut violations: Vec<_> = linter.violations(config).cloned().collect();
+        violations.sort_by_key(|violation| violation.pos.clone());
+        assert_eq!(diagnostics.len(), violations.len());
+        violations
+            .into_iter()
+            .map(|violation| (violation.message, violation.suggestion))
+            .collect()
+    }
+
+    #[test]
+    fn checks_names_of_each_kind_of_declaration() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+  generic (g_width : natural; depth : natural);
+  port (
+    i_clk : in bit;
+    rst_n : in bit;
+    o_data : out bit;
+    valid : out bit
+  );
+end entity;
+
+architecture rtl of ent is
+  type state_t is (idle, busy);
+  type mode is (read, write);
+  constant C_SIZE : natural := 8;
+  constant size : natural := 8;
+  signal s_ready_n : bit;
+begin
+end architecture;
+",
+        );
+        let config = config(
+            "
+in_port = '^i_'
+out_port = '^o_'
+generic = '^g_'
+type = '_t$'
+constant = '^C_[A-Z0-9_]+$'
+signal = '^s_.*(_n)?$'
+",
+        );
+        assert_eq!(
+            messages(builder, &config),
+            vec![
+                (
+                    "Generic 'depth' does not match the naming convention '^g_'".to_owned(),
+                    Some("g_depth".to_owned())
+                ),
+                (
+                    "In port 'rst_n' does not match the naming convention '^i_'".to_owned(),
+                    Some("i_rst_n".to_owned())
+                ),
+                (
+                    "Out port 'valid' does not match the naming convention '^o_'".to_owned(),
+                    Some("o_valid".to_owned())
+                ),
+                (
+                    "Type 'mode' does not match the naming convention '_t$'".to_owned(),
+                    Some("mode_t".to_owned())
+                ),
+                (
+                    "Constant 'size' does not match the naming convention '^C_[A-Z0-9_]+$'"
+                        .to_owned(),
+                    Some("C_SIZE".to_owned())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn third_party_libraries_are_not_checked_by_default() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "third",
+            "
+package pkg is
+  constant size : natural := 8;
+end package;
+",
+        );
+        assert_eq!(messages(builder, &config("constant = '^C_'")), vec![]);
+
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "third",
+            "
+package pkg is
+  constant size : natural := 8;
+end package;
+",
+        );
+        let config = Config::from_str(
+            "
+[libraries]
+third.files = []
+third.is_third_party = true
+third.naming = true
+
+[naming]
+constant = '^C_'
+",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(messages(builder, &config).len(), 1);
+    }
+
+    #[test]
+    fn suggests_names_from_literal_prefix_and_suffix() {
+        let suggest = |pattern: &str, name: &str| suggestion(&Regex::new(pattern).unwrap(), name);
+        assert_eq!(suggest("^i_", "clk"), Some("i_clk".to_owned()));
+        assert_eq!(suggest("^C_", "c_width"), Some("C_width".to_owned()));
+        assert_eq!(suggest("_t$", "state_T"), Some("state_t".to_owned()));
+        assert_eq!(suggest("^C_[A-Z]+$", "width"), Some("C_WIDTH".to_owned()));
+        assert_eq!(suggest("_\\d$", "data"), None);
+        assert_eq!(suggest("[0-9]", "data"), None);
+    }
+}
@@ -0,0 +1,294 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Lint rules that check the style and structure of the design units of a file
+
+use super::style::{
+    AfterClause, ArchitectureVariable, BufferPort, CaseWithoutOthers, FileNameMismatch,
+    KeywordCase, MagicNumber, MissingEndLabel, MultiplePrimaryUnits, NonStandardPackage,
+    UnlabeledProcess,
+};
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::visitor::{walk, Visitor};
+use crate::ast::{HasIdent, HasUnitId, UnitId};
+use crate::config::Config;
+use crate::data::{DiagnosticHandler, HasSource, Severity};
+use crate::{Diagnostic, Source, SrcPos};
+use fnv::{FnvHashMap, FnvHashSet};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The design units of a single library that were parsed from a file
+pub(crate) struct LintFile<'a> {
+    pub root: &'a DesignRoot,
+    pub source: &'a Source,
+    /// The units in the order they appear in the file
+    pub units: Vec<&'a LockedUnit>,
+}
+
+impl<'a> LintFile<'a> {
+    /// Walk the units of the file that have been analyzed
+    pub fn walk(&self, visitor: &mut dyn Visitor) {
+        for unit in self.units.iter() {
+            walk_unit(unit, visitor);
+        }
+    }
+}
+
+pub(crate) fn walk_unit(unit: &LockedUnit, visitor: &mut dyn Visitor) {
+    if let Some(guard) = unit.unit.get() {
+        walk(guard.data(), visitor, &unit.tokens);
+    }
+}
+
+/// A rule that reports the violations within a file
+pub(crate) trait LintRule: Sync {
+    /// The code of the rule that is used in the configuration and shown with its diagnostics
+    fn code(&self) -> &'static str;
+
+    fn check(&self, file: &LintFile, violations: &mut Vec<(SrcPos, String)>);
+}
+
+static LINT_RULES: [&dyn LintRule; 11] = [
+    &MissingEndLabel,
+    &KeywordCase,
+    &NonStandardPackage,
+    &CaseWithoutOthers,
+    &AfterClause,
+    &ArchitectureVariable,
+    &BufferPort,
+    &MagicNumber,
+    &UnlabeledProcess,
+    &FileNameMismatch,
+    &MultiplePrimaryUnits,
+];
+
+/// The code of the lint rule with a name, `None` if there is no such rule
+pub(crate) fn rule_code(name: &str) -> Option<&'static str> {
+    LINT_RULES
+        .iter()
+        .map(|rule| rule.code())
+        .find(|code| *code == name)
+}
+
+/// The files of the libraries that are not third party, with the units of a single library
+/// for files that belong to several libraries
+fn checked_files<'a>(root: &'a DesignRoot, config: &Config) -> Vec<LintFile<'a>> {
+    let mut units_by_file: FnvHashMap<&Path, (&Source, BTreeMap<String, Vec<&LockedUnit>>)> =
+        FnvHashMap::default();
+    for unit in root.units() {
+        let library_name = unit.unit_id().library_name().name_utf8();
+        if config
+            .get_library(&library_name)
+            .is_some_and(|library| !library.is_third_party)
+        {
+            let source = unit.source();
+            units_by_file
+                .entry(source.file_name())
+                .or_insert_with(|| (source, BTreeMap::new()))
+                .1
+                .entry(library_name)
+                .or_default()
+                .push(unit);
+        }
+    }
+
+    units_by_file
+        .into_values()
+        .filter_map(|(source, libraries)| {
+            let (_, mut units) = libraries.into_iter().next()?;
+            units.sort_by_key(|unit| unit.ident().pos.start());
+            Some(LintFile {
+                root,
+                source,
+                units,
+            })
+        })
+        .collect()
+}
+
+/// Keeps the diagnostics of files that have not changed since the previous analysis
+#[derive(Default, Clone)]
+pub(crate) struct RuleLinter {
+    rules: Vec<(&'static str, Severity)>,
+    files: FnvHashMap<Source, (Vec<UnitId>, Vec<Diagnostic>)>,
+}
+
+impl RuleLinter {
+    pub fn lint(
+        &mut self,
+        root: &DesignRoot,
+        config: &Config,
+        analyzed_units: &[UnitId],
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) {
+        let rules: Vec<_> = LINT_RULES
+            .iter()
+            .filter_map(|rule| Some((rule.code(), config.lint_severity(rule.code())?)))
+            .collect();
+        if self.rules != rules {
+            self.rules = rules;
+            self.files.clear();
+        }
+        if self.rules.is_empty() {
+            return;
+        }
+
+        // The diagnostics of a file may depend on the declarations of the units it uses
+        for unit_id in analyzed_units {
+            if let Some(unit) = root.get_unit(unit_id) {
+                self.files.remove(unit.source());
+            }
+        }
+
+        let files = checked_files(root, config);
+        let file_names: FnvHashSet<&Path> =
+            files.iter().map(|file| file.source.file_name()).collect();
+        self.files
+            .retain(|source, _| file_names.contains(source.file_name()));
+        for file in files.iter() {
+            let source = file.source;
+            let unit_ids: Vec<_> = file
+                .units
+                .iter()
+                .map(|unit| unit.unit_id().clone())
+                .collect();
+            if self
+                .files
+                .get(source)
+                .is_some_and(|(cached_ids, _)| *cached_ids == unit_ids)
+            {
+                continue;
+            }
+
+            let mut file_diagnostics = Vec::new();
+            for (code, severity) in self.rules.iter() {
+                let Some(rule) = LINT_RULES.iter().find(|rule| rule.code() == *code) else {
+                    continue;
+                };
+                let mut violations = Vec::new();
+                rule.check(file, &mut violations);
+                for (pos, message) in violations {
+                    file_diagnostics.push(Diagnostic::new(pos, message, *severity).with_code(code));
+                }
+            }
+            self.files
+                .insert(source.clone(), (unit_ids, file_diagnostics));
+        }
+
+        for (_, file_diagnostics) in self.files.values() {
+            diagnostics.append(file_diagnostics.iter().cloned());
+        }
+    }
+
+    /// Forget the diagnostics of a file that has changed
+    pub fn invalidate(&mut self, source: &Source) {
+        self.files.remove(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::check_no_diagnostics;
+
+    fn config(lint: &str) -> Config {
+        Config::from_str(
+            &format!("[libraries]\nlibname.files = []\n[lint]\n{lint}"),
+            Path::new(""),
+        )
+        .unwrap()
+    }
+
+    /// A design root with a single file `file.vhd` in library `libname`
+    fn root_of_file(code: &str) -> (DesignRoot, Source) {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code_with_file_name("libname", "file.vhd", code);
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        (root, code.source().clone())
+    }
+
+    fn lint(
+        linter: &mut RuleLinter,
+        root: &DesignRoot,
+        config: &Config,
+        analyzed_units: &[UnitId],
+    ) -> Vec<(&'static str, Severity)> {
+        let mut diagnostics = Vec::new();
+        linter.lint(root, config, analyzed_units, &mut diagnostics);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code.unwrap(), diagnostic.severity))
+            .collect()
+    }
+
+    #[test]
+    fn diagnostics_of_invalidated_files_are_recomputed() {
+        let (root, source) = root_of_file(
+            "
+package pkg is
+end package;
+",
+        );
+        let (changed_root, _) = root_of_file(
+            "
+package pkg is
+end package pkg;
+",
+        );
+        let units: Vec<_> = root.units().map(|unit| unit.unit_id().clone()).collect();
+        let config = config("missing_end_label = true");
+        let mut linter = RuleLinter::default();
+        let expected = vec![("missing_end_label", Severity::Warning)];
+        assert_eq!(lint(&mut linter, &root, &config, &units), expected);
+
+        // The file has the same units, the diagnostics are kept until it is invalidated
+        assert_eq!(lint(&mut linter, &changed_root, &config, &[]), expected);
+        linter.invalidate(&source);
+        assert_eq!(lint(&mut linter, &changed_root, &config, &[]), vec![]);
+    }
+
+    #[test]
+    fn diagnostics_are_recomputed_when_the_severity_changes() {
+        let (root, _) = root_of_file(
+            "
+package pkg is
+end package;
+",
+        );
+        let units: Vec<_> = root.units().map(|unit| unit.unit_id().clone()).collect();
+        let mut linter = RuleLinter::default();
+        assert_eq!(
+            lint(
+                &mut linter,
+                &root,
+                &config("missing_end_label = true"),
+                &units
+            ),
+            vec![("missing_end_label", Severity::Warning)]
+        );
+        assert_eq!(
+            lint(
+                &mut linter,
+                &root,
+                &config("missing_end_label = 'error'"),
+                &[]
+            ),
+            vec![("missing_end_label", Severity::Error)]
+        );
+        assert_eq!(
+            lint(
+                &mut linter,
+                &root,
+                &config("missing_end_label = false"),
+                &[]
+            ),
+            vec![]
+        );
+    }
+}
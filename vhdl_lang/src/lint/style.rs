@@ -0,0 +1,1324 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! The lint rules that check the style and structure of a file
+
+use super::rules::{walk_unit, LintFile, LintRule};
+use crate::analysis::LockedUnit;
+use crate::ast::visitor::{ASTNode, NextItemPos, Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Range, Source, SrcPos, WithPos};
+use crate::syntax::{kind_str, Token, TokenAccess};
+use crate::{AnyEntKind, Design};
+
+type Violations = Vec<(SrcPos, String)>;
+
+/// The analyzed design unit of a locked unit
+fn with_design_unit(unit: &LockedUnit, f: impl FnOnce(&AnyDesignUnit)) {
+    if let Some(guard) = unit.unit.get() {
+        f(guard.data());
+    }
+}
+
+/// The text of a position within a single line
+fn text_at(source: &Source, pos: &SrcPos) -> String {
+    let range = pos.range();
+    let contents = source.contents();
+    contents
+        .get_line(range.start.line as usize)
+        .unwrap_or_default()
+        .chars()
+        .skip(range.start.character as usize)
+        .take(range.end.character.saturating_sub(range.start.character) as usize)
+        .collect()
+}
+
+/// The position of the first token of an item
+fn first_token_pos(tokens: &[Token], pos: &SrcPos) -> SrcPos {
+    tokens
+        .iter()
+        .find(|token| token.pos.start() == pos.start())
+        .map(|token| token.pos.clone())
+        .unwrap_or_else(|| pos.clone())
+}
+
+/// Labeled compound statements and named declarations without the label or name after `end`
+pub(crate) struct MissingEndLabel;
+
+struct EndLabelVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> EndLabelVisitor<'a> {
+    fn end_label(&mut self, label: &WithDecl<Option<Ident>>, end_label_pos: &Option<SrcPos>) {
+        if let (Some(ref label), None) = (&label.tree, end_label_pos) {
+            self.violations.push((
+                label.pos.clone(),
+                format!("Missing end label '{}'", label.item),
+            ));
+        }
+    }
+
+    fn end_name(
+        &mut self,
+        pos: &SrcPos,
+        end_ident_pos: &Option<SrcPos>,
+        kind: &str,
+        name: &dyn std::fmt::Display,
+    ) {
+        if end_ident_pos.is_none() {
+            self.violations.push((
+                pos.clone(),
+                format!("Missing name '{name}' at the end of {kind}"),
+            ));
+        }
+    }
+}
+
+impl<'a> Visitor for EndLabelVisitor<'a> {
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let end_label_pos = match node.statement.item {
+            ConcurrentStatement::Block(ref block) => &block.end_label_pos,
+            ConcurrentStatement::Process(ref process) => &process.end_label_pos,
+            ConcurrentStatement::ForGenerate(ref gen) => &gen.end_label_pos,
+            ConcurrentStatement::IfGenerate(ref gen) => &gen.end_label_pos,
+            ConcurrentStatement::CaseGenerate(ref gen) => &gen.end_label_pos,
+            _ => return VisitorResult::Continue,
+        };
+        self.end_label(&node.label, end_label_pos);
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let end_label_pos = match node.statement.item {
+            SequentialStatement::If(ref statement) => &statement.end_label_pos,
+            SequentialStatement::Case(ref statement) => &statement.end_label_pos,
+            SequentialStatement::Loop(ref statement) => &statement.end_label_pos,
+            _ => return VisitorResult::Continue,
+        };
+        self.end_label(&node.label, end_label_pos);
+        VisitorResult::Continue
+    }
+
+    fn visit_entity_declaration(
+        &mut self,
+        node: &EntityDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "entity", &ident.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_architecture_body(
+        &mut self,
+        node: &ArchitectureBody,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "architecture", &ident.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_declaration(
+        &mut self,
+        node: &PackageDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "package", &ident.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_package_body(&mut self, node: &PackageBody, _ctx: &dyn TokenAccess) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "package body", &ident.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_configuration_declaration(
+        &mut self,
+        node: &ConfigurationDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(
+            &ident.pos,
+            &node.end_ident_pos,
+            "configuration",
+            &ident.item,
+        );
+        VisitorResult::Continue
+    }
+
+    fn visit_context_declaration(
+        &mut self,
+        node: &ContextDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "context", &ident.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_subprogram_body(
+        &mut self,
+        node: &SubprogramBody,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let (kind, designator) = match node.specification {
+            SubprogramSpecification::Procedure(ref procedure) => {
+                ("procedure", &procedure.designator.tree)
+            }
+            SubprogramSpecification::Function(ref function) => {
+                ("function", &function.designator.tree)
+            }
+        };
+        self.end_name(&designator.pos, &node.end_ident_pos, kind, &designator.item);
+        VisitorResult::Continue
+    }
+
+    fn visit_component_declaration(
+        &mut self,
+        node: &ComponentDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let ident = &node.ident.tree;
+        self.end_name(&ident.pos, &node.end_ident_pos, "component", &ident.item);
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for MissingEndLabel {
+    fn code(&self) -> &'static str {
+        "missing_end_label"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        file.walk(&mut EndLabelVisitor { violations });
+    }
+}
+
+/// Keywords that are not written in the same case as the first keyword of the file
+pub(crate) struct KeywordCase;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Lower,
+    Upper,
+    Mixed,
+}
+
+impl Case {
+    fn of(text: &str) -> Case {
+        if text == text.to_ascii_lowercase() {
+            Case::Lower
+        } else if text == text.to_ascii_uppercase() {
+            Case::Upper
+        } else {
+            Case::Mixed
+        }
+    }
+}
+
+impl LintRule for KeywordCase {
+    fn code(&self) -> &'static str {
+        "keyword_case"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        let mut expected = None;
+        for unit in file.units.iter() {
+            for token in unit.tokens.iter() {
+                if !kind_str(token.kind).starts_with(|chr: char| chr.is_ascii_alphabetic()) {
+                    continue;
+                }
+                let text = text_at(file.source, &token.pos);
+                let case = Case::of(&text);
+                match expected {
+                    None if case != Case::Mixed => expected = Some(case),
+                    Some(expected) if case == expected => {}
+                    _ => {
+                        let expected = match expected {
+                            Some(Case::Upper) => "uppercase",
+                            _ => "lowercase",
+                        };
+                        violations.push((
+                            token.pos.clone(),
+                            format!("Keyword '{text}' is not {expected} like the first keyword of the file"),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Use of the Synopsys arithmetic packages that are compiled into the ieee library
+pub(crate) struct NonStandardPackage;
+
+const NON_STANDARD_PACKAGES: [&str; 3] =
+    ["std_logic_arith", "std_logic_unsigned", "std_logic_signed"];
+
+/// The package selected from the ieee library by a name such as `ieee.std_logic_arith.all`
+fn ieee_package(name: &WithPos<Name>) -> Option<&WithPos<WithRef<Designator>>> {
+    match name.item {
+        Name::SelectedAll(ref prefix) => ieee_package(prefix),
+        Name::Selected(ref prefix, ref suffix) => match prefix.item {
+            Name::Designator(WithRef {
+                item: Designator::Identifier(ref library),
+                ..
+            }) if library.name_utf8().eq_ignore_ascii_case("ieee") => Some(suffix),
+            _ => ieee_package(prefix),
+        },
+        _ => None,
+    }
+}
+
+struct NonStandardPackageVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for NonStandardPackageVisitor<'a> {
+    fn visit_use_clause(&mut self, node: &UseClause, _ctx: &dyn TokenAccess) -> VisitorResult {
+        for name in node.name_list.items.iter() {
+            let Some(package) = ieee_package(name) else {
+                continue;
+            };
+            let Designator::Identifier(ref symbol) = package.item.item else {
+                continue;
+            };
+            let package_name = symbol.name_utf8().to_ascii_lowercase();
+            if NON_STANDARD_PACKAGES.contains(&package_name.as_str()) {
+                self.violations.push((
+                    package.pos.clone(),
+                    format!(
+                        "Package '{package_name}' is not standard, use ieee.numeric_std instead"
+                    ),
+                ));
+            }
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for NonStandardPackage {
+    fn code(&self) -> &'static str {
+        "non_standard_package"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        file.walk(&mut NonStandardPackageVisitor { violations });
+    }
+}
+
+/// Case statements without an `others` choice
+pub(crate) struct CaseWithoutOthers;
+
+struct CaseVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for CaseVisitor<'a> {
+    fn visit_case_statement(
+        &mut self,
+        node: &CaseStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let has_others = node.alternatives.iter().any(|alternative| {
+            alternative
+                .choices
+                .iter()
+                .any(|choice| matches!(choice.item, Choice::Others))
+        });
+        if !has_others {
+            self.violations.push((
+                node.expression.pos.clone(),
+                "Case statement without an 'others' choice".to_owned(),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for CaseWithoutOthers {
+    fn code(&self) -> &'static str {
+        "case_without_others"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        file.walk(&mut CaseVisitor { violations });
+    }
+}
+
+/// Delays of signal assignments in architectures of entities with ports.
+/// An entity without ports is assumed to be a testbench that is not synthesized.
+pub(crate) struct AfterClause;
+
+struct AfterClauseVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for AfterClauseVisitor<'a> {
+    fn visit_waveform_element(
+        &mut self,
+        node: &WaveformElement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let Some(ref after) = node.after {
+            self.violations.push((
+                after.pos.clone(),
+                "Delay of 'after' clause is ignored by synthesis".to_owned(),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for AfterClause {
+    fn code(&self) -> &'static str {
+        "after_clause"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        for unit in file.units.iter() {
+            let mut is_synthesized = false;
+            with_design_unit(unit, |design_unit| {
+                let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(ref architecture)) =
+                    design_unit
+                else {
+                    return;
+                };
+                let Some(entity) = architecture.entity_name.reference else {
+                    return;
+                };
+                if let AnyEntKind::Design(Design::Entity(_, region)) =
+                    file.root.get_ent(entity).kind()
+                {
+                    let (_, ports) = region.to_entity_formal();
+                    is_synthesized = !ports.is_empty();
+                }
+            });
+            if is_synthesized {
+                walk_unit(unit, &mut AfterClauseVisitor { violations });
+            }
+        }
+    }
+}
+
+/// Shared variables declared in an architecture
+pub(crate) struct ArchitectureVariable;
+
+struct ArchitectureVariableVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for ArchitectureVariableVisitor<'a> {
+    fn visit_object_declaration(
+        &mut self,
+        node: &ObjectDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if node.class == ObjectClass::SharedVariable {
+            let ident = &node.ident.tree;
+            self.violations.push((
+                ident.pos.clone(),
+                format!(
+                    "Shared variable '{}' declared in an architecture",
+                    ident.item
+                ),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for ArchitectureVariable {
+    fn code(&self) -> &'static str {
+        "architecture_variable"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        for unit in file.units.iter() {
+            if matches!(unit.kind(), AnyKind::Secondary(SecondaryKind::Architecture)) {
+                walk_unit(unit, &mut ArchitectureVariableVisitor { violations });
+            }
+        }
+    }
+}
+
+/// Ports with mode buffer
+pub(crate) struct BufferPort;
+
+struct BufferPortVisitor<'a> {
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for BufferPortVisitor<'a> {
+    fn visit_interface_object_declaration(
+        &mut self,
+        node: &InterfaceObjectDeclaration,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if node.list_type == InterfaceType::Port && node.mode == Mode::Buffer {
+            let ident = &node.ident.tree;
+            self.violations.push((
+                ident.pos.clone(),
+                format!(
+                    "Port '{}' has mode buffer, use mode out instead",
+                    ident.item
+                ),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for BufferPort {
+    fn code(&self) -> &'static str {
+        "buffer_port"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        file.walk(&mut BufferPortVisitor { violations });
+    }
+}
+
+/// Numeric literals other than 0 and 1 within statements instead of named constants.
+/// The actuals of map aspects and the bounds of ranges are not reported.
+pub(crate) struct MagicNumber;
+
+/// Source ranges that are pushed in the order they are visited
+#[derive(Default)]
+struct NestedRanges(Vec<Range>);
+
+impl NestedRanges {
+    fn push(&mut self, pos: &SrcPos) {
+        self.0.push(pos.range());
+    }
+
+    fn contains(&mut self, pos: &SrcPos) -> bool {
+        // Items are visited in the order they appear in the file
+        while let Some(range) = self.0.last() {
+            if range.end < pos.start() {
+                self.0.pop();
+            } else {
+                return range.start <= pos.start();
+            }
+        }
+        false
+    }
+}
+
+struct MagicNumberVisitor<'a> {
+    source: &'a Source,
+    violations: &'a mut Violations,
+    /// The enclosing statements that cannot contain declarations
+    statements: NestedRanges,
+    /// The enclosing map aspects and ranges
+    excluded: NestedRanges,
+    next_pos: NextItemPos,
+}
+
+impl<'a> Visitor for MagicNumberVisitor<'a> {
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if !matches!(
+            node.statement.item,
+            ConcurrentStatement::Block(_)
+                | ConcurrentStatement::Process(_)
+                | ConcurrentStatement::ForGenerate(_)
+                | ConcurrentStatement::IfGenerate(_)
+                | ConcurrentStatement::CaseGenerate(_)
+        ) {
+            self.statements.push(&node.statement.pos);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.statements.push(&node.statement.pos);
+        VisitorResult::Continue
+    }
+
+    fn visit_map_aspect(&mut self, node: &MapAspect, ctx: &dyn TokenAccess) -> VisitorResult {
+        self.excluded.push(
+            &ctx.get_pos(node.start)
+                .combine(ctx.get_pos(node.closing_paren)),
+        );
+        VisitorResult::Continue
+    }
+
+    fn visit_range_constraint(
+        &mut self,
+        node: &RangeConstraint,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.excluded
+            .push(&node.left_expr.pos.combine(&node.right_expr.pos));
+        VisitorResult::Continue
+    }
+
+    fn visit_item_with_pos(
+        &mut self,
+        pos: &SrcPos,
+        node: &dyn ASTNode,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.next_pos.set(pos, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_expression(&mut self, node: &Expression, _ctx: &dyn TokenAccess) -> VisitorResult {
+        let Some(pos) = self.next_pos.take(node) else {
+            return VisitorResult::Continue;
+        };
+        let Expression::Literal(Literal::AbstractLiteral(ref literal)) = node else {
+            return VisitorResult::Continue;
+        };
+        let is_trivial = match literal {
+            AbstractLiteral::Integer(value) => *value <= 1,
+            AbstractLiteral::Real(value) => *value == 0.0 || *value == 1.0,
+        };
+        if !is_trivial && !self.excluded.contains(&pos) && self.statements.contains(&pos) {
+            self.violations.push((
+                pos.clone(),
+                format!(
+                    "Numeric literal '{}' should be a named constant",
+                    text_at(self.source, &pos)
+                ),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for MagicNumber {
+    fn code(&self) -> &'static str {
+        "magic_number"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        file.walk(&mut MagicNumberVisitor {
+            source: file.source,
+            violations,
+            statements: NestedRanges::default(),
+            excluded: NestedRanges::default(),
+            next_pos: NextItemPos::default(),
+        });
+    }
+}
+
+/// Processes without a label
+pub(crate) struct UnlabeledProcess;
+
+struct UnlabeledProcessVisitor<'a> {
+    tokens: &'a [Token],
+    violations: &'a mut Violations,
+}
+
+impl<'a> Visitor for UnlabeledProcessVisitor<'a> {
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if node.label.tree.is_none()
+            && matches!(node.statement.item, ConcurrentStatement::Process(_))
+        {
+            self.violations.push((
+                first_token_pos(self.tokens, &node.statement.pos),
+                "Process without a label".to_owned(),
+            ));
+        }
+        VisitorResult::Continue
+    }
+}
+
+impl LintRule for UnlabeledProcess {
+    fn code(&self) -> &'static str {
+        "unlabeled_process"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        for unit in file.units.iter() {
+            walk_unit(
+                unit,
+                &mut UnlabeledProcessVisitor {
+                    tokens: &unit.tokens,
+                    violations,
+                },
+            );
+        }
+    }
+}
+
+fn is_primary(unit: &LockedUnit) -> bool {
+    unit.unit_id().secondary_name().is_none()
+}
+
+/// Files with primary units where the name of the file is not the name of any of them
+pub(crate) struct FileNameMismatch;
+
+impl LintRule for FileNameMismatch {
+    fn code(&self) -> &'static str {
+        "file_name_mismatch"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        let Some(stem) = file.source.file_name().file_stem() else {
+            return;
+        };
+        let stem = stem.to_string_lossy();
+        let mut primary_units = file.units.iter().filter(|unit| is_primary(unit)).peekable();
+        let Some(first) = primary_units.peek().copied() else {
+            return;
+        };
+        if primary_units.all(|unit| !unit.name().name_utf8().eq_ignore_ascii_case(&stem)) {
+            let file_name = file.source.file_name().file_name().unwrap_or_default();
+            violations.push((
+                first.ident().pos.clone(),
+                format!(
+                    "Name of file '{}' does not match the name of primary unit '{}'",
+                    file_name.to_string_lossy(),
+                    first.name()
+                ),
+            ));
+        }
+    }
+}
+
+/// Primary units after the first one of a file
+pub(crate) struct MultiplePrimaryUnits;
+
+impl LintRule for MultiplePrimaryUnits {
+    fn code(&self) -> &'static str {
+        "multiple_primary_units"
+    }
+
+    fn check(&self, file: &LintFile, violations: &mut Violations) {
+        let mut primary_units = file.units.iter().filter(|unit| is_primary(unit));
+        let Some(first) = primary_units.next() else {
+            return;
+        };
+        for unit in primary_units {
+            violations.push((
+                unit.ident().pos.clone(),
+                format!(
+                    "Primary unit '{}' is in the same file as primary unit '{}'",
+                    unit.name(),
+                    first.name()
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::ast::HasUnitId;
+    use crate::config::Config;
+    use crate::lint::rules::RuleLinter;
+    use crate::syntax::test::check_no_diagnostics;
+    use crate::Severity;
+    use std::path::Path;
+
+    /// The code, the text of the position and the message of each diagnostic
+    fn lint(builder: LibraryBuilder, rules: &str) -> Vec<(&'static str, String, String)> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        let config = Config::from_str(
+            &format!("[libraries]\nlibname.files = []\n[lint]\n{rules}"),
+            Path::new(""),
+        )
+        .unwrap();
+        let units: Vec<_> = root.units().map(|unit| unit.unit_id().clone()).collect();
+        let mut diagnostics = Vec::new();
+        RuleLinter::default().lint(&root, &config, &units, &mut diagnostics);
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.pos.clone());
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.severity, Severity::Warning);
+                (
+                    diagnostic.code.unwrap(),
+                    super::text_at(&diagnostic.pos.source, &diagnostic.pos),
+                    diagnostic.message,
+                )
+            })
+            .collect()
+    }
+
+    fn violation(code: &'static str, text: &str, message: &str) -> (&'static str, String, String) {
+        (code, text.to_owned(), message.to_owned())
+    }
+
+    #[test]
+    fn missing_end_labels_of_statements_and_declarations() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  component comp is
+  end component comp;
+begin
+  main : process
+  begin
+    check : if true then
+    end if;
+    other : if true then
+    end if other;
+    wait;
+  end process main;
+
+  blk : block
+  begin
+  end block;
+end architecture rtl;
+
+package pkg is
+  procedure proc;
+end package pkg;
+
+package body pkg is
+  procedure proc is
+  begin
+  end procedure;
+end package body pkg;
+",
+        );
+        assert_eq!(
+            lint(builder, "missing_end_label = true"),
+            vec![
+                violation(
+                    "missing_end_label",
+                    "ent",
+                    "Missing name 'ent' at the end of entity"
+                ),
+                violation("missing_end_label", "check", "Missing end label 'check'"),
+                violation("missing_end_label", "blk", "Missing end label 'blk'"),
+                violation(
+                    "missing_end_label",
+                    "proc",
+                    "Missing name 'proc' at the end of procedure"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keyword_case_follows_the_first_keyword_of_each_file() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+ENTITY ent IS
+END ENTITY;
+
+ARCHITECTURE rtl OF ent IS
+BEGIN
+  main : Process
+  BEGIN
+    wait;
+  END PROCESS main;
+END ARCHITECTURE;
+",
+        );
+        builder.code(
+            "libname",
+            "
+package pkg is
+end package;
+",
+        );
+        assert_eq!(
+            lint(builder, "keyword_case = true"),
+            vec![
+                violation(
+                    "keyword_case",
+                    "Process",
+                    "Keyword 'Process' is not uppercase like the first keyword of the file"
+                ),
+                violation(
+                    "keyword_case",
+                    "wait",
+                    "Keyword 'wait' is not uppercase like the first keyword of the file"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_standard_packages_of_the_ieee_library() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "ieee",
+            "
+package std_logic_arith is
+end package;
+
+package std_logic_unsigned is
+end package;
+
+package numeric_std is
+end package;
+",
+        );
+        builder.code(
+            "libname",
+            "
+package std_logic_signed is
+end package;
+
+library ieee;
+use ieee.std_logic_arith.all;
+use IEEE.STD_LOGIC_UNSIGNED.all;
+use ieee.numeric_std.all;
+use work.std_logic_signed.all;
+
+package pkg is
+end package;
+",
+        );
+        assert_eq!(
+            lint(builder, "non_standard_package = true"),
+            vec![
+                violation(
+                    "non_standard_package",
+                    "std_logic_arith",
+                    "Package 'std_logic_arith' is not standard, use ieee.numeric_std instead"
+                ),
+                violation(
+                    "non_standard_package",
+                    "STD_LOGIC_UNSIGNED",
+                    "Package 'std_logic_unsigned' is not standard, use ieee.numeric_std instead"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn case_statements_without_others() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal with_others, without_others : bit;
+begin
+  main : process
+  begin
+    case without_others is
+      when '0' => null;
+      when '1' => null;
+    end case;
+    case with_others is
+      when '0' => null;
+      when others => null;
+    end case;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "case_without_others = true"),
+            vec![violation(
+                "case_without_others",
+                "without_others",
+                "Case statement without an 'others' choice"
+            )]
+        );
+    }
+
+    #[test]
+    fn after_clauses_in_architectures_of_entities_with_ports() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+  port (o : out bit);
+end entity;
+
+architecture rtl of ent is
+begin
+  o <= '1' after 1 ns;
+
+  main : process
+  begin
+    wait for 2 ns;
+  end process;
+end architecture;
+
+entity tb is
+end entity;
+
+architecture sim of tb is
+  signal s : bit;
+begin
+  s <= '1' after 3 ns;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "after_clause = true"),
+            vec![violation(
+                "after_clause",
+                "1 ns",
+                "Delay of 'after' clause is ignored by synthesis"
+            )]
+        );
+    }
+
+    #[test]
+    fn after_clauses_in_testbenches_with_ports_are_reported() {
+        // Only entities without ports are known to be testbenches
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity tb is
+  port (done : out bit);
+end entity;
+
+architecture sim of tb is
+  signal clk : bit;
+begin
+  clk <= not clk after 5 ns;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "after_clause = true"),
+            vec![violation(
+                "after_clause",
+                "5 ns",
+                "Delay of 'after' clause is ignored by synthesis"
+            )]
+        );
+    }
+
+    #[test]
+    fn shared_variables_in_architectures() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  type counter_t is protected
+  end protected;
+  shared variable pkg_counter : counter_t;
+end package;
+
+package body pkg is
+  type counter_t is protected body
+  end protected body;
+end package body;
+
+entity ent is
+end entity;
+
+use work.pkg.all;
+
+architecture rtl of ent is
+  shared variable counter : counter_t;
+begin
+  main : process
+    variable count : natural;
+  begin
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "architecture_variable = true"),
+            vec![violation(
+                "architecture_variable",
+                "counter",
+                "Shared variable 'counter' declared in an architecture"
+            )]
+        );
+    }
+
+    #[test]
+    fn buffer_ports() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    i : in bit;
+    o : out bit;
+    b : buffer bit
+  );
+end entity;
+",
+        );
+        assert_eq!(
+            lint(builder, "buffer_port = true"),
+            vec![violation(
+                "buffer_port",
+                "b",
+                "Port 'b' has mode buffer, use mode out instead"
+            )]
+        );
+    }
+
+    #[test]
+    fn magic_numbers_in_statements() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant width : natural := 8;
+begin
+  main : process
+    variable count : natural := 3;
+    variable ratio : real := 0.5;
+  begin
+    count := count + 1;
+    count := count * 4;
+    ratio := ratio * 2.5;
+    ratio := 0.0;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "magic_number = true"),
+            vec![
+                violation(
+                    "magic_number",
+                    "4",
+                    "Numeric literal '4' should be a named constant"
+                ),
+                violation(
+                    "magic_number",
+                    "2.5",
+                    "Numeric literal '2.5' should be a named constant"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn magic_numbers_exclude_map_aspects_and_ranges() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity child is
+  generic (width : natural);
+  port (i : in bit_vector(width - 1 downto 0));
+end entity;
+
+architecture rtl of child is
+begin
+end architecture;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal x : bit_vector(7 downto 0);
+  signal y : bit_vector(3 downto 0);
+  signal n : natural;
+begin
+  inst: entity work.child
+    generic map (width => 8)
+    port map (i => x);
+  y <= x(7 downto 4);
+  n <= 5;
+
+  main: process
+  begin
+    for idx in 2 to 5 loop
+      y(0) <= x(idx);
+    end loop;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "magic_number = true"),
+            vec![violation(
+                "magic_number",
+                "5",
+                "Numeric literal '5' should be a named constant"
+            )]
+        );
+    }
+
+    #[test]
+    fn unlabeled_processes() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+  main : process
+  begin
+    wait;
+  end process;
+
+  process
+  begin
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "unlabeled_process = true"),
+            vec![violation(
+                "unlabeled_process",
+                "process",
+                "Process without a label"
+            )]
+        );
+    }
+
+    #[test]
+    fn file_name_mismatch_with_all_primary_units() {
+        let mut builder = LibraryBuilder::new();
+        builder.code_with_file_name(
+            "libname",
+            "wrong.vhd",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture;
+",
+        );
+        builder.code_with_file_name(
+            "libname",
+            "ent_rtl.vhd",
+            "
+architecture rtl2 of ent is
+begin
+end architecture;
+",
+        );
+        builder.code_with_file_name(
+            "libname",
+            "PKG.vhd",
+            "
+package pkg is
+end package;
+",
+        );
+        builder.code_with_file_name(
+            "libname",
+            "second.vhd",
+            "
+package first is
+end package;
+
+package second is
+end package;
+",
+        );
+        assert_eq!(
+            lint(builder, "file_name_mismatch = true"),
+            vec![violation(
+                "file_name_mismatch",
+                "ent",
+                "Name of file 'wrong.vhd' does not match the name of primary unit 'ent'"
+            )]
+        );
+    }
+
+    #[test]
+    fn primary_units_after_the_first_one_of_a_file() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+end package;
+
+package body pkg is
+end package body;
+
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture;
+
+context ctx is
+end context;
+",
+        );
+        builder.code(
+            "libname",
+            "
+entity other is
+end entity;
+
+architecture rtl of other is
+begin
+end architecture;
+",
+        );
+        assert_eq!(
+            lint(builder, "multiple_primary_units = true"),
+            vec![
+                violation(
+                    "multiple_primary_units",
+                    "ent",
+                    "Primary unit 'ent' is in the same file as primary unit 'pkg'"
+                ),
+                violation(
+                    "multiple_primary_units",
+                    "ctx",
+                    "Primary unit 'ctx' is in the same file as primary unit 'pkg'"
+                ),
+            ]
+        );
+    }
+}
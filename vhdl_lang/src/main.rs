@@ -9,14 +9,18 @@
 // Track here: https://github.com/rust-lang/rust-clippy/issues/1981
 #![allow(clippy::ptr_arg)]
 
-use clap::Parser;
-use std::path::Path;
+use clap::{ArgGroup, Parser, ValueEnum};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
-use vhdl_lang::{Config, Diagnostic, MessagePrinter, NullMessages, Project, Severity};
+use vhdl_lang::{
+    Config, DependencyGraph, Diagnostic, Documentation, DocumentationFormat, LibraryFile, Message,
+    MessageHandler, MessagePrinter, NullMessages, Project, Severity, UnitKind,
+};
 
 /// Run vhdl analysis
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("export").args(["dependency_graph", "documentation"])))]
 struct Args {
     /// The number of threads to use. By default the maximum is selected based on process cores
     #[arg(short = 'p', long)]
@@ -47,6 +51,65 @@ struct Args {
     /// This is used for development to test where the language server is blind
     #[arg(long)]
     count_unresolved: bool,
+
+    /// Print the files in the order they must be compiled instead of the diagnostics.
+    /// Files of the std and ieee libraries which are provided by the tools are not printed
+    #[arg(long)]
+    compile_order: bool,
+
+    /// Only print the files needed by these top units, given as a unit name or library.unit
+    #[arg(long, requires = "compile_order")]
+    top: Vec<String>,
+
+    /// The output format of the compile order
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, requires = "compile_order")]
+    format: OutputFormat,
+
+    /// Print the dependencies between design units instead of the diagnostics.
+    /// Units of the std and ieee libraries are only included when selected with --library
+    #[arg(long, conflicts_with = "compile_order")]
+    dependency_graph: bool,
+
+    /// The output format of the dependency graph
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot, requires = "dependency_graph")]
+    graph_format: GraphFormat,
+
+    /// Only include the design units of these libraries in the dependency graph or documentation
+    #[arg(long = "library", requires = "export")]
+    libraries: Vec<String>,
+
+    /// Only include design units of these kinds in the dependency graph, such as package or package_body
+    #[arg(long = "kind", requires = "dependency_graph")]
+    kinds: Vec<UnitKind>,
+
+    /// Show the dependencies between libraries instead of design units in the dependency graph
+    #[arg(long, requires = "dependency_graph")]
+    collapse_libraries: bool,
+
+    /// Write documentation of the entities and packages to this directory, one file per library.
+    /// The std and ieee libraries are only documented when selected with --library
+    #[arg(long, value_name = "DIR", conflicts_with = "compile_order")]
+    documentation: Option<PathBuf>,
+
+    /// The format of the documentation, markdown or html
+    #[arg(long, default_value_t = DocumentationFormat::Markdown, requires = "documentation")]
+    doc_format: DocumentationFormat,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// The library name and file name separated by a space on each line
+    Text,
+    /// An array of objects with library and file fields
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GraphFormat {
+    /// A Graphviz digraph
+    Dot,
+    /// An object with arrays of nodes and dependencies
+    Json,
 }
 
 fn main() {
@@ -57,11 +120,18 @@ fn main() {
         .unwrap();
 
     let mut config = Config::default();
-    let mut msg_printer = MessagePrinter::default();
-    config.load_external_config(&mut msg_printer);
+    // Keep stdout for the compile order, dependency graph or documentation only
+    let mut msg_printer: Box<dyn MessageHandler> =
+        if args.compile_order || args.dependency_graph || args.documentation.is_some() {
+            Box::new(StderrMessagePrinter)
+        } else {
+            Box::<MessagePrinter>::default()
+        };
+    let msg_printer = msg_printer.as_mut();
+    config.load_external_config(msg_printer);
     config.append(
         &Config::read_file_path(Path::new(&args.config)).expect("Failed to read config file"),
-        &mut msg_printer,
+        msg_printer,
     );
 
     let start = SystemTime::now();
@@ -78,10 +148,37 @@ fn main() {
         1
     };
 
-    let mut project = Project::from_config(config, &mut msg_printer);
+    let mut project = Project::from_config(config, msg_printer);
     let mut diagnostics = project.analyse();
     let duration = start.elapsed().unwrap() / iterations;
 
+    if args.compile_order {
+        match project.compile_order(&args.top) {
+            Ok(files) => show_compile_order(&files, args.format),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    if args.dependency_graph {
+        show_dependency_graph(&project.dependency_graph(), &args);
+        std::process::exit(0);
+    }
+
+    if let Some(ref directory) = args.documentation {
+        if let Err(err) = write_documentation(&project.documentation(), directory, &args) {
+            eprintln!(
+                "Failed to write documentation to {}: {err}",
+                directory.display()
+            );
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
     if args.no_hint {
         diagnostics.retain(|diag| diag.severity != Severity::Hint);
     }
@@ -132,3 +229,98 @@ fn show_diagnostics(diagnostics: &[Diagnostic]) {
         println!("Found {} diagnostics", diagnostics.len());
     }
 }
+
+struct StderrMessagePrinter;
+
+impl MessageHandler for StderrMessagePrinter {
+    fn push(&mut self, message: Message) {
+        eprintln!("{message}");
+    }
+}
+
+fn show_compile_order(files: &[LibraryFile], format: OutputFormat) {
+    let files = files
+        .iter()
+        .filter(|file| !matches!(file.library_name.as_str(), "std" | "ieee"));
+
+    match format {
+        OutputFormat::Text => {
+            for file in files {
+                println!("{} {}", file.library_name, file.file_name.to_string_lossy());
+            }
+        }
+        OutputFormat::Json => {
+            let files: Vec<_> = files
+                .map(|file| {
+                    serde_json::json!({
+                        "library": file.library_name,
+                        "file": file.file_name.to_string_lossy(),
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(files));
+        }
+    }
+}
+
+fn is_selected_library(library_name: &str, args: &Args) -> bool {
+    if args.libraries.is_empty() {
+        !matches!(library_name, "std" | "ieee")
+    } else {
+        args.libraries
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(library_name))
+    }
+}
+
+fn write_documentation(
+    documentation: &Documentation,
+    directory: &Path,
+    args: &Args,
+) -> std::io::Result<()> {
+    let documentation = documentation.filter(|library| is_selected_library(&library.name, args));
+    std::fs::create_dir_all(directory)?;
+    for library in documentation.libraries.iter() {
+        let file_name = directory.join(library.file_name(args.doc_format));
+        std::fs::write(&file_name, library.render(args.doc_format))?;
+        println!("{}", file_name.to_string_lossy());
+    }
+    Ok(())
+}
+
+fn show_dependency_graph(graph: &DependencyGraph, args: &Args) {
+    let graph = graph.filter(|unit| {
+        is_selected_library(&unit.library_name, args)
+            && (args.kinds.is_empty() || args.kinds.contains(&unit.kind))
+    });
+
+    let output = if args.collapse_libraries {
+        let graph = graph.library_dependencies();
+        match args.graph_format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Json => graph.to_json().to_string(),
+        }
+    } else {
+        match args.graph_format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Json => graph.to_json().to_string(),
+        }
+    };
+    println!("{}", output.trim_end());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_formats_require_their_output() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_from([&["vhdl_lang", "--config", "vhdl_ls.toml"], args].concat())
+        };
+        assert!(parse(&["--compile-order", "--format", "json"]).is_ok());
+        assert!(parse(&["--format", "json"]).is_err());
+        assert!(parse(&["--dependency-graph", "--graph-format", "json"]).is_ok());
+        assert!(parse(&["--graph-format", "json"]).is_err());
+    }
+}
@@ -13,6 +13,7 @@ use crate::ast::{
 };
 use crate::ast::{ExternalObjectClass, InterfaceDeclaration, InterfaceObjectDeclaration};
 use crate::data::*;
+use std::hash::{Hash, Hasher};
 
 mod types;
 use fnv::FnvHashMap;
@@ -474,6 +475,78 @@ impl<'a> AnyEnt<'a> {
         }
     }
 
+    /// Hash everything about the entity that design units using it depend on:
+    /// its id, name, kind and the ids of the entities it refers to.
+    /// Entities with the same hash can be used in place of each other by other design units.
+    ///
+    /// The position is left out such that moving a declaration does not re-analyze users.
+    /// Their named entities still refer to the entity of the previous analysis, its position
+    /// is found through `DesignRoot::latest`.
+    pub(crate) fn hash_declaration(&self, hasher: &mut impl Hasher) {
+        self.id.hash(hasher);
+        self.designator.hash(hasher);
+        self.describe().hash(hasher);
+        self.parent.map(|parent| parent.id()).hash(hasher);
+        match self.related {
+            Related::ImplicitOf(ent) | Related::InstanceOf(ent) | Related::DeclaredBy(ent) => {
+                Some(ent.id()).hash(hasher)
+            }
+            Related::None => None::<EntityId>.hash(hasher),
+        }
+        for implicit in self.implicits.iter() {
+            implicit.id().hash(hasher);
+        }
+        let mut attrs: Vec<_> = self
+            .attrs
+            .iter()
+            .map(|(name, (_, attr))| (name.name_utf8(), EntRef::from(*attr).id()))
+            .collect();
+        attrs.sort_by(|(name, ..), (other, ..)| name.cmp(other));
+        attrs.hash(hasher);
+
+        match self.kind() {
+            AnyEntKind::ExternalAlias { type_mark, .. } => type_mark.id().hash(hasher),
+            AnyEntKind::ObjectAlias {
+                base_object,
+                type_mark,
+            } => {
+                base_object.id().hash(hasher);
+                type_mark.id().hash(hasher);
+            }
+            AnyEntKind::File(subtype)
+            | AnyEntKind::ElementDeclaration(subtype)
+            | AnyEntKind::DeferredConstant(subtype) => subtype.type_mark().id().hash(hasher),
+            AnyEntKind::InterfaceFile(type_mark)
+            | AnyEntKind::Attribute(type_mark)
+            | AnyEntKind::PhysicalLiteral(type_mark) => type_mark.id().hash(hasher),
+            AnyEntKind::Overloaded(Overloaded::Alias(overloaded)) => overloaded.id().hash(hasher),
+            AnyEntKind::Overloaded(overloaded) => overloaded.signature().key().hash(hasher),
+            AnyEntKind::Object(object) => {
+                object.subtype.type_mark().id().hash(hasher);
+                object.has_default.hash(hasher);
+                object
+                    .iface
+                    .map(|iface| iface.mode().to_string())
+                    .hash(hasher);
+            }
+            AnyEntKind::LoopParameter(typ) => typ.map(|typ| typ.id()).hash(hasher),
+            AnyEntKind::Type(typ) => match typ {
+                Type::Array { indexes, elem_type } => {
+                    for index in indexes.iter() {
+                        index.map(|index| index.id()).hash(hasher);
+                    }
+                    elem_type.id().hash(hasher);
+                }
+                Type::Access(subtype) | Type::Subtype(subtype) => {
+                    subtype.type_mark().id().hash(hasher)
+                }
+                Type::Alias(type_mark) => type_mark.id().hash(hasher),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
     #[cfg(test)]
     pub fn lookup_implicit_of(&self, name: &str) -> OverloadedEnt<'a> {
         let ent = self
@@ -10,7 +10,8 @@ use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHasher};
+use std::hash::Hasher;
 
 use crate::ast::Designator;
 use crate::Diagnostic;
@@ -113,15 +114,42 @@ impl<'a> FinalArena {
         }
     }
 
+    /// The named entity with the id, `None` if it is not found in the linked arenas
+    pub fn try_get(&'a self, id: EntityId) -> Option<EntRef<'a>> {
+        let arena = self.refs.get(&id.arena_id().0)?;
+        let item = arena.items.get(id.local_id().0 as usize)?;
+        Some(std::pin::Pin::into_inner(item) as &'a AnyEnt)
+    }
+
     pub fn link(&mut self, referenced: &FinalArena) {
         for (id, arena) in referenced.refs.iter() {
             self.refs.entry(*id).or_insert_with(|| arena.clone());
         }
     }
 
+    /// Link the arena with the given id from `referenced`, replacing an already linked
+    /// version of it. Used to prefer the latest analysis of a design unit over an older
+    /// analysis that is still referenced from design units that were not re-analyzed.
+    pub fn link_latest(&mut self, referenced: &FinalArena, id: ArenaId) {
+        if let Some(arena) = referenced.refs.get(&id.0) {
+            self.refs.insert(id.0, arena.clone());
+        }
+    }
+
     pub fn clear(&mut self) {
         self.refs.clear();
     }
+
+    /// Hash of the declarations of all entities allocated in the arena with the given id,
+    /// see `AnyEnt::hash_declaration`. `None` if the arena is not linked.
+    pub fn declarations_hash(&self, id: ArenaId) -> Option<u64> {
+        let arena = self.refs.get(&id.0)?;
+        let mut hasher = FnvHasher::default();
+        for idx in 0..arena.items.len() {
+            arena.items.get(idx).unwrap().hash_declaration(&mut hasher);
+        }
+        Some(hasher.finish())
+    }
 }
 
 /// A combination of a local mutable arena together with non-local immutable references
@@ -151,6 +179,10 @@ impl Arena {
         self.refs.borrow_mut().link(referenced)
     }
 
+    pub fn link_latest(&self, referenced: &FinalArena, id: ArenaId) {
+        self.refs.borrow_mut().link_latest(referenced, id)
+    }
+
     pub fn alloc<'a>(
         &'a self,
         designator: Designator,
@@ -46,6 +46,25 @@ impl<'a> InterfaceEnt<'a> {
         }
     }
 
+    /// Returns true if the actual associated with the formal may be written
+    pub fn is_written(&self) -> bool {
+        match self.ent.kind() {
+            AnyEntKind::Object(obj) => matches!(
+                obj.mode(),
+                Some(Mode::Out) | Some(Mode::InOut) | Some(Mode::Buffer)
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the formal is of mode out, so its actual is only written
+    pub fn is_out(&self) -> bool {
+        match self.ent.kind() {
+            AnyEntKind::Object(obj) => obj.mode() == Some(Mode::Out),
+            _ => false,
+        }
+    }
+
     pub fn is_out_or_inout_signal(&self) -> bool {
         match self.ent.kind() {
             AnyEntKind::Object(obj) => {
@@ -117,6 +117,18 @@ impl<'a> Visibility<'a> {
         }
     }
 
+    /// All named entities that are potentially visible
+    pub fn visible_entities(&self) -> impl Iterator<Item = EntRef<'a>> + '_ {
+        self.all_in_regions
+            .iter()
+            .flat_map(|visible_region| visible_region.region.immediates())
+            .chain(
+                self.visible
+                    .values()
+                    .flat_map(|visible| visible.values().map(|visible_ent| visible_ent.entity)),
+            )
+    }
+
     /// Helper function lookup a visible declaration within the region
     pub fn lookup_into(&self, designator: &Designator, visible: &mut Visible<'a>) {
         for visible_region in self.all_in_regions.iter() {
@@ -4,18 +4,92 @@
 //
 // Copyright (c) 2018, Olof Kraigher olof.kraigher@gmail.com
 
-use crate::analysis::DesignRoot;
-use crate::ast::DesignFile;
-use crate::completion::{list_completion_options, CompletionItem};
-use crate::config::Config;
+use crate::analysis::{DesignRoot, Library};
+use crate::ast::{ContextItem, DesignFile, Designator, Name};
+use crate::completion::{list_completion_options, list_snippets, CompletionItem};
+use crate::config::{Config, LibraryMapping};
+use crate::dependency_graph::{compile_order, DependencyGraph, LibraryFile};
+use crate::documentation::Documentation;
 use crate::lint::dead_code::UnusedDeclarationsLinter;
+use crate::lint::naming::{NamingLinter, NamingViolation};
+use crate::lint::rules::RuleLinter;
 use crate::named_entity::{AnyEnt, EntRef};
+use crate::rename::{parse_identifier, RenamedSources};
+use crate::standard::VHDLStandard;
 use crate::syntax::VHDLParser;
-use crate::{data::*, EntHierarchy, EntityId};
+use crate::workspace_symbols::{SymbolIndex, SymbolMatch};
+use crate::{
+    data::*, Calls, CodeLens, DocumentHighlight, EntHierarchy, EntityId, FoldingRange, HoverInfo,
+    InlayHint, Refactoring, RenameError, ResolvedCodeLens,
+};
 use fnv::{FnvHashMap, FnvHashSet};
+use parking_lot::RwLock;
 use std::collections::hash_map::Entry;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Parsed files of third-party libraries, such as `std` and `ieee`, shared by several projects.
+/// A file that is mapped to third-party libraries only is read and parsed once for all
+/// projects that are created with the same shared libraries, as long as it is not changed on disk.
+#[derive(Clone, Default)]
+pub struct SharedLibraries {
+    parser: VHDLParser,
+    files: Arc<RwLock<FnvHashMap<(FilePath, VHDLStandard), SharedFile>>>,
+}
+
+#[derive(Clone)]
+struct SharedFile {
+    stamp: Option<FileStamp>,
+    source: Source,
+    design_file: DesignFile,
+    parser_diagnostics: Vec<Diagnostic>,
+}
+
+/// The modification time and length of a file when it was read
+#[derive(Clone, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(file_name: &Path) -> Option<FileStamp> {
+        let metadata = std::fs::metadata(file_name).ok()?;
+        Some(FileStamp {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+impl SharedLibraries {
+    /// Returns the parsed file unless the file on disk has changed since it was read
+    fn get(&self, file_name: &FilePath, standard: VHDLStandard) -> Option<SharedFile> {
+        let stamp = FileStamp::of(file_name)?;
+        self.files
+            .read()
+            .get(&(file_name.clone(), standard))
+            .filter(|file| file.stamp.as_ref() == Some(&stamp))
+            .cloned()
+    }
+
+    fn insert(&self, standard: VHDLStandard, file: SharedFile) {
+        let file_name = FilePath::new(file.source.file_name());
+        self.files.write().insert((file_name, standard), file);
+    }
+
+    /// Forget a file that was changed, such that it is parsed again by the next project loading it
+    fn invalidate(&self, file_name: &FilePath) {
+        self.files
+            .write()
+            .retain(|(shared_file_name, _), _| shared_file_name != file_name);
+    }
+}
 
+#[derive(Clone)]
 pub struct Project {
     parser: VHDLParser,
     config: Config,
@@ -23,18 +97,37 @@ pub struct Project {
     files: FnvHashMap<FilePath, SourceFile>,
     empty_libraries: FnvHashSet<Symbol>,
     lint: Option<UnusedDeclarationsLinter>,
+    naming: NamingLinter,
+    rules: RuleLinter,
+    symbol_index: SymbolIndex,
+    /// The errors of the latest analysis
+    errors: Vec<Diagnostic>,
+    shared: Option<SharedLibraries>,
 }
 
 impl Project {
     pub fn new() -> Project {
-        let parser = VHDLParser::default();
+        Project::with_parser(VHDLParser::default(), None)
+    }
+
+    /// Create a project that shares the files of third-party libraries with other projects
+    pub fn with_shared_libraries(shared: SharedLibraries) -> Project {
+        Project::with_parser(shared.parser.clone(), Some(shared))
+    }
+
+    fn with_parser(parser: VHDLParser, shared: Option<SharedLibraries>) -> Project {
         Project {
             root: DesignRoot::new(parser.symbols.clone()),
             files: FnvHashMap::default(),
             empty_libraries: FnvHashSet::default(),
             parser,
             lint: None,
+            naming: NamingLinter::default(),
+            rules: RuleLinter::default(),
+            symbol_index: SymbolIndex::default(),
+            errors: Vec::new(),
             config: Config::default(),
+            shared,
         }
     }
 
@@ -47,8 +140,8 @@ impl Project {
     pub fn from_config(config: Config, messages: &mut dyn MessageHandler) -> Project {
         let mut project = Project::new();
         let files = project.load_files_from_config(&config, messages);
-        project.parse_and_add_files(files, messages);
         project.config = config;
+        project.parse_and_add_files(files, messages);
         project
     }
 
@@ -56,8 +149,14 @@ impl Project {
     /// The design state is reset, new files are added and parsed. Existing source files will be
     /// kept and parsed from in-memory source (required for incremental document updates).
     pub fn update_config(&mut self, config: Config, messages: &mut dyn MessageHandler) {
-        self.parser = VHDLParser::default();
+        self.parser = match self.shared {
+            Some(ref shared) => shared.parser.clone(),
+            None => VHDLParser::default(),
+        };
         self.root = DesignRoot::new(self.parser.symbols.clone());
+        self.symbol_index.invalidate();
+        self.naming = NamingLinter::default();
+        self.rules = RuleLinter::default();
 
         // Reset library associations for known files,
         // all project files are added to the corresponding libraries later on.
@@ -67,22 +166,26 @@ impl Project {
 
         // Files might already be part of self.files, these have to be parsed
         // from in-memory source. New files can be parsed as usual.
-        let (known_files, new_files) = self
+        let (known_files, new_files): (FnvHashMap<FilePath, _>, _) = self
             .load_files_from_config(&config, messages)
             .into_iter()
             .partition(|(file_name, _library_names)| self.files.contains_key(file_name));
 
+        self.config = config;
+
         for (file_name, library_names) in known_files {
+            let standard = self.standard_of(&file_name, &library_names);
             if let Some(source_file) = self.files.get_mut(&file_name) {
                 source_file.parser_diagnostics.clear();
                 source_file.library_names = library_names;
-                source_file.design_file = self
-                    .parser
-                    .parse_design_source(&source_file.source, &mut source_file.parser_diagnostics);
+                source_file.design_file = self.parser.parse_design_source_with_standard(
+                    &source_file.source,
+                    standard,
+                    &mut source_file.parser_diagnostics,
+                );
             }
         }
 
-        self.config = config;
         self.parse_and_add_files(new_files, messages);
     }
 
@@ -129,19 +232,53 @@ impl Project {
     ) {
         use rayon::prelude::*;
 
+        let mut shared_files = Vec::new();
+        let files_to_parse: Vec<_> = files_to_parse
+            .into_iter()
+            .filter_map(|(file_name, library_names)| {
+                let standard = self.standard_of(&file_name, &library_names);
+                let shared = self
+                    .shared
+                    .as_ref()
+                    .filter(|_| self.is_third_party(&library_names));
+                match shared.and_then(|shared| shared.get(&file_name, standard)) {
+                    Some(file) => {
+                        shared_files.push((library_names, file));
+                        None
+                    }
+                    None => Some((file_name, library_names, standard)),
+                }
+            })
+            .collect();
+
         let parsed: Vec<_> = files_to_parse
             .into_par_iter()
             .map_init(
                 || &self.parser,
-                |parser, (file_name, library_names)| {
+                |parser, (file_name, library_names, standard)| {
+                    // Taken before reading, such that a change while parsing is noticed later
+                    let stamp = FileStamp::of(&file_name);
                     let mut diagnostics = Vec::new();
-                    let result = parser.parse_design_file(&file_name, &mut diagnostics);
-                    (file_name, library_names, diagnostics, result)
+                    let result = parser.parse_design_file_with_standard(
+                        &file_name,
+                        standard,
+                        &mut diagnostics,
+                    );
+                    (
+                        file_name,
+                        library_names,
+                        standard,
+                        stamp,
+                        diagnostics,
+                        result,
+                    )
                 },
             )
             .collect();
 
-        for (file_name, library_names, parser_diagnostics, result) in parsed.into_iter() {
+        for (file_name, library_names, standard, stamp, parser_diagnostics, result) in
+            parsed.into_iter()
+        {
             let (source, design_file) = match result {
                 Ok(result) => result,
                 Err(err) => {
@@ -149,19 +286,51 @@ impl Project {
                     continue;
                 }
             };
+            let file = SharedFile {
+                stamp,
+                source,
+                design_file,
+                parser_diagnostics,
+            };
+            if let Some(ref shared) = self.shared {
+                if self.is_third_party(&library_names) {
+                    shared.insert(standard, file.clone());
+                }
+            }
+            shared_files.push((library_names, file));
+        }
 
+        for (library_names, file) in shared_files {
             self.files.insert(
-                FilePath::new(source.file_name()),
+                FilePath::new(file.source.file_name()),
                 SourceFile {
-                    source,
+                    source: file.source,
                     library_names,
-                    parser_diagnostics,
-                    design_file,
+                    parser_diagnostics: file.parser_diagnostics,
+                    design_file: file.design_file,
                 },
             );
         }
     }
 
+    /// True if all libraries of a file are third-party libraries
+    fn is_third_party(&self, library_names: &FnvHashSet<Symbol>) -> bool {
+        !library_names.is_empty()
+            && library_names.iter().all(|library_name| {
+                self.config
+                    .get_library(&library_name.name_utf8())
+                    .is_some_and(|library| library.is_third_party)
+            })
+    }
+
+    /// The revision of the standard used to parse and analyze a file
+    fn standard_of(&self, file_name: &Path, library_names: &FnvHashSet<Symbol>) -> VHDLStandard {
+        let mut library_names: Vec<_> = library_names.iter().map(|lib| lib.name_utf8()).collect();
+        library_names.sort();
+        self.config
+            .standard_of_file(file_name, library_names.iter().map(|lib| lib.as_str()))
+    }
+
     pub fn library_mapping_of(&self, source: &Source) -> Vec<Symbol> {
         let file = if let Some(file) = self.files.get(source.file_path()) {
             file
@@ -180,6 +349,10 @@ impl Project {
     }
 
     pub fn update_source(&mut self, source: &Source) {
+        self.rules.invalidate(source);
+        if let Some(ref shared) = self.shared {
+            shared.invalidate(source.file_path());
+        }
         let mut source_file = {
             if let Some(mut source_file) = self.files.remove(source.file_path()) {
                 // File is already part of the project
@@ -190,12 +363,14 @@ impl Project {
                 source_file
             } else {
                 // File is not part of the project
-                // @TODO use config wildcards to map to library
-
-                // Add unmapped files to an anonymous library work
+                // Map it to the libraries inferred from the configuration
                 // To still get some semantic analysis for unmapped files
-                let mut library_names = FnvHashSet::default();
-                library_names.insert(self.root.symbol_utf8("work"));
+                let library_names = self
+                    .config
+                    .libraries_of_unmapped_file(source.file_name())
+                    .into_iter()
+                    .map(|library_name| self.root.symbol_utf8(library_name))
+                    .collect();
 
                 SourceFile {
                     source: source.clone(),
@@ -205,15 +380,208 @@ impl Project {
                 }
             }
         };
+        let standard = self.standard_of(source.file_name(), &source_file.library_names);
         source_file.parser_diagnostics.clear();
-        source_file.design_file = self
-            .parser
-            .parse_design_source(source, &mut source_file.parser_diagnostics);
+        source_file.design_file = self.parser.parse_design_source_with_standard(
+            source,
+            standard,
+            &mut source_file.parser_diagnostics,
+        );
         self.files
             .insert(source.file_path().to_owned(), source_file);
     }
 
+    /// Load a file that was created or changed on disk.
+    ///
+    /// A file that is already part of the project is reloaded, other files are only added
+    /// when they match the file patterns of a library. A file that no longer exists is removed.
+    pub fn load_file(&mut self, file_name: &Path, messages: &mut dyn MessageHandler) {
+        if file_name.is_dir() {
+            return;
+        }
+
+        let source = match Source::from_latin1_file(file_name) {
+            Ok(source) => source,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.remove_files(file_name);
+                return;
+            }
+            Err(err) => {
+                messages.push(Message::file_error(err.to_string(), file_name));
+                return;
+            }
+        };
+
+        if !self.files.contains_key(source.file_path()) {
+            let library_names: FnvHashSet<Symbol> = self
+                .config
+                .iter_libraries()
+                .filter(|library| library.matches_file(file_name))
+                .map(|library| self.root.symbol_utf8(library.name()))
+                .collect();
+
+            if library_names.is_empty() {
+                return;
+            }
+
+            self.files.insert(
+                source.file_path().to_owned(),
+                SourceFile {
+                    source: source.clone(),
+                    library_names,
+                    parser_diagnostics: vec![],
+                    design_file: DesignFile::default(),
+                },
+            );
+        }
+        self.update_source(&source);
+    }
+
+    /// Remove a file, or all files within a directory, that was deleted from disk
+    pub fn remove_files(&mut self, path: &Path) {
+        let removed: Vec<FilePath> = self
+            .files
+            .keys()
+            .filter(|file_path| file_path.starts_with(path))
+            .cloned()
+            .collect();
+
+        for file_path in removed {
+            if let Some(source_file) = self.files.remove(&file_path) {
+                for library_name in source_file.library_names.iter() {
+                    self.root
+                        .remove_source(library_name.clone(), &source_file.source);
+                }
+            }
+        }
+    }
+
+    /// Move a file, or all files within a directory, that was renamed on disk.
+    /// The renamed files are mapped to libraries by their new names.
+    pub fn rename_files(&mut self, from: &Path, to: &Path, messages: &mut dyn MessageHandler) {
+        let mut renamed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|file_path| file_path.strip_prefix(from).ok())
+            .map(|relative| {
+                if relative.as_os_str().is_empty() {
+                    to.to_owned()
+                } else {
+                    to.join(relative)
+                }
+            })
+            .collect();
+
+        if renamed.is_empty() {
+            renamed.push(to.to_owned());
+        }
+
+        self.remove_files(from);
+        for file_name in renamed {
+            self.load_file(&file_name, messages);
+        }
+    }
+
+    /// Suggest library mappings from the library units that the context clauses of a file refer to
+    ///
+    /// A reference to `lib.unit` where `unit` is not found in `lib` but is declared in another
+    /// library suggests mapping the file declaring `unit` to `lib`.
+    /// A reference to `work.unit` where `unit` is only declared in another library
+    /// suggests mapping the file itself to that library.
+    /// The suggestions are based on the design units of the latest analysis.
+    pub fn suggest_library_mappings(&self, source: &Source) -> Vec<LibraryMapping> {
+        let library_names = match self.files.get(source.file_path()) {
+            Some(file) => file.library_names.clone(),
+            None => FnvHashSet::default(),
+        };
+        let standard = self.standard_of(source.file_name(), &library_names);
+        let design_file = self.parser.parse_design_source_with_standard(
+            source,
+            standard,
+            &mut Vec::<Diagnostic>::new(),
+        );
+
+        let work_sym = self.root.symbol_utf8("work");
+        let default_sym = self.root.symbol_utf8(self.config.default_library());
+        let mut mappings = Vec::new();
+        let mut add_mapping = |library_name: &Symbol, file_name: &Path| {
+            let mapping = LibraryMapping {
+                library_name: library_name.name_utf8(),
+                file_name: file_name.to_owned(),
+            };
+            if !mappings.contains(&mapping) {
+                mappings.push(mapping);
+            }
+        };
+
+        for (_, design_unit) in design_file.design_units.iter() {
+            for item in design_unit.context_clause().iter() {
+                let name_list = match item {
+                    ContextItem::Use(use_clause) => &use_clause.name_list,
+                    ContextItem::Context(context_reference) => &context_reference.name_list,
+                    ContextItem::Library(_) => continue,
+                };
+
+                for name in name_list.items.iter() {
+                    let Some((library_name, unit_name)) = library_unit_of(&name.item) else {
+                        continue;
+                    };
+
+                    if library_name == &work_sym {
+                        if library_names
+                            .iter()
+                            .any(|library_name| self.declares_primary_unit(library_name, unit_name))
+                        {
+                            continue;
+                        }
+                        for other_library in self.libraries_declaring(unit_name) {
+                            if ![&work_sym, &default_sym].contains(&other_library.name()) {
+                                add_mapping(other_library.name(), source.file_name());
+                            }
+                        }
+                    } else if !self.declares_primary_unit(library_name, unit_name) {
+                        for other_library in self.libraries_declaring(unit_name) {
+                            if let Some(unit) = other_library.primary_unit(unit_name) {
+                                add_mapping(library_name, unit.source().file_name());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        mappings
+    }
+
+    fn declares_primary_unit(&self, library_name: &Symbol, unit_name: &Symbol) -> bool {
+        self.root
+            .get_lib(library_name)
+            .and_then(|library| library.primary_unit(unit_name))
+            .is_some()
+    }
+
+    /// The libraries that declare a primary unit, sorted by name
+    fn libraries_declaring(&self, unit_name: &Symbol) -> Vec<&Library> {
+        let mut libraries: Vec<_> = self
+            .root
+            .libraries()
+            .filter(|library| library.primary_unit(unit_name).is_some())
+            .collect();
+        libraries.sort_by_key(|library| library.name().name_utf8());
+        libraries
+    }
+
     pub fn analyse(&mut self) -> Vec<Diagnostic> {
+        self.analyse_cancellable(&AtomicBool::new(false))
+            .expect("Analysis was cancelled")
+    }
+
+    /// Analyse the project unless `cancelled` is set while the analysis is running.
+    ///
+    /// Returns `None` when the analysis was cancelled. The project then needs to be
+    /// analysed again before it can be queried, the next analysis reuses the design
+    /// units that were already analysed.
+    pub fn analyse_cancellable(&mut self, cancelled: &AtomicBool) -> Option<Vec<Diagnostic>> {
         let mut diagnostics = Vec::new();
 
         for source_file in self.files.values_mut() {
@@ -235,13 +603,28 @@ impl Project {
             self.root.ensure_library(library_name.clone());
         }
 
-        let analyzed_units = self.root.analyze(&mut diagnostics);
+        let Some(analyzed_units) = self.root.analyze_cancellable(&mut diagnostics, cancelled)
+        else {
+            // The units analysed before the cancellation are not analysed again
+            self.symbol_index.invalidate();
+            return None;
+        };
+        self.symbol_index.update(&self.root, &analyzed_units);
 
         if let Some(ref mut lint) = self.lint {
             lint.lint(&self.root, &self.config, &analyzed_units, &mut diagnostics);
         }
-
-        diagnostics
+        self.naming
+            .lint(&self.root, &self.config, &analyzed_units, &mut diagnostics);
+        self.rules
+            .lint(&self.root, &self.config, &analyzed_units, &mut diagnostics);
+
+        self.errors = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .cloned()
+            .collect();
+        Some(diagnostics)
     }
 
     /// Search for reference at position
@@ -261,6 +644,16 @@ impl Project {
         Some(ent.declaration())
     }
 
+    /// Search for the type of the object, element or function at the cursor
+    pub fn find_type_definition<'a>(
+        &'a self,
+        source: &Source,
+        cursor: Position,
+    ) -> Option<EntRef<'a>> {
+        let ent = self.root.search_reference(source, cursor)?;
+        self.root.find_type_definition_of(ent)
+    }
+
     pub fn item_at_cursor<'a>(
         &'a self,
         source: &Source,
@@ -274,6 +667,18 @@ impl Project {
         self.root.public_symbols()
     }
 
+    /// The public named entities best matching a workspace symbol query, at most `limit` of them.
+    ///
+    /// The query is a fuzzy pattern of the name, optionally qualified by the names of enclosing
+    /// libraries or design units such as `ieee.numeric_std.to_uns`. A kind filter such as `e:`
+    /// selects entities, `a:` architectures, `p:` packages, `c:` components, `t:` types,
+    /// `f:` subprograms and `o:` objects.
+    ///
+    /// The matches are ordered so that the matches of several projects can be merged by sorting.
+    pub fn search_symbols<'a>(&'a self, query: &str, limit: usize) -> Vec<SymbolMatch<'a>> {
+        self.symbol_index.search(&self.root, query, limit)
+    }
+
     // Find symbols that are public such as primary design units and their interfaces
     pub fn document_symbols<'a>(
         &'a self,
@@ -296,6 +701,59 @@ impl Project {
         self.root.format_declaration(ent)
     }
 
+    /// The inlay hints of a source file within a range
+    pub fn inlay_hints(&self, source: &Source, range: Range) -> Vec<InlayHint> {
+        self.root.inlay_hints(source, range)
+    }
+
+    /// The code lenses of the declarations of a source file
+    pub fn code_lenses(&self, source: &Source) -> Vec<CodeLens> {
+        self.root.code_lenses(source)
+    }
+
+    /// The uses summarized by a code lens
+    pub fn resolve_code_lens(&self, lens: &CodeLens) -> Option<ResolvedCodeLens> {
+        self.root.resolve_code_lens(lens)
+    }
+
+    /// The refactorings applicable to a selection of a source file
+    pub fn refactorings(&self, source: &Source, selection: Range) -> Vec<Refactoring> {
+        self.root.refactorings(source, selection)
+    }
+
+    /// The declarations within a range of a source file that do not follow the naming conventions
+    pub fn naming_violations(&self, source: &Source, range: Range) -> Vec<NamingViolation> {
+        self.naming
+            .violations(&self.config)
+            .filter(|violation| {
+                violation.pos.source == *source
+                    && violation.pos.start() <= range.end
+                    && range.start <= violation.pos.end()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// All uses within a source file of the named entity at the cursor
+    pub fn document_highlights(&self, source: &Source, cursor: Position) -> Vec<DocumentHighlight> {
+        self.root.document_highlights(source, cursor)
+    }
+
+    /// The folding ranges of a source file sorted by their start line
+    pub fn folding_ranges(&self, source: &Source) -> Vec<FoldingRange> {
+        self.root.folding_ranges(source)
+    }
+
+    /// The ranges of the AST nodes containing the cursor from the innermost to the outermost
+    pub fn selection_ranges(&self, source: &Source, cursor: Position) -> Vec<Range> {
+        self.root.selection_ranges(source, cursor)
+    }
+
+    /// Collect the information shown when hovering over a named entity
+    pub fn hover_info(&self, ent: &AnyEnt) -> Option<HoverInfo> {
+        self.root.hover_info(ent)
+    }
+
     pub fn format_entity(&self, id: EntityId) -> Option<String> {
         let ent = self.root.get_ent(id);
         self.format_declaration(ent)
@@ -306,22 +764,196 @@ impl Project {
         self.root.find_all_references(ent)
     }
 
+    /// Rename a named entity and find the positions of all names to replace by the new name.
+    ///
+    /// The new name must be a basic or extended identifier that is not a reserved word.
+    /// The renamed design is analysed again to make sure that no homographs are created
+    /// and that every name still denotes the same declaration as before.
+    /// With `with_bindings` the entities and component declarations that are bound by default
+    /// binding are renamed together, as are their generics and ports.
+    pub fn rename(
+        &self,
+        ent: &AnyEnt,
+        new_name: &str,
+        with_bindings: bool,
+    ) -> Result<Vec<SrcPos>, RenameError> {
+        let Designator::Identifier(old_name) = ent.designator() else {
+            return Err(RenameError::NotAnIdentifier(format!(
+                "Cannot rename {}",
+                ent.describe()
+            )));
+        };
+        let standard = ent
+            .decl_pos()
+            .and_then(|pos| self.files.get(pos.source.file_path()))
+            .map(|file| self.standard_of(file.source.file_name(), &file.library_names))
+            .unwrap_or_default();
+        let new_symbol = parse_identifier(&self.parser.symbols, standard, new_name)?;
+
+        let ents = if with_bindings {
+            self.root.rename_bindings(ent)
+        } else {
+            vec![ent]
+        };
+        let mut positions: Vec<SrcPos> = ents
+            .iter()
+            .flat_map(|ent| self.root.find_all_references(ent))
+            .collect();
+        positions.sort();
+        positions.dedup();
+
+        if new_symbol != *old_name {
+            self.check_rename(&ents, &positions, new_name)?;
+        }
+        Ok(positions)
+    }
+
+    /// Analyse a copy of the project where the names at `positions` are replaced.
+    /// Only the units of the renamed sources and their users are analysed again.
+    fn check_rename(
+        &self,
+        ents: &[EntRef],
+        positions: &[SrcPos],
+        new_name: &str,
+    ) -> Result<(), RenameError> {
+        let errors_before: FnvHashSet<(&Path, u32, &str)> = self
+            .errors
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.pos.file_name(),
+                    diagnostic.pos.start().line,
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+
+        let renamed_sources = RenamedSources::new(positions, new_name);
+        let mut renamed = self.clone();
+        for source in renamed_sources.sources() {
+            renamed.update_source(source);
+        }
+
+        // Renaming only moves text within a line so errors are compared by line
+        if let Some(diagnostic) = renamed.analyse().into_iter().find(|diagnostic| {
+            diagnostic.severity == Severity::Error
+                && !errors_before.contains(&(
+                    diagnostic.pos.file_name(),
+                    diagnostic.pos.start().line,
+                    diagnostic.message.as_str(),
+                ))
+        }) {
+            return Err(RenameError::Conflict {
+                message: diagnostic.message,
+                pos: diagnostic.pos,
+            });
+        }
+
+        let declarations: Vec<SrcPos> = ents
+            .iter()
+            .filter_map(|ent| ent.decl_pos())
+            .map(|pos| renamed_sources.map(pos))
+            .collect();
+        let references: Vec<SrcPos> = positions
+            .iter()
+            .map(|pos| renamed_sources.map(pos))
+            .collect();
+        renamed
+            .root
+            .check_renamed(new_name, &declarations, &references)
+    }
+
+    /// The subprograms, processes and design units that call a subprogram
+    pub fn incoming_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        self.root.incoming_calls(ent)
+    }
+
+    /// The subprograms called by a subprogram, process or design unit
+    pub fn outgoing_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        self.root.outgoing_calls(ent)
+    }
+
+    /// The type or subtype that a subtype or type alias is declared from
+    pub fn supertype<'a>(&'a self, ent: EntRef<'a>) -> Option<EntRef<'a>> {
+        self.root.supertype(ent)
+    }
+
+    /// The subtypes and type aliases declared from a type or subtype
+    pub fn subtypes<'a>(&'a self, ent: EntRef<'a>) -> Vec<EntRef<'a>> {
+        self.root.subtypes(ent)
+    }
+
     /// Get source positions that are not resolved to a declaration
     /// This is used for development to test where the language server is blind
     pub fn find_all_unresolved(&self) -> (usize, Vec<SrcPos>) {
         self.root.find_all_unresolved()
     }
 
+    /// The files of the analyzed design in the order they must be compiled
+    ///
+    /// When top units are given only the files needed to elaborate them are included.
+    /// The project must be analyzed before since the order is based on the dependencies found by the analysis.
+    pub fn compile_order(&self, top_units: &[String]) -> Result<Vec<LibraryFile>, String> {
+        compile_order(&self.root, top_units)
+    }
+
+    /// The dependencies between the design units of the analyzed design
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        self.root.dependency_graph()
+    }
+
+    /// The documentation of the entities and packages of the analyzed design
+    pub fn documentation(&self) -> Documentation {
+        self.root.documentation()
+    }
+
     pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
         self.files.values()
     }
 
+    /// The completion options at the cursor followed by the applicable snippets of the configuration
     pub fn list_completion_options(
         &self,
         source: &Source,
         cursor: Position,
     ) -> Vec<CompletionItem> {
-        list_completion_options(&self.root, source, cursor)
+        let mut options = list_completion_options(&self.root, source, cursor);
+        options.extend(list_snippets(
+            &self.root,
+            source,
+            cursor,
+            &self.config.snippets(),
+        ));
+        options
+    }
+}
+
+/// The library and primary unit name of a selected name such as `lib.pkg.all`
+fn library_unit_of(name: &Name) -> Option<(&Symbol, &Symbol)> {
+    let mut prefixes = Vec::new();
+    let mut name = name;
+    loop {
+        match name {
+            Name::Designator(designator) => {
+                prefixes.push(&designator.item);
+                break;
+            }
+            Name::Selected(prefix, suffix) => {
+                prefixes.push(&suffix.item.item);
+                name = &prefix.item;
+            }
+            Name::SelectedAll(prefix) => {
+                name = &prefix.item;
+            }
+            _ => return None,
+        }
+    }
+
+    match prefixes.as_slice() {
+        [.., Designator::Identifier(unit_name), Designator::Identifier(library_name)] => {
+            Some((library_name, unit_name))
+        }
+        _ => None,
     }
 }
 
@@ -348,6 +980,7 @@ impl Default for Project {
     }
 }
 
+#[derive(Clone)]
 pub struct SourceFile {
     library_names: FnvHashSet<Symbol>,
     source: Source,
@@ -436,64 +1069,255 @@ end architecture;
         assert_eq!(diag.message, "Duplicate architecture 'rtl' of entity 'ent'")
     }
 
-    /// Test that the same file can be added to several libraries
     #[test]
-    fn test_same_file_in_multiple_libraries() {
+    fn cancelled_analysis_is_resumed() {
         let root = tempfile::tempdir().unwrap();
-        let vhdl_file_path1 = root.path().join("file.vhd");
-        std::fs::write(
-            vhdl_file_path1,
-            "
-package pkg is
-end package;
-        ",
-        )
-        .unwrap();
-
-        let vhdl_file_path2 = root.path().join("use_file.vhd");
+        let vhdl_file_path = root.path().join("file.vhd");
         std::fs::write(
-            vhdl_file_path2,
+            &vhdl_file_path,
             "
-library lib1;
-use lib1.pkg.all;
-
-package use_pkg1 is
-end package;
-
-library lib2;
-use lib2.pkg.all;
+entity ent is
+end ent;
 
-package use_pkg2 is
-end package;
-        ",
+architecture rtl of ent2 is
+begin
+end architecture;
+",
         )
         .unwrap();
-
-        let config_str = "
-[libraries]
-lib1.files = ['file.vhd']
-lib2.files = ['file.vhd']
-use_lib.files = ['use_file.vhd']
-        ";
-
-        let config = Config::from_str(config_str, root.path()).unwrap();
+        let config =
+            Config::from_str("[libraries]\nlib.files = ['file.vhd']", root.path()).unwrap();
         let mut messages = Vec::new();
         let mut project = Project::from_config(config, &mut messages);
         assert_eq!(messages, vec![]);
+
+        assert_eq!(project.analyse_cancellable(&AtomicBool::new(true)), None);
+        let diagnostics = project.analyse();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "No primary unit 'ent2' within library 'lib'"
+        );
+
+        // A copy of the analysed project is not affected by later changes
+        let mut snapshot = project.clone();
+        let source = project
+            .get_source(&vhdl_file_path)
+            .unwrap()
+            .changed(None, "entity ent is\nend ent;");
+        project.update_source(&source);
         check_no_diagnostics(&project.analyse());
+        assert_eq!(snapshot.analyse().len(), 1);
     }
 
-    fn update(project: &mut Project, source: &mut Source, contents: &str) {
-        std::fs::write(std::path::Path::new(source.file_name()), contents).unwrap();
-        *source = Source::from_latin1_file(source.file_name()).unwrap();
-        project.update_source(source);
+    #[test]
+    fn copy_of_analysed_project_only_copies_changed_units() {
+        for num_files in [10, 100] {
+            let root = tempfile::tempdir().unwrap();
+            for i in 0..num_files {
+                std::fs::write(
+                    root.path().join(format!("file{i}.vhd")),
+                    format!(
+                        "
+entity ent{i} is
+end entity;
+
+architecture a of ent{i} is
+begin
+end architecture;
+"
+                    ),
+                )
+                .unwrap();
+            }
+            let config =
+                Config::from_str("[libraries]\nlib.files = ['*.vhd']", root.path()).unwrap();
+            let mut messages = Vec::new();
+            let mut project = Project::from_config(config, &mut messages);
+            assert_eq!(messages, vec![]);
+            check_no_diagnostics(&project.analyse());
+
+            let num_units = 2 * num_files;
+            let snapshot = project.clone();
+            assert_eq!(project.root.units_shared_with(&snapshot.root), num_units);
+
+            // Only the changed architecture is copied when it is analysed again
+            let file_path = root.path().join("file0.vhd");
+            let source = project.get_source(&file_path).unwrap().changed(
+                None,
+                "
+entity ent0 is
+end entity;
+
+architecture a of ent0 is
+begin
+  blk: block
+  begin
+  end block;
+end architecture;
+",
+            );
+            project.update_source(&source);
+            check_no_diagnostics(&project.analyse());
+            assert_eq!(
+                project.root.units_shared_with(&snapshot.root),
+                num_units - 1
+            );
+            let snapshot_source = snapshot.get_source(&file_path).unwrap();
+            assert_eq!(snapshot_source.contents().num_lines(), 7);
+        }
     }
 
-    /// Test that the same file can be added to several libraries
     #[test]
-    fn test_re_analyze_after_update() {
-        let tempdir = tempfile::tempdir().unwrap();
-        let root = dunce::canonicalize(tempdir.path()).unwrap();
+    fn third_party_files_are_shared_between_projects() {
+        let root = tempfile::tempdir().unwrap();
+        let pkg_path = root.path().join("pkg.vhd");
+        std::fs::write(&pkg_path, "package pkg is\nend package;\n").unwrap();
+        std::fs::write(
+            root.path().join("file.vhd"),
+            "
+library third;
+use third.pkg.all;
+
+entity ent is
+end entity;
+",
+        )
+        .unwrap();
+        let config = Config::from_str(
+            "
+[libraries]
+third.files = ['pkg.vhd']
+third.is_third_party = true
+lib.files = ['file.vhd']
+",
+            root.path(),
+        )
+        .unwrap();
+
+        let shared = SharedLibraries::default();
+        let load = |shared: &SharedLibraries| {
+            let mut messages = Vec::new();
+            let mut project = Project::with_shared_libraries(shared.clone());
+            project.update_config(config.clone(), &mut messages);
+            assert_eq!(messages, vec![]);
+            check_no_diagnostics(&project.analyse());
+            project
+        };
+        let second_line = |project: &Project| {
+            let source = project.get_source(&pkg_path).unwrap();
+            let line = source.contents().get_line(1).unwrap().trim().to_owned();
+            line
+        };
+
+        let first = load(&shared);
+        assert_eq!(second_line(&first), "end package;");
+        assert_eq!(second_line(&load(&shared)), "end package;");
+
+        // A file that changed on disk is read again by the next project
+        std::fs::write(&pkg_path, "package pkg is\n  -- changed\nend package;\n").unwrap();
+        assert_eq!(second_line(&load(&shared)), "-- changed");
+        assert_eq!(second_line(&first), "end package;");
+    }
+
+    #[test]
+    fn symbol_search_follows_changed_sources() {
+        let root = tempfile::tempdir().unwrap();
+        let vhdl_file_path = root.path().join("file.vhd");
+        std::fs::write(
+            &vhdl_file_path,
+            "
+package pkg is
+  component old_name
+  end component;
+end package;
+",
+        )
+        .unwrap();
+        let config =
+            Config::from_str("[libraries]\nlib.files = ['file.vhd']", root.path()).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        check_no_diagnostics(&project.analyse());
+
+        let names = |project: &Project, query| {
+            project
+                .search_symbols(query, 10)
+                .into_iter()
+                .map(|symbol| symbol.ent.path_name())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&project, "lib.old_na"), vec!["lib.pkg.old_name"]);
+
+        let source = project.get_source(&vhdl_file_path).unwrap();
+        source.change(
+            None,
+            "package pkg is\n  component new_name\n  end component;\nend package;",
+        );
+        project.update_source(&source);
+        check_no_diagnostics(&project.analyse());
+        assert_eq!(names(&project, "lib.old_na"), Vec::<String>::new());
+        assert_eq!(names(&project, "lib.new_na"), vec!["lib.pkg.new_name"]);
+    }
+
+    /// Test that the same file can be added to several libraries
+    #[test]
+    fn test_same_file_in_multiple_libraries() {
+        let root = tempfile::tempdir().unwrap();
+        let vhdl_file_path1 = root.path().join("file.vhd");
+        std::fs::write(
+            vhdl_file_path1,
+            "
+package pkg is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let vhdl_file_path2 = root.path().join("use_file.vhd");
+        std::fs::write(
+            vhdl_file_path2,
+            "
+library lib1;
+use lib1.pkg.all;
+
+package use_pkg1 is
+end package;
+
+library lib2;
+use lib2.pkg.all;
+
+package use_pkg2 is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let config_str = "
+[libraries]
+lib1.files = ['file.vhd']
+lib2.files = ['file.vhd']
+use_lib.files = ['use_file.vhd']
+        ";
+
+        let config = Config::from_str(config_str, root.path()).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        assert_eq!(messages, vec![]);
+        check_no_diagnostics(&project.analyse());
+    }
+
+    fn update(project: &mut Project, source: &mut Source, contents: &str) {
+        std::fs::write(std::path::Path::new(source.file_name()), contents).unwrap();
+        *source = Source::from_latin1_file(source.file_name()).unwrap();
+        project.update_source(source);
+    }
+
+    /// Test that the same file can be added to several libraries
+    #[test]
+    fn test_re_analyze_after_update() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
 
         let path1 = root.join("file1.vhd");
         let path2 = root.join("file2.vhd");
@@ -586,6 +1410,95 @@ end package;
         check_no_diagnostics(&project.analyse());
     }
 
+    /// Test that files created, renamed and deleted on disk are added and removed
+    #[test]
+    fn files_changed_on_disk() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let src = root.join("src");
+        std::fs::create_dir(&src).unwrap();
+
+        let pkg_path = src.join("pkg.vhd");
+        std::fs::write(
+            &pkg_path,
+            "
+package pkg is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let use_path = root.join("use.vhd");
+        std::fs::write(
+            &use_path,
+            "
+use work.pkg.all;
+
+package use_pkg is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let config_str = "
+[libraries]
+lib.files = ['src/*.vhd', 'use.vhd']
+        ";
+        let config = Config::from_str(config_str, &root).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        assert_eq!(messages, vec![]);
+        check_no_diagnostics(&project.analyse());
+
+        // A created file matching a library pattern is added
+        let pkg2_path = src.join("pkg2.vhd");
+        std::fs::write(
+            &pkg2_path,
+            "
+package pkg2 is
+end package;
+        ",
+        )
+        .unwrap();
+        project.load_file(&pkg2_path, &mut messages);
+        std::fs::write(
+            &use_path,
+            "
+use work.pkg.all;
+use work.pkg2.all;
+
+package use_pkg is
+end package;
+        ",
+        )
+        .unwrap();
+        project.load_file(&use_path, &mut messages);
+        assert_eq!(messages, vec![]);
+        check_no_diagnostics(&project.analyse());
+
+        // A created file not matching any library pattern is ignored
+        let other_path = root.join("other.vhd");
+        std::fs::write(&other_path, "").unwrap();
+        project.load_file(&other_path, &mut messages);
+        assert!(project.get_source(&other_path).is_none());
+
+        // A file renamed to no longer match the library pattern is removed
+        let renamed_path = root.join("pkg2.vhd");
+        std::fs::rename(&pkg2_path, &renamed_path).unwrap();
+        project.rename_files(&pkg2_path, &renamed_path, &mut messages);
+        assert!(project.get_source(&renamed_path).is_none());
+        let diagnostics = project.analyse();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].pos.source.file_name(), use_path);
+
+        // Deleting a directory removes the files within it
+        std::fs::remove_dir_all(&src).unwrap();
+        project.remove_files(&src);
+        assert!(project.get_source(&pkg_path).is_none());
+        assert_eq!(project.analyse().len(), 2);
+        assert_eq!(messages, vec![]);
+    }
+
     /// Test that the configuration can be updated
     #[test]
     fn test_config_update() {
@@ -652,4 +1565,464 @@ lib.files = ['file2.vhd']
         assert_eq!(diagnostics[0].pos.source, source2); // No such library
         assert_eq!(diagnostics[1].pos.source, source2); // No declaration
     }
+
+    /// A configuration with the standard library and the files of the lint tests in library `lib`
+    fn lint_config(root: &Path, lint: &str) -> Config {
+        let config_str = format!(
+            "
+[libraries]
+std.files = ['{}/../vhdl_libraries/std/*.vhd']
+std.is_third_party = true
+lib.files = ['ent.vhd', 'rtl.vhd']
+
+[lint]
+{lint}
+        ",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap()
+        );
+        Config::from_str(&config_str, root).unwrap()
+    }
+
+    /// A project with an entity with ports and an architecture with an after clause in another file
+    fn lint_project(lint: &str) -> (tempfile::TempDir, Project, Source, Source) {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let ent_path = root.join("ent.vhd");
+        std::fs::write(
+            &ent_path,
+            "
+entity ent is
+  port (o : out bit);
+end entity;
+",
+        )
+        .unwrap();
+        let rtl_path = root.join("rtl.vhd");
+        std::fs::write(
+            &rtl_path,
+            "
+architecture rtl of ent is
+  signal s : bit;
+begin
+  s <= '1' after 1 ns;
+
+  process
+  begin
+    wait;
+  end process;
+end architecture;
+",
+        )
+        .unwrap();
+
+        let mut messages = Vec::new();
+        let project = Project::from_config(lint_config(&root, lint), &mut messages);
+        assert_eq!(messages, vec![]);
+        let ent_source = project.get_source(&ent_path).unwrap();
+        let rtl_source = project.get_source(&rtl_path).unwrap();
+        (tempdir, project, ent_source, rtl_source)
+    }
+
+    /// The code and severity of each diagnostic of a lint rule
+    fn lint_codes(diagnostics: &[Diagnostic]) -> Vec<(&'static str, Severity)> {
+        let mut codes: Vec<_> = diagnostics
+            .iter()
+            .filter_map(|diagnostic| Some((diagnostic.code?, diagnostic.severity)))
+            .collect();
+        codes.sort_by_key(|(code, _)| *code);
+        codes
+    }
+
+    #[test]
+    fn lint_diagnostics_follow_changed_sources() {
+        let (_tempdir, mut project, mut ent_source, mut rtl_source) =
+            lint_project("after_clause = true\nunlabeled_process = true");
+        let expected = vec![
+            ("after_clause", Severity::Warning),
+            ("unlabeled_process", Severity::Warning),
+        ];
+        assert_eq!(lint_codes(&project.analyse()), expected);
+
+        // Diagnostics of files without analysed units are kept
+        assert_eq!(lint_codes(&project.analyse()), expected);
+
+        // The architecture is not analysed again since the entity declares the same port
+        update(
+            &mut project,
+            &mut ent_source,
+            "
+-- The entity
+entity ent is
+  port (o : out bit);
+end entity;
+",
+        );
+        assert_eq!(lint_codes(&project.analyse()), expected);
+
+        update(
+            &mut project,
+            &mut rtl_source,
+            "
+architecture rtl of ent is
+  signal s : bit;
+begin
+  s <= '1' after 1 ns;
+
+  main : process
+  begin
+    wait;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            lint_codes(&project.analyse()),
+            vec![("after_clause", Severity::Warning)]
+        );
+
+        // The after clause depends on the ports of the entity in another file
+        update(
+            &mut project,
+            &mut ent_source,
+            "
+entity ent is
+end entity;
+",
+        );
+        check_no_diagnostics(&project.analyse());
+    }
+
+    #[test]
+    fn lint_diagnostics_follow_the_configuration() {
+        let (tempdir, mut project, _, _) = lint_project("after_clause = true");
+        assert_eq!(
+            lint_codes(&project.analyse()),
+            vec![("after_clause", Severity::Warning)]
+        );
+
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let mut messages = Vec::new();
+        project.update_config(
+            lint_config(&root, "after_clause = 'error'\nunlabeled_process = 'hint'"),
+            &mut messages,
+        );
+        assert_eq!(messages, vec![]);
+        assert_eq!(
+            lint_codes(&project.analyse()),
+            vec![
+                ("after_clause", Severity::Error),
+                ("unlabeled_process", Severity::Hint)
+            ]
+        );
+
+        project.update_config(lint_config(&root, ""), &mut messages);
+        assert_eq!(messages, vec![]);
+        check_no_diagnostics(&project.analyse());
+    }
+
+    #[test]
+    fn unmapped_files_are_added_to_inferred_library() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        std::fs::create_dir(root.join("uart")).unwrap();
+
+        let pkg_path = root.join("pkg.vhd");
+        std::fs::write(
+            &pkg_path,
+            "
+package pkg is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let config_str = "
+[libraries]
+lib.files = ['pkg.vhd', 'src/*.vhd']
+
+[directory_libraries]
+'uart' = 'uart_lib'
+        ";
+        let config = Config::from_str(config_str, &root).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+
+        let source = Source::inline(&root.join("src").join("new.vhd"), "");
+        project.update_source(&source);
+        assert_eq!(
+            project.library_mapping_of(&source),
+            vec![project.root.symbol_utf8("lib")]
+        );
+
+        let source = Source::inline(&root.join("uart").join("rx.vhd"), "");
+        project.update_source(&source);
+        assert_eq!(
+            project.library_mapping_of(&source),
+            vec![project.root.symbol_utf8("uart_lib")]
+        );
+
+        let source = Source::inline(&root.join("other.vhd"), "");
+        project.update_source(&source);
+        assert_eq!(
+            project.library_mapping_of(&source),
+            vec![project.root.symbol_utf8("work")]
+        );
+    }
+
+    #[test]
+    fn suggest_library_mappings_from_use_clauses() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+
+        let pkg_path = root.join("pkg.vhd");
+        std::fs::write(
+            &pkg_path,
+            "
+package pkg is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let util_path = root.join("util.vhd");
+        std::fs::write(
+            &util_path,
+            "
+package util is
+end package;
+        ",
+        )
+        .unwrap();
+
+        let config_str = "
+[libraries]
+lib.files = ['pkg.vhd']
+        ";
+        let config = Config::from_str(config_str, &root).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        project.update_source(&Source::from_latin1_file(&util_path).unwrap());
+
+        let source = Source::inline(
+            &root.join("ent.vhd"),
+            "
+library util_lib;
+use util_lib.util.all;
+use work.pkg.all;
+
+entity ent is
+end entity;
+        ",
+        );
+        project.update_source(&source);
+        project.analyse();
+
+        assert_eq!(
+            project.suggest_library_mappings(&source),
+            vec![
+                LibraryMapping {
+                    library_name: "util_lib".to_owned(),
+                    file_name: util_path,
+                },
+                LibraryMapping {
+                    library_name: "lib".to_owned(),
+                    file_name: root.join("ent.vhd"),
+                },
+            ]
+        );
+    }
+
+    /// A project with the standard library and a single file in library `lib`
+    fn project_with_std(code: &str) -> (tempfile::TempDir, Project, Source) {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let file_path = root.join("file.vhd");
+        std::fs::write(&file_path, code).unwrap();
+
+        let config_str = format!(
+            "
+[libraries]
+std.files = ['{}/../vhdl_libraries/std/*.vhd']
+lib.files = ['file.vhd']
+        ",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap()
+        );
+        let config = Config::from_str(&config_str, &root).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        check_no_diagnostics(&project.analyse());
+        let source = project.get_source(&file_path).unwrap();
+        (tempdir, project, source)
+    }
+
+    fn position_of(source: &Source, substr: &str, occurence: usize) -> Position {
+        let contents = source.contents();
+        let mut found = 0;
+        for lineno in 0..contents.num_lines() {
+            let line = contents.get_line(lineno).unwrap();
+            for (character, _) in line.match_indices(substr) {
+                found += 1;
+                if found == occurence {
+                    return Position::new(lineno as u32, character as u32);
+                }
+            }
+        }
+        panic!("Could not find occurence {occurence} of '{substr}'")
+    }
+
+    fn rename(
+        project: &Project,
+        source: &Source,
+        substr: &str,
+        new_name: &str,
+        with_bindings: bool,
+    ) -> Result<Vec<SrcPos>, RenameError> {
+        let ent = project
+            .find_declaration(source, position_of(source, substr, 1))
+            .unwrap();
+        project.rename(ent, new_name, with_bindings)
+    }
+
+    #[test]
+    fn rename_validates_the_new_name() {
+        let (_tempdir, project, source) = project_with_std(
+            "
+entity ent is
+end entity;
+",
+        );
+
+        assert!(rename(&project, &source, "ent is", "ent2", false).is_ok());
+        assert!(rename(&project, &source, "ent is", "\\my ent\\", false).is_ok());
+        assert_eq!(
+            rename(&project, &source, "ent is", "signal", false),
+            Err(RenameError::InvalidName(
+                "'signal' is a reserved word".to_owned()
+            ))
+        );
+        for new_name in [
+            "",
+            "1ent",
+            "ent 2",
+            " ent2",
+            "ent2 -- comment",
+            "a__b",
+            "ent_",
+        ] {
+            assert_eq!(
+                rename(&project, &source, "ent is", new_name, false),
+                Err(RenameError::InvalidName(format!(
+                    "'{new_name}' is not a valid identifier"
+                ))),
+                "{new_name}"
+            );
+        }
+    }
+
+    #[test]
+    fn rename_replaces_all_references() {
+        let (_tempdir, project, source) = project_with_std(
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal a, b : bit;
+begin
+  main : process
+  begin
+    a <= b; b <= a;
+    wait;
+  end process;
+end architecture;
+",
+        );
+
+        let positions = rename(&project, &source, "a,", "long_name", false).unwrap();
+        assert_eq!(
+            positions
+                .iter()
+                .map(|pos| (pos.start().line, pos.start().character))
+                .collect::<Vec<_>>(),
+            vec![(5, 9), (9, 4), (9, 17)]
+        );
+    }
+
+    #[test]
+    fn rename_detects_homographs_and_hidden_declarations() {
+        let (_tempdir, project, source) = project_with_std(
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal a, b : bit;
+begin
+  main : process
+    variable v : bit;
+  begin
+    v := a;
+    wait;
+  end process;
+end architecture;
+",
+        );
+
+        assert!(matches!(
+            rename(&project, &source, "a,", "b", false),
+            Err(RenameError::Conflict { message, .. }) if message.contains("Duplicate declaration")
+        ));
+        assert!(matches!(
+            rename(&project, &source, "v :", "a", false),
+            Err(RenameError::Conflict { .. })
+        ));
+        assert!(rename(&project, &source, "v :", "c", false).is_ok());
+    }
+
+    #[test]
+    fn rename_through_default_binding() {
+        let (_tempdir, project, source) = project_with_std(
+            "
+entity ent is
+  port (p : in bit);
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+  component ent is
+    port (p : in bit);
+  end component;
+  signal s : bit;
+begin
+  inst : ent port map (p => s);
+end architecture;
+",
+        );
+
+        let lines = |positions: Vec<SrcPos>| {
+            positions
+                .iter()
+                .map(|pos| pos.start().line)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lines(rename(&project, &source, "ent is", "ent2", false).unwrap()),
+            vec![1, 5]
+        );
+        assert_eq!(
+            lines(rename(&project, &source, "ent is", "ent2", true).unwrap()),
+            vec![1, 5, 13, 18]
+        );
+        assert_eq!(
+            lines(rename(&project, &source, "p :", "q", true).unwrap()),
+            vec![2, 14, 18]
+        );
+    }
 }
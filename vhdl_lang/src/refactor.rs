@@ -0,0 +1,1921 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Refactorings extracting expressions and statements into declarations, inlining constants
+//! and converting association lists between positional and named association
+
+use crate::analysis::{positional_formals, DesignRoot};
+use crate::ast::search::{NotFinished, NotFound, SearchState, Searcher};
+use crate::ast::visitor::{ASTNode, NextItemPos, Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Contents, Position, Range, Source, SrcPos, Symbol, WithPos};
+use crate::document_highlight::{formal_reference, WriteVisitor};
+use crate::named_entity::{FormalRegion, InterfaceEnt, Object, Type, TypeEnt, UniversalType};
+use crate::syntax::{HasTokenSpan, Kind, TokenAccess};
+use crate::{AnyEnt, AnyEntKind, Design, EntRef, EntityId};
+use fnv::FnvHashSet;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RefactoringKind {
+    /// Extract an expression into a constant
+    ExtractConstant,
+    /// Extract an expression into a signal driven by a concurrent signal assignment
+    ExtractSignal,
+    /// Extract sequential statements into a procedure
+    ExtractProcedure,
+    /// Replace the uses of a constant by its value
+    InlineConstant,
+    /// Name the formals of the positional associations of a map aspect or subprogram call
+    ConvertToNamedAssociation,
+    /// Remove the formals of the named associations of a map aspect or subprogram call
+    ConvertToPositionalAssociation,
+    /// Sort named associations in the order of the formals and associate unconnected outputs with `open`
+    SortNamedAssociations,
+}
+
+/// A replacement of the text of a source file
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SourceEdit {
+    /// The replaced text, which is empty for an insertion
+    pub pos: SrcPos,
+    pub text: String,
+}
+
+/// A refactoring together with the edits that perform it.
+/// The edits do not overlap and are sorted by position.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Refactoring {
+    pub kind: RefactoringKind,
+    pub title: String,
+    pub edits: Vec<SourceEdit>,
+}
+
+impl DesignRoot {
+    /// The refactorings applicable to a selection of a source file
+    pub fn refactorings(&self, source: &Source, selection: Range) -> Vec<Refactoring> {
+        let contents = source.contents();
+        let selection = trim(&contents, selection);
+
+        // A constant is inlined from its designator or from one of its uses
+        let inline_target = self
+            .item_at_cursor(source, selection.start)
+            .filter(|(pos, _)| selection.start == selection.end || pos.range() == selection)
+            .map(|(_, ent)| ent)
+            .filter(|ent| {
+                matches!(
+                    ent.kind(),
+                    AnyEntKind::Object(Object {
+                        class: ObjectClass::Constant,
+                        iface: None,
+                        ..
+                    })
+                )
+            });
+
+        let mut visitor = RefactoringVisitor {
+            selection,
+            inline_target: inline_target.map(|ent| ent.id()),
+            regions: Vec::new(),
+            begins: Vec::new(),
+            concurrent_statements: Vec::new(),
+            statement_lists: Vec::new(),
+            loops: Vec::new(),
+            jumps: Vec::new(),
+            next_pos: NextItemPos::default(),
+            expression: None,
+            object_declarations: Vec::new(),
+            constant: None,
+            association_list: None,
+        };
+        self.walk_source(source, &mut visitor);
+
+        let mut searcher = ReferenceSearcher {
+            source: source.clone(),
+            references: Vec::new(),
+        };
+        let _ = self.search(&mut searcher);
+
+        let mut writes = WriteVisitor::new(self);
+        self.walk_source(source, &mut writes);
+
+        let refactorer = Refactorer {
+            root: self,
+            source,
+            contents: &contents,
+            found: visitor,
+            references: searcher.references,
+            writes: writes.writes,
+        };
+        let mut refactorings = Vec::new();
+        refactorings.extend(refactorer.extract_constant());
+        refactorings.extend(refactorer.extract_signal());
+        refactorings.extend(refactorer.extract_procedure());
+        if let Some(ent) = inline_target {
+            refactorings.extend(refactorer.inline_constant(ent));
+        }
+        if let Some(ref list) = refactorer.found.association_list {
+            refactorings.extend(refactorer.convert_associations(list));
+        }
+        refactorings
+    }
+}
+
+struct Refactorer<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    contents: &'a Contents,
+    found: RefactoringVisitor,
+    references: Vec<(SrcPos, EntityId)>,
+    writes: FnvHashSet<SrcPos>,
+}
+
+impl<'a> Refactorer<'a> {
+    fn extract_constant(&self) -> Option<Refactoring> {
+        let expr = self.found.expression.as_ref()?;
+        let typ = type_of_expression(self.root, &expr.item)?;
+        // The value of a constant is computed once when the constant is elaborated
+        let is_static = self
+            .references_within(&expr.pos)
+            .all(|(_, ent)| match ent.kind() {
+                AnyEntKind::Object(object) => object.class == ObjectClass::Constant,
+                AnyEntKind::LoopParameter(_)
+                | AnyEntKind::ObjectAlias { .. }
+                | AnyEntKind::ExternalAlias { .. }
+                | AnyEntKind::File(_)
+                | AnyEntKind::InterfaceFile(_) => false,
+                _ => true,
+            });
+        if !is_static {
+            return None;
+        }
+        let region = self.innermost_region(&expr.pos)?;
+
+        let name = unused_name(self.contents, "new_constant");
+        let value = text_of(self.contents, expr.pos.range());
+        Some(Refactoring {
+            kind: RefactoringKind::ExtractConstant,
+            title: "Extract constant".to_owned(),
+            edits: vec![
+                self.declare(
+                    region,
+                    &expr.pos,
+                    &[format!("constant {name} : {} := {value};", name_of(&typ))],
+                ),
+                SourceEdit {
+                    pos: expr.pos.clone(),
+                    text: name,
+                },
+            ],
+        })
+    }
+
+    fn extract_signal(&self) -> Option<Refactoring> {
+        let expr = self.found.expression.as_ref()?;
+        if self.writes.contains(&expr.pos) {
+            return None;
+        }
+        let typ = type_of_expression(self.root, &expr.item)?;
+        // The constraint of a signal of an unconstrained array type cannot be inferred
+        if matches!(typ.base_type().kind(), Type::Array { .. })
+            && !matches!(typ.kind(), Type::Subtype(_))
+        {
+            return None;
+        }
+        let region = self.innermost_region(&expr.pos)?;
+        if !matches!(region.kind, BodyKind::Architecture | BodyKind::Block) {
+            return None;
+        }
+        // The statement that reads the expression
+        let statement = self
+            .found
+            .concurrent_statements
+            .iter()
+            .filter(|statement| encloses(statement, &expr.pos) && encloses(&region.pos, statement))
+            .max_by_key(|statement| statement.start())?;
+
+        let name = unused_name(self.contents, "new_signal");
+        let value = text_of(self.contents, expr.pos.range());
+        let indent = indentation(self.contents, statement.start().line);
+        Some(Refactoring {
+            kind: RefactoringKind::ExtractSignal,
+            title: "Extract signal".to_owned(),
+            edits: vec![
+                self.declare(
+                    region,
+                    &expr.pos,
+                    &[format!("signal {name} : {};", name_of(&typ))],
+                ),
+                SourceEdit {
+                    pos: statement.pos_at_beginning(),
+                    text: format!("{name} <= {value};\n{indent}"),
+                },
+                SourceEdit {
+                    pos: expr.pos.clone(),
+                    text: name,
+                },
+            ],
+        })
+    }
+
+    fn extract_procedure(&self) -> Option<Refactoring> {
+        let statements = self.selected_statements()?;
+        let region = self.innermost_region(&statements)?;
+        if !matches!(region.kind, BodyKind::Process | BodyKind::Subprogram) {
+            return None;
+        }
+
+        // Jumps out of the statements cannot be extracted
+        for (pos, jump) in self.found.jumps.iter() {
+            if !encloses(&statements, pos) {
+                continue;
+            }
+            let Jump::Loop(ref label) = jump else {
+                return None;
+            };
+            // The loop of the jump is the innermost enclosing loop with its label, if any
+            let target = self
+                .found
+                .loops
+                .iter()
+                .filter(|(lp, lp_label)| {
+                    encloses(lp, pos)
+                        && lp.start() < pos.start()
+                        && (label.is_none() || lp_label == label)
+                })
+                .max_by_key(|(lp, _)| lp.start());
+            if !target.is_some_and(|(lp, _)| encloses(&statements, lp)) {
+                return None;
+            }
+        }
+
+        // The objects declared in the process or subprogram are passed as parameters
+        let mut parameters: Vec<(EntRef, bool, bool)> = Vec::new();
+        for (pos, ent) in self.references_within(&statements) {
+            if !matches!(
+                ent.kind(),
+                AnyEntKind::Object(_) | AnyEntKind::LoopParameter(_)
+            ) {
+                continue;
+            }
+            let Some(decl_pos) = ent.decl_pos() else {
+                continue;
+            };
+            if &decl_pos.source != self.source
+                || !encloses(&region.pos, decl_pos)
+                || encloses(&statements, decl_pos)
+            {
+                continue;
+            }
+            let is_written = self.writes.contains(pos);
+            match parameters
+                .iter_mut()
+                .find(|(other, ..)| other.id() == ent.id())
+            {
+                Some((_, read, written)) => {
+                    *read |= !is_written;
+                    *written |= is_written;
+                }
+                None => parameters.push((ent, !is_written, is_written)),
+            }
+        }
+        let mut formals = Vec::with_capacity(parameters.len());
+        for (ent, read, written) in parameters.iter() {
+            formals.push(formal(self.root, ent, *read, *written)?);
+        }
+
+        let name = unused_name(self.contents, "new_procedure");
+        let (specification, call) = if parameters.is_empty() {
+            (format!("procedure {name} is"), format!("{name};"))
+        } else {
+            let actuals: Vec<String> = parameters
+                .iter()
+                .map(|(ent, ..)| ent.designator().to_string())
+                .collect();
+            (
+                format!("procedure {name}({}) is", formals.join("; ")),
+                format!("{name}({});", actuals.join(", ")),
+            )
+        };
+
+        let mut lines = vec![specification, "begin".to_owned()];
+        let base_indent = indentation(self.contents, statements.start().line);
+        for (i, line) in text_of(self.contents, statements.range())
+            .lines()
+            .enumerate()
+        {
+            let line = if i == 0 {
+                line
+            } else {
+                line.strip_prefix(&base_indent).unwrap_or(line.trim_start())
+            };
+            lines.push(format!("  {line}"));
+        }
+        lines.push("end procedure;".to_owned());
+
+        Some(Refactoring {
+            kind: RefactoringKind::ExtractProcedure,
+            title: "Extract procedure".to_owned(),
+            edits: vec![
+                self.declare(region, &statements, &lines),
+                SourceEdit {
+                    pos: statements,
+                    text: call,
+                },
+            ],
+        })
+    }
+
+    fn inline_constant(&self, ent: EntRef) -> Option<Refactoring> {
+        let constant = self.found.constant.as_ref()?;
+        // Removing a declaration of an identifier list would remove the other constants
+        let declarations = self
+            .found
+            .object_declarations
+            .iter()
+            .filter(|pos| **pos == constant.pos)
+            .count();
+        if declarations != 1 {
+            return None;
+        }
+
+        let value = text_of(self.contents, constant.value.pos.range());
+        let value = match constant.value.item {
+            Expression::Literal(Literal::String(_) | Literal::BitString(_))
+            | Expression::Literal(Literal::Character(_)) => {
+                // Qualified by the type since the literal may be ambiguous where it is used
+                let type_mark = text_of(self.contents, constant.type_mark.range());
+                format!("{type_mark}'({value})")
+            }
+            Expression::Aggregate(_) => {
+                let type_mark = text_of(self.contents, constant.type_mark.range());
+                format!("{type_mark}'{value}")
+            }
+            Expression::Name(_) | Expression::Literal(_) | Expression::Qualified(_) => value,
+            Expression::Binary(..) | Expression::Unary(..) | Expression::New(_) => {
+                format!("({value})")
+            }
+        };
+        let is_name = matches!(constant.value.item, Expression::Name(_));
+
+        let uses = self.root.find_uses(ent);
+        let mut edits = Vec::with_capacity(uses.len() + 1);
+        for pos in uses {
+            if &pos.source != self.source || previous_char(self.contents, pos.start()) == Some('.')
+            {
+                return None;
+            }
+            // Only names can be the prefix of an indexed, selected or attribute name
+            if !is_name && matches!(next_char(self.contents, pos.end()), Some('(' | '.' | '\'')) {
+                return None;
+            }
+            edits.push(SourceEdit {
+                pos,
+                text: value.clone(),
+            });
+        }
+        edits.push(SourceEdit {
+            pos: SrcPos::new(
+                self.source.clone(),
+                removed_lines(self.contents, constant.pos.range()),
+            ),
+            text: String::new(),
+        });
+        edits.sort_by(|a, b| a.pos.cmp(&b.pos));
+
+        Some(Refactoring {
+            kind: RefactoringKind::InlineConstant,
+            title: format!("Inline constant '{}'", ent.designator()),
+            edits,
+        })
+    }
+
+    /// Convert an association list between positional and named association
+    fn convert_associations(&self, list: &AssociationList) -> Vec<Refactoring> {
+        let Some(formals) = self.formals_of(list) else {
+            return Vec::new();
+        };
+        let Some(associations) = associated_formals(&formals, &list.elems) else {
+            return Vec::new();
+        };
+        let spans: Vec<_> = list.elems.iter().map(element_span).collect();
+        let mut refactorings = Vec::new();
+        refactorings.extend(self.convert_to_named(list, &formals, &associations, &spans));
+        refactorings.extend(self.convert_to_positional(list, &formals, &associations, &spans));
+        refactorings
+    }
+
+    fn convert_to_named(
+        &self,
+        list: &AssociationList,
+        formals: &FormalRegion,
+        associations: &[Association],
+        spans: &[SrcPos],
+    ) -> Option<Refactoring> {
+        // The new elements with the index of their formal and the element they replace
+        let mut elems: Vec<_> = list
+            .elems
+            .iter()
+            .zip(associations)
+            .enumerate()
+            .map(|(i, (elem, association))| {
+                let text = match elem.formal {
+                    Some(_) => text_of(self.contents, spans[i].range()),
+                    None => format!(
+                        "{} => {}",
+                        name_of(&association.formal),
+                        text_of(self.contents, elem.actual.pos.range())
+                    ),
+                };
+                (association.index, text, Some(i))
+            })
+            .collect();
+        if list.kind == AssociationKind::Ports {
+            for (index, formal) in formals.iter().enumerate() {
+                if formal.is_written() && associations.iter().all(|assoc| assoc.index != index) {
+                    elems.push((index, format!("{} => open", name_of(&formal)), None));
+                }
+            }
+        }
+
+        let is_positional = list.elems.iter().any(|elem| elem.formal.is_none());
+        let is_sorted = associations
+            .windows(2)
+            .all(|pair| pair[0].index <= pair[1].index);
+        let adds_open = elems.len() > list.elems.len();
+        let (kind, title) = match (is_positional, is_sorted, adds_open) {
+            (true, _, _) => (
+                RefactoringKind::ConvertToNamedAssociation,
+                "Convert to named association",
+            ),
+            (false, false, false) => (
+                RefactoringKind::SortNamedAssociations,
+                "Sort named associations",
+            ),
+            (false, true, true) => (
+                RefactoringKind::SortNamedAssociations,
+                "Associate unconnected outputs with open",
+            ),
+            (false, false, true) => (
+                RefactoringKind::SortNamedAssociations,
+                "Sort named associations and associate unconnected outputs with open",
+            ),
+            (false, true, false) => return None,
+        };
+        elems.sort_by_key(|(index, ..)| *index);
+        Some(Refactoring {
+            kind,
+            title: title.to_owned(),
+            edits: self.rewrite_elements(
+                spans,
+                elems
+                    .into_iter()
+                    .map(|(_, text, origin)| (text, origin))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn convert_to_positional(
+        &self,
+        list: &AssociationList,
+        formals: &FormalRegion,
+        associations: &[Association],
+        spans: &[SrcPos],
+    ) -> Option<Refactoring> {
+        // Only whole formals can be associated by position
+        if list.elems.iter().all(|elem| elem.formal.is_none())
+            || !associations.iter().all(|assoc| assoc.whole)
+        {
+            return None;
+        }
+        let last = associations.iter().map(|assoc| assoc.index).max()?;
+        let mut elems = Vec::new();
+        for index in 0..=last {
+            let mut associated = associations
+                .iter()
+                .enumerate()
+                .filter(|(_, assoc)| assoc.index == index);
+            match (associated.next(), associated.next()) {
+                (Some((i, _)), None) => elems.push((
+                    text_of(self.contents, list.elems[i].actual.pos.range()),
+                    Some(i),
+                )),
+                // Outputs that are not associated are skipped with open
+                (None, _)
+                    if list.kind == AssociationKind::Ports
+                        && formals.nth(index).is_some_and(|formal| formal.is_written()) =>
+                {
+                    elems.push(("open".to_owned(), None))
+                }
+                _ => return None,
+            }
+        }
+        Some(Refactoring {
+            kind: RefactoringKind::ConvertToPositionalAssociation,
+            title: "Convert to positional association".to_owned(),
+            edits: self.rewrite_elements(spans, elems),
+        })
+    }
+
+    /// The formals of the instantiated unit or called subprogram of an association list
+    fn formals_of(&self, list: &AssociationList) -> Option<FormalRegion<'a>> {
+        let region = match self.root.get_ent(list.owner).kind() {
+            AnyEntKind::Overloaded(overloaded) if list.kind == AssociationKind::Parameters => {
+                return Some(overloaded.signature().formals.clone());
+            }
+            AnyEntKind::Design(Design::Entity(_, region)) | AnyEntKind::Component(region) => region,
+            _ => return None,
+        };
+        let (generics, ports) = region.to_entity_formal();
+        match list.kind {
+            AssociationKind::Generics => Some(generics),
+            AssociationKind::Ports => Some(ports),
+            AssociationKind::Parameters => None,
+        }
+    }
+
+    /// Replace the elements of an association list by new elements together with the element
+    /// they were made from, if any, where the new elements after the last element are added.
+    /// The comments at the end of the lines move with the elements when every element ends a line.
+    fn rewrite_elements(
+        &self,
+        spans: &[SrcPos],
+        elems: Vec<(String, Option<usize>)>,
+    ) -> Vec<SourceEdit> {
+        let last = spans.len() - 1;
+        let start = spans[last].start();
+        let indent: String = self
+            .contents
+            .get_line(start.line as usize)
+            .unwrap_or_default()
+            .chars()
+            .take(start.character as usize)
+            .map(|chr| if chr == '\t' { chr } else { ' ' })
+            .collect();
+
+        let mut edits = Vec::new();
+        let line_ends: Option<Vec<_>> = spans
+            .iter()
+            .map(|span| line_end(self.contents, span.end()))
+            .collect();
+        if let Some(line_ends) = line_ends {
+            let comment = |origin: Option<usize>| origin.map_or("", |i| &line_ends[i].comment);
+            for (i, (span, end)) in spans.iter().zip(line_ends.iter()).enumerate() {
+                let (ref elem, origin) = elems[i];
+                let mut text = elem.clone();
+                if end.comma || (i == last && elems.len() > spans.len()) {
+                    text.push(',');
+                }
+                text.push_str(comment(origin));
+                if i == last {
+                    for (j, (elem, origin)) in elems.iter().enumerate().skip(spans.len()) {
+                        text.push_str(&format!("\n{indent}{elem}"));
+                        if j + 1 < elems.len() {
+                            text.push(',');
+                        }
+                        text.push_str(comment(*origin));
+                    }
+                }
+                edits.extend(self.replace(Range::new(span.start(), end.pos), text));
+            }
+        } else {
+            for (i, span) in spans.iter().enumerate() {
+                let mut text = elems[i].0.clone();
+                if i == last {
+                    for (elem, _) in elems[spans.len()..].iter() {
+                        text.push_str(", ");
+                        text.push_str(elem);
+                    }
+                }
+                edits.extend(self.replace(span.range(), text));
+            }
+        }
+        edits
+    }
+
+    /// An edit replacing a range unless it already has the text
+    fn replace(&self, range: Range, text: String) -> Option<SourceEdit> {
+        (text_of(self.contents, range) != text).then(|| SourceEdit {
+            pos: self.source.pos(range.start, range.end),
+            text,
+        })
+    }
+
+    /// The named entities referenced within a span
+    fn references_within<'b>(
+        &'b self,
+        span: &'b SrcPos,
+    ) -> impl Iterator<Item = (&'b SrcPos, EntRef<'a>)> + 'b {
+        self.references
+            .iter()
+            .filter(move |(pos, _)| encloses(span, pos))
+            .map(|(pos, id)| (pos, self.root.get_ent(*id)))
+    }
+
+    /// The innermost declarative region where a declaration used at a position can be inserted
+    fn innermost_region(&self, pos: &SrcPos) -> Option<&DeclarativeRegion> {
+        let region = self
+            .found
+            .regions
+            .iter()
+            .filter(|region| encloses(&region.pos, pos))
+            .max_by_key(|region| region.pos.start())?;
+        // The declarations of generate statements are not supported
+        (region.kind != BodyKind::Generate).then_some(region)
+    }
+
+    /// The complete statements of a statement list that are selected
+    fn selected_statements(&self) -> Option<SrcPos> {
+        let selection = self.found.selection;
+        self.found.statement_lists.iter().find_map(|statements| {
+            let first = statements
+                .iter()
+                .position(|pos| pos.start() == selection.start)?;
+            let last = statements[first..]
+                .iter()
+                .find(|pos| pos.end() == selection.end)?;
+            Some(statements[first].combine(last))
+        })
+    }
+
+    /// Insert a declaration into a declarative region before its use.
+    /// The lines of the declaration are indented relative to the first line.
+    fn declare(
+        &self,
+        region: &DeclarativeRegion,
+        used_at: &SrcPos,
+        lines: &[String],
+    ) -> SourceEdit {
+        if let Some(decl) = region.decls.iter().find(|decl| encloses(decl, used_at)) {
+            // Used by another declaration of the region
+            let indent = indentation(self.contents, decl.start().line);
+            SourceEdit {
+                pos: decl.pos_at_beginning(),
+                text: format!("{}\n{indent}", indent_lines(lines, &indent)),
+            }
+        } else if let Some(decl) = region.decls.last() {
+            let indent = indentation(self.contents, decl.start().line);
+            SourceEdit {
+                pos: decl.pos_at_end(),
+                text: format!("\n{indent}{}", indent_lines(lines, &indent)),
+            }
+        } else {
+            let begin = self
+                .found
+                .begins
+                .iter()
+                .filter(|begin| **begin > region.pos.start())
+                .min()
+                .copied()
+                .unwrap_or(region.pos.start());
+            let indent = indentation(self.contents, begin.line);
+            let decl_indent = format!("{indent}  ");
+            SourceEdit {
+                pos: self.source.pos(begin, begin),
+                text: format!("  {}\n{indent}", indent_lines(lines, &decl_indent)),
+            }
+        }
+    }
+}
+
+/// The parameter declaration of an object used by an extracted procedure
+fn formal(root: &DesignRoot, ent: EntRef, read: bool, written: bool) -> Option<String> {
+    let typ = root
+        .find_type_definition_of(ent)
+        .and_then(TypeEnt::from_any)
+        .and_then(|typ| named_type(root, typ))?;
+    let name = ent.designator();
+    let class = match ent.kind() {
+        AnyEntKind::Object(object) => object.class,
+        _ => ObjectClass::Constant,
+    };
+    Some(match class {
+        ObjectClass::Signal => {
+            let mode = match (read, written) {
+                (true, true) => "inout",
+                (false, true) => "out",
+                _ => "in",
+            };
+            format!("signal {name} : {mode} {}", name_of(&typ))
+        }
+        // Variables are copied back after the call so that conditional writes are preserved
+        ObjectClass::Variable | ObjectClass::SharedVariable
+            if written || matches!(typ.base_type().kind(), Type::Protected(..)) =>
+        {
+            format!("variable {name} : inout {}", name_of(&typ))
+        }
+        _ => format!("{name} : in {}", name_of(&typ)),
+    })
+}
+
+/// The type of an expression as resolved by the analysis
+fn type_of_expression<'a>(root: &'a DesignRoot, expr: &Expression) -> Option<TypeEnt<'a>> {
+    let typ = match expr {
+        Expression::Binary(op, ..) | Expression::Unary(op, ..) => {
+            TypeEnt::from_any(root.find_type_definition_of(root.get_ent(op.item.reference?))?)?
+        }
+        Expression::Qualified(qexpr) => {
+            TypeEnt::from_any(root.get_ent(qexpr.type_mark.item.name.item.reference()?))?
+        }
+        Expression::Name(name) => type_of_name(root, name)?,
+        _ => return None,
+    };
+    named_type(root, typ)
+}
+
+fn type_of_name<'a>(root: &'a DesignRoot, name: &Name) -> Option<TypeEnt<'a>> {
+    match name {
+        Name::Designator(_) | Name::Selected(..) => {
+            let ent = root.get_ent(name.get_suffix_reference()?);
+            TypeEnt::from_any(root.find_type_definition_of(ent)?)
+        }
+        Name::CallOrIndexed(coi) => {
+            let ent = root.get_ent(coi.name.item.get_suffix_reference()?);
+            if let AnyEntKind::Overloaded(_) = ent.kind() {
+                return TypeEnt::from_any(root.find_type_definition_of(ent)?);
+            }
+            // An element of an array
+            match type_of_name(root, &coi.name.item)?.base_type().kind() {
+                Type::Array { indexes, elem_type } if indexes.len() == coi.parameters.len() => {
+                    Some(*elem_type)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A type that can be denoted by its name, where a universal integer has the type integer
+fn named_type<'a>(root: &'a DesignRoot, typ: TypeEnt<'a>) -> Option<TypeEnt<'a>> {
+    let typ = match typ.kind() {
+        Type::Universal(UniversalType::Integer) => TypeEnt::from_any(root.integer_type()?)?,
+        Type::Universal(UniversalType::Real) => return None,
+        _ => typ,
+    };
+    matches!(typ.designator(), Designator::Identifier(_)).then_some(typ)
+}
+
+/// The name of a named entity, where the upper case names of the standard library are written in lower case
+fn name_of(ent: &AnyEnt) -> String {
+    let name = ent.designator().to_string();
+    if ent
+        .library_name()
+        .is_some_and(|library| library.name_utf8() == "std")
+    {
+        name.to_lowercase()
+    } else {
+        name
+    }
+}
+
+/// The formal of an association element
+struct Association<'a> {
+    /// The position of the formal among the formals
+    index: usize,
+    formal: InterfaceEnt<'a>,
+    /// The element associates the whole formal rather than a part of it
+    whole: bool,
+}
+
+/// The formals of the elements of an association list when all of them are known
+fn associated_formals<'a>(
+    formals: &FormalRegion<'a>,
+    elems: &[AssociationElement],
+) -> Option<Vec<Association<'a>>> {
+    let positional = positional_formals(formals, elems);
+    elems
+        .iter()
+        .enumerate()
+        .map(|(idx, elem)| {
+            let id = match elem.formal {
+                Some(ref formal) => formal_reference(&formal.item)?,
+                None => positional.get(idx)?.1.id(),
+            };
+            let (index, formal) = formals
+                .iter()
+                .enumerate()
+                .find(|(_, formal)| formal.id() == id)?;
+            Some(Association {
+                index,
+                formal,
+                whole: elem
+                    .formal
+                    .as_ref()
+                    .is_none_or(|formal| matches!(formal.item, Name::Designator(_))),
+            })
+        })
+        .collect()
+}
+
+/// The formal and actual of an association element
+fn element_span(elem: &AssociationElement) -> SrcPos {
+    match elem.formal {
+        Some(ref formal) => formal.pos.combine(&elem.actual.pos),
+        None => elem.actual.pos.clone(),
+    }
+}
+
+/// The separator and comment after an association element that ends its line
+struct LineEnd {
+    comma: bool,
+    /// The comment including the whitespace before it, or nothing
+    comment: String,
+    /// The end of the text of the line
+    pos: Position,
+}
+
+fn line_end(contents: &Contents, pos: Position) -> Option<LineEnd> {
+    let line = contents.get_line(pos.line as usize)?;
+    let rest: String = line.chars().skip(pos.character as usize).collect();
+    let rest = rest.trim_end();
+    let (comma, after) = match rest.trim_start().strip_prefix(',') {
+        Some(after) => (true, after),
+        None => (false, rest),
+    };
+    let after = after.trim_end();
+    if !after.trim_start().is_empty() && !after.trim_start().starts_with("--") {
+        return None;
+    }
+    Some(LineEnd {
+        comma,
+        comment: after.to_owned(),
+        pos: Position::new(pos.line, pos.character + rest.chars().count() as u32),
+    })
+}
+
+fn encloses(outer: &SrcPos, inner: &SrcPos) -> bool {
+    outer.start() <= inner.start() && inner.end() <= outer.end()
+}
+
+/// The selection without surrounding whitespace
+fn trim(contents: &Contents, selection: Range) -> Range {
+    let Range { mut start, mut end } = selection;
+    while start < end {
+        match char_at(contents, start) {
+            Some(chr) if chr.is_whitespace() => {
+                start = if chr == '\n' {
+                    Position::new(start.line + 1, 0)
+                } else {
+                    start.next_char()
+                }
+            }
+            _ => break,
+        }
+    }
+    while start < end {
+        let before = if end.character == 0 {
+            let Some(line) = contents.get_line(end.line as usize - 1) else {
+                break;
+            };
+            Position::new(end.line - 1, line.chars().count() as u32 - 1)
+        } else {
+            end.prev_char()
+        };
+        match char_at(contents, before) {
+            Some(chr) if chr.is_whitespace() => end = before,
+            _ => break,
+        }
+    }
+    Range::new(start, end)
+}
+
+fn char_at(contents: &Contents, pos: Position) -> Option<char> {
+    contents
+        .get_line(pos.line as usize)?
+        .chars()
+        .nth(pos.character as usize)
+}
+
+fn previous_char(contents: &Contents, pos: Position) -> Option<char> {
+    let line = contents.get_line(pos.line as usize)?;
+    line.chars()
+        .take(pos.character as usize)
+        .filter(|chr| !chr.is_whitespace())
+        .last()
+}
+
+fn next_char(contents: &Contents, pos: Position) -> Option<char> {
+    let line = contents.get_line(pos.line as usize)?;
+    line.chars()
+        .skip(pos.character as usize)
+        .find(|chr| !chr.is_whitespace())
+}
+
+fn text_of(contents: &Contents, range: Range) -> String {
+    let mut text = String::new();
+    for lineno in range.start.line..=range.end.line {
+        let Some(line) = contents.get_line(lineno as usize) else {
+            break;
+        };
+        let start = if lineno == range.start.line {
+            range.start.character as usize
+        } else {
+            0
+        };
+        let chars = line.chars().skip(start);
+        if lineno == range.end.line {
+            text.extend(chars.take((range.end.character as usize).saturating_sub(start)));
+        } else {
+            text.extend(chars);
+        }
+    }
+    text
+}
+
+/// The whitespace at the start of a line
+fn indentation(contents: &Contents, line: u32) -> String {
+    contents
+        .get_line(line as usize)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|chr| *chr == ' ' || *chr == '\t')
+        .collect()
+}
+
+/// Join lines where all but the first are indented, except when they are empty
+fn indent_lines(lines: &[String], indent: &str) -> String {
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+            if !line.trim().is_empty() {
+                text.push_str(indent);
+            }
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// The lines of a declaration when nothing else is written on them, otherwise the declaration
+fn removed_lines(contents: &Contents, range: Range) -> Range {
+    let is_blank = |lineno: u32, from: u32, to: Option<u32>| {
+        contents.get_line(lineno as usize).is_some_and(|line| {
+            let chars = line.chars().skip(from as usize);
+            match to {
+                Some(to) => chars.take((to - from) as usize).all(char::is_whitespace),
+                None => chars.into_iter().all(char::is_whitespace),
+            }
+        })
+    };
+    if is_blank(range.start.line, 0, Some(range.start.character))
+        && is_blank(range.end.line, range.end.character, None)
+    {
+        Range::new(
+            Position::new(range.start.line, 0),
+            Position::new(range.end.line + 1, 0),
+        )
+    } else {
+        range
+    }
+}
+
+/// A name that is not yet used within a source file
+fn unused_name(contents: &Contents, base: &str) -> String {
+    let is_used = |name: &str| {
+        (0..contents.num_lines()).any(|lineno| {
+            let line = contents.get_line(lineno).unwrap_or_default().to_lowercase();
+            line.match_indices(name).any(|(idx, _)| {
+                let is_part =
+                    |chr: Option<char>| chr.is_some_and(|chr| chr.is_alphanumeric() || chr == '_');
+                !is_part(line[..idx].chars().last())
+                    && !is_part(line[idx + name.len()..].chars().next())
+            })
+        })
+    };
+    (0..)
+        .map(|n| {
+            if n == 0 {
+                base.to_owned()
+            } else {
+                format!("{base}_{n}")
+            }
+        })
+        .find(|name| !is_used(name))
+        .unwrap()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BodyKind {
+    Architecture,
+    Block,
+    Process,
+    Subprogram,
+    Generate,
+}
+
+/// A region where declarations can be inserted
+struct DeclarativeRegion {
+    kind: BodyKind,
+    pos: SrcPos,
+    decls: Vec<SrcPos>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum AssociationKind {
+    Generics,
+    Ports,
+    Parameters,
+}
+
+/// The association list of a generic map, port map or subprogram call
+struct AssociationList {
+    kind: AssociationKind,
+    /// The instantiated unit or the called subprogram
+    owner: EntityId,
+    elems: Vec<AssociationElement>,
+}
+
+struct ConstantDeclaration {
+    pos: SrcPos,
+    type_mark: SrcPos,
+    value: WithPos<Expression>,
+}
+
+/// A statement that leaves the sequence of statements containing it
+enum Jump {
+    Return,
+    /// An exit or next statement with the label of the loop it leaves
+    Loop(Option<Symbol>),
+}
+
+/// Collects the parts of the AST of a source file that are relevant to the refactorings
+struct RefactoringVisitor {
+    selection: Range,
+    inline_target: Option<EntityId>,
+    regions: Vec<DeclarativeRegion>,
+    /// The start of every `begin` keyword
+    begins: Vec<Position>,
+    concurrent_statements: Vec<SrcPos>,
+    /// The statements of sequences of sequential statements including their labels
+    statement_lists: Vec<Vec<SrcPos>>,
+    /// Loop statements including their labels together with the labels
+    loops: Vec<(SrcPos, Option<Symbol>)>,
+    jumps: Vec<(SrcPos, Jump)>,
+    next_pos: NextItemPos,
+    /// The expression that is selected
+    expression: Option<WithPos<Expression>>,
+    object_declarations: Vec<SrcPos>,
+    /// The declaration of the constant to inline
+    constant: Option<ConstantDeclaration>,
+    /// The innermost association list containing the selection
+    association_list: Option<AssociationList>,
+}
+
+impl RefactoringVisitor {
+    fn push_region(
+        &mut self,
+        kind: BodyKind,
+        pos: SrcPos,
+        decls: &[Declaration],
+        ctx: &dyn TokenAccess,
+    ) {
+        self.regions.push(DeclarativeRegion {
+            kind,
+            pos,
+            decls: decls.iter().map(|decl| decl.get_pos(ctx)).collect(),
+        });
+    }
+
+    fn push_association_list(
+        &mut self,
+        kind: AssociationKind,
+        owner: EntityId,
+        pos: SrcPos,
+        elems: &[AssociationElement],
+    ) {
+        // Nested lists are visited after the lists containing them
+        if !elems.is_empty()
+            && pos.start() <= self.selection.start
+            && self.selection.end <= pos.end()
+        {
+            self.association_list = Some(AssociationList {
+                kind,
+                owner,
+                elems: elems.to_vec(),
+            });
+        }
+    }
+
+    fn push_statements(&mut self, statements: &[LabeledSequentialStatement]) {
+        self.statement_lists.push(
+            statements
+                .iter()
+                .map(|statement| statement_span(&statement.label, &statement.statement))
+                .collect(),
+        );
+    }
+}
+
+fn statement_span<T>(label: &WithDecl<Option<Ident>>, statement: &WithPos<T>) -> SrcPos {
+    match label.tree {
+        Some(ref label) => label.pos.combine(&statement.pos),
+        None => statement.pos.clone(),
+    }
+}
+
+impl Visitor for RefactoringVisitor {
+    fn visit_any_design_unit(
+        &mut self,
+        node: &AnyDesignUnit,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.begins.extend(
+            node.get_token_slice(ctx)
+                .iter()
+                .filter(|token| token.kind == Kind::Begin)
+                .map(|token| token.pos.start()),
+        );
+        VisitorResult::Continue
+    }
+
+    fn visit_architecture_body(
+        &mut self,
+        node: &ArchitectureBody,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.push_region(BodyKind::Architecture, node.get_pos(ctx), &node.decl, ctx);
+        VisitorResult::Continue
+    }
+
+    fn visit_subprogram_body(
+        &mut self,
+        node: &SubprogramBody,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.push_region(
+            BodyKind::Subprogram,
+            node.get_pos(ctx),
+            &node.declarations,
+            ctx,
+        );
+        self.push_statements(&node.statements);
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let span = statement_span(&node.label, &node.statement);
+        match node.statement.item {
+            ConcurrentStatement::Process(ref process) => {
+                self.push_region(BodyKind::Process, span.clone(), &process.decl, ctx);
+                self.push_statements(&process.statements);
+            }
+            ConcurrentStatement::Block(ref block) => {
+                self.push_region(BodyKind::Block, span.clone(), &block.decl, ctx);
+            }
+            ConcurrentStatement::ForGenerate(_)
+            | ConcurrentStatement::IfGenerate(_)
+            | ConcurrentStatement::CaseGenerate(_) => {
+                self.push_region(BodyKind::Generate, span.clone(), &[], ctx);
+            }
+            _ => {}
+        }
+        self.concurrent_statements.push(span);
+        VisitorResult::Continue
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let span = statement_span(&node.label, &node.statement);
+        match node.statement.item {
+            SequentialStatement::If(ref ifstmt) => {
+                for conditional in ifstmt.conds.conditionals.iter() {
+                    self.push_statements(&conditional.item);
+                }
+                if let Some(ref statements) = ifstmt.conds.else_item {
+                    self.push_statements(statements);
+                }
+            }
+            SequentialStatement::Case(ref case) => {
+                for alternative in case.alternatives.iter() {
+                    self.push_statements(&alternative.item);
+                }
+            }
+            SequentialStatement::Loop(ref lp) => {
+                self.push_statements(&lp.statements);
+                let label = node.label.tree.as_ref().map(|label| label.item.clone());
+                self.loops.push((span, label));
+            }
+            SequentialStatement::Return(_) => self.jumps.push((span, Jump::Return)),
+            SequentialStatement::Exit(ExitStatement { ref loop_label, .. })
+            | SequentialStatement::Next(NextStatement { ref loop_label, .. }) => {
+                let label = loop_label.as_ref().map(|label| label.item.item.clone());
+                self.jumps.push((span, Jump::Loop(label)))
+            }
+            _ => {}
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_object_declaration(
+        &mut self,
+        node: &ObjectDeclaration,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        let pos = node.get_pos(ctx);
+        if self.inline_target.is_some() && node.ident.decl == self.inline_target {
+            if let Some(ref value) = node.expression {
+                self.constant = Some(ConstantDeclaration {
+                    pos: pos.clone(),
+                    type_mark: node.subtype_indication.type_mark.pos.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        self.object_declarations.push(pos);
+        VisitorResult::Continue
+    }
+
+    fn visit_instantiation_statement(
+        &mut self,
+        node: &InstantiationStatement,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let Some(id) = node.entity_reference() {
+            for (kind, map_aspect) in [
+                (AssociationKind::Generics, &node.generic_map),
+                (AssociationKind::Ports, &node.port_map),
+            ] {
+                if let Some(map_aspect) = map_aspect {
+                    let pos = ctx.get_span(map_aspect.start, map_aspect.closing_paren);
+                    self.push_association_list(kind, id, pos, &map_aspect.list.items);
+                }
+            }
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_call_or_indexed(
+        &mut self,
+        node: &CallOrIndexed,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        if let (Some(id), Some(last)) = (
+            node.name.item.get_suffix_reference(),
+            node.parameters.last(),
+        ) {
+            let pos = node.name.pos.combine(&last.actual.pos);
+            self.push_association_list(AssociationKind::Parameters, id, pos, &node.parameters);
+        }
+        VisitorResult::Continue
+    }
+
+    fn visit_item_with_pos(
+        &mut self,
+        pos: &SrcPos,
+        node: &dyn ASTNode,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.next_pos.set(pos, node);
+        VisitorResult::Continue
+    }
+
+    fn visit_expression(&mut self, node: &Expression, _ctx: &dyn TokenAccess) -> VisitorResult {
+        if let Some(pos) = self.next_pos.take(node) {
+            if self.expression.is_none() && pos.range() == self.selection {
+                self.expression = Some(WithPos::new(node.clone(), pos));
+            }
+        }
+        VisitorResult::Continue
+    }
+}
+
+/// Collects the references within a source file
+struct ReferenceSearcher {
+    source: Source,
+    references: Vec<(SrcPos, EntityId)>,
+}
+
+impl Searcher for ReferenceSearcher {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &mut Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.as_ref() {
+            self.references.push((pos.clone(), *id));
+        }
+        NotFinished
+    }
+
+    fn search_source(&mut self, _ctx: &dyn TokenAccess, source: &Source) -> SearchState {
+        if source == &self.source {
+            NotFinished
+        } else {
+            SearchState::Finished(NotFound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::syntax::test::Code;
+
+    /// The code after the edits of a refactoring
+    fn apply(code: &Code, refactoring: &Refactoring) -> String {
+        let contents = code.source().contents();
+        let text: String = (0..contents.num_lines())
+            .filter_map(|lineno| contents.get_line(lineno))
+            .collect();
+        let offset = |pos: Position| {
+            let line_start: usize = text
+                .split_inclusive('\n')
+                .take(pos.line as usize)
+                .map(str::len)
+                .sum();
+            line_start + pos.character as usize
+        };
+        let mut result = text.clone();
+        for edit in refactoring.edits.iter().rev() {
+            result.replace_range(offset(edit.pos.start())..offset(edit.pos.end()), &edit.text);
+        }
+        result
+    }
+
+    fn refactoring(refactorings: &[Refactoring], kind: RefactoringKind) -> Option<&Refactoring> {
+        refactorings
+            .iter()
+            .find(|refactoring| refactoring.kind == kind)
+    }
+
+    #[test]
+    fn extracts_static_expression_into_constant() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant width : natural := 8;
+begin
+  main : process
+    variable v : natural;
+  begin
+    v := width * 2 + v;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let refactorings = root.refactorings(code.source(), code.s1("width * 2").pos().range());
+        assert_eq!(
+            refactorings
+                .iter()
+                .map(|refactoring| refactoring.kind)
+                .collect::<Vec<_>>(),
+            vec![RefactoringKind::ExtractConstant]
+        );
+        assert_eq!(
+            apply(&code, &refactorings[0]),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant width : natural := 8;
+begin
+  main : process
+    variable v : natural;
+    constant new_constant : integer := width * 2;
+  begin
+    v := new_constant + v;
+    wait;
+  end process;
+end architecture;
+"
+        );
+
+        // The value of a variable changes after elaboration
+        assert_eq!(
+            root.refactorings(code.source(), code.s1("width * 2 + v").pos().range()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn extracts_concurrently_read_expression_into_signal() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal a, b, c : bit;
+begin
+  c <= a and b;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        // Surrounding whitespace is not part of the selection
+        let selection = code.s1(" a and b").pos().range();
+        let refactorings = root.refactorings(code.source(), selection);
+        assert_eq!(refactorings.len(), 1);
+        let extract = refactoring(&refactorings, RefactoringKind::ExtractSignal).unwrap();
+        assert_eq!(
+            extract.edits[2].pos,
+            code.s1("a and b").pos(),
+            "The selected expression is replaced"
+        );
+        assert_eq!(
+            apply(&code, extract),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal a, b, c : bit;
+  signal new_signal : bit;
+begin
+  new_signal <= a and b;
+  c <= new_signal;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn extracts_statements_into_procedure_with_parameters() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal q : natural;
+begin
+  main : process
+    variable count : natural;
+  begin
+    wait until clk = '1';
+    count := count + 1;
+    q <= count;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let selection = code
+            .s1("count := count + 1;")
+            .pos()
+            .combine(&code.s1("q <= count;").pos());
+        let refactorings = root.refactorings(code.source(), selection.range());
+        assert_eq!(refactorings.len(), 1);
+        assert_eq!(
+            apply(&code, &refactorings[0]),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal q : natural;
+begin
+  main : process
+    variable count : natural;
+    procedure new_procedure(variable count : inout natural) is
+    begin
+      count := count + 1;
+      q <= count;
+    end procedure;
+  begin
+    wait until clk = '1';
+    new_procedure(count);
+  end process;
+end architecture;
+"
+        );
+
+        // Partially selected statements are not extracted
+        let selection = code
+            .s1("count := count + 1;")
+            .pos()
+            .combine(&code.s1("q <= count").pos());
+        assert_eq!(root.refactorings(code.source(), selection.range()), vec![]);
+    }
+
+    #[test]
+    fn extracts_procedure_without_declarations_and_jumps() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal q : natural;
+begin
+  main : process
+  begin
+    for i in 0 to 3 loop
+      q <= i;
+      exit when clk = '1';
+    end loop;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let refactorings = root.refactorings(code.source(), code.s1("q <= i;").pos().range());
+        let extract = refactoring(&refactorings, RefactoringKind::ExtractProcedure).unwrap();
+        assert_eq!(
+            apply(&code, extract),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal q : natural;
+begin
+  main : process
+    procedure new_procedure(i : in integer) is
+    begin
+      q <= i;
+    end procedure;
+  begin
+    for i in 0 to 3 loop
+      new_procedure(i);
+      exit when clk = '1';
+    end loop;
+    wait;
+  end process;
+end architecture;
+"
+        );
+
+        // The exit statement leaves a loop outside of the selection
+        let selection = code
+            .s1("q <= i;")
+            .pos()
+            .combine(&code.s1("exit when clk = '1';").pos());
+        assert_eq!(root.refactorings(code.source(), selection.range()), vec![]);
+
+        // The whole loop can be extracted
+        let selection = code.s1("for i").pos().combine(&code.s1("end loop;").pos());
+        let refactorings = root.refactorings(code.source(), selection.range());
+        assert_eq!(
+            refactorings
+                .iter()
+                .map(|refactoring| refactoring.kind)
+                .collect::<Vec<_>>(),
+            vec![RefactoringKind::ExtractProcedure]
+        );
+    }
+
+    #[test]
+    fn extracts_procedure_only_with_the_loops_left_by_labeled_jumps() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk : bit;
+  signal q : natural;
+begin
+  main : process
+  begin
+    outer : for i in 0 to 3 loop
+      for j in 0 to 3 loop
+        q <= j;
+        next outer when clk = '1';
+      end loop;
+    end loop;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+        let kinds = |selection: SrcPos| {
+            root.refactorings(code.source(), selection.range())
+                .into_iter()
+                .map(|refactoring| refactoring.kind)
+                .collect::<Vec<_>>()
+        };
+
+        // The next statement leaves the outer loop which is not selected
+        let selection = code
+            .s1("for j")
+            .pos()
+            .combine(&code.s("end loop;", 1).pos());
+        assert_eq!(kinds(selection), vec![]);
+
+        let selection = code
+            .s1("outer :")
+            .pos()
+            .combine(&code.s("end loop;", 2).pos());
+        assert_eq!(kinds(selection), vec![RefactoringKind::ExtractProcedure]);
+    }
+
+    #[test]
+    fn inlines_constants() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant init : bit_vector := \"0101\";
+  constant offset : natural := 2 + 1;
+  signal v : bit_vector(3 downto 0) := init;
+  signal n : natural := offset * 2;
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let refactorings = root.refactorings(
+            code.source(),
+            Range::new(code.s1("init").start(), code.s1("init").start()),
+        );
+        let inline = refactoring(&refactorings, RefactoringKind::InlineConstant).unwrap();
+        assert_eq!(inline.title, "Inline constant 'init'");
+        assert_eq!(
+            apply(&code, inline),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant offset : natural := 2 + 1;
+  signal v : bit_vector(3 downto 0) := bit_vector'(\"0101\");
+  signal n : natural := offset * 2;
+begin
+end architecture;
+"
+        );
+
+        let refactorings = root.refactorings(code.source(), code.s("offset", 2).pos().range());
+        let inline = refactoring(&refactorings, RefactoringKind::InlineConstant).unwrap();
+        assert_eq!(
+            apply(&code, inline),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  constant init : bit_vector := \"0101\";
+  signal v : bit_vector(3 downto 0) := init;
+  signal n : natural := (2 + 1) * 2;
+begin
+end architecture;
+"
+        );
+    }
+
+    const CHILD: &str = "
+entity child is
+  port (
+    clk : in bit;
+    d : in bit;
+    q : out bit;
+    valid : out bit
+  );
+end entity;
+
+architecture rtl of child is
+begin
+end architecture;
+";
+
+    fn at(code: &Code, substr: &str) -> Range {
+        let start = code.s1(substr).pos().start();
+        Range::new(start, start)
+    }
+
+    #[test]
+    fn converts_port_map_to_named_association() {
+        let mut builder = LibraryBuilder::new();
+        builder.code("libname", CHILD);
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk, d, q : bit;
+begin
+  inst : entity work.child
+    port map (
+      clk, -- clock
+      d,
+      q
+    );
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let refactorings = root.refactorings(code.source(), at(&code, "clk, -- clock"));
+        assert_eq!(refactorings.len(), 1);
+        assert_eq!(
+            refactorings[0].kind,
+            RefactoringKind::ConvertToNamedAssociation
+        );
+        assert_eq!(
+            apply(&code, &refactorings[0]),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk, d, q : bit;
+begin
+  inst : entity work.child
+    port map (
+      clk => clk, -- clock
+      d => d,
+      q => q,
+      valid => open
+    );
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn sorts_named_associations_and_converts_to_positional_association() {
+        let mut builder = LibraryBuilder::new();
+        builder.code("libname", CHILD);
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk, d, q : bit;
+begin
+  inst : entity work.child
+    port map (
+      q => q, -- output
+      clk => clk, -- clock
+      d => d
+    );
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let refactorings = root.refactorings(code.source(), at(&code, "clk =>"));
+        let sort = refactoring(&refactorings, RefactoringKind::SortNamedAssociations).unwrap();
+        assert_eq!(
+            sort.title,
+            "Sort named associations and associate unconnected outputs with open"
+        );
+        assert_eq!(
+            apply(&code, sort),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk, d, q : bit;
+begin
+  inst : entity work.child
+    port map (
+      clk => clk, -- clock
+      d => d,
+      q => q, -- output
+      valid => open
+    );
+end architecture;
+"
+        );
+
+        let positional = refactoring(
+            &refactorings,
+            RefactoringKind::ConvertToPositionalAssociation,
+        )
+        .unwrap();
+        assert_eq!(
+            apply(&code, positional),
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal clk, d, q : bit;
+begin
+  inst : entity work.child
+    port map (
+      clk, -- clock
+      d,
+      q -- output
+    );
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn converts_subprogram_call_between_positional_and_named_association() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  procedure add(a, b : natural; variable sum : out natural) is
+  begin
+    sum := a + b;
+  end procedure;
+begin
+  main : process
+    variable v : natural;
+  begin
+    add(b => 2, sum => v, a => 1);
+    add(1, 2, v);
+    add(1, b => 2, sum => v);
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        assert_eq!(diagnostics, Vec::new());
+
+        let converted = |substr: &str, kind: RefactoringKind| {
+            let refactorings = root.refactorings(code.source(), at(&code, substr));
+            let refactoring = refactoring(&refactorings, kind)?;
+            let result = apply(&code, refactoring);
+            let line = code.s1(substr).pos().start().line as usize;
+            Some(result.lines().nth(line).unwrap().trim().to_owned())
+        };
+        assert_eq!(
+            converted(
+                "b => 2, sum",
+                RefactoringKind::ConvertToPositionalAssociation
+            ),
+            Some("add(1, 2, v);".to_owned())
+        );
+        assert_eq!(
+            converted("b => 2, sum", RefactoringKind::SortNamedAssociations),
+            Some("add(a => 1, b => 2, sum => v);".to_owned())
+        );
+        assert_eq!(
+            converted("1, 2, v", RefactoringKind::ConvertToNamedAssociation),
+            Some("add(a => 1, b => 2, sum => v);".to_owned())
+        );
+        assert_eq!(
+            converted("1, b => 2", RefactoringKind::ConvertToNamedAssociation),
+            Some("add(a => 1, b => 2, sum => v);".to_owned())
+        );
+        assert_eq!(
+            converted("1, b => 2", RefactoringKind::ConvertToPositionalAssociation),
+            Some("add(1, 2, v);".to_owned())
+        );
+        // Already positional
+        assert_eq!(
+            converted("1, 2, v", RefactoringKind::ConvertToPositionalAssociation),
+            None
+        );
+    }
+}
@@ -0,0 +1,272 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Renaming of named entities with validation of the new name and detection of conflicts
+
+use crate::analysis::DesignRoot;
+use crate::data::{ContentReader, Contents, Position, Range, Source, SrcPos, Symbol};
+use crate::named_entity::{AnyEntKind, Design, EntRef};
+use crate::standard::VHDLStandard;
+use crate::syntax::{Kind, Symbols, Tokenizer, Value};
+use std::fmt;
+use std::path::Path;
+
+/// The reason why a named entity cannot be renamed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RenameError {
+    /// The new name is not a basic or extended identifier or it is a reserved word
+    InvalidName(String),
+    /// Operator symbols and character literals cannot be renamed
+    NotAnIdentifier(String),
+    /// The new name would create a homograph or change the declaration that a name denotes
+    Conflict { message: String, pos: SrcPos },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::InvalidName(message) | RenameError::NotAnIdentifier(message) => {
+                write!(f, "{message}")
+            }
+            RenameError::Conflict { message, pos } => write!(
+                f,
+                "{message} at {}:{}",
+                pos.file_name().to_string_lossy(),
+                pos.start().line + 1
+            ),
+        }
+    }
+}
+
+/// Parse a new name that must be exactly one basic or extended identifier
+pub(crate) fn parse_identifier(
+    symbols: &Symbols,
+    standard: VHDLStandard,
+    name: &str,
+) -> Result<Symbol, RenameError> {
+    let source = Source::inline(Path::new("rename"), name);
+    let contents = source.contents();
+    let mut tokenizer = Tokenizer::new(symbols, standard, &source, ContentReader::new(&contents));
+
+    let token = match (tokenizer.pop(), tokenizer.pop()) {
+        (Ok(Some(token)), Ok(None)) => token,
+        _ => {
+            return Err(RenameError::InvalidName(format!(
+                "'{name}' is not a valid identifier"
+            )))
+        }
+    };
+
+    // Whitespace and comments around the identifier are not part of the token
+    let whole = token.pos.start() == contents.start() && token.pos.end() == contents.end();
+    // The tokenizer accepts underscores that are not followed by a letter or digit
+    let is_extended = name.starts_with('\\');
+    if !is_extended && (name.contains("__") || name.ends_with('_')) {
+        return Err(RenameError::InvalidName(format!(
+            "'{name}' is not a valid identifier"
+        )));
+    }
+    match (token.kind, token.value) {
+        (Kind::Identifier, Value::Identifier(symbol)) if whole => Ok(symbol),
+        (_, Value::NoValue) if whole && name.chars().all(char::is_alphabetic) => Err(
+            RenameError::InvalidName(format!("'{name}' is a reserved word")),
+        ),
+        _ => Err(RenameError::InvalidName(format!(
+            "'{name}' is not a valid identifier"
+        ))),
+    }
+}
+
+impl DesignRoot {
+    /// The named entities renamed together with a named entity through default binding.
+    ///
+    /// These are the component declarations with the same name as an entity and the
+    /// entity with the same name as a component declaration. For a generic or port the
+    /// generics or ports with the same name of those entities and components are included.
+    pub fn rename_bindings<'a>(&'a self, ent: EntRef<'a>) -> Vec<EntRef<'a>> {
+        let mut result = vec![ent];
+        if is_bindable(ent) {
+            result.extend(self.default_bindings(ent));
+        } else if let Some(parent) = ent.parent.filter(|parent| is_bindable(parent)) {
+            if matches!(ent.kind(), AnyEntKind::Object(_)) {
+                result.extend(
+                    self.default_bindings(parent)
+                        .into_iter()
+                        .filter_map(|other| {
+                            let region = match other.kind() {
+                                AnyEntKind::Component(region) => region,
+                                AnyEntKind::Design(Design::Entity(_, region)) => region,
+                                _ => return None,
+                            };
+                            region
+                                .lookup_immediate(ent.designator())?
+                                .as_non_overloaded()
+                        }),
+                );
+            }
+        }
+        result
+    }
+
+    fn default_bindings<'a>(&'a self, ent: EntRef<'a>) -> Vec<EntRef<'a>> {
+        self.find_implementation(ent)
+            .into_iter()
+            .filter(|other| is_bindable(other) && other.designator() == ent.designator())
+            .collect()
+    }
+
+    /// Check that the references of renamed named entities in a renamed copy of the design
+    /// denote the same named entities as before
+    pub(crate) fn check_renamed(
+        &self,
+        new_name: &str,
+        declarations: &[SrcPos],
+        references: &[SrcPos],
+    ) -> Result<(), RenameError> {
+        let mut renamed = Vec::with_capacity(declarations.len());
+        for pos in declarations {
+            match self.search_reference(&pos.source, pos.start()) {
+                Some(ent) if ent.decl_pos() == Some(pos) => renamed.push(ent),
+                _ => {
+                    return Err(RenameError::Conflict {
+                        message: format!("Cannot declare '{new_name}'"),
+                        pos: pos.clone(),
+                    })
+                }
+            }
+        }
+
+        let is_renamed = |ent: EntRef| {
+            renamed
+                .iter()
+                .any(|other| other.id() == ent.id() || other.id() == ent.declaration().id())
+        };
+
+        for pos in references {
+            match self.search_reference(&pos.source, pos.start()) {
+                Some(ent) if is_renamed(ent) => {}
+                _ => {
+                    return Err(RenameError::Conflict {
+                        message: format!("'{new_name}' would denote another declaration"),
+                        pos: pos.clone(),
+                    })
+                }
+            }
+        }
+
+        for ent in renamed.iter() {
+            if let Some(pos) = self
+                .find_all_references(ent)
+                .into_iter()
+                .find(|pos| !references.contains(pos))
+            {
+                return Err(RenameError::Conflict {
+                    message: format!("'{new_name}' would hide the declaration denoted here"),
+                    pos,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_bindable(ent: EntRef) -> bool {
+    matches!(
+        ent.kind(),
+        AnyEntKind::Component(_) | AnyEntKind::Design(Design::Entity(..))
+    )
+}
+
+/// The sources with the text at the renamed positions replaced by a new name.
+///
+/// Identifiers never span several lines so only the characters following a renamed
+/// position on the same line are moved.
+pub(crate) struct RenamedSources {
+    new_name: String,
+    /// The renamed source together with the renamed ranges of the original source
+    sources: Vec<(Source, Vec<Range>)>,
+}
+
+impl RenamedSources {
+    pub fn new(positions: &[SrcPos], new_name: &str) -> RenamedSources {
+        let mut by_source: Vec<(&Source, Vec<Range>)> = Vec::new();
+        for pos in positions {
+            match by_source
+                .iter_mut()
+                .find(|(source, _)| **source == pos.source)
+            {
+                Some((_, ranges)) => ranges.push(pos.range()),
+                None => by_source.push((&pos.source, vec![pos.range()])),
+            }
+        }
+
+        let sources = by_source
+            .into_iter()
+            .map(|(source, mut ranges)| {
+                ranges.sort_by_key(|range| range.start);
+                let mut contents = Contents::from_str(&text_of(&source.contents()));
+                for range in ranges.iter().rev() {
+                    contents.change(range, new_name);
+                }
+                let renamed = Source::inline(source.file_name(), &text_of(&contents));
+                (renamed, ranges)
+            })
+            .collect();
+
+        RenamedSources {
+            new_name: new_name.to_owned(),
+            sources,
+        }
+    }
+
+    /// The renamed sources that replace the original sources
+    pub fn sources(&self) -> impl Iterator<Item = &Source> {
+        self.sources.iter().map(|(source, _)| source)
+    }
+
+    /// The position in the renamed source corresponding to a position in an original source
+    pub fn map(&self, pos: &SrcPos) -> SrcPos {
+        // The renamed source has the same file name and thus compares equal to the original
+        let Some((source, ranges)) = self
+            .sources
+            .iter()
+            .find(|(source, _)| *source == pos.source)
+        else {
+            return pos.clone();
+        };
+
+        let range = pos.range();
+        let start = self.map_position(ranges, range.start);
+        let end = if ranges.contains(&range) {
+            Position::new(
+                start.line,
+                start.character + self.new_name.encode_utf16().count() as u32,
+            )
+        } else {
+            self.map_position(ranges, range.end)
+        };
+        SrcPos::new(source.clone(), Range::new(start, end))
+    }
+
+    fn map_position(&self, ranges: &[Range], position: Position) -> Position {
+        let new_len = self.new_name.encode_utf16().count() as i64;
+        let shift: i64 = ranges
+            .iter()
+            .filter(|range| range.start.line == position.line && range.end <= position)
+            .map(|range| new_len - (range.end.character - range.start.character) as i64)
+            .sum();
+        Position::new(
+            position.line,
+            (position.character as i64 + shift).max(0) as u32,
+        )
+    }
+}
+
+fn text_of(contents: &Contents) -> String {
+    (0..contents.num_lines())
+        .filter_map(|lineno| contents.get_line(lineno))
+        .collect()
+}
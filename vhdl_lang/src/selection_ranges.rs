@@ -0,0 +1,155 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Selection ranges expanding along the nodes of the AST
+
+use crate::analysis::DesignRoot;
+use crate::ast::visitor::{ASTNode, Visitor, VisitorResult};
+use crate::ast::*;
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::syntax::{HasTokenSpan, TokenAccess};
+use std::cmp::Reverse;
+
+impl DesignRoot {
+    /// The ranges of the AST nodes containing the cursor from the innermost to the outermost.
+    /// Each range contains the range before it.
+    pub fn selection_ranges(&self, source: &Source, cursor: Position) -> Vec<Range> {
+        let mut visitor = SelectionRangeVisitor {
+            cursor,
+            ranges: Vec::new(),
+        };
+        self.walk_source(source, &mut visitor);
+
+        let mut ranges = visitor.ranges;
+        ranges.sort_by_key(|range| (Reverse(range.start), range.end));
+
+        let mut nested: Vec<Range> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let contains_previous = match nested.last() {
+                Some(previous) => {
+                    range != *previous && range.start <= previous.start && previous.end <= range.end
+                }
+                None => true,
+            };
+            if contains_previous {
+                nested.push(range);
+            }
+        }
+        nested
+    }
+}
+
+struct SelectionRangeVisitor {
+    cursor: Position,
+    ranges: Vec<Range>,
+}
+
+impl SelectionRangeVisitor {
+    /// Nodes not containing the cursor are skipped together with their children
+    fn push(&mut self, pos: &SrcPos) -> VisitorResult {
+        if pos.contains(self.cursor) {
+            self.ranges.push(pos.range());
+            VisitorResult::Continue
+        } else {
+            VisitorResult::Skip
+        }
+    }
+}
+
+impl Visitor for SelectionRangeVisitor {
+    fn visit_any_design_unit(
+        &mut self,
+        node: &AnyDesignUnit,
+        ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.push(&node.get_pos(ctx))
+    }
+
+    fn visit_context_item(&mut self, node: &ContextItem, ctx: &dyn TokenAccess) -> VisitorResult {
+        self.push(&node.get_pos(ctx))
+    }
+
+    fn visit_declaration(&mut self, node: &Declaration, ctx: &dyn TokenAccess) -> VisitorResult {
+        self.push(&node.get_pos(ctx))
+    }
+
+    fn visit_labeled_concurrent_statement(
+        &mut self,
+        node: &LabeledConcurrentStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        match node.label.tree {
+            Some(ref label) => self.push(&label.pos.combine(&node.statement.pos)),
+            None => VisitorResult::Continue,
+        }
+    }
+
+    fn visit_labeled_sequential_statement(
+        &mut self,
+        node: &LabeledSequentialStatement,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        match node.label.tree {
+            Some(ref label) => self.push(&label.pos.combine(&node.statement.pos)),
+            None => VisitorResult::Continue,
+        }
+    }
+
+    fn visit_item_with_pos(
+        &mut self,
+        pos: &SrcPos,
+        _node: &dyn ASTNode,
+        _ctx: &dyn TokenAccess,
+    ) -> VisitorResult {
+        self.push(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::tests::LibraryBuilder;
+
+    #[test]
+    fn selection_expands_from_name_to_design_unit() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal a, b : bit;
+begin
+  main : process
+  begin
+    a <= not b;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let ranges = root.selection_ranges(code.source(), code.s1("not b").s1("b").start());
+        assert_eq!(
+            ranges,
+            vec![
+                code.s1("not b").s1("b").pos().range(),
+                code.s1("not b").pos().range(),
+                code.s1("a <= not b;").pos().range(),
+                code.s1_to_end("process")
+                    .s1_from_start("end process;")
+                    .pos()
+                    .range(),
+                code.s1_to_end("main : process")
+                    .s1_from_start("end process;")
+                    .pos()
+                    .range(),
+                code.s1_to_end("architecture rtl").pos().range(),
+            ]
+        );
+    }
+}
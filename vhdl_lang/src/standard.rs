@@ -0,0 +1,87 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Revisions of the VHDL language standard
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A revision of the IEEE 1076 VHDL standard.
+///
+/// The variants are ordered such that a later revision compares greater than an earlier one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum VHDLStandard {
+    VHDL1993,
+    VHDL2002,
+    #[default]
+    VHDL2008,
+    VHDL2019,
+}
+
+impl VHDLStandard {
+    pub const ALL: [VHDLStandard; 4] = [
+        VHDLStandard::VHDL1993,
+        VHDLStandard::VHDL2002,
+        VHDLStandard::VHDL2008,
+        VHDLStandard::VHDL2019,
+    ];
+
+    /// The year of the revision as used in configuration files
+    pub fn year(&self) -> &'static str {
+        match self {
+            VHDLStandard::VHDL1993 => "1993",
+            VHDLStandard::VHDL2002 => "2002",
+            VHDLStandard::VHDL2008 => "2008",
+            VHDLStandard::VHDL2019 => "2019",
+        }
+    }
+}
+
+impl FromStr for VHDLStandard {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "1993" | "93" => Ok(VHDLStandard::VHDL1993),
+            "2002" | "02" => Ok(VHDLStandard::VHDL2002),
+            "2008" | "08" => Ok(VHDLStandard::VHDL2008),
+            "2019" | "19" => Ok(VHDLStandard::VHDL2019),
+            _ => Err(format!(
+                "Unknown VHDL standard '{value}', expected one of 1993, 2002, 2008 or 2019"
+            )),
+        }
+    }
+}
+
+impl Display for VHDLStandard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "VHDL-{}", self.year())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_standard() {
+        for standard in VHDLStandard::ALL {
+            assert_eq!(standard.year().parse(), Ok(standard));
+        }
+        assert_eq!("93".parse(), Ok(VHDLStandard::VHDL1993));
+        assert_eq!("08".parse(), Ok(VHDLStandard::VHDL2008));
+        assert!("2000".parse::<VHDLStandard>().is_err());
+    }
+
+    #[test]
+    fn standards_are_ordered() {
+        assert!(VHDLStandard::VHDL1993 < VHDLStandard::VHDL2002);
+        assert!(VHDLStandard::VHDL2002 < VHDLStandard::VHDL2008);
+        assert!(VHDLStandard::VHDL2008 < VHDLStandard::VHDL2019);
+        assert_eq!(VHDLStandard::default(), VHDLStandard::VHDL2008);
+        assert_eq!(VHDLStandard::VHDL1993.to_string(), "VHDL-1993");
+    }
+}
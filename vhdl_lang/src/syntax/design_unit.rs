@@ -276,12 +276,16 @@ pub fn parse_design_file(
         ));
     }
 
-    Ok(DesignFile { design_units })
+    Ok(DesignFile {
+        design_units,
+        standard: stream.standard(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::standard::VHDLStandard;
     use itertools::Itertools;
 
     use crate::data::Diagnostic;
@@ -791,7 +795,8 @@ end entity;
                         statements: vec![],
                         end_ident_pos: None,
                     }))
-                )]
+                )],
+                standard: VHDLStandard::default(),
             }
         );
     }
@@ -826,7 +831,8 @@ context lib.ctx;
         assert_eq!(
             design_file,
             DesignFile {
-                design_units: vec![]
+                design_units: vec![],
+                standard: VHDLStandard::default(),
             }
         );
     }
@@ -8,10 +8,11 @@ use super::design_unit::parse_design_file;
 use super::tokens::{Symbols, TokenStream, Tokenizer};
 use crate::ast::DesignFile;
 use crate::data::*;
+use crate::standard::VHDLStandard;
 use std::io;
 use std::sync::Arc;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct VHDLParser {
     pub symbols: Arc<Symbols>,
 }
@@ -27,16 +28,33 @@ impl VHDLParser {
         &self,
         source: &Source,
         diagnostics: &mut dyn DiagnosticHandler,
+    ) -> DesignFile {
+        self.parse_design_source_with_standard(source, VHDLStandard::default(), diagnostics)
+    }
+
+    pub fn parse_design_source_with_standard(
+        &self,
+        source: &Source,
+        standard: VHDLStandard,
+        diagnostics: &mut dyn DiagnosticHandler,
     ) -> DesignFile {
         let contents = source.contents();
-        let tokenizer = Tokenizer::new(&self.symbols, source, ContentReader::new(&contents));
+        let tokenizer = Tokenizer::new(
+            &self.symbols,
+            standard,
+            source,
+            ContentReader::new(&contents),
+        );
         let stream = TokenStream::new(tokenizer, diagnostics);
 
         match parse_design_file(&stream, diagnostics) {
             Ok(design_file) => design_file,
             Err(diagnostic) => {
                 diagnostics.push(diagnostic);
-                DesignFile::default()
+                DesignFile {
+                    design_units: Vec::new(),
+                    standard,
+                }
             }
         }
     }
@@ -45,9 +63,18 @@ impl VHDLParser {
         &self,
         file_name: &Path,
         diagnostics: &mut dyn DiagnosticHandler,
+    ) -> ParserResult {
+        self.parse_design_file_with_standard(file_name, VHDLStandard::default(), diagnostics)
+    }
+
+    pub fn parse_design_file_with_standard(
+        &self,
+        file_name: &Path,
+        standard: VHDLStandard,
+        diagnostics: &mut dyn DiagnosticHandler,
     ) -> ParserResult {
         let source = Source::from_latin1_file(file_name)?;
-        let design_file = self.parse_design_source(&source, diagnostics);
+        let design_file = self.parse_design_source_with_standard(&source, standard, diagnostics);
         Ok((source, design_file))
     }
 }
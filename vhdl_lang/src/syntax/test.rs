@@ -35,6 +35,7 @@ use crate::ast;
 use crate::ast::*;
 use crate::data::Range;
 use crate::data::*;
+use crate::standard::VHDLStandard;
 use crate::syntax::concurrent_statement::parse_map_aspect;
 use crate::syntax::context::{parse_context, DeclarationOrReference};
 use crate::syntax::names::parse_association_element;
@@ -49,6 +50,7 @@ use std::sync::Arc;
 
 pub struct CodeBuilder {
     pub symbols: Arc<Symbols>,
+    pub standard: VHDLStandard,
 }
 
 impl AnyDesignUnit {
@@ -80,6 +82,14 @@ impl CodeBuilder {
     pub fn new() -> CodeBuilder {
         CodeBuilder {
             symbols: Arc::new(Symbols::default()),
+            standard: VHDLStandard::default(),
+        }
+    }
+
+    pub fn with_standard(standard: VHDLStandard) -> CodeBuilder {
+        CodeBuilder {
+            standard,
+            ..CodeBuilder::new()
         }
     }
 
@@ -90,6 +100,7 @@ impl CodeBuilder {
 
         let code = Code {
             symbols: self.symbols.clone(),
+            standard: self.standard,
             pos,
         };
 
@@ -126,6 +137,7 @@ impl<T> SeparatedList<T> {
 #[derive(Clone)]
 pub struct Code {
     pub symbols: Arc<Symbols>,
+    standard: VHDLStandard,
     pos: SrcPos,
 }
 
@@ -141,6 +153,7 @@ impl Code {
     fn in_range(&self, range: Range) -> Code {
         Code {
             symbols: self.symbols.clone(),
+            standard: self.standard,
             pos: SrcPos::new(self.pos.source.clone(), range),
         }
     }
@@ -148,6 +161,7 @@ impl Code {
     fn pos_to_end(&self, start: Position) -> Code {
         Code {
             symbols: self.symbols.clone(),
+            standard: self.standard,
             pos: SrcPos::new(
                 self.pos.source.clone(),
                 Range {
@@ -161,6 +175,7 @@ impl Code {
     fn start_to_pos(&self, end: Position) -> Code {
         Code {
             symbols: self.symbols.clone(),
+            standard: self.standard,
             pos: SrcPos::new(
                 self.pos.source.clone(),
                 Range {
@@ -267,7 +282,7 @@ impl Code {
             );
             let contents = source.contents();
             let reader = ContentReader::new(&contents);
-            let mut tokenizer = Tokenizer::new(&self.symbols, &source, reader);
+            let mut tokenizer = Tokenizer::new(&self.symbols, self.standard, &source, reader);
             loop {
                 let token = tokenizer.pop();
 
@@ -318,7 +333,7 @@ impl Code {
         );
         let contents = source.contents();
         let reader = ContentReader::new(&contents);
-        let tokenizer = Tokenizer::new(&self.symbols, &source, reader);
+        let tokenizer = Tokenizer::new(&self.symbols, self.standard, &source, reader);
         let stream = TokenStream::new(tokenizer, &mut NoDiagnostics);
         forward(&stream, self.pos.start());
         stream.peek().expect("No token found");
@@ -337,7 +352,7 @@ impl Code {
         );
         let contents = source.contents();
         let reader = ContentReader::new(&contents);
-        let tokenizer = Tokenizer::new(&self.symbols, &source, reader);
+        let tokenizer = Tokenizer::new(&self.symbols, self.standard, &source, reader);
         let mut stream = TokenStream::new(tokenizer, &mut NoDiagnostics);
         forward(&stream, self.pos.start());
         parse_fun(&mut stream)
@@ -362,7 +377,7 @@ impl Code {
     {
         let contents = self.pos.source.contents();
         let reader = ContentReader::new(&contents);
-        let tokenizer = Tokenizer::new(&self.symbols, &self.pos.source, reader);
+        let tokenizer = Tokenizer::new(&self.symbols, self.standard, &self.pos.source, reader);
         let mut stream = TokenStream::new(tokenizer, &mut NoDiagnostics);
         parse_fun(&mut stream)
     }
@@ -9,6 +9,7 @@ use fnv::FnvHashMap;
 use crate::ast::{self, AttributeDesignator, Operator, WithRef};
 use crate::ast::{BaseSpecifier, Ident};
 use crate::data::*;
+use crate::standard::VHDLStandard;
 
 /// The kind of a Token
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -97,6 +98,8 @@ pub enum Kind {
     Vunit,
     Parameter,
     Literal,
+    Private,
+    View,
 
     // Unary operators
     Abs,
@@ -350,6 +353,8 @@ pub fn kind_str(kind: Kind) -> &'static str {
         Vunit => "vunit",
         Parameter => "parameter",
         Literal => "literal",
+        Private => "private",
+        View => "view",
 
         // Unary operators
         Abs => "abs",
@@ -1272,6 +1277,7 @@ fn parse_basic_identifier_or_keyword(
     buffer: &mut Latin1String,
     reader: &mut ContentReader,
     symbols: &Symbols,
+    standard: VHDLStandard,
 ) -> Result<(Kind, Value), TokenError> {
     buffer.bytes.clear();
     while let Some(b) = reader.peek()? {
@@ -1286,7 +1292,7 @@ fn parse_basic_identifier_or_keyword(
         }
     }
 
-    Ok(symbols.insert_or_keyword(buffer))
+    Ok(symbols.insert_or_keyword(buffer, standard))
 }
 
 /// Assumes leading ' has already been consumed
@@ -1427,16 +1433,27 @@ impl Symbols {
         &self.symtab
     }
 
-    fn insert_or_keyword(&self, name: &Latin1String) -> (Kind, Value) {
+    /// Keywords that are reserved in a later revision of the standard
+    /// are identifiers in earlier revisions
+    fn insert_or_keyword(&self, name: &Latin1String, standard: VHDLStandard) -> (Kind, Value) {
         let symbol = self.symtab.insert(name);
-        if let Some(kind) = self.keywords.get(symbol.id) {
-            (*kind, Value::NoValue)
-        } else {
-            (Identifier, Value::Identifier(symbol))
+        match self.keywords.get(symbol.id) {
+            Some(kind) if keyword_standard(*kind) <= standard => (*kind, Value::NoValue),
+            _ => (Identifier, Value::Identifier(symbol)),
         }
     }
 }
 
+/// The first revision of the standard where the keyword is reserved
+fn keyword_standard(kind: Kind) -> VHDLStandard {
+    match kind {
+        Protected => VHDLStandard::VHDL2002,
+        Context | Force | Release | Parameter | Default | Vunit => VHDLStandard::VHDL2008,
+        Private | View => VHDLStandard::VHDL2019,
+        _ => VHDLStandard::VHDL1993,
+    }
+}
+
 impl std::default::Default for Symbols {
     fn default() -> Symbols {
         let keywords_init = [
@@ -1538,6 +1555,8 @@ impl std::default::Default for Symbols {
             ("rem", Rem),
             ("vunit", Vunit),
             ("parameter", Parameter),
+            ("private", Private),
+            ("view", View),
         ];
 
         let attributes = [
@@ -1640,6 +1659,7 @@ impl std::default::Default for Symbols {
 
 pub struct Tokenizer<'a> {
     symbols: &'a Symbols,
+    standard: VHDLStandard,
     buffer: Latin1String,
     state: TokenState,
     pub source: &'a Source,
@@ -1650,11 +1670,13 @@ pub struct Tokenizer<'a> {
 impl<'a> Tokenizer<'a> {
     pub fn new(
         symbols: &'a Symbols,
+        standard: VHDLStandard,
         source: &'a Source,
         reader: ContentReader<'a>,
     ) -> Tokenizer<'a> {
         Tokenizer {
             symbols,
+            standard,
             state: TokenState::new(reader.state()),
             buffer: Latin1String::empty(),
             source,
@@ -1663,6 +1685,10 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    pub fn standard(&self) -> VHDLStandard {
+        self.standard
+    }
+
     pub fn attribute(&self, sym: Symbol) -> AttributeDesignator {
         self.symbols
             .attributes
@@ -1698,6 +1724,7 @@ impl<'a> Tokenizer<'a> {
                         &mut self.buffer,
                         &mut self.reader,
                         self.symbols,
+                        self.standard,
                     )?
                 }
             }
@@ -1971,7 +1998,7 @@ impl<'a> Tokenizer<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::syntax::test::Code;
+    use crate::syntax::test::{Code, CodeBuilder};
     use pretty_assertions::assert_eq;
 
     fn kinds(tokens: &[Token]) -> Vec<Kind> {
@@ -2044,6 +2071,34 @@ end entity"
         );
     }
 
+    #[test]
+    fn tokenize_keywords_of_later_standard_as_identifiers() {
+        let kinds_tokenize_with_standard = |standard: VHDLStandard, code: &str| {
+            kinds(&CodeBuilder::with_standard(standard).code(code).tokenize())
+        };
+
+        assert_eq!(
+            kinds_tokenize_with_standard(VHDLStandard::VHDL1993, "context protected force"),
+            vec![Identifier, Identifier, Identifier]
+        );
+        assert_eq!(
+            kinds_tokenize_with_standard(VHDLStandard::VHDL2002, "context protected force"),
+            vec![Identifier, Protected, Identifier]
+        );
+        assert_eq!(
+            kinds_tokenize_with_standard(VHDLStandard::VHDL2008, "context protected force"),
+            vec![Context, Protected, Force]
+        );
+        assert_eq!(
+            kinds_tokenize_with_standard(VHDLStandard::VHDL2008, "private view"),
+            vec![Identifier, Identifier]
+        );
+        assert_eq!(
+            kinds_tokenize_with_standard(VHDLStandard::VHDL2019, "private view"),
+            vec![Private, View]
+        );
+    }
+
     #[test]
     fn tokenize_keywords_case_insensitive() {
         assert_eq!(kinds_tokenize("entity"), vec![Entity]);
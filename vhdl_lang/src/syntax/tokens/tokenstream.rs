@@ -10,6 +10,7 @@ use super::tokenizer::Kind::*;
 use super::tokenizer::*;
 use crate::ast::{AttributeDesignator, Ident, RangeAttribute, TypeAttribute};
 use crate::data::{DiagnosticHandler, DiagnosticResult, WithPos};
+use crate::standard::VHDLStandard;
 use crate::{Diagnostic, SrcPos};
 
 pub struct TokenStream<'a> {
@@ -77,6 +78,11 @@ impl<'a> TokenStream<'a> {
         }
     }
 
+    /// The revision of the standard the tokens were produced for
+    pub fn standard(&self) -> VHDLStandard {
+        self.tokenizer.standard()
+    }
+
     pub fn state(&self) -> usize {
         self.get_idx()
     }
@@ -357,13 +363,23 @@ mod tests {
         ($code:ident, $stream:ident) => {
             let source = $code.source();
             let contents = source.contents();
-            let tokenizer = Tokenizer::new(&$code.symbols, source, ContentReader::new(&contents));
+            let tokenizer = Tokenizer::new(
+                &$code.symbols,
+                VHDLStandard::default(),
+                source,
+                ContentReader::new(&contents),
+            );
             let $stream = TokenStream::new(tokenizer, &mut NoDiagnostics);
         };
         ($code:ident, $stream:ident, $diagnostics:ident) => {
             let source = $code.source();
             let contents = source.contents();
-            let tokenizer = Tokenizer::new(&$code.symbols, source, ContentReader::new(&contents));
+            let tokenizer = Tokenizer::new(
+                &$code.symbols,
+                VHDLStandard::default(),
+                source,
+                ContentReader::new(&contents),
+            );
             let $stream = TokenStream::new(tokenizer, &mut $diagnostics);
         };
     }
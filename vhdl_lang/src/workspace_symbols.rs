@@ -0,0 +1,460 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Fuzzy search of the public named entities of all design units
+
+use crate::analysis::DesignRoot;
+use crate::ast::{Designator, HasUnitId, UnitId};
+use crate::{AnyEntKind, Design, EntRef, EntityId, Overloaded};
+use fnv::FnvHashMap;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::sync::Arc;
+
+const MATCH_SCORE: i32 = 1;
+const WORD_START_SCORE: i32 = 8;
+const CONSECUTIVE_SCORE: i32 = 4;
+const PREFIX_SCORE: i32 = 16;
+const EXACT_SCORE: i32 = 64;
+
+/// A public named entity together with the names used to search for it
+#[derive(Clone)]
+struct IndexedSymbol {
+    id: EntityId,
+    name: String,
+    /// The lower case designators of the enclosing library and design units
+    qualifiers: Vec<String>,
+}
+
+/// The public named entities of all design units.
+/// Only the units of each analysis are indexed again.
+#[derive(Clone, Default)]
+pub(crate) struct SymbolIndex {
+    /// `None` before the first analysis and after a cancelled analysis.
+    /// The symbols of a unit are shared with copies of the index.
+    units: Option<FnvHashMap<UnitId, Arc<[IndexedSymbol]>>>,
+}
+
+impl SymbolIndex {
+    /// Index the units of the latest analysis and prune units that no longer exist
+    pub fn update(&mut self, root: &DesignRoot, analyzed_units: &[UnitId]) {
+        match self.units {
+            Some(ref mut units) => {
+                units.retain(|unit_id, _| root.get_unit(unit_id).is_some());
+                for unit_id in analyzed_units {
+                    units.insert(unit_id.clone(), index_unit(root, unit_id).into());
+                }
+            }
+            None => {
+                self.units = Some(
+                    root.units()
+                        .map(|unit| {
+                            let symbols = index_unit(root, unit.unit_id());
+                            (unit.unit_id().clone(), symbols.into())
+                        })
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    /// The index is rebuilt from all units by the next update
+    pub fn invalidate(&mut self) {
+        self.units = None;
+    }
+
+    /// The named entities matching the query with the best match first.
+    ///
+    /// The query is a fuzzy pattern optionally preceded by the names of enclosing libraries
+    /// or design units, such as `ieee.to_uns`, and by a kind filter such as `e:` for entities.
+    pub fn search<'a>(
+        &self,
+        root: &'a DesignRoot,
+        query: &str,
+        limit: usize,
+    ) -> Vec<SymbolMatch<'a>> {
+        let Some(ref units) = self.units else {
+            return Vec::new();
+        };
+        let query = SymbolQuery::parse(query);
+
+        let libraries = root.libraries().map(|library| {
+            let ent = root.get_ent(library.id());
+            (ent, Cow::Owned(ent.designator().to_string()), &[][..])
+        });
+        let symbols = units
+            .values()
+            .flat_map(|symbols| symbols.iter())
+            .map(|symbol| {
+                (
+                    root.get_ent(symbol.id),
+                    Cow::Borrowed(symbol.name.as_str()),
+                    symbol.qualifiers.as_slice(),
+                )
+            });
+
+        let mut matches: Vec<_> = libraries
+            .chain(symbols)
+            .filter(|(ent, _, qualifiers)| {
+                query.matches_qualifiers(qualifiers)
+                    && query.filter.is_none_or(|filter| filter.matches(ent.kind()))
+            })
+            .filter_map(|(ent, name, _)| {
+                let score = fuzzy_score(&query.pattern, &name)?;
+                Some(SymbolMatch {
+                    ent,
+                    rank: (
+                        Reverse(score),
+                        kind_rank(ent.kind()),
+                        name.len(),
+                        name.to_lowercase(),
+                    ),
+                })
+            })
+            .collect();
+        matches.sort();
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// A named entity matching a workspace symbol query.
+/// Matches are ordered with the best match first, also across projects.
+pub struct SymbolMatch<'a> {
+    pub ent: EntRef<'a>,
+    /// The score of the name, the kind of entity, and the length and lower case name
+    rank: (Reverse<i32>, u8, usize, String),
+}
+
+impl PartialEq for SymbolMatch<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl Eq for SymbolMatch<'_> {}
+
+impl PartialOrd for SymbolMatch<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SymbolMatch<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+fn index_unit(root: &DesignRoot, unit_id: &UnitId) -> Vec<IndexedSymbol> {
+    let Some(unit) = root.get_unit(unit_id) else {
+        return Vec::new();
+    };
+    root.public_symbols_of_unit(unit)
+        .filter_map(|ent| {
+            let name = match ent.designator() {
+                Designator::Identifier(_) | Designator::Character(_) => {
+                    ent.designator().to_string()
+                }
+                Designator::OperatorSymbol(op) => op.to_string(),
+                Designator::Anonymous(_) => return None,
+            };
+            let mut qualifiers = Vec::new();
+            let mut parent = ent.parent;
+            while let Some(ent) = parent {
+                qualifiers.push(ent.designator().to_string().to_lowercase());
+                parent = ent.parent;
+            }
+            qualifiers.reverse();
+            Some(IndexedSymbol {
+                id: ent.id(),
+                name,
+                qualifiers,
+            })
+        })
+        .collect()
+}
+
+/// Design units are ranked before the declarations of packages
+fn kind_rank(kind: &AnyEntKind) -> u8 {
+    match kind {
+        AnyEntKind::Design(
+            Design::Entity(..)
+            | Design::Package(..)
+            | Design::UninstPackage(..)
+            | Design::PackageInstance(..)
+            | Design::Configuration
+            | Design::Context(..),
+        ) => 0,
+        AnyEntKind::Design(Design::Architecture(..) | Design::PackageBody)
+        | AnyEntKind::Library => 1,
+        AnyEntKind::Component(..) | AnyEntKind::Type(..) | AnyEntKind::Overloaded(..) => 2,
+        _ => 3,
+    }
+}
+
+/// The kinds of named entities selected by a query prefix such as `e:`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum KindFilter {
+    Entity,
+    Architecture,
+    Package,
+    Component,
+    Type,
+    Subprogram,
+    Object,
+}
+
+impl KindFilter {
+    fn from_prefix(prefix: &str) -> Option<KindFilter> {
+        match prefix {
+            "e" => Some(KindFilter::Entity),
+            "a" => Some(KindFilter::Architecture),
+            "p" => Some(KindFilter::Package),
+            "c" => Some(KindFilter::Component),
+            "t" => Some(KindFilter::Type),
+            "f" => Some(KindFilter::Subprogram),
+            "o" => Some(KindFilter::Object),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, kind: &AnyEntKind) -> bool {
+        match self {
+            KindFilter::Entity => matches!(kind, AnyEntKind::Design(Design::Entity(..))),
+            KindFilter::Architecture => {
+                matches!(kind, AnyEntKind::Design(Design::Architecture(..)))
+            }
+            KindFilter::Package => matches!(
+                kind,
+                AnyEntKind::Design(
+                    Design::Package(..) | Design::UninstPackage(..) | Design::PackageInstance(..)
+                )
+            ),
+            KindFilter::Component => matches!(kind, AnyEntKind::Component(..)),
+            KindFilter::Type => matches!(kind, AnyEntKind::Type(..)),
+            KindFilter::Subprogram => matches!(
+                kind,
+                AnyEntKind::Overloaded(
+                    Overloaded::SubprogramDecl(..)
+                        | Overloaded::Subprogram(..)
+                        | Overloaded::UninstSubprogramDecl(..)
+                        | Overloaded::UninstSubprogram(..)
+                )
+            ),
+            KindFilter::Object => matches!(
+                kind,
+                AnyEntKind::Object(..)
+                    | AnyEntKind::DeferredConstant(..)
+                    | AnyEntKind::ObjectAlias { .. }
+                    | AnyEntKind::ExternalAlias { .. }
+                    | AnyEntKind::File(..)
+            ),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+struct SymbolQuery {
+    filter: Option<KindFilter>,
+    /// Prefixes of the names of enclosing libraries and design units, in order
+    qualifiers: Vec<String>,
+    /// The lower case fuzzy pattern of the name
+    pattern: String,
+}
+
+impl SymbolQuery {
+    fn parse(query: &str) -> SymbolQuery {
+        let query = query.trim();
+        let (filter, query) = match query.split_once(':') {
+            Some((prefix, rest)) => match KindFilter::from_prefix(prefix.trim()) {
+                Some(filter) => (Some(filter), rest.trim()),
+                None => (None, query),
+            },
+            None => (None, query),
+        };
+        let mut qualifiers: Vec<String> = query
+            .split('.')
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        let pattern = qualifiers.pop().unwrap_or_default();
+        qualifiers.retain(|qualifier| !qualifier.is_empty());
+        SymbolQuery {
+            filter,
+            qualifiers,
+            pattern,
+        }
+    }
+
+    /// Every qualifier is the prefix of an enclosing name, in the same order
+    fn matches_qualifiers(&self, names: &[String]) -> bool {
+        let mut names = names.iter();
+        self.qualifiers
+            .iter()
+            .all(|qualifier| names.any(|name| name.starts_with(qualifier.as_str())))
+    }
+}
+
+/// A name starts a word at its start, after an underscore, at an upper case letter following
+/// a lower case letter and at a digit following a letter
+fn is_word_start(name: &[char], idx: usize) -> bool {
+    let Some(prev) = idx.checked_sub(1).map(|prev| name[prev]) else {
+        return true;
+    };
+    let current = name[idx];
+    (prev == '_' && current != '_')
+        || (prev.is_lowercase() && current.is_uppercase())
+        || (prev.is_alphabetic() && current.is_ascii_digit())
+}
+
+/// The score of the best match of the lower case pattern as a subsequence of the name.
+/// Characters starting words and consecutive characters score higher,
+/// as do names that start with or equal the pattern.
+/// Returns `None` when the pattern does not match the name.
+fn fuzzy_score(pattern: &str, name: &str) -> Option<i32> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = name.chars().collect();
+
+    // The best score of the pattern matched so far with its last character at each index
+    let mut scores: Vec<Option<i32>> = Vec::new();
+    for (pattern_idx, pattern_char) in pattern.chars().enumerate() {
+        let mut next = vec![None; chars.len()];
+        // The best score ending at least two characters before the current index
+        let mut best_before: Option<i32> = None;
+        for (idx, chr) in chars.iter().enumerate() {
+            if idx >= 2 && pattern_idx > 0 {
+                best_before = best_before.max(scores[idx - 2]);
+            }
+            if !chr.to_lowercase().eq(pattern_char.to_lowercase()) {
+                continue;
+            }
+            let score = MATCH_SCORE
+                + if is_word_start(&chars, idx) {
+                    WORD_START_SCORE
+                } else {
+                    0
+                };
+            next[idx] = if pattern_idx == 0 {
+                Some(score)
+            } else {
+                let consecutive = idx
+                    .checked_sub(1)
+                    .and_then(|prev| scores[prev])
+                    .map(|prev| prev + CONSECUTIVE_SCORE);
+                best_before.max(consecutive).map(|prev| prev + score)
+            };
+        }
+        scores = next;
+    }
+
+    let score = scores.into_iter().max().flatten()?;
+    let lower_name = name.to_lowercase();
+    Some(if lower_name == pattern {
+        score + EXACT_SCORE
+    } else if lower_name.starts_with(pattern) {
+        score + PREFIX_SCORE
+    } else {
+        score
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    fn search(root: &DesignRoot, query: &str) -> Vec<String> {
+        let mut index = SymbolIndex::default();
+        index.update(root, &[]);
+        index
+            .search(root, query, 10)
+            .into_iter()
+            .map(|symbol| symbol.ent.path_name())
+            .collect()
+    }
+
+    #[test]
+    fn fuzzy_matches_are_subsequences() {
+        assert!(fuzzy_score("tuns", "to_unsigned").is_some());
+        assert!(fuzzy_score("nus", "to_unsigned").is_none());
+        assert_eq!(fuzzy_score("", "to_unsigned"), Some(0));
+    }
+
+    #[test]
+    fn word_starts_and_prefixes_score_higher() {
+        let score = |name| fuzzy_score("tu", name).unwrap();
+        assert!(score("to_unsigned") > score("status"));
+        assert!(score("toUnsigned") > score("status"));
+        assert!(score("tu") > score("tu_tu"));
+        assert!(score("tu_x") > score("x_tu"));
+    }
+
+    #[test]
+    fn parses_kind_filters_and_qualifiers() {
+        assert_eq!(
+            SymbolQuery::parse("e: lib.Ent"),
+            SymbolQuery {
+                filter: Some(KindFilter::Entity),
+                qualifiers: vec!["lib".to_owned()],
+                pattern: "ent".to_owned(),
+            }
+        );
+        assert_eq!(
+            SymbolQuery::parse("x:name"),
+            SymbolQuery {
+                filter: None,
+                qualifiers: vec![],
+                pattern: "x:name".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn searches_by_kind_qualifier_and_match_quality() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  constant to_upper : natural := 0;
+  function to_unsigned(value : natural) return natural;
+  type tuple_t is record
+    first : natural;
+  end record;
+end package;
+
+entity top_unit is
+end entity;
+        ",
+        );
+        builder.code(
+            "lib2",
+            "
+package other is
+  constant to_unsigned : natural := 0;
+end package;
+        ",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        assert_eq!(
+            search(&root, "tu")[..5],
+            [
+                "libname.pkg.tuple_t",
+                "libname.top_unit",
+                "libname.pkg.to_unsigned",
+                "libname.pkg.to_upper",
+                "lib2.other.to_unsigned",
+            ]
+        );
+        assert_eq!(search(&root, "e:tu"), vec!["libname.top_unit"]);
+        assert_eq!(search(&root, "f:tu"), vec!["libname.pkg.to_unsigned"]);
+        assert_eq!(search(&root, "lib2.to_uns"), vec!["lib2.other.to_unsigned"]);
+        assert_eq!(search(&root, "p:libname."), vec!["libname.pkg"]);
+    }
+}
@@ -0,0 +1,412 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2023, Olof Kraigher olof.kraigher@gmail.com
+
+//! Analysis of the project in a background thread.
+//!
+//! Changes to the project are collected and analysed together once no further changes
+//! arrived for a short while. A running analysis is cancelled when new changes arrive.
+//! The worker thread owns the project that the changes are applied to, and hands out a copy
+//! of it after each completed analysis. The copy shares the design units that are unchanged.
+//! Requests are meanwhile served from that copy.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use vhdl_lang::{Config, Diagnostic, Message, MessageHandler, Project, Source};
+
+/// A change to the project that requires a new analysis
+pub enum ProjectChange {
+    /// The contents of a source file have changed or a new source file was opened
+    Source(Source),
+    /// The configuration was reloaded
+    Config(Box<Config>),
+    /// A file was created or changed on disk, or closed without saving
+    Load(PathBuf),
+    /// A file or directory was deleted from disk
+    Remove(PathBuf),
+    /// A file or directory was renamed on disk
+    Rename(PathBuf, PathBuf),
+}
+
+impl ProjectChange {
+    pub fn apply(self, project: &mut Project, messages: &mut dyn MessageHandler) {
+        match self {
+            ProjectChange::Source(source) => project.update_source(&source),
+            ProjectChange::Config(config) => project.update_config(*config, messages),
+            ProjectChange::Load(file_name) => project.load_file(&file_name, messages),
+            ProjectChange::Remove(path) => project.remove_files(&path),
+            ProjectChange::Rename(from, to) => project.rename_files(&from, &to, messages),
+        }
+    }
+}
+
+/// Events sent from the worker thread
+pub enum AnalysisEvent {
+    /// Describes the current step of the analysis
+    Progress(String),
+    /// The analysis has completed or was cancelled
+    Done(Box<AnalysisOutcome>),
+}
+
+/// An event together with the id of the worker that sent it
+pub type WorkerEvent = (usize, AnalysisEvent);
+
+pub struct AnalysisOutcome {
+    /// Messages produced while applying the changes
+    pub messages: Vec<Message>,
+    /// A copy of the analysed project and its diagnostics, `None` when the analysis was cancelled
+    analysed: Option<(Project, Vec<Diagnostic>)>,
+}
+
+pub struct AnalysisWorker {
+    jobs: Sender<Vec<ProjectChange>>,
+    cancelled: Arc<AtomicBool>,
+    debounce: Duration,
+    /// Changes that have not yet been sent to the worker thread
+    pending: Vec<ProjectChange>,
+    /// Sources sent to the worker thread that are not part of a completed analysis yet
+    sent: Vec<Source>,
+    /// A configuration was sent to the worker thread that is not part of a completed analysis yet
+    sent_config: bool,
+    running: bool,
+    /// Time when the next analysis shall start
+    deadline: Option<Instant>,
+}
+
+impl AnalysisWorker {
+    /// Spawn the worker thread that applies changes to `project`,
+    /// analysis starts when no changes were made during `debounce`.
+    ///
+    /// Events are sent to `events` together with `id` and should be passed to `finish`.
+    /// The thread stops when the worker is dropped.
+    pub fn spawn(
+        debounce: Duration,
+        id: usize,
+        events: Sender<WorkerEvent>,
+        project: Project,
+    ) -> AnalysisWorker {
+        let (jobs, job_receiver) = unbounded();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let worker_cancelled = cancelled.clone();
+        thread::Builder::new()
+            .name("analysis".to_owned())
+            .spawn(move || run_jobs(job_receiver, id, events, worker_cancelled, project))
+            .expect("Failed to spawn analysis thread");
+
+        AnalysisWorker {
+            jobs,
+            cancelled,
+            debounce,
+            pending: Vec::new(),
+            sent: Vec::new(),
+            sent_config: false,
+            running: false,
+            deadline: None,
+        }
+    }
+
+    /// Time when `start` should be called, `None` if there is nothing to start
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.running {
+            None
+        } else {
+            self.deadline
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Add a change to the next analysis
+    pub fn push(&mut self, change: ProjectChange) {
+        self.pending.push(change);
+    }
+
+    /// Analyse the project after the debounce time,
+    /// a running analysis is cancelled to restart with the new changes
+    pub fn schedule(&mut self) {
+        self.deadline = Some(Instant::now() + self.debounce);
+        if self.running {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// The most recent version of a source that has changed since the last completed analysis
+    pub fn get_source(&self, file_name: &Path) -> Option<Source> {
+        let changed_sources = self
+            .pending
+            .iter()
+            .rev()
+            .filter_map(|change| match change {
+                ProjectChange::Source(source) => Some(source),
+                _ => None,
+            })
+            .chain(self.sent.iter().rev());
+
+        changed_sources
+            .into_iter()
+            .find(|source| source.file_name() == file_name)
+            .cloned()
+    }
+
+    /// True while a changed configuration has not been analysed,
+    /// until then files of the configuration may be missing from the analysed project
+    pub fn is_loading(&self) -> bool {
+        self.sent_config
+            || self
+                .pending
+                .iter()
+                .any(|change| matches!(change, ProjectChange::Config(_)))
+    }
+
+    /// Start the analysis if the deadline has passed and no analysis is running.
+    /// The pending changes are sent to the worker thread and applied to its project.
+    ///
+    /// Returns true if an analysis was started.
+    pub fn start(&mut self) -> bool {
+        match self.deadline() {
+            Some(deadline) if deadline <= Instant::now() => {}
+            _ => return false,
+        }
+
+        let changes = std::mem::take(&mut self.pending);
+        self.sent
+            .extend(changes.iter().filter_map(|change| match change {
+                ProjectChange::Source(source) => Some(source.clone()),
+                _ => None,
+            }));
+        self.sent_config |= changes
+            .iter()
+            .any(|change| matches!(change, ProjectChange::Config(_)));
+        self.running = true;
+        self.deadline = None;
+        self.cancelled.store(false, Ordering::Relaxed);
+        self.jobs
+            .send(changes)
+            .expect("Analysis thread has stopped");
+        true
+    }
+
+    /// Handle the end of an analysis.
+    ///
+    /// Returns a copy of the analysed project and its diagnostics unless the analysis was
+    /// cancelled. A cancelled analysis is continued by the next call to `start`.
+    pub fn finish(&mut self, outcome: Box<AnalysisOutcome>) -> Option<(Project, Vec<Diagnostic>)> {
+        self.running = false;
+        match outcome.analysed {
+            Some(analysed) => {
+                self.sent.clear();
+                self.sent_config = false;
+                Some(analysed)
+            }
+            None => {
+                self.deadline.get_or_insert_with(Instant::now);
+                None
+            }
+        }
+    }
+}
+
+fn run_jobs(
+    jobs: Receiver<Vec<ProjectChange>>,
+    id: usize,
+    events: Sender<WorkerEvent>,
+    cancelled: Arc<AtomicBool>,
+    mut project: Project,
+) {
+    for changes in jobs.iter() {
+        let mut messages = Vec::new();
+        for change in changes {
+            if let ProjectChange::Config(_) = change {
+                let _ = events.send((id, AnalysisEvent::Progress("Loading files".to_owned())));
+            }
+            change.apply(&mut project, &mut messages);
+        }
+
+        let _ = events.send((
+            id,
+            AnalysisEvent::Progress("Analysing design units".to_owned()),
+        ));
+        let analysed = project
+            .analyse_cancellable(&cancelled)
+            .map(|diagnostics| (project.clone(), diagnostics));
+
+        let outcome = AnalysisOutcome { messages, analysed };
+        if events
+            .send((id, AnalysisEvent::Done(Box::new(outcome))))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(debounce: Duration) -> (AnalysisWorker, Receiver<WorkerEvent>) {
+        let (events, receiver) = unbounded();
+        let worker = AnalysisWorker::spawn(debounce, 0, events, Project::new());
+        (worker, receiver)
+    }
+
+    fn source(contents: &str) -> ProjectChange {
+        ProjectChange::Source(Source::inline(Path::new("file.vhd"), contents))
+    }
+
+    fn contents_of(worker: &AnalysisWorker) -> Option<String> {
+        let source = worker.get_source(Path::new("file.vhd"))?;
+        let line = source.contents().get_line(0)?.to_owned();
+        Some(line)
+    }
+
+    fn completed() -> Box<AnalysisOutcome> {
+        Box::new(AnalysisOutcome {
+            messages: Vec::new(),
+            analysed: Some((Project::new(), Vec::new())),
+        })
+    }
+
+    fn cancelled() -> Box<AnalysisOutcome> {
+        Box::new(AnalysisOutcome {
+            messages: Vec::new(),
+            analysed: None,
+        })
+    }
+
+    #[test]
+    fn analysis_starts_after_the_debounce_time() {
+        let (mut worker, _events) = spawn(Duration::from_secs(3600));
+        assert_eq!(worker.deadline(), None);
+        assert!(!worker.start());
+
+        let before = Instant::now();
+        worker.push(source("entity ent is end;"));
+        worker.schedule();
+        let deadline = worker.deadline().unwrap();
+        assert!(deadline >= before + Duration::from_secs(3600));
+        assert!(!worker.start());
+
+        // A later change postpones the analysis
+        worker.schedule();
+        assert!(worker.deadline().unwrap() >= deadline);
+    }
+
+    #[test]
+    fn no_deadline_while_running() {
+        let (mut worker, events) = spawn(Duration::ZERO);
+        worker.push(source("entity ent is end;"));
+        worker.schedule();
+        assert!(worker.start());
+        assert!(worker.is_running());
+        assert_eq!(worker.deadline(), None);
+
+        // A change while running is analysed once the running analysis has finished
+        worker.schedule();
+        assert_eq!(worker.deadline(), None);
+        assert!(!worker.start());
+
+        let (_, AnalysisEvent::Done(outcome)) = events
+            .iter()
+            .find(|(_, event)| matches!(event, AnalysisEvent::Done(_)))
+            .unwrap()
+        else {
+            unreachable!();
+        };
+        worker.finish(outcome);
+        assert!(!worker.is_running());
+        assert!(worker.deadline().is_some());
+        assert!(worker.start());
+    }
+
+    #[test]
+    fn cancelled_analysis_is_resumed() {
+        let (mut worker, _events) = spawn(Duration::ZERO);
+        worker.push(source("entity ent is end;"));
+        worker.schedule();
+        assert!(worker.start());
+
+        // The changes are analysed again right away
+        assert!(worker.finish(cancelled()).is_none());
+        assert!(worker.deadline().unwrap() <= Instant::now());
+        assert!(worker.start());
+        assert_eq!(contents_of(&worker).as_deref(), Some("entity ent is end;"));
+    }
+
+    #[test]
+    fn cancelled_analysis_keeps_the_deadline_of_later_changes() {
+        let (mut worker, _events) = spawn(Duration::from_secs(3600));
+        worker.push(source("entity ent is end;"));
+        worker.schedule();
+        worker.deadline = Some(Instant::now());
+        assert!(worker.start());
+
+        // The change cancelling the analysis is still debounced
+        worker.push(source("entity ent2 is end;"));
+        worker.schedule();
+        let deadline = worker.deadline;
+        assert!(worker.finish(cancelled()).is_none());
+        assert_eq!(worker.deadline(), deadline);
+        assert!(!worker.start());
+    }
+
+    #[test]
+    fn pending_source_takes_precedence_over_sent_source() {
+        let (mut worker, _events) = spawn(Duration::ZERO);
+        // The source of the project last analysed is used
+        assert_eq!(contents_of(&worker), None);
+
+        worker.push(source("sent"));
+        worker.schedule();
+        assert!(worker.start());
+        assert_eq!(contents_of(&worker).as_deref(), Some("sent"));
+
+        worker.push(source("pending"));
+        worker.push(source("latest"));
+        worker.schedule();
+        assert_eq!(contents_of(&worker).as_deref(), Some("latest"));
+
+        // The sent source is part of the analysed project once the analysis has completed
+        assert!(worker.finish(completed()).is_some());
+        assert_eq!(contents_of(&worker).as_deref(), Some("latest"));
+        assert!(worker.start());
+        assert!(worker.finish(completed()).is_some());
+        assert_eq!(contents_of(&worker), None);
+    }
+
+    #[test]
+    fn sent_source_is_kept_when_cancelled() {
+        let (mut worker, _events) = spawn(Duration::ZERO);
+        worker.push(source("sent"));
+        worker.schedule();
+        assert!(worker.start());
+        assert!(worker.finish(cancelled()).is_none());
+        assert_eq!(contents_of(&worker).as_deref(), Some("sent"));
+    }
+
+    #[test]
+    fn is_loading_until_the_configuration_is_analysed() {
+        let (mut worker, _events) = spawn(Duration::ZERO);
+        assert!(!worker.is_loading());
+        worker.push(ProjectChange::Config(Box::default()));
+        worker.schedule();
+        assert!(worker.is_loading());
+        assert!(worker.start());
+        assert!(worker.is_loading());
+        assert!(worker.finish(cancelled()).is_none());
+        assert!(worker.is_loading());
+        assert!(worker.start());
+        assert!(worker.finish(completed()).is_some());
+        assert!(!worker.is_loading());
+    }
+}
@@ -8,6 +8,7 @@
 #[macro_use]
 extern crate log;
 
+mod analysis_worker;
 mod rpc_channel;
 mod stdio_server;
 mod vhdl_server;
@@ -92,7 +92,7 @@ pub mod test_support {
                 });
         }
 
-        fn expect_notification_contains(
+        pub fn expect_notification_contains(
             &self,
             method: impl Into<String>,
             contains: impl Into<String>,
@@ -8,10 +8,14 @@
 //! It also contains the main event loop for handling incoming messages from the LSP client and
 //! dispatching them to the appropriate server methods.
 
+use crossbeam_channel::{after, never, select};
 use lsp_server::{Connection, ExtractError, Request, RequestId};
-use lsp_types::{notification, request, InitializeParams};
+use lsp_types::notification::Notification;
+use lsp_types::{notification, request, CancelParams, InitializeParams, NumberOrString};
 use serde_json::Value;
 
+use std::collections::VecDeque;
+use std::time::Instant;
 use std::{cell::RefCell, rc::Rc};
 
 use crate::rpc_channel::{RpcChannel, SharedRpcChannel};
@@ -80,34 +84,102 @@ impl ConnectionRpcChannel {
         server.initialized_notification();
     }
 
-    /// Main event loop handling incoming messages from the client.
+    /// Main event loop handling incoming messages from the client
+    /// as well as events of the background analysis.
     fn main_event_loop(&self, mut server: VHDLServer) {
         info!("Language server initialized, waiting for messages ...");
-        while let Ok(message) = self.connection.receiver.recv() {
-            trace!("Received message: {:?}", message);
-            match message {
-                lsp_server::Message::Request(request) => {
-                    match self.connection.handle_shutdown(&request) {
-                        Ok(shutdown) => {
-                            if shutdown {
-                                server.shutdown_server();
-                            } else {
-                                self.handle_request(&mut server, request)
-                            }
+        let analysis_events = server.analysis_events();
+        let mut queue = VecDeque::new();
+
+        loop {
+            if queue.is_empty() {
+                let deadline = match server.analysis_deadline() {
+                    Some(deadline) => after(deadline.saturating_duration_since(Instant::now())),
+                    None => never(),
+                };
+                select! {
+                    recv(self.connection.receiver) -> message => match message {
+                        Ok(message) => queue.push_back(message),
+                        Err(_) => break,
+                    },
+                    recv(analysis_events) -> event => {
+                        if let Ok(event) = event {
+                            server.analysis_event(event);
                         }
-                        Err(err) => panic!("{err:?}"),
-                    }
-                }
-                lsp_server::Message::Notification(notification) => {
-                    self.handle_notification(&mut server, notification);
+                    },
+                    recv(deadline) -> _ => server.start_analysis(),
                 }
-                lsp_server::Message::Response(response) => {
-                    self.handle_response(&mut server, response)
+            }
+
+            // Receive all messages that have arrived such that
+            // cancelled requests are not handled
+            queue.extend(self.connection.receiver.try_iter());
+            self.cancel_requests(&mut queue);
+
+            if let Some(message) = queue.pop_front() {
+                self.handle_message(&mut server, message);
+            }
+        }
+    }
+
+    /// Remove requests from the queue that have been cancelled by the client.
+    fn cancel_requests(&self, queue: &mut VecDeque<lsp_server::Message>) {
+        let mut cancelled = Vec::new();
+        queue.retain(|message| match message {
+            lsp_server::Message::Notification(notification)
+                if notification.method == notification::Cancel::METHOD =>
+            {
+                if let Ok(params) =
+                    serde_json::from_value::<CancelParams>(notification.params.clone())
+                {
+                    cancelled.push(match params.id {
+                        NumberOrString::Number(id) => RequestId::from(id),
+                        NumberOrString::String(id) => RequestId::from(id),
+                    });
                 }
-            };
+                false
+            }
+            _ => true,
+        });
+
+        for id in cancelled {
+            let position = queue.iter().position(|message| {
+                matches!(message, lsp_server::Message::Request(request) if request.id == id)
+            });
+            if let Some(position) = position {
+                trace!("Cancelled request: {:?}", id);
+                queue.remove(position);
+                self.send_response(lsp_server::Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::RequestCanceled as i32,
+                    "Request cancelled".to_string(),
+                ));
+            }
         }
     }
 
+    fn handle_message(&self, server: &mut VHDLServer, message: lsp_server::Message) {
+        trace!("Received message: {:?}", message);
+        match message {
+            lsp_server::Message::Request(request) => {
+                match self.connection.handle_shutdown(&request) {
+                    Ok(shutdown) => {
+                        if shutdown {
+                            server.shutdown_server();
+                        } else {
+                            self.handle_request(server, request)
+                        }
+                    }
+                    Err(err) => panic!("{err:?}"),
+                }
+            }
+            lsp_server::Message::Notification(notification) => {
+                self.handle_notification(server, notification);
+            }
+            lsp_server::Message::Response(response) => self.handle_response(server, response),
+        };
+    }
+
     /// Send responses (to requests sent by the client) back to the client.
     fn send_response(&self, response: lsp_server::Response) {
         trace!("Sending response: {:?}", response);
@@ -149,6 +221,15 @@ impl ConnectionRpcChannel {
             }
             Err(request) => request,
         };
+        let request = match extract::<request::GotoTypeDefinition>(request) {
+            Ok((id, params)) => {
+                let result =
+                    server.text_document_type_definition(&params.text_document_position_params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
         let request = match extract::<request::GotoImplementation>(request) {
             Ok((id, params)) => {
                 let result =
@@ -160,8 +241,14 @@ impl ConnectionRpcChannel {
         };
         let request = match extract::<request::Rename>(request) {
             Ok((id, params)) => {
-                let result = server.rename(&params);
-                self.send_response(lsp_server::Response::new_ok(id, result));
+                match server.rename(&params) {
+                    Ok(result) => self.send_response(lsp_server::Response::new_ok(id, result)),
+                    Err(message) => self.send_response(lsp_server::Response::new_err(
+                        id,
+                        lsp_server::ErrorCode::InvalidParams as i32,
+                        message,
+                    )),
+                }
                 return;
             }
             Err(request) => request,
@@ -198,6 +285,118 @@ impl ConnectionRpcChannel {
             }
             Err(request) => request,
         };
+        let request = match extract::<request::InlayHintRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_inlay_hint(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyPrepare>(request) {
+            Ok((id, params)) => {
+                let result = server.prepare_call_hierarchy(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyIncomingCalls>(request) {
+            Ok((id, params)) => {
+                let result = server.call_hierarchy_incoming_calls(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyOutgoingCalls>(request) {
+            Ok((id, params)) => {
+                let result = server.call_hierarchy_outgoing_calls(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::TypeHierarchyPrepare>(request) {
+            Ok((id, params)) => {
+                let result = server.prepare_type_hierarchy(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::TypeHierarchySupertypes>(request) {
+            Ok((id, params)) => {
+                let result = server.type_hierarchy_supertypes(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::TypeHierarchySubtypes>(request) {
+            Ok((id, params)) => {
+                let result = server.type_hierarchy_subtypes(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::DocumentHighlightRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_document_highlight(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeLensRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_code_lens(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeLensResolve>(request) {
+            Ok((id, params)) => {
+                let result = server.code_lens_resolve(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeActionRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_code_action(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeActionResolveRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.code_action_resolve(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::FoldingRangeRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_folding_range(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::SelectionRangeRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_selection_range(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
         let request = match extract::<request::References>(request) {
             Ok((id, params)) => {
                 let result = server.text_document_references(&params);
@@ -222,6 +421,22 @@ impl ConnectionRpcChannel {
             }
             Err(request) => request,
         };
+        let request = match extract::<request::WillRenameFiles>(request) {
+            Ok((id, params)) => {
+                let res = server.workspace_will_rename_files(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::ExecuteCommand>(request) {
+            Ok((id, params)) => {
+                let res = server.workspace_execute_command(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
 
         debug!("Unhandled request: {:?}", request);
         self.send_response(lsp_server::Response::new_err(
@@ -259,6 +474,26 @@ impl ConnectionRpcChannel {
             Ok(params) => return server.text_document_did_open_notification(&params),
             Err(notification) => notification,
         };
+        // textDocument/didClose
+        let notification = match extract::<notification::DidCloseTextDocument>(notification) {
+            Ok(params) => return server.text_document_did_close_notification(&params),
+            Err(notification) => notification,
+        };
+        // textDocument/didSave
+        let notification = match extract::<notification::DidSaveTextDocument>(notification) {
+            Ok(params) => return server.text_document_did_save_notification(&params),
+            Err(notification) => notification,
+        };
+        // workspace/didRenameFiles
+        let notification = match extract::<notification::DidRenameFiles>(notification) {
+            Ok(params) => return server.workspace_did_rename_files(&params),
+            Err(notification) => notification,
+        };
+        // workspace/didChangeWorkspaceFolders
+        let notification = match extract::<notification::DidChangeWorkspaceFolders>(notification) {
+            Ok(params) => return server.workspace_did_change_workspace_folders(&params),
+            Err(notification) => notification,
+        };
         // workspace.didChangeWatchedFiles
         let notification = match extract::<notification::DidChangeWatchedFiles>(notification) {
             Ok(params) => return server.workspace_did_change_watched_files(&params),
@@ -282,3 +517,152 @@ impl ConnectionRpcChannel {
         // outgoing requests do not require confirmation by the client.
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::{Message, Response};
+    use lsp_types::{
+        DidOpenTextDocumentParams, HoverParams, Position, TextDocumentIdentifier, TextDocumentItem,
+        TextDocumentPositionParams, Url,
+    };
+
+    fn hover_request(id: i32) -> Message {
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::parse("file:///ent.vhd").unwrap(),
+                },
+                position: Position::default(),
+            },
+            work_done_progress_params: Default::default(),
+        };
+        Request::new(
+            RequestId::from(id),
+            <request::HoverRequest as request::Request>::METHOD.to_owned(),
+            params,
+        )
+        .into()
+    }
+
+    #[test]
+    fn queued_requests_can_be_cancelled() {
+        let (connection, client) = Connection::memory();
+        let connection_rpc = Rc::new(ConnectionRpcChannel::new(connection));
+        let rpc = SharedRpcChannel::new(connection_rpc.clone());
+        let server = VHDLServer::new_settings(rpc, VHDLServerSettings::default());
+
+        client.sender.send(hover_request(1)).unwrap();
+        client.sender.send(hover_request(2)).unwrap();
+        client
+            .sender
+            .send(
+                lsp_server::Notification::new(
+                    notification::Cancel::METHOD.to_owned(),
+                    CancelParams {
+                        id: NumberOrString::Number(1),
+                    },
+                )
+                .into(),
+            )
+            .unwrap();
+        let Connection { sender, receiver } = client;
+        drop(sender);
+
+        connection_rpc.main_event_loop(server);
+
+        let responses: Vec<Response> = receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Response(response) => response,
+                message => panic!("Expected response, got {message:?}"),
+            })
+            .collect();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, RequestId::from(1));
+        assert_eq!(
+            responses[0].error.as_ref().map(|error| error.code),
+            Some(lsp_server::ErrorCode::RequestCanceled as i32)
+        );
+        assert_eq!(responses[1].id, RequestId::from(2));
+        assert_eq!(responses[1].result, Some(Value::Null));
+    }
+
+    #[test]
+    fn opening_a_file_while_the_project_is_loaded_does_not_warn() {
+        let root = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        std::fs::write(
+            root_path.join("vhdl_ls.toml"),
+            "[libraries]\nlib.files = ['ent.vhd']\n",
+        )
+        .unwrap();
+        let file_path = root_path.join("ent.vhd");
+        std::fs::write(&file_path, "entity ent is\nend entity;\n").unwrap();
+
+        let (connection, client) = Connection::memory();
+        let connection_rpc = Rc::new(ConnectionRpcChannel::new(connection));
+        let rpc = SharedRpcChannel::new(connection_rpc.clone());
+        let mut server = VHDLServer::new_background(rpc);
+
+        #[allow(deprecated)]
+        let initialize_params = InitializeParams {
+            root_uri: Some(Url::from_file_path(&root_path).unwrap()),
+            ..Default::default()
+        };
+        client
+            .sender
+            .send(
+                Request::new(
+                    RequestId::from(0),
+                    <request::Initialize as request::Request>::METHOD.to_owned(),
+                    initialize_params,
+                )
+                .into(),
+            )
+            .unwrap();
+        client
+            .sender
+            .send(
+                lsp_server::Notification::new(
+                    notification::Initialized::METHOD.to_owned(),
+                    lsp_types::InitializedParams {},
+                )
+                .into(),
+            )
+            .unwrap();
+        // The file is opened before the background analysis has loaded the project
+        client
+            .sender
+            .send(
+                lsp_server::Notification::new(
+                    notification::DidOpenTextDocument::METHOD.to_owned(),
+                    DidOpenTextDocumentParams {
+                        text_document: TextDocumentItem {
+                            uri: Url::from_file_path(&file_path).unwrap(),
+                            language_id: "vhdl".to_owned(),
+                            version: 0,
+                            text: "entity ent is\nend entity;\n".to_owned(),
+                        },
+                    },
+                )
+                .into(),
+            )
+            .unwrap();
+        let Connection { sender, receiver } = client;
+        drop(sender);
+
+        connection_rpc.handle_initialization(&mut server);
+        connection_rpc.main_event_loop(server);
+
+        let warnings: Vec<_> = receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                Message::Notification(notification) => Some(notification.params.to_string()),
+                _ => None,
+            })
+            .filter(|params| params.contains("not part of the project"))
+            .collect();
+        assert_eq!(warnings, Vec::<String>::new());
+    }
+}
@@ -6,34 +6,126 @@
 
 use lsp_types::*;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use vhdl_lang::ast::{Designator, ObjectClass};
 
+use crate::analysis_worker::{AnalysisEvent, AnalysisWorker, ProjectChange, WorkerEvent};
 use crate::rpc_channel::SharedRpcChannel;
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use vhdl_lang::{
-    kind_str, AnyEntKind, Concurrent, Config, Diagnostic, EntHierarchy, EntRef, EntityId, Message,
-    MessageHandler, Object, Overloaded, Project, Severity, Source, SrcPos, Type,
+    kind_str, AnyEntKind, CodeLensKind, Concurrent, Config, Diagnostic, EntHierarchy, EntRef,
+    EntityId, HoverInfo, InlayHintKind, LibraryMapping, Message, MessageHandler, Object,
+    Overloaded, Project, RefactoringKind, ResolvedCodeLens, Severity, SharedLibraries, Source,
+    SrcPos, Type,
 };
 
+/// Command returning the library mappings suggested for a file
+const SUGGEST_LIBRARY_MAPPINGS_COMMAND: &str = "vhdl_ls.suggestLibraryMappings";
+/// Command writing the library mappings suggested for a file to the workspace configuration file
+const ADD_LIBRARY_MAPPINGS_COMMAND: &str = "vhdl_ls.addLibraryMappings";
+/// Command returning the locations of the uses summarized by a code lens.
+/// The arguments are the uri of the document followed by the range and kind of the code lens.
+const CODE_LENS_LOCATIONS_COMMAND: &str = "vhdl_ls.codeLensLocations";
+/// Time without further changes before the project is analysed
+const ANALYSIS_DEBOUNCE: Duration = Duration::from_millis(200);
+/// Pattern of VHDL files watched for changes on disk
+const VHDL_FILE_PATTERN: &str = "**/*.vhd{,l}";
+/// Token of the work done progress reported while loading the project
+const LOAD_PROGRESS_TOKEN: &str = "vhdl_ls/load";
+
 #[derive(Default, Clone)]
 pub struct VHDLServerSettings {
     pub no_lint: bool,
     pub silent: bool,
 }
 
+/// The kinds of inlay hints that are shown, given by the `inlayHints` initialization option
+/// such as `{"inlayHints": {"formalNames": true, "types": false, "endLabels": true}}`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct InlayHintSettings {
+    formal_names: bool,
+    types: bool,
+    end_labels: bool,
+}
+
+impl InlayHintSettings {
+    fn from_initialization_options(options: Option<&serde_json::Value>) -> InlayHintSettings {
+        let enabled = |name: &str| {
+            options
+                .and_then(|options| options.get("inlayHints"))
+                .and_then(|hints| hints.get(name))
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(true)
+        };
+        InlayHintSettings {
+            formal_names: enabled("formalNames"),
+            types: enabled("types"),
+            end_labels: enabled("endLabels"),
+        }
+    }
+
+    fn is_enabled(&self, kind: InlayHintKind) -> bool {
+        match kind {
+            InlayHintKind::FormalName => self.formal_names,
+            InlayHintKind::Type => self.types,
+            InlayHintKind::EndLabel => self.end_labels,
+        }
+    }
+}
+
+/// Reporting of work done progress while the project is loaded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum LoadProgress {
+    Idle,
+    /// Report progress when the next analysis starts
+    Requested,
+    /// Progress has been reported to begin
+    Reported,
+}
+
+/// A workspace folder with its own configuration file and project
+struct WorkspaceProject {
+    // Identifies the folder in the events of its analysis worker
+    id: usize,
+    uri: Option<Url>,
+    root: Option<PathBuf>,
+    config_file: Option<PathBuf>,
+    // Project of the last completed analysis, used to serve requests
+    project: Project,
+    // Analyses changes to the project in the background, analysis is done directly when None
+    analysis: Option<AnalysisWorker>,
+    load_progress: LoadProgress,
+    files_with_notifications: FnvHashMap<Url, ()>,
+}
+
+impl WorkspaceProject {
+    /// Token of the work done progress reported while loading the project of the folder
+    fn load_progress_token(&self) -> NumberOrString {
+        NumberOrString::String(format!("{LOAD_PROGRESS_TOKEN}/{}", self.id))
+    }
+}
+
 pub struct VHDLServer {
     rpc: SharedRpcChannel,
     settings: VHDLServerSettings,
     // To have well defined unit tests that are not affected by environment
     use_external_config: bool,
-    project: Project,
-    files_with_notifications: FnvHashMap<Url, ()>,
+    // Projects are analysed in the background after this debounce time, directly when None
+    analysis_debounce: Option<Duration>,
+    // Events of the analysis workers of all folders, tagged with the id of the folder
+    analysis_events: (Sender<WorkerEvent>, Receiver<WorkerEvent>),
+    folders: Vec<WorkspaceProject>,
+    next_folder_id: usize,
+    // Files of third-party libraries such as std and ieee, parsed once for all folders
+    shared_libraries: SharedLibraries,
+    // Files opened in the editor, their contents are managed by the client
+    open_files: FnvHashSet<PathBuf>,
     init_params: Option<InitializeParams>,
-    config_file: Option<PathBuf>,
 }
 
 impl VHDLServer {
@@ -42,10 +134,13 @@ impl VHDLServer {
             rpc,
             settings,
             use_external_config: true,
-            project: Project::new(),
-            files_with_notifications: FnvHashMap::default(),
+            analysis_debounce: Some(ANALYSIS_DEBOUNCE),
+            analysis_events: unbounded(),
+            folders: Vec::new(),
+            next_folder_id: 0,
+            shared_libraries: SharedLibraries::default(),
+            open_files: FnvHashSet::default(),
             init_params: None,
-            config_file: None,
         }
     }
 
@@ -55,16 +150,28 @@ impl VHDLServer {
             rpc,
             settings: Default::default(),
             use_external_config,
-            project: Project::new(),
-            files_with_notifications: FnvHashMap::default(),
+            analysis_debounce: None,
+            analysis_events: unbounded(),
+            folders: Vec::new(),
+            next_folder_id: 0,
+            shared_libraries: SharedLibraries::default(),
+            open_files: FnvHashSet::default(),
             init_params: None,
-            config_file: None,
         }
     }
 
-    /// Load the workspace root configuration file
-    fn load_root_uri_config(&self) -> io::Result<Config> {
-        let config_file = self.config_file.as_ref().ok_or_else(|| {
+    /// Server analysing in the background, without external configuration
+    #[cfg(test)]
+    pub(crate) fn new_background(rpc: SharedRpcChannel) -> VHDLServer {
+        VHDLServer {
+            analysis_debounce: Some(ANALYSIS_DEBOUNCE),
+            ..Self::new_external_config(rpc, false)
+        }
+    }
+
+    /// Load the configuration file of a workspace folder
+    fn load_root_uri_config(&self, config_file: Option<&Path>) -> io::Result<Config> {
+        let config_file = config_file.ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
                 "Workspace root configuration file not set",
@@ -83,14 +190,14 @@ impl VHDLServer {
 
     /// Load the configuration or use a default configuration if unsuccessful
     /// Log info/error messages to the client
-    fn load_config(&self) -> Config {
+    fn load_config(&self, config_file: Option<&Path>) -> Config {
         let mut config = Config::default();
 
         if self.use_external_config {
             config.load_external_config(&mut self.message_filter());
         }
 
-        match self.load_root_uri_config() {
+        match self.load_root_uri_config(config_file) {
             Ok(root_config) => {
                 config.append(&root_config, &mut self.message_filter());
             }
@@ -108,26 +215,61 @@ impl VHDLServer {
     }
 
     pub fn initialize_request(&mut self, init_params: InitializeParams) -> InitializeResult {
-        self.config_file = self.root_uri_config_file(&init_params);
-        let config = self.load_config();
-        self.project = Project::from_config(config, &mut self.message_filter());
-        self.project.enable_unused_declaration_detection();
+        match init_params.workspace_folders {
+            Some(ref workspace_folders) if !workspace_folders.is_empty() => {
+                for workspace_folder in workspace_folders.iter() {
+                    self.add_folder(Some(workspace_folder.uri.clone()));
+                }
+            }
+            _ => {
+                if init_params.root_uri.is_none() {
+                    self.message(Message::error(
+                        "Cannot load workspace: Initialize request is missing rootUri parameter.",
+                    ));
+                }
+                self.add_folder(init_params.root_uri.clone());
+            }
+        }
         self.init_params = Some(init_params);
         let trigger_chars: Vec<String> = r".".chars().map(|ch| ch.to_string()).collect();
 
         let capabilities = ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                TextDocumentSyncKind::INCREMENTAL,
+            text_document_sync: Some(TextDocumentSyncCapability::Options(
+                TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
+                    save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                    ..Default::default()
+                },
             )),
             declaration_provider: Some(DeclarationCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
+            type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
             references_provider: Some(OneOf::Left(true)),
+            document_highlight_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
             implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
             rename_provider: Some(OneOf::Right(RenameOptions {
                 prepare_provider: Some(true),
                 work_done_progress_options: Default::default(),
             })),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(true),
+            }),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::REFACTOR_EXTRACT,
+                    CodeActionKind::REFACTOR_INLINE,
+                    CodeActionKind::REFACTOR_REWRITE,
+                    CodeActionKind::QUICKFIX,
+                ]),
+                work_done_progress_options: Default::default(),
+                resolve_provider: Some(true),
+            })),
             workspace_symbol_provider: Some(OneOf::Left(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
             completion_provider: Some(CompletionOptions {
@@ -138,6 +280,25 @@ impl VHDLServer {
                 }),
                 ..Default::default()
             }),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![
+                    SUGGEST_LIBRARY_MAPPINGS_COMMAND.to_owned(),
+                    ADD_LIBRARY_MAPPINGS_COMMAND.to_owned(),
+                    CODE_LENS_LOCATIONS_COMMAND.to_owned(),
+                ],
+                work_done_progress_options: Default::default(),
+            }),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(vhdl_file_operation_options()),
+                    did_rename: Some(vhdl_file_operation_options()),
+                    ..Default::default()
+                }),
+            }),
             ..Default::default()
         };
 
@@ -147,27 +308,134 @@ impl VHDLServer {
         }
     }
 
-    /// Extract path of workspace root configuration file from InitializeParams
-    fn root_uri_config_file(&self, params: &InitializeParams) -> Option<PathBuf> {
-        match params.root_uri.clone() {
-            Some(root_uri) => root_uri
-                .to_file_path()
-                .map(|root_path| root_path.join("vhdl_ls.toml"))
-                .map_err(|_| {
-                    self.message(Message::error(format!(
-                        "{} {} {:?} ",
-                        "Cannot load workspace:",
-                        "initializeParams.rootUri is not a valid file path:",
-                        root_uri,
-                    )))
-                })
-                .ok(),
+    /// Extract the root path of a workspace folder from its uri
+    fn folder_root(&self, uri: &Url) -> Option<PathBuf> {
+        uri.to_file_path()
+            .map_err(|_| {
+                self.message(Message::error(format!(
+                    "{} {} {:?} ",
+                    "Cannot load workspace:", "workspace folder uri is not a valid file path:", uri,
+                )))
+            })
+            .ok()
+    }
+
+    /// Add a workspace folder and load its configuration file.
+    /// The folder is analysed when `analyse_project` is called.
+    fn add_folder(&mut self, uri: Option<Url>) -> usize {
+        let root = uri.as_ref().and_then(|uri| self.folder_root(uri));
+        let config_file = root.as_ref().map(|root| root.join("vhdl_ls.toml"));
+        let config = self.load_config(config_file.as_deref());
+
+        let mut project = Project::with_shared_libraries(self.shared_libraries.clone());
+        project.enable_unused_declaration_detection();
+        let mut folder = WorkspaceProject {
+            id: self.next_folder_id,
+            uri,
+            root,
+            config_file,
+            project,
+            analysis: None,
+            load_progress: LoadProgress::Idle,
+            files_with_notifications: FnvHashMap::default(),
+        };
+        self.next_folder_id += 1;
+
+        match self.analysis_debounce {
+            Some(debounce) => {
+                // The worker thread starts from its own copy of the still empty project
+                let mut analysis = AnalysisWorker::spawn(
+                    debounce,
+                    folder.id,
+                    self.analysis_events.0.clone(),
+                    folder.project.clone(),
+                );
+                // Files are loaded in the background together with the first analysis
+                analysis.push(ProjectChange::Config(Box::new(config)));
+                folder.analysis = Some(analysis);
+                folder.load_progress = LoadProgress::Requested;
+            }
             None => {
-                self.message(Message::error(
-                    "Cannot load workspace: Initialize request is missing rootUri parameter.",
-                ));
-                None
+                folder
+                    .project
+                    .update_config(config, &mut self.message_filter());
+            }
+        }
+        self.folders.push(folder);
+        self.folders.len() - 1
+    }
+
+    /// Remove a workspace folder and clear the diagnostics of its project
+    fn remove_folder(&mut self, uri: &Url) {
+        let Some(idx) = self
+            .folders
+            .iter()
+            .position(|folder| folder.uri.as_ref() == Some(uri))
+        else {
+            return;
+        };
+        let folder = self.folders.remove(idx);
+        for file_uri in folder.files_with_notifications.into_keys() {
+            if self
+                .folders
+                .iter()
+                .any(|folder| folder.files_with_notifications.contains_key(&file_uri))
+            {
+                continue;
             }
+            self.rpc.send_notification(
+                "textDocument/publishDiagnostics",
+                PublishDiagnosticsParams {
+                    uri: file_uri,
+                    diagnostics: vec![],
+                    version: None,
+                },
+            );
+        }
+    }
+
+    /// Index of the folder serving a file.
+    /// This is the innermost folder containing the file, or otherwise the first folder
+    /// whose project contains the file, or otherwise the first folder.
+    fn folder_of(&self, file_name: &Path) -> Option<usize> {
+        let containing = self
+            .folders
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, folder)| {
+                let root = folder.root.as_ref()?;
+                file_name
+                    .starts_with(root)
+                    .then(|| (idx, root.components().count()))
+            })
+            .max_by_key(|(_, depth)| *depth)
+            .map(|(idx, _)| idx);
+
+        containing
+            .or_else(|| {
+                self.folders
+                    .iter()
+                    .position(|folder| folder.project.get_source(file_name).is_some())
+            })
+            .or_else(|| (!self.folders.is_empty()).then_some(0))
+    }
+
+    /// The project of the folder serving a document
+    fn project_of(&self, uri: &Url) -> Option<&Project> {
+        let idx = self.folder_of(&uri_to_file_name(uri))?;
+        Some(&self.folders[idx].project)
+    }
+
+    pub fn workspace_did_change_workspace_folders(
+        &mut self,
+        params: &DidChangeWorkspaceFoldersParams,
+    ) {
+        for removed in params.event.removed.iter() {
+            self.remove_folder(&removed.uri);
+        }
+        for added in params.event.added.iter() {
+            let idx = self.add_folder(Some(added.uri.clone()));
+            self.analyse_project(idx);
         }
     }
 
@@ -184,39 +452,214 @@ impl VHDLServer {
 
     /// Register capabilities on the client side:
     /// - watch workspace config file for changes
+    /// - watch VHDL files for changes on disk
+    /// - type hierarchy, which has no static server capability
     fn register_capabilities(&mut self) {
+        let mut registrations = Vec::new();
         if self.client_supports_did_change_watched_files() {
             let register_options = DidChangeWatchedFilesRegistrationOptions {
-                watchers: vec![FileSystemWatcher {
-                    glob_pattern: GlobPattern::String("**/vhdl_ls.toml".to_owned()),
-                    kind: None,
-                }],
+                watchers: vec![
+                    FileSystemWatcher {
+                        glob_pattern: GlobPattern::String("**/vhdl_ls.toml".to_owned()),
+                        kind: None,
+                    },
+                    FileSystemWatcher {
+                        glob_pattern: GlobPattern::String(VHDL_FILE_PATTERN.to_owned()),
+                        kind: None,
+                    },
+                ],
             };
-            let params = RegistrationParams {
-                registrations: vec![Registration {
-                    id: "workspace/didChangeWatchedFiles".to_owned(),
-                    method: "workspace/didChangeWatchedFiles".to_owned(),
-                    register_options: serde_json::to_value(register_options).ok(),
-                }],
+            registrations.push(Registration {
+                id: "workspace/didChangeWatchedFiles".to_owned(),
+                method: "workspace/didChangeWatchedFiles".to_owned(),
+                register_options: serde_json::to_value(register_options).ok(),
+            });
+        }
+        if self.client_supports_type_hierarchy_registration() {
+            let register_options = TypeHierarchyRegistrationOptions {
+                text_document_registration_options: TextDocumentRegistrationOptions {
+                    document_selector: Some(vec![DocumentFilter {
+                        language: Some("vhdl".to_owned()),
+                        scheme: None,
+                        pattern: None,
+                    }]),
+                },
+                type_hierarchy_options: Default::default(),
+                static_registration_options: Default::default(),
             };
+            registrations.push(Registration {
+                id: "textDocument/prepareTypeHierarchy".to_owned(),
+                method: "textDocument/prepareTypeHierarchy".to_owned(),
+                register_options: serde_json::to_value(register_options).ok(),
+            });
+        }
+        if !registrations.is_empty() {
+            let params = RegistrationParams { registrations };
             self.rpc.send_request("client/registerCapability", params);
         }
     }
 
     pub fn initialized_notification(&mut self) {
         self.register_capabilities();
-        self.publish_diagnostics();
+        for idx in 0..self.folders.len() {
+            self.analyse_project(idx);
+        }
+    }
+
+    /// Apply a change to the project of a folder and analyse it
+    fn update_project(&mut self, idx: usize, change: ProjectChange) {
+        self.push_project_change(idx, change);
+        self.analyse_project(idx);
+    }
+
+    /// Apply a change to the project of a folder without analysing it
+    fn push_project_change(&mut self, idx: usize, change: ProjectChange) {
+        let mut messages = self.message_filter();
+        let folder = &mut self.folders[idx];
+        match folder.analysis {
+            Some(ref mut analysis) => analysis.push(change),
+            None => change.apply(&mut folder.project, &mut messages),
+        }
+    }
+
+    /// Analyse the project of a folder and publish the diagnostics.
+    /// With an analysis worker the analysis is only scheduled to run in the background.
+    fn analyse_project(&mut self, idx: usize) {
+        let folder = &mut self.folders[idx];
+        match folder.analysis {
+            Some(ref mut analysis) => analysis.schedule(),
+            None => {
+                let diagnostics = folder.project.analyse();
+                self.publish_diagnostics(idx, diagnostics);
+            }
+        }
+    }
+
+    /// The most recent version of a source of a folder, including changes not yet analysed
+    fn get_source(&self, idx: usize, file_name: &Path) -> Option<Source> {
+        let folder = &self.folders[idx];
+        folder
+            .analysis
+            .as_ref()
+            .and_then(|analysis| analysis.get_source(file_name))
+            .or_else(|| folder.project.get_source(file_name))
+    }
+
+    /// Events of the background analysis to be passed to `analysis_event`
+    pub fn analysis_events(&self) -> Receiver<WorkerEvent> {
+        self.analysis_events.1.clone()
+    }
+
+    /// Time when `start_analysis` should be called
+    pub fn analysis_deadline(&self) -> Option<Instant> {
+        self.folders
+            .iter()
+            .filter_map(|folder| folder.analysis.as_ref()?.deadline())
+            .min()
+    }
+
+    /// Start the background analysis of the projects that have not changed for a while
+    pub fn start_analysis(&mut self) {
+        for idx in 0..self.folders.len() {
+            let folder = &mut self.folders[idx];
+            let Some(ref mut analysis) = folder.analysis else {
+                continue;
+            };
+            if analysis.start() && folder.load_progress == LoadProgress::Requested {
+                self.begin_load_progress(idx);
+            }
+        }
+    }
+
+    pub fn analysis_event(&mut self, (id, event): WorkerEvent) {
+        // The folder may have been removed while it was analysed
+        let Some(idx) = self.folders.iter().position(|folder| folder.id == id) else {
+            return;
+        };
+
+        match event {
+            AnalysisEvent::Progress(message) => {
+                if self.folders[idx].load_progress == LoadProgress::Reported {
+                    self.report_load_progress(
+                        idx,
+                        WorkDoneProgress::Report(WorkDoneProgressReport {
+                            message: Some(message),
+                            ..Default::default()
+                        }),
+                    );
+                }
+            }
+            AnalysisEvent::Done(mut outcome) => {
+                let messages = std::mem::take(&mut outcome.messages);
+                let folder = &mut self.folders[idx];
+                let Some(ref mut analysis) = folder.analysis else {
+                    return;
+                };
+                let finished = analysis.finish(outcome);
+                for message in messages {
+                    self.message(message);
+                }
+
+                if let Some((project, diagnostics)) = finished {
+                    self.folders[idx].project = project;
+                    if self.folders[idx].load_progress == LoadProgress::Reported {
+                        self.report_load_progress(
+                            idx,
+                            WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
+                        );
+                    }
+                    self.folders[idx].load_progress = LoadProgress::Idle;
+                    self.publish_diagnostics(idx, diagnostics);
+                }
+            }
+        }
+    }
+
+    fn begin_load_progress(&mut self, idx: usize) {
+        if !self.client_supports_work_done_progress() {
+            self.folders[idx].load_progress = LoadProgress::Idle;
+            return;
+        }
+
+        self.rpc.send_request(
+            "window/workDoneProgress/create",
+            WorkDoneProgressCreateParams {
+                token: self.folders[idx].load_progress_token(),
+            },
+        );
+        self.report_load_progress(
+            idx,
+            WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: "Loading project".to_owned(),
+                cancellable: Some(false),
+                ..Default::default()
+            }),
+        );
+        self.folders[idx].load_progress = LoadProgress::Reported;
+    }
+
+    fn report_load_progress(&self, idx: usize, progress: WorkDoneProgress) {
+        self.rpc.send_notification(
+            "$/progress",
+            ProgressParams {
+                token: self.folders[idx].load_progress_token(),
+                value: ProgressParamsValue::WorkDone(progress),
+            },
+        );
     }
 
     pub fn text_document_did_change_notification(&mut self, params: &DidChangeTextDocumentParams) {
         let file_name = uri_to_file_name(&params.text_document.uri);
-        if let Some(source) = self.project.get_source(&file_name) {
+        let Some(idx) = self.folder_of(&file_name) else {
+            return;
+        };
+        // The changes are applied to a copy, the source is still in use by the analysed project
+        if let Some(mut source) = self.get_source(idx, &file_name) {
             for content_change in params.content_changes.iter() {
                 let range = content_change.range.map(from_lsp_range);
-                source.change(range.as_ref(), &content_change.text);
+                source = source.changed(range.as_ref(), &content_change.text);
             }
-            self.project.update_source(&source);
-            self.publish_diagnostics();
+            self.update_project(idx, ProjectChange::Source(source));
         } else {
             self.message(Message::error(format!(
                 "Changing file {} that is not part of the project",
@@ -228,36 +671,290 @@ impl VHDLServer {
     pub fn text_document_did_open_notification(&mut self, params: &DidOpenTextDocumentParams) {
         let TextDocumentItem { uri, text, .. } = &params.text_document;
         let file_name = uri_to_file_name(uri);
-        if let Some(source) = self.project.get_source(&file_name) {
-            source.change(None, text);
-            self.project.update_source(&source);
-            self.publish_diagnostics();
+        self.open_files.insert(file_name.clone());
+        let Some(idx) = self.folder_of(&file_name) else {
+            return;
+        };
+        if let Some(source) = self.get_source(idx, &file_name) {
+            self.update_project(idx, ProjectChange::Source(source.changed(None, text)));
         } else {
-            self.message(Message::warning(format!(
-                "Opening file {} that is not part of the project",
-                file_name.to_string_lossy()
-            )));
-            self.project
-                .update_source(&Source::inline(&file_name, text));
-            self.publish_diagnostics();
+            // The file may be part of a configuration that is still being loaded
+            let is_loading = self.folders[idx]
+                .analysis
+                .as_ref()
+                .is_some_and(|analysis| analysis.is_loading());
+            if !is_loading {
+                self.message(Message::warning(format!(
+                    "Opening file {} that is not part of the project",
+                    file_name.to_string_lossy()
+                )));
+            }
+            self.update_project(idx, ProjectChange::Source(Source::inline(&file_name, text)));
         }
     }
 
-    pub fn workspace_did_change_watched_files(&mut self, params: &DidChangeWatchedFilesParams) {
-        if let Some(config_file) = &self.config_file {
-            let config_file_has_changed = params
-                .changes
-                .iter()
-                .any(|change| uri_to_file_name(&change.uri).as_path() == config_file);
-            if config_file_has_changed {
+    /// Revert a closed file to the contents on disk, discarding unsaved changes
+    pub fn text_document_did_close_notification(&mut self, params: &DidCloseTextDocumentParams) {
+        let file_name = uri_to_file_name(&params.text_document.uri);
+        if self.open_files.remove(&file_name) {
+            if let Some(idx) = self.folder_of(&file_name) {
+                self.update_project(idx, ProjectChange::Load(file_name));
+            }
+        }
+    }
+
+    pub fn text_document_did_save_notification(&mut self, params: &DidSaveTextDocumentParams) {
+        let file_name = uri_to_file_name(&params.text_document.uri);
+        if let Some(idx) = self.folder_of_config_file(&file_name) {
+            // Without watched files the configuration is only reloaded when saved in the editor
+            if !self.client_supports_did_change_watched_files() {
                 self.message(Message::log(
                     "Configuration file has changed, reloading project...",
                 ));
-                let config = self.load_config();
+                self.reload_config(idx);
+            }
+        } else if let Some(ref text) = params.text {
+            let Some(idx) = self.folder_of(&file_name) else {
+                return;
+            };
+            if let Some(source) = self.get_source(idx, &file_name) {
+                self.update_project(idx, ProjectChange::Source(source.changed(None, text)));
+            }
+        }
+    }
 
-                self.project
-                    .update_config(config, &mut self.message_filter());
-                self.publish_diagnostics();
+    /// Index of the folder with the given configuration file
+    fn folder_of_config_file(&self, file_name: &Path) -> Option<usize> {
+        self.folders
+            .iter()
+            .position(|folder| folder.config_file.as_deref() == Some(file_name))
+    }
+
+    pub fn workspace_did_change_watched_files(&mut self, params: &DidChangeWatchedFilesParams) {
+        let mut changed_config_files = BTreeSet::new();
+        let mut changes = Vec::new();
+
+        for event in params.changes.iter() {
+            let file_name = uri_to_file_name(&event.uri);
+            if let Some(idx) = self.folder_of_config_file(&file_name) {
+                changed_config_files.insert(idx);
+            } else if let Some(idx) = self.folder_of(&file_name) {
+                if event.typ == FileChangeType::DELETED {
+                    changes.push((idx, ProjectChange::Remove(file_name)));
+                } else if !self.open_files.contains(&file_name) {
+                    // Contents of open files are managed by the client
+                    changes.push((idx, ProjectChange::Load(file_name)));
+                }
+            }
+        }
+
+        for idx in changed_config_files {
+            self.message(Message::log(
+                "Configuration file has changed, reloading project...",
+            ));
+            self.reload_config(idx);
+        }
+
+        let mut changed_folders = BTreeSet::new();
+        for (idx, change) in changes {
+            self.push_project_change(idx, change);
+            changed_folders.insert(idx);
+        }
+        for idx in changed_folders {
+            self.analyse_project(idx);
+        }
+    }
+
+    /// Files are only renamed on disk once the client has applied the returned edit.
+    /// Design units are referred to by name, so only the files listed by name in the
+    /// configuration files of the workspace folders are replaced.
+    pub fn workspace_will_rename_files(
+        &mut self,
+        params: &RenameFilesParams,
+    ) -> Option<WorkspaceEdit> {
+        let renames: Vec<(PathBuf, PathBuf)> = params
+            .files
+            .iter()
+            .filter_map(|file| {
+                let old_uri = Url::parse(&file.old_uri).ok()?;
+                let new_uri = Url::parse(&file.new_uri).ok()?;
+                Some((uri_to_file_name(&old_uri), uri_to_file_name(&new_uri)))
+            })
+            .collect();
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for folder in self.folders.iter() {
+            let Some(ref config_file) = folder.config_file else {
+                continue;
+            };
+            let Ok(contents) = std::fs::read_to_string(config_file) else {
+                continue;
+            };
+            let parent = config_file.parent().unwrap_or(Path::new(""));
+            let new_contents = match Config::rename_library_files(&contents, parent, &renames) {
+                Ok(new_contents) => new_contents,
+                Err(err) => {
+                    self.message(Message::error(format!(
+                        "Cannot rename files in {}: {err}",
+                        config_file.to_string_lossy()
+                    )));
+                    continue;
+                }
+            };
+            if new_contents != contents {
+                let Ok(uri) = Url::from_file_path(config_file) else {
+                    continue;
+                };
+                changes.entry(uri).or_default().push(TextEdit {
+                    range: lsp_types::Range::new(
+                        lsp_types::Position::new(0, 0),
+                        end_of_text(&contents),
+                    ),
+                    new_text: new_contents,
+                });
+            }
+        }
+
+        if changes.is_empty() {
+            return None;
+        }
+        Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        })
+    }
+
+    pub fn workspace_did_rename_files(&mut self, params: &RenameFilesParams) {
+        let mut changed_folders = BTreeSet::new();
+        for file in params.files.iter() {
+            let (Ok(old_uri), Ok(new_uri)) = (Url::parse(&file.old_uri), Url::parse(&file.new_uri))
+            else {
+                continue;
+            };
+            let (from, to) = (uri_to_file_name(&old_uri), uri_to_file_name(&new_uri));
+            if self.open_files.remove(&from) {
+                self.open_files.insert(to.clone());
+            }
+
+            let (Some(from_idx), Some(to_idx)) = (self.folder_of(&from), self.folder_of(&to))
+            else {
+                continue;
+            };
+            if from_idx == to_idx {
+                self.push_project_change(from_idx, ProjectChange::Rename(from, to));
+            } else {
+                // Moved to another workspace folder
+                self.push_project_change(from_idx, ProjectChange::Remove(from));
+                self.push_project_change(to_idx, ProjectChange::Load(to));
+            }
+            changed_folders.insert(from_idx);
+            changed_folders.insert(to_idx);
+        }
+
+        for idx in changed_folders {
+            self.analyse_project(idx);
+        }
+    }
+
+    fn reload_config(&mut self, idx: usize) {
+        let config = self.load_config(self.folders[idx].config_file.as_deref());
+        if self.folders[idx].analysis.is_some() {
+            self.folders[idx].load_progress = LoadProgress::Requested;
+        }
+        self.update_project(idx, ProjectChange::Config(Box::new(config)));
+    }
+
+    /// Execute a command where the first argument is the uri of a document
+    pub fn workspace_execute_command(
+        &mut self,
+        params: &ExecuteCommandParams,
+    ) -> Option<serde_json::Value> {
+        let uri = params
+            .arguments
+            .first()
+            .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+        let Some(uri) = uri else {
+            self.message(Message::error(format!(
+                "Command {} expects the uri of a document as argument",
+                params.command
+            )));
+            return None;
+        };
+
+        match params.command.as_str() {
+            SUGGEST_LIBRARY_MAPPINGS_COMMAND => {
+                let mappings = self.suggest_library_mappings(&uri);
+                Some(library_mappings_to_json(&mappings))
+            }
+            ADD_LIBRARY_MAPPINGS_COMMAND => {
+                let mappings = self.suggest_library_mappings(&uri);
+                if let Some(idx) = self.folder_of(&uri_to_file_name(&uri)) {
+                    self.add_library_mappings(idx, &mappings);
+                }
+                Some(library_mappings_to_json(&mappings))
+            }
+            CODE_LENS_LOCATIONS_COMMAND => {
+                let range = params
+                    .arguments
+                    .get(1)
+                    .and_then(|arg| serde_json::from_value(arg.clone()).ok())?;
+                let kind = params
+                    .arguments
+                    .get(2)
+                    .and_then(|arg| code_lens_kind(arg.as_str()?))?;
+                let resolved = self.resolve_code_lens(&uri, range, kind)?;
+                let locations: Vec<Location> =
+                    resolved.locations.iter().map(srcpos_to_location).collect();
+                Some(serde_json::json!(locations))
+            }
+            command => {
+                self.message(Message::error(format!("Unknown command {command}")));
+                None
+            }
+        }
+    }
+
+    fn suggest_library_mappings(&self, uri: &Url) -> Vec<LibraryMapping> {
+        let Some(project) = self.project_of(uri) else {
+            return Vec::new();
+        };
+        match project.get_source(&uri_to_file_name(uri)) {
+            Some(source) => project.suggest_library_mappings(&source),
+            None => Vec::new(),
+        }
+    }
+
+    /// Write library mappings to the configuration file of a folder and reload its project
+    fn add_library_mappings(&mut self, idx: usize, mappings: &[LibraryMapping]) {
+        if mappings.is_empty() {
+            self.message(Message::info("No missing library mappings found"));
+            return;
+        }
+
+        let Some(config_file) = self.folders[idx].config_file.clone() else {
+            self.message(Message::error(
+                "Cannot add library mappings: Workspace root configuration file not set",
+            ));
+            return;
+        };
+
+        match Config::write_library_mappings(&config_file, mappings) {
+            Ok(()) => {
+                for mapping in mappings.iter() {
+                    self.message(Message::info(format!(
+                        "Added {} to library {} in {}",
+                        mapping.file_name.to_string_lossy(),
+                        mapping.library_name,
+                        config_file.to_string_lossy()
+                    )));
+                }
+                self.reload_config(idx);
+            }
+            Err(err) => {
+                self.message(Message::error(format!(
+                    "Cannot add library mappings to {}: {err}",
+                    config_file.to_string_lossy()
+                )));
             }
         }
     }
@@ -294,6 +991,33 @@ impl VHDLServer {
                 kind: Some(CompletionItemKind::KEYWORD),
                 ..Default::default()
             },
+            vhdl_lang::CompletionItem::Attribute(attribute) => CompletionItem {
+                label: attribute.to_string(),
+                detail: Some(format!("'{attribute}")),
+                insert_text: Some(attribute.to_string()),
+                kind: Some(CompletionItemKind::PROPERTY),
+                ..Default::default()
+            },
+            vhdl_lang::CompletionItem::Snippet(snippet) => CompletionItem {
+                label: snippet.name,
+                detail: Some(snippet.description),
+                insert_text: Some(snippet.body),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                kind: Some(CompletionItemKind::SNIPPET),
+                ..Default::default()
+            },
+            vhdl_lang::CompletionItem::AutoImport(ent, edit) => {
+                let mut item = entity_to_completion_item(ent);
+                item.detail = Some(edit.text.lines().collect::<Vec<_>>().join(" "));
+                item.additional_text_edits = Some(vec![TextEdit {
+                    range: Range {
+                        start: to_lsp_pos(edit.position),
+                        end: to_lsp_pos(edit.position),
+                    },
+                    new_text: edit.text,
+                }]);
+                item
+            }
         }
     }
 
@@ -302,14 +1026,20 @@ impl VHDLServer {
     pub fn request_completion(&mut self, params: &CompletionParams) -> CompletionList {
         let binding = uri_to_file_name(&params.text_document_position.text_document.uri);
         let file = binding.as_path();
-        // 1) get source position, and source file
-        let Some(source) = self.project.get_source(file) else {
-            // Do not enable completions for files that are not part of the project
+        let Some(idx) = self.folder_of(file) else {
             return CompletionList {
                 ..Default::default()
             };
         };
-        let cursor = from_lsp_pos(params.text_document_position.position);
+        let folder = &self.folders[idx];
+        // 1) get source position, and source file
+        let Some(source) = folder.project.get_source(file) else {
+            // Do not enable completions for files that are not part of the project
+            return CompletionList {
+                ..Default::default()
+            };
+        };
+        let cursor = from_lsp_pos(params.text_document_position.position);
         // 2) Optimization chance: go to last recognizable token before the cursor. For example:
         //    - Any primary unit (e.g. entity declaration, package declaration, ...)
         //      => keyword `entity`, `package`, ...
@@ -317,11 +1047,25 @@ impl VHDLServer {
         //      => keyword `architecture`, ...
 
         // 3) Run the parser until the point of the cursor. Then exit with possible completions
-        let options = self
+        let options = folder
             .project
             .list_completion_options(&source, cursor)
             .into_iter()
-            .map(|item| self.completion_item_to_lsp_item(item))
+            .filter(|item| {
+                self.client_supports_snippets()
+                    || !matches!(item, vhdl_lang::CompletionItem::Snippet(_))
+            })
+            .enumerate()
+            .map(|(rank, item)| {
+                let mut item = self.completion_item_to_lsp_item(item);
+                // The options are ranked with the most relevant first
+                item.sort_text = Some(format!("{rank:04}"));
+                // Entity ids are only unique within a project
+                item.data = item
+                    .data
+                    .map(|id| serde_json::json!({ "folder": folder.id, "id": id }));
+                item
+            })
             .collect();
 
         CompletionList {
@@ -332,13 +1076,17 @@ impl VHDLServer {
 
     pub fn resolve_completion_item(&mut self, params: &CompletionItem) -> CompletionItem {
         let mut params = params.clone();
-        let eid = params
-            .data
-            .clone()
-            .and_then(|val| serde_json::from_value::<usize>(val).ok())
+        let data = params.data.clone().unwrap_or_default();
+        let folder = data
+            .get("folder")
+            .and_then(|val| serde_json::from_value::<usize>(val.clone()).ok())
+            .and_then(|id| self.folders.iter().find(|folder| folder.id == id));
+        let eid = data
+            .get("id")
+            .and_then(|val| serde_json::from_value::<usize>(val.clone()).ok())
             .map(EntityId::from_raw);
-        if let Some(id) = eid {
-            if let Some(text) = self.project.format_entity(id) {
+        if let (Some(folder), Some(id)) = (folder, eid) {
+            if let Some(text) = folder.project.format_entity(id) {
                 params.documentation = Some(Documentation::MarkupContent(MarkupContent {
                     kind: MarkupKind::Markdown,
                     value: format!("```vhdl\n{text}\n```"),
@@ -376,6 +1124,32 @@ impl VHDLServer {
         try_fun().unwrap_or(false)
     }
 
+    fn client_supports_type_hierarchy_registration(&self) -> bool {
+        let try_fun = || {
+            self.init_params
+                .as_ref()?
+                .capabilities
+                .text_document
+                .as_ref()?
+                .type_hierarchy
+                .as_ref()?
+                .dynamic_registration
+        };
+        try_fun().unwrap_or(false)
+    }
+
+    fn client_supports_work_done_progress(&self) -> bool {
+        let try_fun = || {
+            self.init_params
+                .as_ref()?
+                .capabilities
+                .window
+                .as_ref()?
+                .work_done_progress
+        };
+        try_fun().unwrap_or(false)
+    }
+
     fn client_supports_snippets(&self) -> bool {
         let try_fun = || {
             self.init_params
@@ -406,9 +1180,7 @@ impl VHDLServer {
         try_fun().unwrap_or(false)
     }
 
-    fn publish_diagnostics(&mut self) {
-        let diagnostics = self.project.analyse();
-
+    fn publish_diagnostics(&mut self, idx: usize, diagnostics: Vec<Diagnostic>) {
         if self.settings.no_lint {
             return;
         }
@@ -422,8 +1194,15 @@ impl VHDLServer {
             }
         };
 
-        let mut files_with_notifications = std::mem::take(&mut self.files_with_notifications);
-        for (file_uri, diagnostics) in diagnostics_by_uri(diagnostics).into_iter() {
+        // A file that is part of several folders is only published by the folder serving it
+        let owned: Vec<_> = diagnostics_by_uri(diagnostics)
+            .into_iter()
+            .filter(|(file_uri, _)| self.folder_of(&uri_to_file_name(file_uri)) == Some(idx))
+            .collect();
+
+        let files_with_notifications =
+            std::mem::take(&mut self.folders[idx].files_with_notifications);
+        for (file_uri, diagnostics) in owned {
             let mut lsp_diagnostics = Vec::new();
             for diagnostic in diagnostics {
                 lsp_diagnostics.push(to_lsp_diagnostic(diagnostic));
@@ -438,12 +1217,19 @@ impl VHDLServer {
             self.rpc
                 .send_notification("textDocument/publishDiagnostics", publish_diagnostics);
 
-            self.files_with_notifications.insert(file_uri.clone(), ());
+            self.folders[idx]
+                .files_with_notifications
+                .insert(file_uri.clone(), ());
         }
 
-        for (file_uri, _) in files_with_notifications.drain() {
+        for (file_uri, _) in files_with_notifications.into_iter() {
             // File has no longer any diagnosics, publish empty notification to clear them
-            if !self.files_with_notifications.contains_key(&file_uri) {
+            // unless the file is now served by another folder that has published it
+            if !self.folders[idx]
+                .files_with_notifications
+                .contains_key(&file_uri)
+                && !self.is_published_by_other_folder(idx, &file_uri)
+            {
                 let publish_diagnostics = PublishDiagnosticsParams {
                     uri: file_uri.clone(),
                     diagnostics: vec![],
@@ -456,17 +1242,21 @@ impl VHDLServer {
         }
     }
 
+    /// True if a folder other than the given one has published diagnostics of a file
+    fn is_published_by_other_folder(&self, idx: usize, file_uri: &Url) -> bool {
+        self.folders.iter().enumerate().any(|(other, folder)| {
+            other != idx && folder.files_with_notifications.contains_key(file_uri)
+        })
+    }
+
     pub fn text_document_declaration(
         &mut self,
         params: &TextDocumentPositionParams,
     ) -> Option<Location> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
 
-        let ent = self
-            .project
-            .find_declaration(&source, from_lsp_pos(params.position))?;
+        let ent = project.find_declaration(&source, from_lsp_pos(params.position))?;
         Some(srcpos_to_location(ent.decl_pos()?))
     }
 
@@ -474,13 +1264,21 @@ impl VHDLServer {
         &mut self,
         params: &TextDocumentPositionParams,
     ) -> Option<Location> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
 
-        let ent = self
-            .project
-            .find_definition(&source, from_lsp_pos(params.position))?;
+        let ent = project.find_definition(&source, from_lsp_pos(params.position))?;
+        Some(srcpos_to_location(ent.decl_pos()?))
+    }
+
+    pub fn text_document_type_definition(
+        &mut self,
+        params: &TextDocumentPositionParams,
+    ) -> Option<Location> {
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
+
+        let ent = project.find_type_definition(&source, from_lsp_pos(params.position))?;
         Some(srcpos_to_location(ent.decl_pos()?))
     }
 
@@ -488,13 +1286,10 @@ impl VHDLServer {
         &mut self,
         params: &TextDocumentPositionParams,
     ) -> Option<GotoDefinitionResponse> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
 
-        let ents = self
-            .project
-            .find_implementation(&source, from_lsp_pos(params.position));
+        let ents = project.find_implementation(&source, from_lsp_pos(params.position));
 
         Some(GotoDefinitionResponse::Array(
             ents.into_iter()
@@ -507,13 +1302,10 @@ impl VHDLServer {
         &mut self,
         params: &TextDocumentPositionParams,
     ) -> Option<PrepareRenameResponse> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
 
-        let (pos, ent) = self
-            .project
-            .item_at_cursor(&source, from_lsp_pos(params.position))?;
+        let (pos, ent) = project.item_at_cursor(&source, from_lsp_pos(params.position))?;
 
         if let Designator::Identifier(_) = ent.designator() {
             Some(PrepareRenameResponse::Range(to_lsp_range(pos.range)))
@@ -525,19 +1317,41 @@ impl VHDLServer {
         }
     }
 
-    pub fn rename(&mut self, params: &RenameParams) -> Option<WorkspaceEdit> {
-        let source = self.project.get_source(&uri_to_file_name(
-            &params.text_document_position.text_document.uri,
-        ))?;
-
-        let ent = self.project.find_declaration(
+    /// Rename the declaration at the cursor and all references to it.
+    ///
+    /// Entities and component declarations bound by default binding, and their generics and
+    /// ports, are renamed together when the `rename` initialization option contains
+    /// `{"withBindings": true}`. An invalid name or a conflict is returned as an error message.
+    pub fn rename(&mut self, params: &RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let with_bindings = self
+            .init_params
+            .as_ref()
+            .and_then(|init_params| init_params.initialization_options.as_ref())
+            .and_then(|options| options.get("rename"))
+            .and_then(|rename| rename.get("withBindings"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+
+        let uri = &params.text_document_position.text_document.uri;
+        let Some(project) = self.project_of(uri) else {
+            return Ok(None);
+        };
+        let Some(source) = project.get_source(&uri_to_file_name(uri)) else {
+            return Ok(None);
+        };
+        let Some(ent) = project.find_declaration(
             &source,
             from_lsp_pos(params.text_document_position.position),
-        )?;
+        ) else {
+            return Ok(None);
+        };
 
-        let mut changes: HashMap<Url, Vec<TextEdit>> = Default::default();
+        let positions = project
+            .rename(ent, &params.new_name, with_bindings)
+            .map_err(|err| err.to_string())?;
 
-        for srcpos in self.project.find_all_references(ent) {
+        let mut changes: HashMap<Url, Vec<TextEdit>> = Default::default();
+        for srcpos in positions {
             let loc = srcpos_to_location(&srcpos);
             changes.entry(loc.uri).or_default().push(TextEdit {
                 range: loc.range,
@@ -545,10 +1359,10 @@ impl VHDLServer {
             });
         }
 
-        Some(WorkspaceEdit {
+        Ok(Some(WorkspaceEdit {
             changes: Some(changes),
             ..Default::default()
-        })
+        }))
     }
 
     pub fn workspace_symbol(
@@ -556,36 +1370,27 @@ impl VHDLServer {
         params: &WorkspaceSymbolParams,
     ) -> Option<WorkspaceSymbolResponse> {
         let trunc_limit = 200;
-        let query = params.query.to_ascii_lowercase();
-        let mut symbols: Vec<_> = self
-            .project
-            .public_symbols()
-            .filter_map(|ent| match ent.designator() {
-                Designator::Identifier(_) | Designator::Character(_) => {
-                    Some((ent, ent.designator().to_string().to_ascii_lowercase()))
-                }
-                Designator::OperatorSymbol(op) => Some((ent, op.to_string().to_ascii_lowercase())),
-                Designator::Anonymous(_) => None,
-            })
+        // Rank the matches of all workspace folders together before truncating
+        let mut matches: Vec<_> = self
+            .folders
+            .iter()
+            .flat_map(|folder| folder.project.search_symbols(&params.query, trunc_limit))
             .collect();
-        symbols.sort_by(|(_, n1), (_, n2)| n1.cmp(n2));
+        matches.sort();
         Some(WorkspaceSymbolResponse::Nested(
-            symbols
+            matches
                 .into_iter()
-                .filter_map(|(ent, name)| {
+                .map(|symbol| symbol.ent)
+                .filter_map(|ent| {
                     let decl_pos = ent.decl_pos()?;
-                    if name.starts_with(&query) {
-                        Some(WorkspaceSymbol {
-                            name: ent.describe(),
-                            kind: to_symbol_kind(ent.kind()),
-                            tags: None,
-                            container_name: ent.parent.map(|ent| ent.path_name()),
-                            location: OneOf::Left(srcpos_to_location(decl_pos)),
-                            data: None,
-                        })
-                    } else {
-                        None
-                    }
+                    Some(WorkspaceSymbol {
+                        name: ent.describe(),
+                        kind: to_symbol_kind(ent.kind()),
+                        tags: None,
+                        container_name: ent.parent.map(|ent| ent.path_name()),
+                        location: OneOf::Left(srcpos_to_location(decl_pos)),
+                        data: None,
+                    })
                 })
                 .take(trunc_limit)
                 .collect(),
@@ -593,16 +1398,11 @@ impl VHDLServer {
     }
 
     pub fn document_symbol(&self, params: &DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
 
         // Some files are mapped to multiple libraries, only use the first library for document symbols
-        let library_name = self
-            .project
-            .library_mapping_of(&source)
-            .into_iter()
-            .next()?;
+        let library_name = project.library_mapping_of(&source).into_iter().next()?;
 
         if self.client_has_hierarchical_document_symbol_support() {
             fn to_document_symbol(
@@ -632,7 +1432,7 @@ impl VHDLServer {
             }
 
             Some(DocumentSymbolResponse::Nested(
-                self.project
+                project
                     .document_symbols(&library_name, &source)
                     .into_iter()
                     .filter_map(to_document_symbol)
@@ -653,7 +1453,7 @@ impl VHDLServer {
             }
 
             Some(DocumentSymbolResponse::Flat(
-                self.project
+                project
                     .document_symbols(&library_name, &source)
                     .into_iter()
                     .flat_map(|ent| ent.into_flat())
@@ -664,39 +1464,72 @@ impl VHDLServer {
     }
 
     pub fn text_document_hover(&mut self, params: &TextDocumentPositionParams) -> Option<Hover> {
-        let source = self
-            .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
-        let ent = self
-            .project
-            .find_declaration(&source, from_lsp_pos(params.position))?;
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let ent = project.find_declaration(&source, from_lsp_pos(params.position))?;
 
-        let value = self.project.format_declaration(ent)?;
+        let info = project.hover_info(ent)?;
 
         Some(Hover {
             contents: HoverContents::Markup(MarkupContent {
                 kind: MarkupKind::Markdown,
-                value: format!("```vhdl\n{value}\n```"),
+                value: hover_to_markdown(&info),
             }),
             range: None,
         })
     }
 
+    pub fn text_document_inlay_hint(&mut self, params: &InlayHintParams) -> Vec<InlayHint> {
+        let uri = &params.text_document.uri;
+        let Some(project) = self.project_of(uri) else {
+            return Vec::new();
+        };
+        let Some(source) = project.get_source(&uri_to_file_name(uri)) else {
+            return Vec::new();
+        };
+        let settings = InlayHintSettings::from_initialization_options(
+            self.init_params
+                .as_ref()
+                .and_then(|init_params| init_params.initialization_options.as_ref()),
+        );
+
+        project
+            .inlay_hints(&source, from_lsp_range(params.range))
+            .into_iter()
+            .filter(|hint| settings.is_enabled(hint.kind))
+            .map(|hint| InlayHint {
+                position: to_lsp_pos(hint.pos),
+                padding_left: Some(hint.kind == InlayHintKind::EndLabel),
+                padding_right: Some(hint.kind == InlayHintKind::FormalName),
+                label: InlayHintLabel::String(hint.label),
+                kind: match hint.kind {
+                    InlayHintKind::FormalName => Some(lsp_types::InlayHintKind::PARAMETER),
+                    InlayHintKind::Type => Some(lsp_types::InlayHintKind::TYPE),
+                    InlayHintKind::EndLabel => None,
+                },
+                text_edits: None,
+                tooltip: None,
+                data: None,
+            })
+            .collect()
+    }
+
     pub fn text_document_references(&mut self, params: &ReferenceParams) -> Vec<Location> {
-        let ent = self
-            .project
-            .get_source(&uri_to_file_name(
-                &params.text_document_position.text_document.uri,
-            ))
+        let uri = &params.text_document_position.text_document.uri;
+        let Some(project) = self.project_of(uri) else {
+            return Vec::new();
+        };
+        let ent = project
+            .get_source(&uri_to_file_name(uri))
             .and_then(|source| {
-                self.project.find_declaration(
+                project.find_declaration(
                     &source,
                     from_lsp_pos(params.text_document_position.position),
                 )
             });
 
         if let Some(ent) = ent {
-            self.project
+            project
                 .find_all_references(ent)
                 .iter()
                 .map(srcpos_to_location)
@@ -706,6 +1539,393 @@ impl VHDLServer {
         }
     }
 
+    pub fn text_document_document_highlight(
+        &mut self,
+        params: &DocumentHighlightParams,
+    ) -> Option<Vec<DocumentHighlight>> {
+        let position = &params.text_document_position_params;
+        let project = self.project_of(&position.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&position.text_document.uri))?;
+
+        Some(
+            project
+                .document_highlights(&source, from_lsp_pos(position.position))
+                .into_iter()
+                .map(|highlight| DocumentHighlight {
+                    range: to_lsp_range(highlight.pos.range()),
+                    kind: Some(match highlight.kind {
+                        vhdl_lang::DocumentHighlightKind::Declaration => {
+                            DocumentHighlightKind::TEXT
+                        }
+                        vhdl_lang::DocumentHighlightKind::Read => DocumentHighlightKind::READ,
+                        vhdl_lang::DocumentHighlightKind::Write => DocumentHighlightKind::WRITE,
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    /// The code lenses of a document.
+    /// Their commands are found when they are resolved since finding all uses is expensive.
+    pub fn text_document_code_lens(&mut self, params: &CodeLensParams) -> Option<Vec<CodeLens>> {
+        let uri = &params.text_document.uri;
+        let project = self.project_of(uri)?;
+        let source = project.get_source(&uri_to_file_name(uri))?;
+
+        Some(
+            project
+                .code_lenses(&source)
+                .into_iter()
+                .map(|lens| CodeLens {
+                    range: to_lsp_range(lens.pos.range()),
+                    command: None,
+                    data: Some(serde_json::json!({
+                        "uri": uri,
+                        "kind": code_lens_kind_name(lens.kind),
+                    })),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn code_lens_resolve(&mut self, params: &CodeLens) -> CodeLens {
+        let mut lens = params.clone();
+        let resolved = params.data.as_ref().and_then(|data| {
+            let uri = Url::parse(data.get("uri")?.as_str()?).ok()?;
+            let kind = code_lens_kind(data.get("kind")?.as_str()?)?;
+            let resolved = self.resolve_code_lens(&uri, params.range, kind)?;
+            Some((uri, kind, resolved))
+        });
+
+        if let Some((uri, kind, resolved)) = resolved {
+            lens.command = Some(Command {
+                title: resolved.title,
+                command: CODE_LENS_LOCATIONS_COMMAND.to_owned(),
+                arguments: Some(vec![
+                    serde_json::json!(uri),
+                    serde_json::json!(params.range),
+                    serde_json::json!(code_lens_kind_name(kind)),
+                ]),
+            });
+        }
+        lens
+    }
+
+    fn resolve_code_lens(
+        &self,
+        uri: &Url,
+        range: lsp_types::Range,
+        kind: CodeLensKind,
+    ) -> Option<ResolvedCodeLens> {
+        let project = self.project_of(uri)?;
+        let source = project.get_source(&uri_to_file_name(uri))?;
+        project.resolve_code_lens(&vhdl_lang::CodeLens {
+            kind,
+            pos: SrcPos::new(source, from_lsp_range(range)),
+        })
+    }
+
+    /// The refactorings applicable to the selection of a document
+    pub fn text_document_code_action(
+        &mut self,
+        params: &CodeActionParams,
+    ) -> Option<CodeActionResponse> {
+        let uri = &params.text_document.uri;
+        let project = self.project_of(uri)?;
+        let source = project.get_source(&uri_to_file_name(uri))?;
+
+        // The client may only ask for some kinds, where a kind includes its sub kinds
+        let is_requested = |kind: &CodeActionKind| {
+            params.context.only.as_ref().is_none_or(|only| {
+                only.iter().any(|requested| {
+                    kind.as_str() == requested.as_str()
+                        || kind
+                            .as_str()
+                            .starts_with(&format!("{}.", requested.as_str()))
+                })
+            })
+        };
+
+        let mut actions: Vec<_> = project
+            .refactorings(&source, from_lsp_range(params.range))
+            .into_iter()
+            .filter_map(|refactoring| {
+                let kind = match refactoring.kind {
+                    RefactoringKind::ExtractConstant
+                    | RefactoringKind::ExtractSignal
+                    | RefactoringKind::ExtractProcedure => CodeActionKind::REFACTOR_EXTRACT,
+                    RefactoringKind::InlineConstant => CodeActionKind::REFACTOR_INLINE,
+                    RefactoringKind::ConvertToNamedAssociation
+                    | RefactoringKind::ConvertToPositionalAssociation
+                    | RefactoringKind::SortNamedAssociations => CodeActionKind::REFACTOR_REWRITE,
+                };
+                if !is_requested(&kind) {
+                    return None;
+                }
+
+                let mut changes: HashMap<Url, Vec<TextEdit>> = Default::default();
+                for edit in refactoring.edits {
+                    let loc = srcpos_to_location(&edit.pos);
+                    changes.entry(loc.uri).or_default().push(TextEdit {
+                        range: loc.range,
+                        new_text: edit.text,
+                    });
+                }
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: refactoring.title,
+                    kind: Some(kind),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(changes),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }))
+            })
+            .collect();
+
+        // Rename declarations to follow the naming conventions.
+        // The edits are found when the action is resolved since renaming finds all references.
+        if is_requested(&CodeActionKind::QUICKFIX) {
+            for violation in project.naming_violations(&source, from_lsp_range(params.range)) {
+                let Some(new_name) = violation.suggestion else {
+                    continue;
+                };
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Rename to '{new_name}'"),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![to_lsp_diagnostic(Diagnostic::warning(
+                        &violation.pos,
+                        violation.message,
+                    ))]),
+                    is_preferred: Some(true),
+                    data: Some(serde_json::json!({
+                        "uri": uri,
+                        "position": to_lsp_pos(violation.pos.start()),
+                        "newName": new_name,
+                    })),
+                    ..Default::default()
+                }));
+            }
+        }
+        Some(actions)
+    }
+
+    /// Find the edits of a quick fix renaming a declaration
+    pub fn code_action_resolve(&mut self, params: &CodeAction) -> CodeAction {
+        let mut action = params.clone();
+        let edit = params.data.as_ref().and_then(|data| {
+            let uri = Url::parse(data.get("uri")?.as_str()?).ok()?;
+            let position: lsp_types::Position =
+                serde_json::from_value(data.get("position")?.clone()).ok()?;
+            let new_name = data.get("newName")?.as_str()?;
+            let project = self.project_of(&uri)?;
+            let source = project.get_source(&uri_to_file_name(&uri))?;
+            let ent = project.find_declaration(&source, from_lsp_pos(position))?;
+            let positions = project.rename(ent, new_name, false).ok()?;
+
+            let mut changes: HashMap<Url, Vec<TextEdit>> = Default::default();
+            for srcpos in positions {
+                let loc = srcpos_to_location(&srcpos);
+                changes.entry(loc.uri).or_default().push(TextEdit {
+                    range: loc.range,
+                    new_text: new_name.to_owned(),
+                });
+            }
+            Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            })
+        });
+        if edit.is_some() {
+            action.edit = edit;
+        }
+        action
+    }
+
+    pub fn text_document_folding_range(
+        &mut self,
+        params: &FoldingRangeParams,
+    ) -> Option<Vec<FoldingRange>> {
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
+
+        Some(
+            project
+                .folding_ranges(&source)
+                .into_iter()
+                .map(|range| FoldingRange {
+                    start_line: range.start_line,
+                    start_character: None,
+                    end_line: range.end_line,
+                    end_character: None,
+                    kind: Some(match range.kind {
+                        vhdl_lang::FoldingRangeKind::Region => FoldingRangeKind::Region,
+                        vhdl_lang::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                        vhdl_lang::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+                    }),
+                    collapsed_text: None,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn text_document_selection_range(
+        &mut self,
+        params: &SelectionRangeParams,
+    ) -> Option<Vec<SelectionRange>> {
+        let project = self.project_of(&params.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&params.text_document.uri))?;
+
+        Some(
+            params
+                .positions
+                .iter()
+                .map(|position| {
+                    // The parent of each range is the range that contains it
+                    let mut selection: Option<SelectionRange> = None;
+                    for range in project
+                        .selection_ranges(&source, from_lsp_pos(*position))
+                        .into_iter()
+                        .rev()
+                    {
+                        selection = Some(SelectionRange {
+                            range: to_lsp_range(range),
+                            parent: selection.map(Box::new),
+                        });
+                    }
+                    // Without a surrounding node the selection is the position itself
+                    selection.unwrap_or(SelectionRange {
+                        range: lsp_types::Range::new(*position, *position),
+                        parent: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// The entity declared at the selection range of a hierarchy item
+    fn hierarchy_item_ent(
+        &self,
+        uri: &Url,
+        selection_range: &lsp_types::Range,
+    ) -> Option<EntRef<'_>> {
+        let project = self.project_of(uri)?;
+        let source = project.get_source(&uri_to_file_name(uri))?;
+        project.find_declaration(&source, from_lsp_pos(selection_range.start))
+    }
+
+    pub fn prepare_call_hierarchy(
+        &mut self,
+        params: &CallHierarchyPrepareParams,
+    ) -> Option<Vec<CallHierarchyItem>> {
+        let position = &params.text_document_position_params;
+        let project = self.project_of(&position.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&position.text_document.uri))?;
+        let ent = project.find_declaration(&source, from_lsp_pos(position.position))?;
+
+        if !is_call_hierarchy_item(ent) {
+            return None;
+        }
+        Some(vec![to_call_hierarchy_item(ent)?])
+    }
+
+    pub fn call_hierarchy_incoming_calls(
+        &mut self,
+        params: &CallHierarchyIncomingCallsParams,
+    ) -> Option<Vec<CallHierarchyIncomingCall>> {
+        let project = self.project_of(&params.item.uri)?;
+        let ent = self.hierarchy_item_ent(&params.item.uri, &params.item.selection_range)?;
+
+        Some(
+            project
+                .incoming_calls(ent)
+                .into_iter()
+                .filter_map(|calls| {
+                    Some(CallHierarchyIncomingCall {
+                        from: to_call_hierarchy_item(calls.ent)?,
+                        from_ranges: calls
+                            .positions
+                            .iter()
+                            .map(|pos| to_lsp_range(pos.range()))
+                            .collect(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn call_hierarchy_outgoing_calls(
+        &mut self,
+        params: &CallHierarchyOutgoingCallsParams,
+    ) -> Option<Vec<CallHierarchyOutgoingCall>> {
+        let project = self.project_of(&params.item.uri)?;
+        let ent = self.hierarchy_item_ent(&params.item.uri, &params.item.selection_range)?;
+
+        Some(
+            project
+                .outgoing_calls(ent)
+                .into_iter()
+                .filter_map(|calls| {
+                    Some(CallHierarchyOutgoingCall {
+                        to: to_call_hierarchy_item(calls.ent)?,
+                        from_ranges: calls
+                            .positions
+                            .iter()
+                            .map(|pos| to_lsp_range(pos.range()))
+                            .collect(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn prepare_type_hierarchy(
+        &mut self,
+        params: &TypeHierarchyPrepareParams,
+    ) -> Option<Vec<TypeHierarchyItem>> {
+        let position = &params.text_document_position_params;
+        let project = self.project_of(&position.text_document.uri)?;
+        let source = project.get_source(&uri_to_file_name(&position.text_document.uri))?;
+        let ent = project.find_declaration(&source, from_lsp_pos(position.position))?;
+
+        if !ent.kind().is_type() {
+            return None;
+        }
+        Some(vec![to_type_hierarchy_item(ent)?])
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &mut self,
+        params: &TypeHierarchySupertypesParams,
+    ) -> Option<Vec<TypeHierarchyItem>> {
+        let project = self.project_of(&params.item.uri)?;
+        let ent = self.hierarchy_item_ent(&params.item.uri, &params.item.selection_range)?;
+
+        Some(
+            project
+                .supertype(ent)
+                .and_then(to_type_hierarchy_item)
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &mut self,
+        params: &TypeHierarchySubtypesParams,
+    ) -> Option<Vec<TypeHierarchyItem>> {
+        let project = self.project_of(&params.item.uri)?;
+        let ent = self.hierarchy_item_ent(&params.item.uri, &params.item.selection_range)?;
+
+        Some(
+            project
+                .subtypes(ent)
+                .into_iter()
+                .filter_map(to_type_hierarchy_item)
+                .collect(),
+        )
+    }
+
     fn message_filter(&self) -> MessageFilter {
         MessageFilter {
             silent: self.settings.silent,
@@ -718,6 +1938,115 @@ impl VHDLServer {
     }
 }
 
+/// Restrict file operations to VHDL files
+fn vhdl_file_operation_options() -> FileOperationRegistrationOptions {
+    FileOperationRegistrationOptions {
+        filters: vec![FileOperationFilter {
+            scheme: Some("file".to_owned()),
+            pattern: FileOperationPattern {
+                glob: VHDL_FILE_PATTERN.to_owned(),
+                matches: None,
+                options: None,
+            },
+        }],
+    }
+}
+
+fn library_mappings_to_json(mappings: &[LibraryMapping]) -> serde_json::Value {
+    serde_json::Value::Array(
+        mappings
+            .iter()
+            .map(|mapping| {
+                serde_json::json!({
+                    "library": mapping.library_name,
+                    "uri": file_name_to_uri(&mapping.file_name),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn hover_to_markdown(info: &HoverInfo) -> String {
+    let mut sections = vec![format!("```vhdl\n{}\n```", info.declaration)];
+
+    if let Some(ref documentation) = info.documentation {
+        sections.push(documentation.clone());
+    }
+
+    let mut details = Vec::new();
+    if let Some(ref subtype) = info.subtype {
+        if let Some(ref base_type) = info.base_type {
+            details.push(format!("Type: `{subtype}` (base type `{base_type}`)"));
+        } else {
+            details.push(format!("Type: `{subtype}`"));
+        }
+    }
+    if let Some(ref value) = info.value {
+        details.push(format!("Value: `{value}`"));
+    }
+    if let (Some(overloads), Some(ref signature)) = (info.overloads, &info.signature) {
+        details.push(format!(
+            "Selected overload `{signature}` of {overloads} overloads"
+        ));
+    }
+    if let Some(ref declared_in) = info.declared_in {
+        details.push(format!("Declared in `{declared_in}`"));
+    }
+    if !details.is_empty() {
+        sections.push(details.join("  \n"));
+    }
+
+    if let Some(ref bound) = info.instance_of {
+        sections.push(format!("Instance of:\n```vhdl\n{}\n```", bound.declaration));
+    }
+
+    sections.join("\n\n")
+}
+
+/// Subprograms are called, processes and design units only call
+fn is_call_hierarchy_item(ent: EntRef) -> bool {
+    match ent.kind() {
+        AnyEntKind::Overloaded(overloaded) => !matches!(overloaded, Overloaded::EnumLiteral(_)),
+        AnyEntKind::Concurrent(Some(Concurrent::Process)) => true,
+        AnyEntKind::Design(design) => matches!(
+            design,
+            vhdl_lang::Design::Entity(..)
+                | vhdl_lang::Design::Architecture(_)
+                | vhdl_lang::Design::Package(..)
+                | vhdl_lang::Design::PackageBody
+        ),
+        _ => false,
+    }
+}
+
+fn to_call_hierarchy_item(ent: EntRef) -> Option<CallHierarchyItem> {
+    let decl_pos = ent.decl_pos()?;
+    Some(CallHierarchyItem {
+        name: ent.designator().to_string(),
+        kind: to_symbol_kind(ent.kind()),
+        tags: None,
+        detail: ent.parent.map(|ent| ent.path_name()),
+        uri: file_name_to_uri(decl_pos.file_name()),
+        range: to_lsp_range(decl_pos.range()),
+        selection_range: to_lsp_range(decl_pos.range()),
+        data: None,
+    })
+}
+
+fn to_type_hierarchy_item(ent: EntRef) -> Option<TypeHierarchyItem> {
+    let decl_pos = ent.decl_pos()?;
+    Some(TypeHierarchyItem {
+        name: ent.designator().to_string(),
+        kind: to_symbol_kind(ent.kind()),
+        tags: None,
+        detail: ent.parent.map(|ent| ent.path_name()),
+        uri: file_name_to_uri(decl_pos.file_name()),
+        range: to_lsp_range(decl_pos.range()),
+        selection_range: to_lsp_range(decl_pos.range()),
+        data: None,
+    })
+}
+
 fn entity_to_completion_item(ent: EntRef) -> CompletionItem {
     CompletionItem {
         label: ent.designator.to_string(),
@@ -904,7 +2233,9 @@ fn to_lsp_diagnostic(diagnostic: Diagnostic) -> lsp_types::Diagnostic {
     lsp_types::Diagnostic {
         range: to_lsp_range(diagnostic.pos.range()),
         severity: Some(severity),
-        code: None,
+        code: diagnostic
+            .code
+            .map(|code| NumberOrString::String(code.to_owned())),
         source: Some("vhdl ls".to_owned()),
         message: diagnostic.message,
         related_information,
@@ -961,6 +2292,29 @@ fn type_kind(t: &Type) -> SymbolKind {
     }
 }
 
+/// The position after the last character of a text
+fn end_of_text(text: &str) -> lsp_types::Position {
+    let (line, last_line) = text.split('\n').enumerate().last().unwrap_or_default();
+    lsp_types::Position::new(line as u32, last_line.encode_utf16().count() as u32)
+}
+
+fn code_lens_kind_name(kind: CodeLensKind) -> &'static str {
+    match kind {
+        CodeLensKind::References => "references",
+        CodeLensKind::Instantiations => "instantiations",
+        CodeLensKind::Architectures => "architectures",
+    }
+}
+
+fn code_lens_kind(name: &str) -> Option<CodeLensKind> {
+    match name {
+        "references" => Some(CodeLensKind::References),
+        "instantiations" => Some(CodeLensKind::Instantiations),
+        "architectures" => Some(CodeLensKind::Architectures),
+        _ => None,
+    }
+}
+
 fn to_symbol_kind(kind: &AnyEntKind) -> SymbolKind {
     match kind {
         AnyEntKind::ExternalAlias { class, .. } => object_class_kind(ObjectClass::from(*class)),
@@ -1089,55 +2443,551 @@ end entity ent;
     }
 
     #[test]
-    fn did_open_with_diagnostics_and_change_without() {
+    fn inlay_hints_can_be_disabled_by_initialization_options() {
         let (mock, mut server) = setup_server();
-
         let (_tempdir, root_uri) = temp_root_uri();
         expect_missing_config_messages(&mock);
         initialize_server(&mut server, root_uri.clone());
 
         let file_url = root_uri.join("ent.vhd").unwrap();
-        let code = "
-entity ent is
-end entity ent2;
-"
-        .to_owned();
-
         let did_open = DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
                 uri: file_url.clone(),
                 language_id: "vhdl".to_owned(),
                 version: 0,
-                text: code,
+                text: "entity ent is\nend;\n".to_owned(),
             },
         };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
 
-        let publish_diagnostics = PublishDiagnosticsParams {
-            uri: file_url.clone(),
-            diagnostics: vec![lsp_types::Diagnostic {
-                range: Range {
-                    start: lsp_types::Position {
-                        line: 2,
-                        character: "end entity ".len() as u32,
-                    },
-                    end: lsp_types::Position {
-                        line: 2,
-                        character: "end entity ent2".len() as u32,
-                    },
-                },
-                code: None,
-                severity: Some(DiagnosticSeverity::ERROR),
-                source: Some("vhdl ls".to_owned()),
-                message: "End identifier mismatch, expected ent".to_owned(),
-                ..Default::default()
-            }],
-            version: None,
+        let params = InlayHintParams {
+            text_document: TextDocumentIdentifier { uri: file_url },
+            range: lsp_types::Range::new(
+                lsp_types::Position::new(0, 0),
+                lsp_types::Position::new(2, 0),
+            ),
+            work_done_progress_params: Default::default(),
         };
+        let hints = server.text_document_inlay_hint(&params);
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].position, lsp_types::Position::new(1, 3));
+        assert!(
+            matches!(hints[0].label, InlayHintLabel::String(ref label) if label == "entity ent")
+        );
+        assert_eq!(hints[0].padding_left, Some(true));
 
-        mock.expect_warning_contains("is not part of the project");
+        server.init_params.as_mut().unwrap().initialization_options =
+            Some(serde_json::json!({"inlayHints": {"endLabels": false}}));
+        assert!(server.text_document_inlay_hint(&params).is_empty());
+    }
 
-        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
-        server.text_document_did_open_notification(&did_open);
+    #[test]
+    fn call_hierarchy_items_resolve_through_their_selection_range() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("pkg.vhd").unwrap();
+        let code = "\
+package pkg is
+  procedure tick;
+end package;
+
+package body pkg is
+  procedure tick is
+  begin
+  end procedure;
+
+  procedure twice is
+  begin
+    tick;
+    tick;
+  end procedure;
+end package body;
+";
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: code.to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let position = |line, character| TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier {
+                uri: file_url.clone(),
+            },
+            position: lsp_types::Position::new(line, character),
+        };
+
+        let items = server
+            .prepare_call_hierarchy(&CallHierarchyPrepareParams {
+                text_document_position_params: position(1, 12),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "tick");
+
+        let incoming = server
+            .call_hierarchy_incoming_calls(&CallHierarchyIncomingCallsParams {
+                item: items[0].clone(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from.name, "twice");
+        assert_eq!(
+            incoming[0].from_ranges,
+            vec![
+                lsp_types::Range::new(
+                    lsp_types::Position::new(11, 4),
+                    lsp_types::Position::new(11, 8)
+                ),
+                lsp_types::Range::new(
+                    lsp_types::Position::new(12, 4),
+                    lsp_types::Position::new(12, 8)
+                ),
+            ]
+        );
+
+        let outgoing = server
+            .call_hierarchy_outgoing_calls(&CallHierarchyOutgoingCallsParams {
+                item: incoming[0].from.clone(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "tick");
+    }
+
+    #[test]
+    fn document_highlight_folding_and_selection_ranges() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "-- first\n-- second\nentity ent is\nbegin\nend entity ent;\n".to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+        let text_document = TextDocumentIdentifier {
+            uri: file_url.clone(),
+        };
+        let range = |start_line, start_character, end_line, end_character| {
+            lsp_types::Range::new(
+                lsp_types::Position::new(start_line, start_character),
+                lsp_types::Position::new(end_line, end_character),
+            )
+        };
+
+        let highlights = server
+            .text_document_document_highlight(&DocumentHighlightParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: text_document.clone(),
+                    position: lsp_types::Position::new(2, 8),
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(
+            highlights,
+            vec![
+                DocumentHighlight {
+                    range: range(2, 7, 2, 10),
+                    kind: Some(DocumentHighlightKind::TEXT),
+                },
+                DocumentHighlight {
+                    range: range(4, 11, 4, 14),
+                    kind: Some(DocumentHighlightKind::TEXT),
+                },
+            ]
+        );
+
+        let folding_ranges = server
+            .text_document_folding_range(&FoldingRangeParams {
+                text_document: text_document.clone(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        let folded: Vec<_> = folding_ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line, range.kind.clone()))
+            .collect();
+        assert_eq!(
+            folded,
+            vec![
+                (0, 1, Some(FoldingRangeKind::Comment)),
+                (2, 3, Some(FoldingRangeKind::Region)),
+            ]
+        );
+
+        let selection_ranges = server
+            .text_document_selection_range(&SelectionRangeParams {
+                text_document,
+                positions: vec![
+                    lsp_types::Position::new(2, 8),
+                    lsp_types::Position::new(5, 0),
+                ],
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(
+            selection_ranges,
+            vec![
+                SelectionRange {
+                    range: range(2, 0, 4, 15),
+                    parent: None,
+                },
+                SelectionRange {
+                    range: range(5, 0, 5, 0),
+                    parent: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn code_lenses_are_resolved_lazily() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "entity ent is\nend entity;\n\narchitecture rtl of ent is\nbegin\nend architecture;\n"
+                    .to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let lenses = server
+            .text_document_code_lens(&CodeLensParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_url.clone(),
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(lenses.len(), 2);
+        assert!(lenses.iter().all(|lens| lens.command.is_none()));
+
+        let instantiations = server.code_lens_resolve(&lenses[0]).command.unwrap();
+        assert_eq!(instantiations.title, "instantiated 0 times");
+        assert_eq!(instantiations.command, CODE_LENS_LOCATIONS_COMMAND);
+
+        let architectures = server.code_lens_resolve(&lenses[1]).command.unwrap();
+        assert_eq!(architectures.title, "architectures: rtl");
+        assert_eq!(architectures.command, CODE_LENS_LOCATIONS_COMMAND);
+
+        // The locations are found by the server when the command is executed
+        let mut locations = |command: Command| {
+            server.workspace_execute_command(&ExecuteCommandParams {
+                command: command.command,
+                arguments: command.arguments.unwrap(),
+                work_done_progress_params: Default::default(),
+            })
+        };
+        assert_eq!(locations(instantiations), Some(serde_json::json!([])));
+        assert_eq!(
+            locations(architectures),
+            Some(serde_json::json!([Location {
+                uri: file_url,
+                range: lsp_types::Range::new(
+                    lsp_types::Position::new(3, 13),
+                    lsp_types::Position::new(3, 16)
+                ),
+            }]))
+        );
+    }
+
+    #[test]
+    fn code_actions_perform_refactorings() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "entity ent is\nend entity;\n\narchitecture rtl of ent is\nbegin\n  main : process\n  begin\n    null;\n    wait;\n  end process;\nend architecture;\n"
+                    .to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let code_actions = |server: &mut VHDLServer, only: Option<Vec<CodeActionKind>>| {
+            server
+                .text_document_code_action(&CodeActionParams {
+                    text_document: TextDocumentIdentifier {
+                        uri: file_url.clone(),
+                    },
+                    range: lsp_types::Range::new(
+                        lsp_types::Position::new(7, 4),
+                        lsp_types::Position::new(7, 9),
+                    ),
+                    context: CodeActionContext {
+                        only,
+                        ..Default::default()
+                    },
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                })
+                .unwrap()
+        };
+
+        let actions = code_actions(&mut server, None);
+        assert_eq!(actions.len(), 1);
+        let CodeActionOrCommand::CodeAction(ref action) = actions[0] else {
+            panic!("Expected a code action");
+        };
+        assert_eq!(action.title, "Extract procedure");
+        assert_eq!(action.kind, Some(CodeActionKind::REFACTOR_EXTRACT));
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        assert_eq!(
+            changes[&file_url],
+            vec![
+                TextEdit {
+                    range: lsp_types::Range::new(
+                        lsp_types::Position::new(6, 2),
+                        lsp_types::Position::new(6, 2)
+                    ),
+                    new_text: "  procedure new_procedure is\n    begin\n      null;\n    end procedure;\n  "
+                        .to_owned(),
+                },
+                TextEdit {
+                    range: lsp_types::Range::new(
+                        lsp_types::Position::new(7, 4),
+                        lsp_types::Position::new(7, 9)
+                    ),
+                    new_text: "new_procedure;".to_owned(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            code_actions(&mut server, Some(vec![CodeActionKind::REFACTOR_INLINE])),
+            vec![]
+        );
+        assert_eq!(
+            code_actions(&mut server, Some(vec![CodeActionKind::REFACTOR])).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn rename_reports_invalid_names() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "entity ent is\nend entity ent;\n".to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let rename_params = |new_name: &str| RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_url.clone(),
+                },
+                position: lsp_types::Position::new(0, 8),
+            },
+            new_name: new_name.to_owned(),
+            work_done_progress_params: Default::default(),
+        };
+
+        assert_eq!(
+            server.rename(&rename_params("signal")),
+            Err("'signal' is a reserved word".to_owned())
+        );
+
+        let edit = server.rename(&rename_params("top")).unwrap().unwrap();
+        let edits = edit.changes.unwrap().remove(&file_url).unwrap();
+        assert_eq!(
+            edits
+                .iter()
+                .map(|edit| edit.range.start)
+                .collect::<Vec<_>>(),
+            vec![
+                lsp_types::Position::new(0, 7),
+                lsp_types::Position::new(1, 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn snippets_are_completed_when_supported_by_the_client() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "entity ent is\nend entity ent;\n\n".to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: file_url },
+                position: lsp_types::Position::new(2, 0),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        };
+        let is_snippet = |item: &CompletionItem| item.kind == Some(CompletionItemKind::SNIPPET);
+
+        assert!(!server
+            .request_completion(&params)
+            .items
+            .iter()
+            .any(is_snippet));
+
+        server
+            .init_params
+            .as_mut()
+            .unwrap()
+            .capabilities
+            .text_document = Some(TextDocumentClientCapabilities {
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    snippet_support: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let items = server.request_completion(&params).items;
+        let entity = items
+            .iter()
+            .find(|item| is_snippet(item) && item.label == "entity")
+            .unwrap();
+        assert_eq!(entity.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert!(entity
+            .insert_text
+            .as_ref()
+            .unwrap()
+            .starts_with("entity ${1:name} is"));
+        assert!(!items.iter().any(|item| item.label == "process clocked"));
+    }
+
+    #[test]
+    fn inlay_hint_settings_default_to_enabled() {
+        let all = InlayHintSettings {
+            formal_names: true,
+            types: true,
+            end_labels: true,
+        };
+        assert_eq!(InlayHintSettings::from_initialization_options(None), all);
+        assert_eq!(
+            InlayHintSettings::from_initialization_options(Some(&serde_json::json!({
+                "inlayHints": {"types": false, "endLabels": "no"}
+            }))),
+            InlayHintSettings {
+                types: false,
+                ..all
+            }
+        );
+    }
+
+    #[test]
+    fn did_open_with_diagnostics_and_change_without() {
+        let (mock, mut server) = setup_server();
+
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let code = "
+entity ent is
+end entity ent2;
+"
+        .to_owned();
+
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: code,
+            },
+        };
+
+        let publish_diagnostics = PublishDiagnosticsParams {
+            uri: file_url.clone(),
+            diagnostics: vec![lsp_types::Diagnostic {
+                range: Range {
+                    start: lsp_types::Position {
+                        line: 2,
+                        character: "end entity ".len() as u32,
+                    },
+                    end: lsp_types::Position {
+                        line: 2,
+                        character: "end entity ent2".len() as u32,
+                    },
+                },
+                code: None,
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("vhdl ls".to_owned()),
+                message: "End identifier mismatch, expected ent".to_owned(),
+                ..Default::default()
+            }],
+            version: None,
+        };
+
+        mock.expect_warning_contains("is not part of the project");
+
+        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
+        server.text_document_did_open_notification(&did_open);
 
         let code = "
 entity ent is
@@ -1167,6 +3017,356 @@ end entity ent;
         server.text_document_did_change_notification(&did_change);
     }
 
+    fn missing_entity_diagnostics(uri: Url) -> PublishDiagnosticsParams {
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics: vec![lsp_types::Diagnostic {
+                range: Range {
+                    start: lsp_types::Position {
+                        line: 0,
+                        character: "architecture rtl of ".len() as u32,
+                    },
+                    end: lsp_types::Position {
+                        line: 0,
+                        character: "architecture rtl of ent".len() as u32,
+                    },
+                },
+                code: None,
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("vhdl ls".to_owned()),
+                message: "No primary unit \'ent\' within library \'lib\'".to_owned(),
+                ..Default::default()
+            }],
+            version: None,
+        }
+    }
+
+    #[test]
+    fn did_close_reverts_to_contents_on_disk() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(
+            &root_uri,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let ent_uri = write_file(
+            &root_uri,
+            "ent.vhd",
+            "\
+entity ent is
+end entity;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['*.vhd']
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri);
+
+        // Unsaved edit removing the entity
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            missing_entity_diagnostics(file_uri.clone()),
+        );
+        server.text_document_did_open_notification(&DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: ent_uri.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "".to_owned(),
+            },
+        });
+
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri: file_uri,
+                diagnostics: vec![],
+                version: None,
+            },
+        );
+        server.text_document_did_close_notification(&DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri: ent_uri },
+        });
+    }
+
+    #[test]
+    fn workspace_symbols_of_all_folders_are_ranked_together() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir_a, root_uri_a) = temp_root_uri();
+        let (_tempdir_b, root_uri_b) = temp_root_uri();
+        write_file(&root_uri_a, "ent.vhd", "entity unnamed is\nend entity;\n");
+        let config_uri_a = write_config(&root_uri_a, "[libraries]\nlib.files = ['*.vhd']\n");
+        write_file(&root_uri_b, "ent.vhd", "entity named is\nend entity;\n");
+        let config_uri_b = write_config(&root_uri_b, "[libraries]\nlib.files = ['*.vhd']\n");
+
+        expect_loaded_config_messages(&mock, &config_uri_a);
+        expect_loaded_config_messages(&mock, &config_uri_b);
+        #[allow(deprecated)]
+        let initialize_params = InitializeParams {
+            root_uri: Some(root_uri_a.clone()),
+            workspace_folders: Some(vec![
+                WorkspaceFolder {
+                    uri: root_uri_a,
+                    name: "a".to_owned(),
+                },
+                WorkspaceFolder {
+                    uri: root_uri_b,
+                    name: "b".to_owned(),
+                },
+            ]),
+            ..Default::default()
+        };
+        server.initialize_request(initialize_params);
+        server.initialized_notification();
+
+        let Some(WorkspaceSymbolResponse::Nested(symbols)) =
+            server.workspace_symbol(&WorkspaceSymbolParams {
+                query: "named".to_owned(),
+                ..Default::default()
+            })
+        else {
+            panic!("Expected nested workspace symbols");
+        };
+        let names: Vec<_> = symbols.into_iter().map(|symbol| symbol.name).collect();
+        assert_eq!(names, vec!["entity 'named'", "entity 'unnamed'"]);
+    }
+
+    #[test]
+    fn workspace_folders_have_separate_projects() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir_a, root_uri_a) = temp_root_uri();
+        let (_tempdir_b, root_uri_b) = temp_root_uri();
+        let file_uri_a = write_file(
+            &root_uri_a,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let config_uri_a = write_config(
+            &root_uri_a,
+            "
+[libraries]
+lib.files = ['*.vhd']
+",
+        );
+        let ent_uri_b = write_file(
+            &root_uri_b,
+            "ent.vhd",
+            "\
+entity ent is
+end entity;
+",
+        );
+        let file_uri_b = write_file(
+            &root_uri_b,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let config_uri_b = write_config(
+            &root_uri_b,
+            "
+[libraries]
+lib.files = ['*.vhd']
+",
+        );
+
+        // The entity of the second folder is not visible in the first folder
+        expect_loaded_config_messages(&mock, &config_uri_a);
+        expect_loaded_config_messages(&mock, &config_uri_b);
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            missing_entity_diagnostics(file_uri_a.clone()),
+        );
+        #[allow(deprecated)]
+        let initialize_params = InitializeParams {
+            root_uri: Some(root_uri_a.clone()),
+            workspace_folders: Some(vec![
+                WorkspaceFolder {
+                    uri: root_uri_a.clone(),
+                    name: "a".to_owned(),
+                },
+                WorkspaceFolder {
+                    uri: root_uri_b.clone(),
+                    name: "b".to_owned(),
+                },
+            ]),
+            ..Default::default()
+        };
+        server.initialize_request(initialize_params);
+        server.initialized_notification();
+
+        let definition = server.text_document_definition(&TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: file_uri_b },
+            position: lsp_types::Position {
+                line: 0,
+                character: "architecture rtl of e".len() as u32,
+            },
+        });
+        assert_eq!(definition.map(|location| location.uri), Some(ent_uri_b));
+
+        // Diagnostics of a removed folder are cleared
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri: file_uri_a,
+                diagnostics: vec![],
+                version: None,
+            },
+        );
+        server.workspace_did_change_workspace_folders(&DidChangeWorkspaceFoldersParams {
+            event: WorkspaceFoldersChangeEvent {
+                added: vec![],
+                removed: vec![WorkspaceFolder {
+                    uri: root_uri_a,
+                    name: "a".to_owned(),
+                }],
+            },
+        });
+        assert_eq!(server.folders.len(), 1);
+    }
+
+    #[test]
+    fn files_of_several_folders_are_published_by_the_serving_folder() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir_a, root_uri_a) = temp_root_uri();
+        let (_tempdir_b, root_uri_b) = temp_root_uri();
+        let file_uri_b = write_file(
+            &root_uri_b,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let file_name_b = file_uri_b.to_file_path().unwrap();
+        let config_uri_a = write_config(
+            &root_uri_a,
+            format!(
+                "
+[libraries]
+lib.files = ['{}']
+",
+                file_name_b.display()
+            ),
+        );
+        let config_uri_b = write_config(
+            &root_uri_b,
+            "
+[libraries]
+lib.files = ['*.vhd']
+",
+        );
+
+        // Both folders report the missing entity, only the folder containing the file publishes it
+        expect_loaded_config_messages(&mock, &config_uri_a);
+        expect_loaded_config_messages(&mock, &config_uri_b);
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            missing_entity_diagnostics(file_uri_b),
+        );
+        #[allow(deprecated)]
+        let initialize_params = InitializeParams {
+            root_uri: Some(root_uri_a.clone()),
+            workspace_folders: Some(vec![
+                WorkspaceFolder {
+                    uri: root_uri_a,
+                    name: "a".to_owned(),
+                },
+                WorkspaceFolder {
+                    uri: root_uri_b,
+                    name: "b".to_owned(),
+                },
+            ]),
+            ..Default::default()
+        };
+        server.initialize_request(initialize_params);
+        server.initialized_notification();
+        assert!(server.folders[0].files_with_notifications.is_empty());
+    }
+
+    #[test]
+    fn watched_files_are_added_and_removed() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(
+            &root_uri,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['*.vhd']
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            missing_entity_diagnostics(file_uri.clone()),
+        );
+        initialize_server(&mut server, root_uri.clone());
+
+        let ent_uri = write_file(
+            &root_uri,
+            "ent.vhd",
+            "\
+entity ent is
+end entity;
+",
+        );
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri: file_uri.clone(),
+                diagnostics: vec![],
+                version: None,
+            },
+        );
+        server.workspace_did_change_watched_files(&DidChangeWatchedFilesParams {
+            changes: vec![FileEvent {
+                typ: FileChangeType::CREATED,
+                uri: ent_uri.clone(),
+            }],
+        });
+
+        std::fs::remove_file(ent_uri.to_file_path().unwrap()).unwrap();
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            missing_entity_diagnostics(file_uri),
+        );
+        server.workspace_did_change_watched_files(&DidChangeWatchedFilesParams {
+            changes: vec![FileEvent {
+                typ: FileChangeType::DELETED,
+                uri: ent_uri,
+            }],
+        });
+    }
+
     fn write_file(root_uri: &Url, file_name: impl AsRef<str>, contents: impl AsRef<str>) -> Url {
         let path = root_uri.to_file_path().unwrap().join(file_name.as_ref());
         std::fs::write(&path, contents.as_ref()).unwrap();
@@ -1226,9 +3426,120 @@ lib.files = [
             version: None,
         };
 
-        expect_loaded_config_messages(&mock, &config_uri);
-        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
+        expect_loaded_config_messages(&mock, &config_uri);
+        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
+
+        initialize_server(&mut server, root_uri);
+    }
+
+    #[test]
+    fn code_actions_rename_declarations_to_follow_naming_conventions() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(
+            &root_uri,
+            "file.vhd",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+  main : process
+  begin
+    wait;
+  end process main;
+end architecture;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['file.vhd']
+
+[naming]
+label = '^p_'
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        mock.expect_notification_contains(
+            "textDocument/publishDiagnostics",
+            "Label 'main' does not match the naming convention '^p_'",
+        );
+        initialize_server(&mut server, root_uri);
+
+        let cursor = lsp_types::Position::new(5, 3);
+        let actions = server
+            .text_document_code_action(&CodeActionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri.clone(),
+                },
+                range: lsp_types::Range::new(cursor, cursor),
+                context: CodeActionContext {
+                    only: Some(vec![CodeActionKind::QUICKFIX]),
+                    ..Default::default()
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        let CodeActionOrCommand::CodeAction(ref action) = actions[0] else {
+            panic!("Expected a code action");
+        };
+        assert_eq!(action.title, "Rename to 'p_main'");
+        assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+        assert!(action.edit.is_none());
+
+        let action = server.code_action_resolve(action);
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        assert_eq!(
+            changes[&file_uri]
+                .iter()
+                .map(|edit| (edit.range.start, edit.new_text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (lsp_types::Position::new(5, 2), "p_main"),
+                (lsp_types::Position::new(8, 14), "p_main"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_diagnostics_have_the_code_of_their_rule() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        write_file(
+            &root_uri,
+            "file.vhd",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+  process
+  begin
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['file.vhd']
 
+[lint]
+unlabeled_process = 'info'
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        mock.expect_notification_contains("textDocument/publishDiagnostics", "unlabeled_process");
         initialize_server(&mut server, root_uri);
     }
 
@@ -1360,10 +3671,16 @@ lib.files = [
         );
 
         let register_options = DidChangeWatchedFilesRegistrationOptions {
-            watchers: vec![FileSystemWatcher {
-                glob_pattern: GlobPattern::String("**/vhdl_ls.toml".to_owned()),
-                kind: None,
-            }],
+            watchers: vec![
+                FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/vhdl_ls.toml".to_owned()),
+                    kind: None,
+                },
+                FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/*.vhd{,l}".to_owned()),
+                    kind: None,
+                },
+            ],
         };
         let register_capability = RegistrationParams {
             registrations: vec![Registration {
@@ -1487,4 +3804,436 @@ lib.files = [
             }],
         });
     }
+
+    #[test]
+    fn renaming_files_replaces_them_in_the_configuration() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(&root_uri, "pkg.vhd", "package pkg is\nend package;\n");
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['pkg.vhd']
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri.clone());
+
+        let root = root_uri.to_file_path().unwrap();
+        let rename = |old_uri: &Url| RenameFilesParams {
+            files: vec![FileRename {
+                old_uri: old_uri.to_string(),
+                new_uri: Url::from_file_path(root.join("renamed.vhd"))
+                    .unwrap()
+                    .to_string(),
+            }],
+        };
+        let edit = server
+            .workspace_will_rename_files(&rename(&file_uri))
+            .unwrap();
+        assert_eq!(
+            edit.changes.unwrap()[&config_uri],
+            vec![TextEdit {
+                range: lsp_types::Range::new(
+                    lsp_types::Position::new(0, 0),
+                    lsp_types::Position::new(3, 0)
+                ),
+                new_text: "
+[libraries]
+lib.files = [\"renamed.vhd\"]
+"
+                .to_owned(),
+            }]
+        );
+
+        // Files that are not listed by name are not replaced
+        let other_uri = Url::from_file_path(root.join("other.vhd")).unwrap();
+        assert_eq!(
+            server.workspace_will_rename_files(&rename(&other_uri)),
+            None
+        );
+    }
+
+    #[test]
+    fn add_library_mappings_command() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        write_file(
+            &root_uri,
+            "pkg.vhd",
+            "\
+package pkg is
+end package;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['pkg.vhd']
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri.clone());
+
+        let code = "\
+use work.pkg.all;
+entity ent is
+end entity;
+"
+        .to_owned();
+        let file_uri = write_file(&root_uri, "ent.vhd", &code);
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_uri.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: code,
+            },
+        };
+
+        mock.expect_warning_contains("is not part of the project");
+        mock.expect_notification_contains(
+            "textDocument/publishDiagnostics",
+            "No primary unit 'pkg' within library 'work'",
+        );
+        server.text_document_did_open_notification(&did_open);
+
+        let params = |command: &str| ExecuteCommandParams {
+            command: command.to_owned(),
+            arguments: vec![serde_json::to_value(&file_uri).unwrap()],
+            work_done_progress_params: Default::default(),
+        };
+        let expected = serde_json::json!([{"library": "lib", "uri": file_uri}]);
+        assert_eq!(
+            server.workspace_execute_command(&params(SUGGEST_LIBRARY_MAPPINGS_COMMAND)),
+            Some(expected.clone())
+        );
+
+        mock.expect_message_contains("to library lib");
+        expect_loaded_config_messages(&mock, &config_uri);
+        mock.expect_notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri: file_uri.clone(),
+                diagnostics: vec![],
+                version: None,
+            },
+        );
+        assert_eq!(
+            server.workspace_execute_command(&params(ADD_LIBRARY_MAPPINGS_COMMAND)),
+            Some(expected)
+        );
+
+        let config = std::fs::read_to_string(config_uri.to_file_path().unwrap()).unwrap();
+        assert!(config.contains("lib.files = ['pkg.vhd', \"ent.vhd\"]"));
+    }
+
+    /// Create RpcMock and VHDLServer analysing in the background without debouncing
+    fn setup_background_server() -> (Rc<RpcMock>, VHDLServer) {
+        let (mock, mut server) = setup_server();
+        server.analysis_debounce = Some(Duration::ZERO);
+        (mock, server)
+    }
+
+    /// Run the background analysis until all changes have been analysed
+    fn wait_for_analysis(server: &mut VHDLServer) {
+        let events = server.analysis_events();
+        loop {
+            server.start_analysis();
+            let is_running = server
+                .folders
+                .iter()
+                .any(|folder| folder.analysis.as_ref().unwrap().is_running());
+            if is_running {
+                server.analysis_event(events.recv().unwrap());
+            } else if server.analysis_deadline().is_none() {
+                break;
+            }
+        }
+    }
+
+    fn load_progress(progress: WorkDoneProgress) -> ProgressParams {
+        ProgressParams {
+            token: NumberOrString::String(format!("{LOAD_PROGRESS_TOKEN}/0")),
+            value: ProgressParamsValue::WorkDone(progress),
+        }
+    }
+
+    #[test]
+    fn background_analysis_reports_load_progress() {
+        let (mock, mut server) = setup_background_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(
+            &root_uri,
+            "file.vhd",
+            "\
+architecture rtl of ent is
+begin
+end;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = [
+  'file.vhd'
+]
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        let capabilities = ClientCapabilities {
+            window: Some(WindowClientCapabilities {
+                work_done_progress: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        #[allow(deprecated)]
+        let initialize_params = InitializeParams {
+            root_uri: Some(root_uri),
+            capabilities,
+            ..Default::default()
+        };
+        server.initialize_request(initialize_params);
+        server.initialized_notification();
+
+        mock.expect_request(
+            "window/workDoneProgress/create",
+            WorkDoneProgressCreateParams {
+                token: NumberOrString::String(format!("{LOAD_PROGRESS_TOKEN}/0")),
+            },
+        );
+        mock.expect_notification(
+            "$/progress",
+            load_progress(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: "Loading project".to_owned(),
+                cancellable: Some(false),
+                ..Default::default()
+            })),
+        );
+        for message in ["Loading files", "Analysing design units"] {
+            mock.expect_notification(
+                "$/progress",
+                load_progress(WorkDoneProgress::Report(WorkDoneProgressReport {
+                    message: Some(message.to_owned()),
+                    ..Default::default()
+                })),
+            );
+        }
+        mock.expect_notification(
+            "$/progress",
+            load_progress(WorkDoneProgress::End(WorkDoneProgressEnd { message: None })),
+        );
+        mock.expect_notification_contains("textDocument/publishDiagnostics", file_uri.as_str());
+        wait_for_analysis(&mut server);
+    }
+
+    #[test]
+    fn background_analysis_of_rapid_changes() {
+        let (mock, mut server) = setup_background_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+        wait_for_analysis(&mut server);
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let did_open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "
+entity ent is
+end entity ent2;
+"
+                .to_owned(),
+            },
+        };
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&did_open);
+
+        let did_change = |version: i32, text: &str| DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: file_url.clone(),
+                version,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_owned(),
+            }],
+        };
+
+        // The erroneous version is never analysed since it is changed before the analysis starts
+        server.text_document_did_change_notification(&did_change(
+            1,
+            "
+entity ent is
+end entity ent;
+",
+        ));
+        wait_for_analysis(&mut server);
+
+        server.text_document_did_change_notification(&did_change(
+            2,
+            "
+entity ent is
+end entity ent3;
+",
+        ));
+        server.text_document_did_change_notification(&did_change(
+            3,
+            "
+entity ent is
+end entity ent4;
+",
+        ));
+        mock.expect_notification_contains(
+            "textDocument/publishDiagnostics",
+            "End identifier mismatch, expected ent",
+        );
+        wait_for_analysis(&mut server);
+
+        // Requests are served from the analysed project
+        let declaration = server.text_document_declaration(&TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier {
+                uri: file_url.clone(),
+            },
+            position: lsp_types::Position {
+                line: 1,
+                character: "entity ".len() as u32,
+            },
+        });
+        assert_eq!(
+            declaration.map(|location| location.uri),
+            Some(file_url.clone())
+        );
+    }
+
+    #[test]
+    fn changes_not_yet_analysed_take_precedence_over_the_analysed_project() {
+        let (mock, mut server) = setup_background_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        expect_missing_config_messages(&mock);
+        initialize_server(&mut server, root_uri.clone());
+        wait_for_analysis(&mut server);
+
+        let file_url = root_uri.join("ent.vhd").unwrap();
+        let file_name = uri_to_file_name(&file_url);
+        mock.expect_warning_contains("is not part of the project");
+        server.text_document_did_open_notification(&DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: file_url.clone(),
+                language_id: "vhdl".to_owned(),
+                version: 0,
+                text: "entity ent is\nend entity;\n".to_owned(),
+            },
+        });
+        wait_for_analysis(&mut server);
+
+        server.text_document_did_change_notification(&DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: file_url.clone(),
+                version: 1,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "entity ent2 is\nend entity;\n".to_owned(),
+            }],
+        });
+        let first_line = |source: Option<Source>| {
+            source
+                .unwrap()
+                .contents()
+                .get_line(0)
+                .unwrap()
+                .trim()
+                .to_owned()
+        };
+        assert_eq!(
+            first_line(server.get_source(0, &file_name)),
+            "entity ent2 is"
+        );
+        assert_eq!(
+            first_line(server.folders[0].project.get_source(&file_name)),
+            "entity ent is"
+        );
+
+        wait_for_analysis(&mut server);
+        assert_eq!(
+            first_line(server.folders[0].project.get_source(&file_name)),
+            "entity ent2 is"
+        );
+    }
+
+    #[test]
+    fn hover_markdown_lists_known_details() {
+        let info = HoverInfo {
+            declaration: "constant width : byte := 8;".to_owned(),
+            documentation: Some("The width of the bus".to_owned()),
+            subtype: Some("byte".to_owned()),
+            base_type: Some("INTEGER".to_owned()),
+            value: Some("8".to_owned()),
+            declared_in: Some("lib.pkg".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hover_to_markdown(&info),
+            "\
+```vhdl
+constant width : byte := 8;
+```
+
+The width of the bus
+
+Type: `byte` (base type `INTEGER`)  
+Value: `8`  
+Declared in `lib.pkg`"
+        );
+
+        let info = HoverInfo {
+            declaration: "function f(arg : boolean) return integer;".to_owned(),
+            overloads: Some(2),
+            signature: Some("f[BOOLEAN return INTEGER]".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hover_to_markdown(&info),
+            "\
+```vhdl
+function f(arg : boolean) return integer;
+```
+
+Selected overload `f[BOOLEAN return INTEGER]` of 2 overloads"
+        );
+
+        let info = HoverInfo {
+            declaration: "inst".to_owned(),
+            instance_of: Some(Box::new(HoverInfo {
+                declaration: "entity child is\nend entity;".to_owned(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        assert_eq!(
+            hover_to_markdown(&info),
+            "\
+```vhdl
+inst
+```
+
+Instance of:
+```vhdl
+entity child is
+end entity;
+```"
+        );
+    }
 }